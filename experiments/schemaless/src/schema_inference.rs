@@ -664,8 +664,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type GitHubActionsImportedStep implements _AnonType5 {
@@ -736,8 +736,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type GitHubActionsImportedStep implements _AnonType5 {
@@ -805,8 +805,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type GitHubActionsImportedStep implements _AnonType5 {
@@ -877,8 +877,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type GitHubActionsRunStep implements _AnonType5 {
@@ -956,8 +956,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type HackerNewsComment implements _AnonType2 {
@@ -1012,8 +1012,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type GitHubRepository implements _AnonType2 {
@@ -158,6 +158,35 @@ fn make_python_value(py: Python, value: FieldValue) -> Py<PyAny> {
             .map(|v| make_python_value(py, v))
             .collect::<Vec<_>>()
             .into_py(py),
+        FieldValue::Json(x) => make_python_json_value(py, x),
+        other => panic!("value cannot be converted to a Python value by this version: {other:?}"),
+    }
+}
+
+fn make_python_json_value(py: Python, value: serde_json::Value) -> Py<PyAny> {
+    match value {
+        serde_json::Value::Null => Option::<i64>::None.into_py(py),
+        serde_json::Value::Bool(x) => x.into_py(py),
+        serde_json::Value::Number(x) => {
+            if let Some(x) = x.as_i64() {
+                x.into_py(py)
+            } else if let Some(x) = x.as_u64() {
+                x.into_py(py)
+            } else {
+                x.as_f64().expect("number is finite").into_py(py)
+            }
+        }
+        serde_json::Value::String(x) => x.into_py(py),
+        serde_json::Value::Array(x) => x
+            .into_iter()
+            .map(|v| make_python_json_value(py, v))
+            .collect::<Vec<_>>()
+            .into_py(py),
+        serde_json::Value::Object(x) => x
+            .into_iter()
+            .map(|(k, v)| (k, make_python_json_value(py, v)))
+            .collect::<BTreeMap<_, _>>()
+            .into_py(py),
     }
 }
 
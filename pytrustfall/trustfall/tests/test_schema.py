@@ -14,8 +14,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 
 type RootSchemaQuery {
     Base: Base
@@ -1,12 +1,22 @@
 #![allow(dead_code)]
 
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    num::{NonZeroU32, NonZeroUsize},
+    sync::Arc,
+    time::Duration,
+};
 
-use hn_api::{types::Item, HnClient};
+use hn_api::{
+    types::{Item, User},
+    HnClient,
+};
 use trustfall::{
     provider::{
-        field_property, resolve_coercion_with, resolve_neighbors_with, resolve_property_with,
-        BasicAdapter, ContextIterator, ContextOutcomeIterator, EdgeParameters, VertexIterator,
+        field_property, resolve_coercion_with, resolve_neighbors_by_ids, resolve_neighbors_with,
+        resolve_property_by_subtype, resolve_property_with, resolve_starting_vertices_from_list,
+        BasicAdapter, ContextIterator, ContextOutcomeIterator, EdgeParameters, RetryPolicy,
+        VertexIterator,
     },
     FieldValue, Schema,
 };
@@ -14,20 +24,90 @@ use trustfall::{
 use crate::vertex::Vertex;
 
 lazy_static! {
-    static ref CLIENT: HnClient = HnClient::init().expect("HnClient instantiated");
     static ref SCHEMA: Schema =
         Schema::parse(include_str!("hackernews.graphql")).expect("valid schema");
 }
 
-#[derive(Debug, Clone, Default)]
+/// The HackerNews API client, retrying requests that time out or hit a server error
+/// before the adapter gives up on them.
+#[derive(Clone)]
+struct Client {
+    client: Arc<HnClient>,
+    retry_policy: RetryPolicy,
+}
+
+impl Client {
+    fn get_item(&self, id: u32) -> Result<Option<Item>, impl std::fmt::Display> {
+        self.retry(
+            || self.client.get_item(id),
+            |e| e.is_timeout() || e.is_server_error(),
+        )
+    }
+
+    fn get_user(&self, username: &str) -> Result<Option<User>, impl std::fmt::Display> {
+        self.retry(
+            || self.client.get_user(username),
+            |e| e.is_timeout() || e.is_server_error(),
+        )
+    }
+
+    /// Fetch the items with the given IDs, keyed by ID.
+    /// Items that don't exist or failed to load are left out.
+    fn get_items(&self, ids: &[u32]) -> HashMap<u32, Vertex> {
+        ids.iter()
+            .filter_map(|id| match self.get_item(*id) {
+                Ok(maybe_item) => maybe_item.map(|item| (*id, item.into())),
+                Err(e) => {
+                    eprintln!("API error while fetching item {id}: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Takes the operation first, so the error type that `is_transient` receives is inferred
+    /// from it: the API client's error type can't be named here.
+    fn retry<T, E>(
+        &self,
+        operation: impl FnMut() -> Result<T, E>,
+        is_transient: impl Fn(&E) -> bool,
+    ) -> Result<T, E> {
+        self.retry_policy.retry(is_transient, operation)
+    }
+}
+
+/// How many vertices' neighboring items to fetch at a time. Fetching the items of several
+/// vertices together lets the adapter fetch items they have in common only once.
+const ITEM_BATCH_SIZE: NonZeroUsize = match NonZeroUsize::new(20) {
+    Some(size) => size,
+    None => unreachable!(),
+};
+
+/// An adapter over the HackerNews API.
+///
+/// Adapters are meant to be constructed for each query execution, so they are a natural home
+/// for the state that a query's resolvers need: the API client is passed in when constructing
+/// the adapter, instead of being a global. A server can share one client across all its queries
+/// by cloning the `Arc`, or construct adapters with different clients, e.g. authenticated
+/// with the credentials of the user making each request.
+#[derive(Clone)]
 pub struct HackerNewsAdapter {
+    client: Client,
+
     /// Set of types that implement the Item interface in the schema.
     item_subtypes: HashSet<String>,
 }
 
 impl HackerNewsAdapter {
-    pub fn new() -> Self {
+    pub fn new(client: Arc<HnClient>) -> Self {
+        let retry_policy =
+            RetryPolicy::new(NonZeroU32::new(3).unwrap(), Duration::from_millis(100))
+                .with_max_backoff(Duration::from_secs(1));
         Self {
+            client: Client {
+                client,
+                retry_policy,
+            },
             item_subtypes: SCHEMA
                 .subtypes("Item")
                 .expect("Item type exists")
@@ -41,12 +121,14 @@ impl HackerNewsAdapter {
     }
 
     fn top(&self, max: Option<usize>) -> VertexIterator<'static, Vertex> {
-        let iterator = CLIENT
+        let client = self.client.clone();
+        let iterator = client
+            .client
             .get_top_stories()
             .unwrap()
             .into_iter()
             .take(max.unwrap_or(usize::MAX))
-            .filter_map(|id| match CLIENT.get_item(id) {
+            .filter_map(move |id| match client.get_item(id) {
                 Ok(maybe_item) => maybe_item.map(|item| item.into()),
                 Err(e) => {
                     eprintln!("Got an error while fetching item: {e}");
@@ -66,10 +148,11 @@ impl HackerNewsAdapter {
                 .json()
                 .unwrap();
 
+        let client = self.client.clone();
         let iterator = story_ids
             .into_iter()
             .take(max.unwrap_or(usize::MAX))
-            .map(move |id| CLIENT.get_item(id))
+            .map(move |id| client.get_item(id))
             .filter_map(|res| match res {
                 Ok(maybe_item) => maybe_item.map(|item| item.into()),
                 Err(e) => {
@@ -82,7 +165,7 @@ impl HackerNewsAdapter {
     }
 
     fn user(&self, username: &str) -> VertexIterator<'static, Vertex> {
-        match CLIENT.get_user(username) {
+        match self.client.get_user(username) {
             Ok(Some(user)) => {
                 // Found a user by that name.
                 let vertex = Vertex::from(user);
@@ -101,22 +184,27 @@ impl HackerNewsAdapter {
 }
 
 macro_rules! item_property_resolver {
-    ($attr:ident) => {
-        |vertex| -> FieldValue {
-            if let Some(s) = vertex.as_story() {
-                s.$attr.clone().into()
-            } else if let Some(j) = vertex.as_job() {
-                j.$attr.clone().into()
-            } else if let Some(c) = vertex.as_comment() {
-                c.$attr.clone().into()
-            } else if let Some(p) = vertex.as_poll() {
-                p.$attr.clone().into()
-            } else if let Some(p) = vertex.as_poll_option() {
-                p.$attr.clone().into()
-            } else {
-                unreachable!("{:?}", vertex)
-            }
-        }
+    ($contexts:ident, $attr:ident) => {
+        resolve_property_by_subtype(
+            $contexts,
+            &[
+                ("Story", |vertex| {
+                    vertex.as_story().unwrap().$attr.clone().into()
+                }),
+                ("Job", |vertex| {
+                    vertex.as_job().unwrap().$attr.clone().into()
+                }),
+                ("Comment", |vertex| {
+                    vertex.as_comment().unwrap().$attr.clone().into()
+                }),
+                ("Poll", |vertex| {
+                    vertex.as_poll().unwrap().$attr.clone().into()
+                }),
+                ("PollOption", |vertex| {
+                    vertex.as_poll_option().unwrap().$attr.clone().into()
+                }),
+            ],
+        )
     };
 }
 
@@ -142,6 +230,21 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
                 let username_value = parameters["name"].as_str().unwrap();
                 self.user(username_value)
             }
+            "Users" => {
+                let client = self.client.clone();
+                resolve_starting_vertices_from_list(parameters, "names", move |name| {
+                    let username = name.as_str().unwrap();
+                    match client.get_user(username) {
+                        Ok(maybe_user) => maybe_user.map(Vertex::from),
+                        Err(e) => {
+                            eprintln!(
+                                "Got an error while getting user profile for user {username}: {e}"
+                            );
+                            None
+                        }
+                    }
+                })
+            }
             _ => unimplemented!("unexpected starting edge: {edge_name}"),
         }
     }
@@ -155,10 +258,10 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
         match (type_name, property_name) {
             // properties on Item and its implementers
             (type_name, "id") if self.item_subtypes.contains(type_name) => {
-                resolve_property_with(contexts, item_property_resolver!(id))
+                item_property_resolver!(contexts, id)
             }
             (type_name, "unixTime") if self.item_subtypes.contains(type_name) => {
-                resolve_property_with(contexts, item_property_resolver!(time))
+                item_property_resolver!(contexts, time)
             }
 
             // properties on Job
@@ -211,13 +314,14 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
         edge_name: &str,
         _parameters: &EdgeParameters,
     ) -> ContextOutcomeIterator<'static, Self::Vertex, VertexIterator<'static, Self::Vertex>> {
+        let client = self.client.clone();
         match (type_name, edge_name) {
             ("Story", "byUser") => {
                 let edge_resolver =
-                    |vertex: &Self::Vertex| -> VertexIterator<'static, Self::Vertex> {
+                    move |vertex: &Self::Vertex| -> VertexIterator<'static, Self::Vertex> {
                         let story = vertex.as_story().unwrap();
                         let author = story.by.as_str();
-                        match CLIENT.get_user(author) {
+                        match client.get_user(author) {
                             Ok(None) => Box::new(std::iter::empty()), // no known author
                             Ok(Some(user)) => Box::new(std::iter::once(user.into())),
                             Err(e) => {
@@ -231,42 +335,18 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
                     };
                 resolve_neighbors_with(contexts, edge_resolver)
             }
-            ("Story", "comment") => {
-                let edge_resolver = |vertex: &Self::Vertex| {
-                    let story = vertex.as_story().unwrap();
-                    let comment_ids = story.kids.clone().unwrap_or_default();
-                    let story_id = story.id;
-
-                    let neighbors: VertexIterator<'static, Self::Vertex> =
-                        Box::new(comment_ids.into_iter().filter_map(move |comment_id| {
-                            match CLIENT.get_item(comment_id) {
-                                Ok(None) => None,
-                                Ok(Some(item)) => {
-                                    if let Item::Comment(comment) = item {
-                                        Some(comment.into())
-                                    } else {
-                                        unreachable!()
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!(
-                                        "API error while fetching story {story_id} comment {comment_id}: {e}",
-                                    );
-                                    None
-                                }
-                            }
-                        }));
-
-                    neighbors
-                };
-                resolve_neighbors_with(contexts, edge_resolver)
-            }
+            ("Story", "comment") => resolve_neighbors_by_ids(
+                contexts,
+                ITEM_BATCH_SIZE,
+                |vertex| vertex.as_story().unwrap().kids.clone().unwrap_or_default(),
+                move |ids| client.get_items(ids),
+            ),
             ("Comment", "byUser") => {
-                let edge_resolver = |vertex: &Self::Vertex| {
+                let edge_resolver = move |vertex: &Self::Vertex| {
                     let comment = vertex.as_comment().unwrap();
                     let author = comment.by.as_str();
                     let neighbors: VertexIterator<'static, Self::Vertex> =
-                        match CLIENT.get_user(author) {
+                        match client.get_user(author) {
                             Ok(None) => Box::new(std::iter::empty()), // no known author
                             Ok(Some(user)) => Box::new(std::iter::once(user.into())),
                             Err(e) => {
@@ -282,12 +362,12 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
                 resolve_neighbors_with(contexts, edge_resolver)
             }
             ("Comment", "parent") => {
-                let edge_resolver = |vertex: &Self::Vertex| {
+                let edge_resolver = move |vertex: &Self::Vertex| {
                     let comment = vertex.as_comment().unwrap();
                     let comment_id = comment.id;
                     let parent_id = comment.parent;
 
-                    let neighbors: VertexIterator<'static, Self::Vertex> = match CLIENT
+                    let neighbors: VertexIterator<'static, Self::Vertex> = match client
                         .get_item(parent_id)
                     {
                         Ok(None) => Box::new(std::iter::empty()),
@@ -304,13 +384,14 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
                 resolve_neighbors_with(contexts, edge_resolver)
             }
             ("Comment", "reply") => {
-                let edge_resolver = |vertex: &Self::Vertex| {
+                let edge_resolver = move |vertex: &Self::Vertex| {
                     let comment = vertex.as_comment().unwrap();
                     let comment_id = comment.id;
                     let reply_ids = comment.kids.clone().unwrap_or_default();
+                    let client = client.clone();
 
                     let neighbors: VertexIterator<'static, Self::Vertex> = Box::new(reply_ids.into_iter().filter_map(move |reply_id| {
-                        match CLIENT.get_item(reply_id) {
+                        match client.get_item(reply_id) {
                             Ok(None) => None,
                             Ok(Some(item)) => {
                                 if let Item::Comment(c) = item {
@@ -331,28 +412,12 @@ impl BasicAdapter<'static> for HackerNewsAdapter {
                 };
                 resolve_neighbors_with(contexts, edge_resolver)
             }
-            ("User", "submitted") => {
-                let edge_resolver = |vertex: &Self::Vertex| {
-                    let user = vertex.as_user().unwrap();
-                    let submitted_ids = user.submitted.clone();
-
-                    let neighbors: VertexIterator<'static, Self::Vertex> =
-                        Box::new(submitted_ids.into_iter().filter_map(move |submission_id| {
-                            match CLIENT.get_item(submission_id) {
-                                Ok(None) => None,
-                                Ok(Some(item)) => Some(item.into()),
-                                Err(e) => {
-                                    eprintln!(
-                                    "API error while fetching submitted item {submission_id}: {e}",
-                                );
-                                    None
-                                }
-                            }
-                        }));
-                    neighbors
-                };
-                resolve_neighbors_with(contexts, edge_resolver)
-            }
+            ("User", "submitted") => resolve_neighbors_by_ids(
+                contexts,
+                ITEM_BATCH_SIZE,
+                |vertex| vertex.as_user().unwrap().submitted.clone(),
+                move |ids| client.get_items(ids),
+            ),
             _ => unreachable!("{} {}", type_name, edge_name),
         }
     }
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use std::{cell::RefCell, fs};
 use std::{env, process};
 
+use hn_api::HnClient;
 use serde::Deserialize;
 use trustfall::{execute_query, FieldValue, Schema, TransparentValue};
 
@@ -32,7 +33,8 @@ fn run_query(path: &str, max_results: Option<usize>) {
     let content = fs::read_to_string(path).unwrap();
     let input_query: InputQuery = ron::from_str(&content).unwrap();
 
-    let adapter = Rc::new(RefCell::new(HackerNewsAdapter::new()));
+    let client = Arc::new(HnClient::init().expect("HnClient instantiated"));
+    let adapter = Rc::new(RefCell::new(HackerNewsAdapter::new(client)));
 
     let query = input_query.query;
     let arguments = input_query.args;
@@ -64,8 +66,14 @@ Examples: (paths relative to `trustfall` crate directory)
     Latest links submitted by users with min 10000 karma
         cargo run --example hackernews query ./examples/hackernews/example_queries/latest_links_by_high_karma_users.ron
 
+    Karma of several users at once:
+        cargo run --example hackernews query ./examples/hackernews/example_queries/several_users_karma.ron
+
     patio11 commenting on his own blog posts
         cargo run --example hackernews query ./examples/hackernews/example_queries/patio11_comments_on_own_blog_posts.ron
+
+    The three most-replied comments on each front page story:
+        cargo run --example hackernews query ./examples/hackernews/example_queries/front_page_stories_with_most_replied_comments.ron
 ";
 
 fn main() {
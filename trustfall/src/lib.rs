@@ -4,35 +4,175 @@ use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
 pub mod provider {
     pub use trustfall_core::interpreter::basic_adapter::BasicAdapter;
     pub use trustfall_core::interpreter::{
-        Adapter, ContextIterator, ContextOutcomeIterator, DataContext, Typename, VertexIterator,
+        composite_keys, Adapter, CandidateValue, CompositeKeys, ContextIterator,
+        ContextOutcomeIterator, DataContext, EdgeInfo, PropertyUsage, QueryInfo, Typename,
+        VertexIterator,
     };
     pub use trustfall_core::ir::{EdgeParameters, Eid, Vid};
 
     // Helpers for common operations when building adapters.
     pub use trustfall_core::interpreter::helpers::{
-        resolve_coercion_with, resolve_neighbors_with, resolve_property_with,
+        resolve_coercion_by_typename, resolve_coercion_with, resolve_neighbors_by_ids,
+        resolve_neighbors_from_property, resolve_neighbors_parallel_with, resolve_neighbors_typed,
+        resolve_neighbors_with, resolve_neighbors_with_fan_out, resolve_property_by_subtype,
+        resolve_property_with, resolve_property_with_timeout, resolve_starting_vertices_from_list,
+        resolve_with_context, TypedVertex,
     };
+    pub use trustfall_core::interpreter::retry::RetryPolicy;
+    pub use trustfall_core::interpreter::stats::FanOutStats;
     pub use trustfall_core::{accessor_property, field_property};
 
+    // Combining adapters.
+    pub use trustfall_core::interpreter::composite::{
+        CompositeAdapter, SubAdapter, UnhandledByCompositeAdapter,
+    };
+
+    // Adapter middleware.
+    #[cfg(feature = "log")]
+    pub use trustfall_core::interpreter::logging::LoggingAdapter;
+    pub use trustfall_core::interpreter::nullability::{NonNullCheckedAdapter, NonNullViolation};
+    pub use trustfall_core::interpreter::rate_limit::RateLimitedAdapter;
+    pub use trustfall_core::interpreter::type_check::{TypeCheckedAdapter, TypePositionError};
+
     // Derive macros for common vertex implementation details.
     pub use trustfall_derive::{TrustfallEnumVertex, Typename};
 }
 
 // Property values and query variables.
 // Useful both for querying and for implementing data providers.
-pub use trustfall_core::ir::{FieldValue, TransparentValue};
+pub use trustfall_core::ir::{FieldValue, NormalizedValue, TransparentValue, TypedValue};
 
 /// Trustfall query schema.
 pub use trustfall_core::schema::Schema;
 
+/// A query that has been parsed and validated against a schema, ready to be executed.
+pub use trustfall_core::ir::indexed::IndexedQuery;
+
+/// Skip query result rows identical to an earlier row.
+pub use trustfall_core::interpreter::execution::distinct_results;
+
+/// Reorder query result rows by a computed score, highest first.
+pub use trustfall_core::interpreter::execution::rank_results;
+
+/// Produce at most a given number of query result rows, recording whether any were left out.
+pub use trustfall_core::interpreter::execution::{limited_results, LimitedResults};
+
+/// Query results as an async stream that only computes each row when it's polled.
+#[cfg(feature = "async")]
+pub use trustfall_core::interpreter::stream::ResultStream;
+
 /// Run a Trustfall query over the data provider specified by the given schema and adapter.
+///
+/// This parses and validates the query on every call. To run the same query many times,
+/// use [`compile_query`] once and then [`execute_compiled_query`] for each execution.
 pub fn execute_query<'vertex>(
     schema: &Schema,
     adapter: Rc<RefCell<impl provider::Adapter<'vertex> + 'vertex>>,
     query: &str,
     variables: BTreeMap<impl Into<Arc<str>>, impl Into<FieldValue>>,
 ) -> anyhow::Result<Box<dyn Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'vertex>> {
-    let parsed_query = trustfall_core::frontend::parse(schema, query)?;
+    let compiled_query = compile_query(schema, query)?;
+    execute_compiled_query(adapter, &compiled_query, variables)
+}
+
+/// Parse the query and validate it against the schema, without executing it.
+///
+/// The result may be executed any number of times, with different variables each time,
+/// using [`execute_compiled_query`]. This way, the cost of parsing and validating
+/// the query is only paid once.
+///
+/// ```
+/// # use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+/// # use trustfall::{
+/// #     compile_query, execute_compiled_query, FieldValue, Schema,
+/// #     provider::{
+/// #         resolve_property_with, BasicAdapter, ContextIterator, ContextOutcomeIterator,
+/// #         EdgeParameters, Typename, VertexIterator,
+/// #     },
+/// # };
+/// #
+/// # #[derive(Debug, Clone)]
+/// # struct Number(i64);
+/// #
+/// # impl Typename for Number {
+/// #     fn typename(&self) -> &'static str {
+/// #         "Number"
+/// #     }
+/// # }
+/// #
+/// # struct NumbersAdapter;
+/// #
+/// # impl BasicAdapter<'static> for NumbersAdapter {
+/// #     type Vertex = Number;
+/// #
+/// #     fn resolve_starting_vertices(
+/// #         &mut self,
+/// #         _edge_name: &str,
+/// #         _parameters: &EdgeParameters,
+/// #     ) -> VertexIterator<'static, Self::Vertex> {
+/// #         Box::new((1..=10).map(Number))
+/// #     }
+/// #
+/// #     fn resolve_property(
+/// #         &mut self,
+/// #         contexts: ContextIterator<'static, Self::Vertex>,
+/// #         _type_name: &str,
+/// #         _property_name: &str,
+/// #     ) -> ContextOutcomeIterator<'static, Self::Vertex, FieldValue> {
+/// #         resolve_property_with(contexts, |vertex| vertex.0.into())
+/// #     }
+/// # }
+/// #
+/// # let schema = Schema::parse(r#"
+/// # schema {
+/// #     query: RootSchemaQuery
+/// # }
+/// # directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
+/// # directive @tag(name: String) on FIELD
+/// # directive @output(name: String) on FIELD
+/// # directive @optional on FIELD
+/// # directive @recurse(depth: Int!, depth_output: String) on FIELD
+/// # directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
+/// #
+/// # type RootSchemaQuery {
+/// #     Number: [Number!]!
+/// # }
+/// #
+/// # type Number {
+/// #     value: Int!
+/// # }
+/// # "#).unwrap();
+/// let adapter = Rc::new(RefCell::new(NumbersAdapter));
+///
+/// // Compile the query once...
+/// let query = compile_query(&schema, r#"
+/// {
+///     Number {
+///         value @output @filter(op: ">", value: ["$min"])
+///     }
+/// }"#).unwrap();
+///
+/// // ... then execute it as many times as needed, e.g. once per incoming request.
+/// for min in [7, 9] {
+///     let variables = BTreeMap::from([("min", min)]);
+///     let results: Vec<_> = execute_compiled_query(adapter.clone(), &query, variables)
+///         .unwrap()
+///         .collect();
+///     assert_eq!(10 - min as usize, results.len());
+/// }
+/// ```
+pub fn compile_query(schema: &Schema, query: &str) -> anyhow::Result<Arc<IndexedQuery>> {
+    Ok(trustfall_core::frontend::parse(schema, query)?)
+}
+
+/// Run a query produced by [`compile_query`] over the given adapter.
+///
+/// The adapter must be for the same schema against which the query was compiled.
+pub fn execute_compiled_query<'vertex>(
+    adapter: Rc<RefCell<impl provider::Adapter<'vertex> + 'vertex>>,
+    query: &Arc<IndexedQuery>,
+    variables: BTreeMap<impl Into<Arc<str>>, impl Into<FieldValue>>,
+) -> anyhow::Result<Box<dyn Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'vertex>> {
     let vars = Arc::new(
         variables
             .into_iter()
@@ -42,7 +182,7 @@ pub fn execute_query<'vertex>(
 
     Ok(trustfall_core::interpreter::execution::interpret_ir(
         adapter,
-        parsed_query,
+        query.clone(),
         vars,
     )?)
 }
@@ -0,0 +1,124 @@
+//! Measures the cost of the per-context vertex storage on a deep query,
+//! where every context is cloned at each of its many edge expansions.
+//!
+//! Compare the default storage with `cargo bench --features dense-vertex-store`.
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use trustfall_core::{
+    frontend::parse,
+    interpreter::{
+        execution::interpret_ir, Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo,
+        VertexIterator,
+    },
+    ir::{EdgeParameters, FieldValue},
+    schema::Schema,
+};
+
+const SCHEMA: &str = r#"
+schema {
+    query: RootSchemaQuery
+}
+directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
+directive @tag(name: String) on FIELD
+directive @output(name: String) on FIELD
+directive @optional on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
+directive @transform(op: String!) on FIELD
+
+type RootSchemaQuery {
+    Root: Node!
+}
+
+type Node {
+    value: Int!
+    child: [Node!]!
+}
+"#;
+
+/// A complete binary tree, in which each node numbered `n` has children `2n` and `2n + 1`.
+struct TreeAdapter;
+
+impl<'vertex> Adapter<'vertex> for TreeAdapter {
+    type Vertex = i64;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        _edge_name: &Arc<str>,
+        _parameters: &EdgeParameters,
+        _query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        Box::new(std::iter::once(1))
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        _type_name: &Arc<str>,
+        _property_name: &Arc<str>,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        Box::new(contexts.map(|ctx| {
+            let value = ctx.active_vertex().copied().into();
+            (ctx, value)
+        }))
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        _type_name: &Arc<str>,
+        _edge_name: &Arc<str>,
+        _parameters: &EdgeParameters,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        Box::new(contexts.map(|ctx| {
+            let neighbors: VertexIterator<'vertex, Self::Vertex> = match ctx.active_vertex() {
+                Some(&vertex) => Box::new([2 * vertex, 2 * vertex + 1].into_iter()),
+                None => Box::new(std::iter::empty()),
+            };
+            (ctx, neighbors)
+        }))
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        _contexts: ContextIterator<'vertex, Self::Vertex>,
+        _type_name: &Arc<str>,
+        _coerce_to_type: &Arc<str>,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        unreachable!("the schema has no subtypes")
+    }
+}
+
+fn deep_query(depth: usize) -> String {
+    let mut query = "{ Root { value @output(name: \"root\")".to_string();
+    for _ in 0..depth {
+        query.push_str(" child {");
+    }
+    query.push_str(" value @output");
+    for _ in 0..depth {
+        query.push_str(" }");
+    }
+    query.push_str(" } }");
+    query
+}
+
+fn deep_query_benchmark(c: &mut Criterion) {
+    let schema = Schema::parse(SCHEMA).expect("schema is not valid");
+    let query = parse(&schema, deep_query(12)).expect("query is not valid");
+    let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+    c.bench_function("deep_query_12_levels", |b| {
+        b.iter(|| {
+            let adapter = Rc::new(RefCell::new(TreeAdapter));
+            let results = interpret_ir(adapter, query.clone(), arguments.clone()).unwrap();
+            assert_eq!(4096, results.count());
+        })
+    });
+}
+
+criterion_group!(benches, deep_query_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,136 @@
+//! Measures computing the candidate values of a starting vertex's property
+//! when it's filtered by several `one_of` filters with long lists of values,
+//! whose intersection must be found without comparing every pair of values.
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use trustfall_core::{
+    frontend::parse,
+    interpreter::{
+        execution::interpret_ir, Adapter, CandidateValue, ContextIterator, ContextOutcomeIterator,
+        QueryInfo, VertexIterator,
+    },
+    ir::{EdgeParameters, FieldValue},
+    schema::Schema,
+};
+
+const SCHEMA: &str = r#"
+schema {
+    query: RootSchemaQuery
+}
+directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
+directive @tag(name: String) on FIELD
+directive @output(name: String) on FIELD
+directive @optional on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
+directive @transform(op: String!) on FIELD
+
+type RootSchemaQuery {
+    Number: [Number!]!
+}
+
+type Number {
+    value: Int!
+}
+"#;
+
+const QUERY: &str = r#"
+{
+    Number {
+        value @filter(op: "one_of", value: ["$first"])
+              @filter(op: "one_of", value: ["$second"])
+              @output
+    }
+}"#;
+
+/// Computes the starting vertices' candidate values and records how many there are,
+/// without producing any vertices.
+struct CandidatesAdapter {
+    candidates: usize,
+}
+
+impl<'vertex> Adapter<'vertex> for CandidatesAdapter {
+    type Vertex = i64;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        _edge_name: &Arc<str>,
+        _parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        self.candidates = match query_info.starting_vertex_candidate("value") {
+            Some(CandidateValue::Multiple(values)) => values.len(),
+            other => panic!("unexpected candidates: {other:?}"),
+        };
+        Box::new(std::iter::empty())
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        _type_name: &Arc<str>,
+        _property_name: &Arc<str>,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        Box::new(contexts.map(|ctx| {
+            let value = ctx.active_vertex().copied().into();
+            (ctx, value)
+        }))
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        _contexts: ContextIterator<'vertex, Self::Vertex>,
+        _type_name: &Arc<str>,
+        _edge_name: &Arc<str>,
+        _parameters: &EdgeParameters,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        unreachable!("the schema has no edges")
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        _contexts: ContextIterator<'vertex, Self::Vertex>,
+        _type_name: &Arc<str>,
+        _coerce_to_type: &Arc<str>,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        unreachable!("the schema has no subtypes")
+    }
+}
+
+fn one_of_candidates_benchmark(c: &mut Criterion) {
+    let schema = Schema::parse(SCHEMA).expect("schema is not valid");
+    let query = parse(&schema, QUERY).expect("query is not valid");
+
+    let mut group = c.benchmark_group("one_of_candidates");
+    for size in [1_000i64, 10_000, 100_000] {
+        // The lists overlap in half their values. The second list holds its values
+        // as unsigned integers, which must still match the first list's signed ones.
+        let first: Vec<FieldValue> = (0..size).map(FieldValue::Int64).collect();
+        let second: Vec<FieldValue> = (size / 2..size + size / 2)
+            .rev()
+            .map(|value| FieldValue::Uint64(value as u64))
+            .collect();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Arc::new(BTreeMap::from([
+            (Arc::from("first"), FieldValue::List(first)),
+            (Arc::from("second"), FieldValue::List(second)),
+        ]));
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                let adapter = Rc::new(RefCell::new(CandidatesAdapter { candidates: 0 }));
+                let results = interpret_ir(adapter.clone(), query.clone(), arguments.clone())
+                    .expect("arguments are not valid");
+                assert_eq!(0, results.count());
+                assert_eq!(size / 2, adapter.borrow().candidates as i64);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, one_of_candidates_benchmark);
+criterion_main!(benches);
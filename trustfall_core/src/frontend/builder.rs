@@ -0,0 +1,456 @@
+//! Programmatic construction of Trustfall queries, type-checked against a [Schema].
+use std::{fmt::Write, sync::Arc};
+
+use crate::{
+    ir::{types::get_base_named_type, FieldValue, IRQuery},
+    schema::Schema,
+};
+
+use super::{error::FrontendError, parse_to_ir};
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QueryBuilderError {
+    #[error("The query already has a starting edge: {0}")]
+    StartingEdgeAlreadySet(String),
+
+    #[error("The query does not have a starting edge yet.")]
+    MissingStartingEdge,
+
+    #[error("Type \"{0}\" does not have a field named \"{1}\"")]
+    NonExistentField(String, String),
+
+    #[error("Field \"{1}\" on type \"{0}\" is a property, not an edge")]
+    NotAnEdge(String, String),
+
+    #[error("Field \"{1}\" on type \"{0}\" is an edge, not a property")]
+    NotAProperty(String, String),
+
+    #[error("Edge \"{1}\" on type \"{0}\" does not have a parameter named \"{2}\"")]
+    NonExistentEdgeParameter(String, String, String),
+
+    #[error("Filter argument \"{0}\" must be a variable like \"$name\" or a tag like \"%name\"")]
+    InvalidFilterArgument(String),
+
+    #[error("The built query failed validation: {0}")]
+    InvalidQuery(#[from] FrontendError),
+}
+
+/// Handle to a vertex that was added to a [QueryBuilder].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VertexHandle(usize);
+
+#[derive(Debug, Clone)]
+struct BuilderVertex {
+    type_name: Arc<str>,
+    edge_name: Arc<str>,
+    parameters: Vec<(Arc<str>, FieldValue)>,
+    properties: Vec<BuilderProperty>,
+    neighbors: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct BuilderProperty {
+    name: Arc<str>,
+    output: Option<Arc<str>>,
+    tag: Option<Arc<str>>,
+    filters: Vec<(Arc<str>, Vec<Arc<str>>)>,
+}
+
+/// Builds a Trustfall query one component at a time, checking each step against the [Schema].
+///
+/// Vertices, edges, and properties are checked as they are added. Anything that can only be
+/// checked in the context of the whole query, such as filter operator types or tag usage,
+/// is checked when calling [QueryBuilder::build].
+///
+/// ```
+/// # use trustfall_core::{frontend::builder::QueryBuilder, schema::Schema};
+/// # let schema = Schema::parse(include_str!("../../test_data/schemas/numbers.graphql")).unwrap();
+/// let mut builder = QueryBuilder::new(&schema);
+/// let number = builder.starting_edge("Number", [("max", 10)]).unwrap();
+/// builder.output(number, "value", "value").unwrap();
+/// let successor = builder.edge(number, "successor", Vec::<(&str, i64)>::new()).unwrap();
+/// builder.filter(successor, "value", ">", &["$min"]).unwrap();
+/// let ir_query = builder.build().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryBuilder<'a> {
+    schema: &'a Schema,
+    vertices: Vec<BuilderVertex>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    pub fn new(schema: &'a Schema) -> Self {
+        Self {
+            schema,
+            vertices: vec![],
+        }
+    }
+
+    /// Set the edge of the root query type from which the query starts.
+    pub fn starting_edge(
+        &mut self,
+        edge_name: &str,
+        parameters: impl IntoIterator<Item = (impl Into<Arc<str>>, impl Into<FieldValue>)>,
+    ) -> Result<VertexHandle, QueryBuilderError> {
+        if let Some(root) = self.vertices.first() {
+            return Err(QueryBuilderError::StartingEdgeAlreadySet(
+                root.edge_name.to_string(),
+            ));
+        }
+
+        let root_type = self.schema.query_type_name().to_string();
+        self.add_vertex(&root_type, edge_name, parameters)
+    }
+
+    /// Expand the given edge from an existing vertex, returning the newly-added neighbor vertex.
+    pub fn edge(
+        &mut self,
+        from: VertexHandle,
+        edge_name: &str,
+        parameters: impl IntoIterator<Item = (impl Into<Arc<str>>, impl Into<FieldValue>)>,
+    ) -> Result<VertexHandle, QueryBuilderError> {
+        let from_type = self.vertices[from.0].type_name.clone();
+        let handle = self.add_vertex(&from_type, edge_name, parameters)?;
+        self.vertices[from.0].neighbors.push(handle.0);
+        Ok(handle)
+    }
+
+    /// Output the given property of a vertex under the given output name.
+    pub fn output(
+        &mut self,
+        vertex: VertexHandle,
+        property_name: &str,
+        output_name: &str,
+    ) -> Result<(), QueryBuilderError> {
+        self.property_mut(vertex, property_name)?.output = Some(output_name.into());
+        Ok(())
+    }
+
+    /// Tag the given property of a vertex under the given tag name.
+    pub fn tag(
+        &mut self,
+        vertex: VertexHandle,
+        property_name: &str,
+        tag_name: &str,
+    ) -> Result<(), QueryBuilderError> {
+        self.property_mut(vertex, property_name)?.tag = Some(tag_name.into());
+        Ok(())
+    }
+
+    /// Filter the given property of a vertex.
+    ///
+    /// Each argument must be either a query variable like `"$name"` or a tag like `"%name"`.
+    pub fn filter(
+        &mut self,
+        vertex: VertexHandle,
+        property_name: &str,
+        operator: &str,
+        arguments: &[&str],
+    ) -> Result<(), QueryBuilderError> {
+        if let Some(invalid) = arguments
+            .iter()
+            .find(|arg| !(arg.starts_with('$') || arg.starts_with('%')) || arg.len() < 2)
+        {
+            return Err(QueryBuilderError::InvalidFilterArgument(
+                invalid.to_string(),
+            ));
+        }
+
+        self.property_mut(vertex, property_name)?.filters.push((
+            operator.into(),
+            arguments.iter().map(|arg| Arc::from(*arg)).collect(),
+        ));
+        Ok(())
+    }
+
+    /// Render the query built so far as Trustfall query text.
+    pub fn to_query_string(&self) -> Result<String, QueryBuilderError> {
+        if self.vertices.is_empty() {
+            return Err(QueryBuilderError::MissingStartingEdge);
+        }
+
+        let mut buf = String::new();
+        buf.push_str("{\n");
+        self.write_vertex(&mut buf, 0, 1);
+        buf.push_str("}\n");
+        Ok(buf)
+    }
+
+    /// Validate the whole query and convert it into IR.
+    pub fn build(&self) -> Result<IRQuery, QueryBuilderError> {
+        let query = self.to_query_string()?;
+        Ok(parse_to_ir(self.schema, query)?)
+    }
+
+    fn add_vertex(
+        &mut self,
+        from_type: &str,
+        edge_name: &str,
+        parameters: impl IntoIterator<Item = (impl Into<Arc<str>>, impl Into<FieldValue>)>,
+    ) -> Result<VertexHandle, QueryBuilderError> {
+        let field_defn = self
+            .schema
+            .fields
+            .get(&(Arc::from(from_type), Arc::from(edge_name)))
+            .ok_or_else(|| {
+                QueryBuilderError::NonExistentField(from_type.to_string(), edge_name.to_string())
+            })?;
+        let target_type = get_base_named_type(&field_defn.ty.node);
+        if !self.schema.vertex_types.contains_key(target_type) {
+            return Err(QueryBuilderError::NotAnEdge(
+                from_type.to_string(),
+                edge_name.to_string(),
+            ));
+        }
+
+        let parameters: Vec<(Arc<str>, FieldValue)> = parameters
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+        for (name, _) in &parameters {
+            if !field_defn
+                .arguments
+                .iter()
+                .any(|arg| arg.node.name.node.as_ref() == name.as_ref())
+            {
+                return Err(QueryBuilderError::NonExistentEdgeParameter(
+                    from_type.to_string(),
+                    edge_name.to_string(),
+                    name.to_string(),
+                ));
+            }
+        }
+
+        self.vertices.push(BuilderVertex {
+            type_name: Arc::from(target_type),
+            edge_name: Arc::from(edge_name),
+            parameters,
+            properties: vec![],
+            neighbors: vec![],
+        });
+        Ok(VertexHandle(self.vertices.len() - 1))
+    }
+
+    fn property_mut(
+        &mut self,
+        vertex: VertexHandle,
+        property_name: &str,
+    ) -> Result<&mut BuilderProperty, QueryBuilderError> {
+        let vertex = &mut self.vertices[vertex.0];
+        let field_defn = self
+            .schema
+            .fields
+            .get(&(vertex.type_name.clone(), Arc::from(property_name)))
+            .ok_or_else(|| {
+                QueryBuilderError::NonExistentField(
+                    vertex.type_name.to_string(),
+                    property_name.to_string(),
+                )
+            })?;
+        if self
+            .schema
+            .vertex_types
+            .contains_key(get_base_named_type(&field_defn.ty.node))
+        {
+            return Err(QueryBuilderError::NotAProperty(
+                vertex.type_name.to_string(),
+                property_name.to_string(),
+            ));
+        }
+
+        let position = match vertex
+            .properties
+            .iter()
+            .position(|prop| prop.name.as_ref() == property_name)
+        {
+            Some(position) => position,
+            None => {
+                vertex.properties.push(BuilderProperty {
+                    name: property_name.into(),
+                    output: None,
+                    tag: None,
+                    filters: vec![],
+                });
+                vertex.properties.len() - 1
+            }
+        };
+        Ok(&mut vertex.properties[position])
+    }
+
+    fn write_vertex(&self, buf: &mut String, index: usize, depth: usize) {
+        let vertex = &self.vertices[index];
+        let indent = "    ".repeat(depth);
+
+        write!(buf, "{indent}{}", vertex.edge_name).expect("writing to a String failed");
+        if !vertex.parameters.is_empty() {
+            buf.push('(');
+            for (idx, (name, value)) in vertex.parameters.iter().enumerate() {
+                if idx > 0 {
+                    buf.push_str(", ");
+                }
+                write!(buf, "{name}: ").expect("writing to a String failed");
+                write_value(buf, value);
+            }
+            buf.push(')');
+        }
+        buf.push_str(" {\n");
+
+        for property in &vertex.properties {
+            write!(buf, "{indent}    {}", property.name).expect("writing to a String failed");
+            for (operator, arguments) in &property.filters {
+                write!(buf, " @filter(op: {operator:?}, value: [")
+                    .expect("writing to a String failed");
+                for (idx, argument) in arguments.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push_str(", ");
+                    }
+                    write!(buf, "{argument:?}").expect("writing to a String failed");
+                }
+                buf.push_str("])");
+            }
+            if let Some(tag) = &property.tag {
+                write!(buf, " @tag(name: {tag:?})").expect("writing to a String failed");
+            }
+            if let Some(output) = &property.output {
+                write!(buf, " @output(name: {output:?})").expect("writing to a String failed");
+            }
+            buf.push('\n');
+        }
+
+        for neighbor in &vertex.neighbors {
+            self.write_vertex(buf, *neighbor, depth + 1);
+        }
+
+        writeln!(buf, "{indent}}}").expect("writing to a String failed");
+    }
+}
+
+fn write_value(buf: &mut String, value: &FieldValue) {
+    match value {
+        FieldValue::Null => buf.push_str("null"),
+        FieldValue::Int64(x) => write!(buf, "{x}").expect("writing to a String failed"),
+        FieldValue::Uint64(x) => write!(buf, "{x}").expect("writing to a String failed"),
+        FieldValue::Float64(x) => write!(buf, "{x:?}").expect("writing to a String failed"),
+        FieldValue::String(x) => write!(buf, "{x:?}").expect("writing to a String failed"),
+        FieldValue::Boolean(x) => write!(buf, "{x}").expect("writing to a String failed"),
+        FieldValue::DateTimeUtc(x) => {
+            write!(buf, "{:?}", x.to_rfc3339()).expect("writing to a String failed")
+        }
+        FieldValue::Enum(x) => buf.push_str(x),
+        #[cfg(feature = "serde_json")]
+        FieldValue::Json(x) => {
+            // GraphQL has no JSON literals, so this parameter fails to type-check.
+            write!(buf, "{:?}", x.to_string()).expect("writing to a String failed")
+        }
+        FieldValue::List(x) => {
+            buf.push('[');
+            for (idx, value) in x.iter().enumerate() {
+                if idx > 0 {
+                    buf.push_str(", ");
+                }
+                write_value(buf, value);
+            }
+            buf.push(']');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{frontend::parse_to_ir, numbers_interpreter::numbers_schema};
+
+    use super::{QueryBuilder, QueryBuilderError};
+
+    #[test]
+    fn builds_same_ir_as_parsed_query() {
+        let schema = numbers_schema();
+        let mut builder = QueryBuilder::new(&schema);
+        let number = builder
+            .starting_edge("Number", [("min", 1), ("max", 10)])
+            .unwrap();
+        builder.output(number, "value", "value").unwrap();
+        builder.filter(number, "value", ">=", &["$lower"]).unwrap();
+        let successor = builder
+            .edge(number, "successor", Vec::<(&str, i64)>::new())
+            .unwrap();
+        builder.output(successor, "name", "successor_name").unwrap();
+
+        let expected = parse_to_ir(
+            &schema,
+            r#"
+{
+    Number(min: 1, max: 10) {
+        value @filter(op: ">=", value: ["$lower"]) @output(name: "value")
+        successor {
+            name @output(name: "successor_name")
+        }
+    }
+}"#,
+        )
+        .unwrap();
+        assert_eq!(expected, builder.build().unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_steps() {
+        let schema = numbers_schema();
+        let mut builder = QueryBuilder::new(&schema);
+        assert_eq!(
+            Err(QueryBuilderError::MissingStartingEdge),
+            builder.build().map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryBuilderError::NonExistentField(
+                "RootSchemaQuery".into(),
+                "Number2".into()
+            )),
+            builder.starting_edge("Number2", Vec::<(&str, i64)>::new())
+        );
+
+        let number = builder
+            .starting_edge("Zero", Vec::<(&str, i64)>::new())
+            .unwrap();
+        assert_eq!(
+            Err(QueryBuilderError::StartingEdgeAlreadySet("Zero".into())),
+            builder.starting_edge("One", Vec::<(&str, i64)>::new())
+        );
+        assert_eq!(
+            Err(QueryBuilderError::NotAnEdge(
+                "Number".into(),
+                "value".into()
+            )),
+            builder.edge(number, "value", Vec::<(&str, i64)>::new())
+        );
+        assert_eq!(
+            Err(QueryBuilderError::NotAProperty(
+                "Number".into(),
+                "successor".into()
+            )),
+            builder.output(number, "successor", "x")
+        );
+        assert_eq!(
+            Err(QueryBuilderError::NonExistentEdgeParameter(
+                "Number".into(),
+                "multiple".into(),
+                "min".into()
+            )),
+            builder.edge(number, "multiple", [("min", 2)])
+        );
+        assert_eq!(
+            Err(QueryBuilderError::InvalidFilterArgument("5".into())),
+            builder.filter(number, "value", "=", &["5"])
+        );
+
+        // Type errors in filters are caught when the whole query is validated.
+        builder
+            .filter(number, "value", "has_prefix", &["$x"])
+            .unwrap();
+        builder.output(number, "value", "value").unwrap();
+        assert!(matches!(
+            builder.build(),
+            Err(QueryBuilderError::InvalidQuery(_))
+        ));
+    }
+}
@@ -100,9 +100,38 @@ pub enum FrontendError {
     )]
     EdgeRecursionNeedingMultipleCoercions(String),
 
+    #[error(
+        "Edge \"{0}\" uses @recurse with a depth_output inside a @fold, \
+        which is currently not supported."
+    )]
+    RecursionDepthOutputInFold(String),
+
+    #[error(
+        "Edge \"{0}\" uses @fold with sort_by \"{2}\", but type \"{1}\" \
+        has no property by that name."
+    )]
+    FoldSortByNonExistentProperty(String, String, String),
+
+    #[error(
+        "Edge \"{0}\" uses @fold with sort_by \"{1}\", but that property \
+        has non-orderable type \"{2}\"."
+    )]
+    FoldSortByNonOrderableProperty(String, String, String),
+
     #[error("Meta field \"{0}\" is a property but the query uses it as an edge.")]
     PropertyMetaFieldUsedAsEdge(String),
 
+    #[error(
+        "Query variable \"{0}\" is declared with a default value, but is not used in the query."
+    )]
+    UnusedVariableDefault(String),
+
+    #[error(
+        "Query variable \"{0}\" has type {1} based on how it is used, but its default value \
+        is not valid for that type: {2:?}"
+    )]
+    InvalidVariableDefault(String, String, FieldValue),
+
     #[error("The query failed to validate against the schema.")]
     ValidationError(#[from] ValidationError),
 
@@ -18,11 +18,12 @@ use crate::{
     },
     ir::{
         indexed::IndexedQuery,
-        types::{intersect_types, is_argument_type_valid, NamedTypedValue},
-        Argument, ContextField, EdgeParameters, Eid, FieldRef, FieldValue, FoldSpecificField,
-        FoldSpecificFieldKind, IREdge, IRFold, IRQuery, IRQueryComponent, IRVertex, LocalField,
-        Operation, Recursive, TransformationKind, VariableRef, Vid, TYPENAME_META_FIELD,
-        TYPENAME_META_FIELD_ARC, TYPENAME_META_FIELD_NAME, TYPENAME_META_FIELD_TYPE,
+        types::{intersect_types, is_argument_type_valid, is_base_type_orderable, NamedTypedValue},
+        Argument, ContextField, EdgeParameters, Eid, FieldRef, FieldValue, FoldSort,
+        FoldSpecificField, FoldSpecificFieldKind, IREdge, IRFold, IRQuery, IRQueryComponent,
+        IRVertex, LocalField, Operation, Recursive, TransformationKind, VariableRef, Vid,
+        TYPENAME_META_FIELD, TYPENAME_META_FIELD_ARC, TYPENAME_META_FIELD_NAME,
+        TYPENAME_META_FIELD_TYPE,
     },
     schema::{FieldOrigin, Schema, BUILTIN_SCALARS},
     util::{BTreeMapTryInsertExt, TryCollectUniqueKey},
@@ -36,6 +37,7 @@ use self::{
     validation::validate_query_against_schema,
 };
 
+pub mod builder;
 pub mod error;
 mod outputs;
 mod tags;
@@ -46,7 +48,9 @@ mod validation;
 /// [Schema](crate::schema::Schema). May fail if [parse_to_ir](parse_to_ir)
 /// fails for the provided schema and query.
 pub fn parse(schema: &Schema, query: impl AsRef<str>) -> Result<Arc<IndexedQuery>, FrontendError> {
-    let ir_query = parse_to_ir(schema, query)?;
+    let document = async_graphql_parser::parse_query(query)?;
+    let q = parse_document(&document)?;
+    let (ir_query, output_order) = make_ir_and_output_order_for_query(schema, &q)?;
 
     // .unwrap() must be safe here, since freshly-generated IRQuery objects must always
     // be safe to convert to IndexedQuery. This is a try_into() instead of into() because
@@ -54,7 +58,7 @@ pub fn parse(schema: &Schema, query: impl AsRef<str>) -> Result<Arc<IndexedQuery
     // before being converted into IndexedQuery.
     let indexed_query: IndexedQuery = ir_query.try_into().unwrap();
 
-    Ok(Arc::from(indexed_query))
+    Ok(Arc::from(indexed_query.with_output_order(output_order)))
 }
 
 /// Parses a query string to IR using a [Schema](crate::schema::Schema)
@@ -404,7 +408,17 @@ fn make_filter_expr<LeftT: NamedTypedValue>(
     }
 }
 
-pub(crate) fn make_ir_for_query(schema: &Schema, query: &Query) -> Result<IRQuery, FrontendError> {
+#[doc(hidden)]
+pub fn make_ir_for_query(schema: &Schema, query: &Query) -> Result<IRQuery, FrontendError> {
+    make_ir_and_output_order_for_query(schema, query).map(|(ir_query, _)| ir_query)
+}
+
+/// Make the IR for the query, together with the names of its outputs in the order
+/// in which the query declares them.
+fn make_ir_and_output_order_for_query(
+    schema: &Schema,
+    query: &Query,
+) -> Result<(IRQuery, Vec<Arc<str>>), FrontendError> {
     validate_query_against_schema(schema, query)?;
 
     let mut vid_maker = successors(Some(Vid::new(NonZeroUsize::new(1).unwrap())), |x| {
@@ -464,6 +478,20 @@ pub(crate) fn make_ir_for_query(schema: &Schema, query: &Query) -> Result<IRQuer
     if let Err(v) = fill_in_query_variables(&mut variables, &root_component) {
         errors.extend(v.into_iter().map(|x| x.into()));
     }
+    for (variable_name, default_value) in &query.variable_defaults {
+        match variables.get(variable_name) {
+            None => errors.push(FrontendError::UnusedVariableDefault(
+                variable_name.to_string(),
+            )),
+            Some(variable_type) if !is_argument_type_valid(variable_type, default_value) => errors
+                .push(FrontendError::InvalidVariableDefault(
+                    variable_name.to_string(),
+                    variable_type.to_string(),
+                    default_value.clone(),
+                )),
+            Some(_) => {}
+        }
+    }
 
     if let Err(e) = tags.finish() {
         errors.push(FrontendError::UnusedTags(
@@ -471,7 +499,11 @@ pub(crate) fn make_ir_for_query(schema: &Schema, query: &Query) -> Result<IRQuer
         ));
     }
 
+    let output_order = output_handler.output_order().to_vec();
     let all_outputs = output_handler.finish();
+    if let Err(e) = check_recursion_depth_output_names(&root_component, &all_outputs) {
+        errors.push(e);
+    }
     if let Err(duplicates) = check_for_duplicate_output_names(all_outputs) {
         let all_vertices = collect_ir_vertices(&root_component);
         let errs = make_duplicated_output_names_error(&all_vertices, duplicates);
@@ -479,12 +511,14 @@ pub(crate) fn make_ir_for_query(schema: &Schema, query: &Query) -> Result<IRQuer
     }
 
     if errors.is_empty() {
-        Ok(IRQuery {
+        let ir_query = IRQuery {
             root_name: root_field_name.as_ref().to_owned().into(),
             root_parameters: root_parameters.unwrap(),
             root_component: root_component.into(),
             variables,
-        })
+            variable_defaults: query.variable_defaults.clone(),
+        };
+        Ok((ir_query, output_order))
     } else {
         Err(errors.into())
     }
@@ -562,6 +596,30 @@ fn fill_in_query_variables(
     }
 }
 
+fn describe_output_field(
+    ir_vertices: &BTreeMap<Vid, IRVertex>,
+    field: &FieldRef,
+) -> (String, String) {
+    match field {
+        FieldRef::ContextField(field) => {
+            let vid = field.vertex_id;
+            (
+                ir_vertices[&vid].type_name.to_string(),
+                field.field_name.to_string(),
+            )
+        }
+        FieldRef::FoldSpecificField(field) => {
+            let vid = field.fold_root_vid;
+            match field.kind {
+                FoldSpecificFieldKind::Count => (
+                    ir_vertices[&vid].type_name.to_string(),
+                    "fold count value".to_string(),
+                ),
+            }
+        }
+    }
+}
+
 fn make_duplicated_output_names_error(
     ir_vertices: &BTreeMap<Vid, IRVertex>,
     duplicates: BTreeMap<Arc<str>, Vec<FieldRef>>,
@@ -572,24 +630,7 @@ fn make_duplicated_output_names_error(
             .map(|(k, fields)| {
                 let duplicate_values = fields
                     .iter()
-                    .map(|field| match field {
-                        FieldRef::ContextField(field) => {
-                            let vid = field.vertex_id;
-                            (
-                                ir_vertices[&vid].type_name.to_string(),
-                                field.field_name.to_string(),
-                            )
-                        }
-                        FieldRef::FoldSpecificField(field) => {
-                            let vid = field.fold_root_vid;
-                            match field.kind {
-                                FoldSpecificFieldKind::Count => (
-                                    ir_vertices[&vid].type_name.to_string(),
-                                    "fold count value".to_string(),
-                                ),
-                            }
-                        }
-                    })
+                    .map(|field| describe_output_field(ir_vertices, field))
                     .collect();
                 (k.to_string(), duplicate_values)
             })
@@ -598,6 +639,52 @@ fn make_duplicated_output_names_error(
     vec![FrontendError::MultipleOutputsWithSameName(conflict_info)]
 }
 
+/// Recursion depth outputs aren't tracked by the output handler, since they don't correspond
+/// to any field in the query. Check that their names don't collide with any other outputs.
+fn check_recursion_depth_output_names(
+    root_component: &IRQueryComponent,
+    all_outputs: &BTreeMap<Arc<str>, Vec<FieldRef>>,
+) -> Result<(), FrontendError> {
+    let mut outputs: BTreeMap<String, Vec<(String, String)>> = Default::default();
+    for edge in root_component.edges.values() {
+        if let Some(name) = edge
+            .recursive
+            .as_ref()
+            .and_then(|recursive| recursive.depth_output.as_ref())
+        {
+            outputs.entry(name.to_string()).or_default().push((
+                root_component.vertices[&edge.to_vid].type_name.to_string(),
+                "recursion depth value".to_string(),
+            ));
+        }
+    }
+    if outputs.is_empty() {
+        return Ok(());
+    }
+
+    let all_vertices = collect_ir_vertices(root_component);
+    for (name, conflicts) in outputs.iter_mut() {
+        if let Some(fields) = all_outputs.get(name.as_str()) {
+            conflicts.extend(
+                fields
+                    .iter()
+                    .map(|field| describe_output_field(&all_vertices, field)),
+            );
+        }
+    }
+    outputs.retain(|_, conflicts| conflicts.len() > 1);
+
+    if outputs.is_empty() {
+        Ok(())
+    } else {
+        Err(FrontendError::MultipleOutputsWithSameName(
+            DuplicatedNamesConflict {
+                duplicates: outputs,
+            },
+        ))
+    }
+}
+
 #[allow(clippy::type_complexity)]
 fn check_for_duplicate_output_names(
     maybe_duplicated_outputs: BTreeMap<Arc<str>, Vec<FieldRef>>,
@@ -722,7 +809,17 @@ where
                     edge_definition,
                     d,
                 ) {
-                    Ok(coerce_to) => Some(Recursive::new(d.depth, coerce_to)),
+                    Ok(coerce_to) => {
+                        if parent_vid.is_some() && d.depth_output.is_some() {
+                            errors.push(FrontendError::RecursionDepthOutputInFold(
+                                field_connection.name.to_string(),
+                            ));
+                        }
+                        Some(Recursive {
+                            depth_output: d.depth_output.clone(),
+                            ..Recursive::new(d.depth, coerce_to)
+                        })
+                    }
                     Err(e) => {
                         errors.push(e);
                         None
@@ -1004,11 +1101,13 @@ fn make_vertex<'schema, 'query>(
     }
 
     if errors.is_empty() {
+        let key_fields = schema.key_fields(&type_name);
         Ok(IRVertex {
             vid,
             type_name,
             coerced_from_type,
             filters,
+            key_fields,
         })
     } else {
         Err(errors)
@@ -1287,7 +1386,7 @@ where
         Some(parent_vid),
         starting_vid,
         starting_pre_coercion_type,
-        starting_post_coercion_type,
+        starting_post_coercion_type.clone(),
         starting_field,
     )?;
     component_path.pop(starting_vid);
@@ -1387,6 +1486,35 @@ where
         }
     }
 
+    let fold_directive = &fold_group.fold;
+    let sort = fold_directive.sort_by.as_ref().and_then(|sort_by| {
+        let field_definition = schema
+            .fields
+            .get(&(starting_post_coercion_type.clone(), sort_by.clone()));
+        match field_definition {
+            None => {
+                errors.push(FrontendError::FoldSortByNonExistentProperty(
+                    starting_field.name.to_string(),
+                    starting_post_coercion_type.to_string(),
+                    sort_by.to_string(),
+                ));
+                None
+            }
+            Some(field_definition) if !is_base_type_orderable(&field_definition.ty.node.base) => {
+                errors.push(FrontendError::FoldSortByNonOrderableProperty(
+                    starting_field.name.to_string(),
+                    sort_by.to_string(),
+                    field_definition.ty.node.to_string(),
+                ));
+                None
+            }
+            Some(_) => Some(FoldSort {
+                field_name: sort_by.clone(),
+                descending: fold_directive.descending,
+            }),
+        }
+    });
+
     if !errors.is_empty() {
         return Err(errors);
     }
@@ -1401,6 +1529,8 @@ where
         imported_tags,
         post_filters,
         fold_specific_outputs,
+        sort,
+        limit: fold_directive.limit,
     })
 }
 
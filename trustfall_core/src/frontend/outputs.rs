@@ -12,6 +12,7 @@ pub(super) struct OutputHandler<'query> {
     root_prefix: Option<&'query str>,
     component_outputs_stack: Vec<BTreeMap<Arc<str>, Vec<FieldRef>>>,
     global_outputs: BTreeMap<Arc<str>, Vec<FieldRef>>,
+    output_order: Vec<Arc<str>>,
 }
 
 impl<'query> OutputHandler<'query> {
@@ -23,6 +24,7 @@ impl<'query> OutputHandler<'query> {
             root_prefix,
             component_outputs_stack: Default::default(),
             global_outputs: Default::default(),
+            output_order: Default::default(),
         }
     }
 
@@ -68,7 +70,11 @@ impl<'query> OutputHandler<'query> {
             .or_default()
             .push(value.clone());
 
-        self.global_outputs.entry(name).or_default().push(value);
+        let outputs = self.global_outputs.entry(name.clone()).or_default();
+        if outputs.is_empty() {
+            self.output_order.push(name);
+        }
+        outputs.push(value);
     }
 
     pub(super) fn register_locally_named_output(
@@ -90,6 +96,12 @@ impl<'query> OutputHandler<'query> {
         self.register_output(explicit_name, value)
     }
 
+    /// The names of all outputs registered so far, in the order in which they were
+    /// first registered.
+    pub(super) fn output_order(&self) -> &[Arc<str>] {
+        &self.output_order
+    }
+
     pub(crate) fn finish(self) -> BTreeMap<Arc<str>, Vec<FieldRef>> {
         assert!(self.vid_stack.is_empty());
         assert!(self.component_outputs_stack.is_empty());
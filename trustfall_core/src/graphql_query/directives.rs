@@ -424,23 +424,129 @@ impl TryFrom<&Positioned<Directive>> for OptionalDirective {
 }
 
 /// A Trustfall `@fold` directive.
+///
+/// For example, the following Trustfall and Rust would be equivalent:
+/// ```graphql
+/// @fold(sort_by: "score", descending: true, limit: 3)
+/// ```
+///
+/// and
+///
+/// ```ignore
+/// FoldDirective {
+///     sort_by: Some(Arc::from("score")),
+///     descending: true,
+///     limit: NonZeroUsize::new(3usize),
+/// }
+/// ```
+///
+/// All arguments are optional. `sort_by` names a property of the folded vertices
+/// by which to sort the fold's elements, in ascending order unless `descending` is set.
+/// `limit` keeps only that many of the fold's elements, after sorting.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub(crate) struct FoldDirective {}
+pub(crate) struct FoldDirective {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<Arc<str>>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub descending: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<NonZeroUsize>,
+}
 
 impl TryFrom<&Positioned<Directive>> for FoldDirective {
     type Error = ParseError;
 
     fn try_from(value: &Positioned<Directive>) -> Result<Self, Self::Error> {
-        if let Some((first_arg_name, _)) = value.node.arguments.get(0) {
-            // Found arguments but this directive doesn't take any.
-            return Err(ParseError::UnrecognizedDirectiveArgument(
-                "@fold".into(),
-                first_arg_name.node.to_string(),
-                first_arg_name.pos,
+        let mut seen_sort_by: bool = false;
+        let mut seen_descending: bool = false;
+        let mut seen_limit: bool = false;
+        for (arg_name, _) in &value.node.arguments {
+            let seen = match arg_name.node.as_ref() {
+                "sort_by" => &mut seen_sort_by,
+                "descending" => &mut seen_descending,
+                "limit" => &mut seen_limit,
+                _ => {
+                    return Err(ParseError::UnrecognizedDirectiveArgument(
+                        "@fold".to_owned(),
+                        arg_name.node.to_string(),
+                        arg_name.pos,
+                    ));
+                }
+            };
+            if *seen {
+                return Err(ParseError::DuplicatedDirectiveArgument(
+                    "@fold".to_owned(),
+                    arg_name.node.to_string(),
+                    arg_name.pos,
+                ));
+            }
+            *seen = true;
+        }
+
+        let sort_by = match value.node.get_argument("sort_by") {
+            None => None,
+            Some(argument) => match &argument.node {
+                Value::String(s) => Some(Arc::from(s.as_str())),
+                _ => {
+                    return Err(ParseError::InappropriateTypeForDirectiveArgument(
+                        "@fold".to_owned(),
+                        "sort_by".to_owned(),
+                        argument.pos,
+                    ))
+                }
+            },
+        };
+
+        let descending = match value.node.get_argument("descending") {
+            None => false,
+            Some(argument) => match &argument.node {
+                Value::Boolean(b) => *b,
+                _ => {
+                    return Err(ParseError::InappropriateTypeForDirectiveArgument(
+                        "@fold".to_owned(),
+                        "descending".to_owned(),
+                        argument.pos,
+                    ))
+                }
+            },
+        };
+        if seen_descending && sort_by.is_none() {
+            // The sort direction is meaningless without a sort key.
+            return Err(ParseError::MissingRequiredDirectiveArgument(
+                "@fold".to_owned(),
+                "sort_by".to_owned(),
+                value.pos,
             ));
         }
 
-        Ok(Self {})
+        let limit = match value.node.get_argument("limit") {
+            None => None,
+            Some(argument) => Some(match &argument.node {
+                Value::Number(n) => n
+                    .as_u64()
+                    .and_then(|v| NonZeroUsize::new(v as usize))
+                    .ok_or_else(|| {
+                        ParseError::InappropriateTypeForDirectiveArgument(
+                            "@fold".to_owned(),
+                            "limit".to_owned(),
+                            argument.pos,
+                        )
+                    }),
+                _ => Err(ParseError::InappropriateTypeForDirectiveArgument(
+                    "@fold".to_owned(),
+                    "limit".to_owned(),
+                    argument.pos,
+                )),
+            }?),
+        };
+
+        Ok(Self {
+            sort_by,
+            descending,
+            limit,
+        })
     }
 }
 
@@ -454,36 +560,48 @@ impl TryFrom<&Positioned<Directive>> for FoldDirective {
 /// and
 ///
 /// ```ignore
-/// RecurseDirective { depth: NonZeroUsize::new(1usize)}
+/// RecurseDirective { depth: NonZeroUsize::new(1usize), depth_output: None }
+/// ```
+///
+/// The optional `depth_output` argument names an output that reports
+/// the recursion depth at which each vertex was reached:
+/// ```graphql
+/// @recurse(depth: 3, depth_output: "reply_depth")
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub(crate) struct RecurseDirective {
     pub depth: NonZeroUsize,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_output: Option<Arc<str>>,
 }
 
 impl TryFrom<&Positioned<Directive>> for RecurseDirective {
     type Error = ParseError;
 
     fn try_from(value: &Positioned<Directive>) -> Result<Self, Self::Error> {
-        let mut seen_name: bool = false;
+        let mut seen_depth: bool = false;
+        let mut seen_depth_output: bool = false;
         for (arg_name, _) in &value.node.arguments {
-            if arg_name.node.as_ref() == "depth" {
-                if !seen_name {
-                    seen_name = true;
-                } else {
-                    return Err(ParseError::DuplicatedDirectiveArgument(
+            let seen = match arg_name.node.as_ref() {
+                "depth" => &mut seen_depth,
+                "depth_output" => &mut seen_depth_output,
+                _ => {
+                    return Err(ParseError::UnrecognizedDirectiveArgument(
                         "@recurse".to_owned(),
                         arg_name.node.to_string(),
                         arg_name.pos,
                     ));
                 }
-            } else {
-                return Err(ParseError::UnrecognizedDirectiveArgument(
+            };
+            if *seen {
+                return Err(ParseError::DuplicatedDirectiveArgument(
                     "@recurse".to_owned(),
                     arg_name.node.to_string(),
                     arg_name.pos,
                 ));
             }
+            *seen = true;
         }
 
         let depth_argument = value.node.get_argument("depth").ok_or_else(|| {
@@ -511,7 +629,29 @@ impl TryFrom<&Positioned<Directive>> for RecurseDirective {
             )),
         }?;
 
-        Ok(Self { depth })
+        let depth_output = match value.node.get_argument("depth_output") {
+            None => None,
+            Some(argument) => match &argument.node {
+                Value::String(s) => {
+                    ensure_name_is_valid(s).map_err(|invalid_chars| {
+                        ParseError::InvalidOutputName(s.to_string(), invalid_chars, argument.pos)
+                    })?;
+                    Some(Arc::from(s.as_str()))
+                }
+                _ => {
+                    return Err(ParseError::InappropriateTypeForDirectiveArgument(
+                        "@recurse".to_owned(),
+                        "depth_output".to_owned(),
+                        argument.pos,
+                    ))
+                }
+            },
+        };
+
+        Ok(Self {
+            depth,
+            depth_output,
+        })
     }
 }
 
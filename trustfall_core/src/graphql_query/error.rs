@@ -82,6 +82,18 @@ pub enum ParseError {
     #[error("Edge {1} specifies a duplicated parameter {0}")]
     DuplicatedEdgeParameter(String, String, Pos),
 
+    #[error(
+        "Query variable \"{0}\" is declared without a default value. Variables are defined \
+        implicitly by their use, so they only need to be declared to give them a default value."
+    )]
+    VariableDeclaredWithoutDefault(String, Pos),
+
+    #[error("Query variable \"{0}\" is declared more than once")]
+    DuplicatedVariableDeclaration(String, Pos),
+
+    #[error("Query variable \"{0}\" has an invalid default value: {1}")]
+    InvalidVariableDefault(String, Value, Pos),
+
     #[error("Unexpected error: {0}")]
     OtherError(String, Pos),
 }
@@ -2,4 +2,5 @@
 //! which are then handed to the frontend for further processing.
 pub(crate) mod directives;
 pub mod error;
-pub(crate) mod query;
+#[doc(hidden)]
+pub mod query;
@@ -73,10 +73,14 @@ pub(crate) struct FieldNode {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub(crate) struct Query {
-    pub root_connection: FieldConnection,
+pub struct Query {
+    pub(crate) root_connection: FieldConnection,
 
-    pub root_field: FieldNode,
+    pub(crate) root_field: FieldNode,
+
+    /// Default values for query variables, used when the variable's value isn't supplied.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) variable_defaults: BTreeMap<Arc<str>, FieldValue>,
 }
 
 #[derive(Debug, Clone)]
@@ -138,14 +142,14 @@ fn try_get_query_root(document: &ExecutableDocument) -> Result<&Positioned<Field
                 return Err(ParseError::DocumentNotAQuery(op.pos));
             }
 
-            if !root_node.variable_definitions.is_empty() {
-                let first_variable_definition = root_node.variable_definitions.first().unwrap();
-                return Err(ParseError::OtherError(
-                    "Found GraphQL query variable definitions. \
-                    These are not necessary since variables are defined implicitly, \
-                    and must be removed."
-                        .to_string(),
-                    first_variable_definition.pos,
+            if let Some(definition) = root_node
+                .variable_definitions
+                .iter()
+                .find(|definition| definition.node.default_value.is_none())
+            {
+                return Err(ParseError::VariableDeclaredWithoutDefault(
+                    definition.node.name.node.to_string(),
+                    definition.pos,
                 ));
             }
             if !root_node.directives.is_empty() {
@@ -511,9 +515,44 @@ fn make_transform_group(
     })
 }
 
+/// Collects the default values of the variables declared by the query's operation,
+/// as in `query ($min: Int = 3) { ... }`.
+fn make_variable_defaults(
+    document: &ExecutableDocument,
+) -> Result<BTreeMap<Arc<str>, FieldValue>, ParseError> {
+    let DocumentOperations::Single(op) = &document.operations else {
+        unreachable!("document was already checked to have a single operation")
+    };
+
+    let mut defaults = BTreeMap::new();
+    for definition in &op.node.variable_definitions {
+        let name = &definition.node.name.node;
+        let default = definition
+            .node
+            .default_value
+            .as_ref()
+            .expect("variable definitions were already checked to have default values");
+        let value = FieldValue::try_from(default.node.clone()).map_err(|_| {
+            ParseError::InvalidVariableDefault(
+                name.to_string(),
+                default.node.clone().into_value(),
+                default.pos,
+            )
+        })?;
+        defaults
+            .insert_or_error(Arc::from(name.as_str()), value)
+            .map_err(|_| {
+                ParseError::DuplicatedVariableDeclaration(name.to_string(), definition.pos)
+            })?;
+    }
+
+    Ok(defaults)
+}
+
 /// Parses a query document. May fail if a query root is missing (see [try_get_query_root](try_get_query_root))
-pub(crate) fn parse_document(document: &ExecutableDocument) -> Result<Query, ParseError> {
+pub fn parse_document(document: &ExecutableDocument) -> Result<Query, ParseError> {
     let query_root = try_get_query_root(document)?;
+    let variable_defaults = make_variable_defaults(document)?;
 
     if let Some(dir) = query_root.node.directives.first() {
         return Err(ParseError::DirectiveNotInsideQueryRoot(
@@ -532,6 +571,7 @@ pub(crate) fn parse_document(document: &ExecutableDocument) -> Result<Query, Par
     Ok(Query {
         root_connection,
         root_field,
+        variable_defaults,
     })
 }
 
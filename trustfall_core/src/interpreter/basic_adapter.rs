@@ -18,6 +18,15 @@ use super::{
 ///
 /// The easiest way to implement this trait is with the `Vertex` associated type set
 /// to an enum that is [`#[derive(Debug, Clone, TrustfallEnumVertex)]`].
+///
+/// Only [`resolve_starting_vertices`](BasicAdapter::resolve_starting_vertices) and
+/// [`resolve_property`](BasicAdapter::resolve_property) must be implemented.
+/// [`resolve_neighbors`](BasicAdapter::resolve_neighbors) and
+/// [`resolve_coercion`](BasicAdapter::resolve_coercion) have default implementations
+/// for schemas without edges (other than starting edges) or without subtyping,
+/// respectively: the default `resolve_neighbors` panics if called.
+/// [`resolve_typename`](BasicAdapter::resolve_typename) has a default
+/// based on the [`Typename`] trait.
 pub trait BasicAdapter<'vertex> {
     /// The type of vertices in the dataset this adapter queries.
     /// It's frequently a good idea to use an Rc<...> type for cheaper cloning here.
@@ -88,7 +97,9 @@ pub trait BasicAdapter<'vertex> {
     /// This method resolves the neighboring vertices for that active vertex.
     ///
     /// If the schema this adapter covers has no edges aside from starting edges,
-    /// then this method will never be called and may be implemented as `unreachable!()`.
+    /// then this method will never be called. In that case, the default implementation
+    /// may be used: it panics with a message naming the edge, since being called means
+    /// the adapter is missing an implementation for an edge in its schema.
     ///
     /// The caller guarantees that:
     /// - `type_name` is a type or interface defined in the schema.
@@ -105,11 +116,16 @@ pub trait BasicAdapter<'vertex> {
     /// - When a context's active vertex is None, it has an empty neighbors iterator.
     fn resolve_neighbors(
         &mut self,
-        contexts: ContextIterator<'vertex, Self::Vertex>,
+        _contexts: ContextIterator<'vertex, Self::Vertex>,
         type_name: &str,
         edge_name: &str,
-        parameters: &EdgeParameters,
-    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>>;
+        _parameters: &EdgeParameters,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        unimplemented!(
+            "resolve_neighbors() is not implemented by this adapter, but was called \
+            for edge \"{edge_name}\" on type \"{type_name}\""
+        )
+    }
 
     /// Attempt to coerce vertices to a subtype, over an iterator of query contexts.
     ///
@@ -133,7 +149,8 @@ pub trait BasicAdapter<'vertex> {
     /// This method checks whether the active vertex is of the specified subtype.
     ///
     /// If this adapter's schema contains no subtyping, then no type coercions are possible:
-    /// this method will never be called and may be implemented as `unreachable!()`.
+    /// this method will never be called. In that case, the default implementation may be used:
+    /// it reports that coercion failed (`false`) for every context.
     ///
     /// The caller guarantees that:
     /// - `type_name` is an interface defined in the schema.
@@ -150,9 +167,11 @@ pub trait BasicAdapter<'vertex> {
     fn resolve_coercion(
         &mut self,
         contexts: ContextIterator<'vertex, Self::Vertex>,
-        type_name: &str,
-        coerce_to_type: &str,
-    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool>;
+        _type_name: &str,
+        _coerce_to_type: &str,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        Box::new(contexts.map(|ctx| (ctx, false)))
+    }
 
     /// Resolve the `__typename` special property over an iterator of query contexts.
     ///
@@ -276,3 +295,119 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+    use crate::{
+        frontend::parse,
+        interpreter::{
+            execution::interpret_ir, helpers::resolve_property_with, ContextIterator,
+            ContextOutcomeIterator, DataContext, Typename, VertexIterator,
+        },
+        ir::{EdgeParameters, FieldValue},
+        schema::Schema,
+    };
+
+    use super::BasicAdapter;
+
+    #[derive(Debug, Clone)]
+    struct Item(i64);
+
+    impl Typename for Item {
+        fn typename(&self) -> &'static str {
+            "Item"
+        }
+    }
+
+    /// An adapter that only implements the required methods.
+    struct ItemsAdapter;
+
+    impl BasicAdapter<'static> for ItemsAdapter {
+        type Vertex = Item;
+
+        fn resolve_starting_vertices(
+            &mut self,
+            _edge_name: &str,
+            _parameters: &EdgeParameters,
+        ) -> VertexIterator<'static, Self::Vertex> {
+            Box::new((1..=3).map(Item))
+        }
+
+        fn resolve_property(
+            &mut self,
+            contexts: ContextIterator<'static, Self::Vertex>,
+            _type_name: &str,
+            _property_name: &str,
+        ) -> ContextOutcomeIterator<'static, Self::Vertex, FieldValue> {
+            resolve_property_with(contexts, |vertex| vertex.0.into())
+        }
+    }
+
+    #[test]
+    fn default_methods() {
+        let schema = Schema::parse(
+            "\
+schema {
+    query: RootSchemaQuery
+}
+directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
+directive @tag(name: String) on FIELD
+directive @output(name: String) on FIELD
+directive @optional on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
+directive @transform(op: String!) on FIELD
+
+type RootSchemaQuery {
+    Item: [Item!]!
+}
+
+type Item {
+    value: Int
+}",
+        )
+        .expect("failed to parse schema");
+        let query = parse(
+            &schema,
+            r#"
+{
+    Item {
+        value @output
+        __typename @output
+    }
+}"#,
+        )
+        .unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let results: Vec<_> = interpret_ir(Rc::new(RefCell::new(ItemsAdapter)), query, arguments)
+            .unwrap()
+            .map(|row| (row["value"].clone(), row["__typename"].clone()))
+            .collect();
+        let expected: Vec<_> = (1..=3)
+            .map(|value| (FieldValue::Int64(value), FieldValue::from("Item")))
+            .collect();
+        assert_eq!(expected, results);
+
+        let contexts =
+            || Box::new(vec![DataContext::new(Some(Item(1))), DataContext::new(None)].into_iter());
+        let coercions: Vec<_> = ItemsAdapter
+            .resolve_coercion(contexts(), "Item", "Subtype")
+            .map(|(_ctx, can_coerce)| can_coerce)
+            .collect();
+        assert_eq!(vec![false, false], coercions);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "resolve_neighbors() is not implemented by this adapter, but was called \
+        for edge \"edge\" on type \"Item\""
+    )]
+    fn default_resolve_neighbors_names_the_edge() {
+        let contexts = Box::new(vec![DataContext::new(Some(Item(1)))].into_iter());
+        let _ =
+            ItemsAdapter.resolve_neighbors(contexts, "Item", "edge", &EdgeParameters::default());
+    }
+}
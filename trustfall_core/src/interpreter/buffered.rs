@@ -0,0 +1,126 @@
+//! Re-iterable query results, buffered in memory as they are produced.
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+use crate::ir::FieldValue;
+
+type ResultRow = BTreeMap<Arc<str>, FieldValue>;
+
+struct ResultsBuffer<'query> {
+    source: Box<dyn Iterator<Item = ResultRow> + 'query>,
+    rows: Vec<ResultRow>,
+    exhausted: bool,
+}
+
+/// Query results that can be iterated multiple times while only being computed once.
+///
+/// Wraps a query's result iterator, such as the one returned by
+/// [`interpret_ir`](super::execution::interpret_ir), and buffers every row it produces.
+/// Cloning the iterator, or calling [`BufferedResults::restart`], produces another iterator
+/// that reads rows from the buffer, only pulling from the underlying query when
+/// it gets further than any previous iterator has. As a result, the adapter is never asked
+/// for the same data twice.
+///
+/// All rows produced so far are kept in memory until every clone has been dropped,
+/// so memory use grows with the number of results consumed.
+pub struct BufferedResults<'query> {
+    buffer: Rc<RefCell<ResultsBuffer<'query>>>,
+    next_index: usize,
+}
+
+impl<'query> BufferedResults<'query> {
+    pub fn new(results: Box<dyn Iterator<Item = ResultRow> + 'query>) -> Self {
+        Self {
+            buffer: Rc::new(RefCell::new(ResultsBuffer {
+                source: results,
+                rows: vec![],
+                exhausted: false,
+            })),
+            next_index: 0,
+        }
+    }
+
+    /// Another iterator over the same results, starting from the first row.
+    pub fn restart(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone(),
+            next_index: 0,
+        }
+    }
+
+    /// The number of rows that have been produced by the underlying query so far.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.borrow().rows.len()
+    }
+}
+
+/// Clones continue from the same position as the original iterator.
+impl<'query> Clone for BufferedResults<'query> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone(),
+            next_index: self.next_index,
+        }
+    }
+}
+
+impl<'query> Iterator for BufferedResults<'query> {
+    type Item = ResultRow;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = self.buffer.borrow_mut();
+        if self.next_index == buffer.rows.len() {
+            if buffer.exhausted {
+                return None;
+            }
+            match buffer.source.next() {
+                Some(row) => buffer.rows.push(row),
+                None => {
+                    buffer.exhausted = true;
+                    return None;
+                }
+            }
+        }
+
+        let row = buffer.rows[self.next_index].clone();
+        self.next_index += 1;
+        Some(row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+    use crate::ir::FieldValue;
+
+    use super::BufferedResults;
+
+    #[test]
+    fn source_is_consumed_only_once() {
+        let pulled = Rc::new(Cell::new(0usize));
+        let pulled_ref = pulled.clone();
+        let source = Box::new((0..4i64).map(move |value| {
+            pulled_ref.set(pulled_ref.get() + 1);
+            BTreeMap::from([(Arc::from("value"), FieldValue::Int64(value))])
+        }));
+
+        let mut results = BufferedResults::new(source);
+        let first_two: Vec<_> = results.by_ref().take(2).collect();
+        assert_eq!(2, pulled.get());
+
+        // A clone continues where the original left off.
+        let rest: Vec<_> = results.clone().collect();
+        assert_eq!(4, pulled.get());
+        assert_eq!(2, rest.len());
+
+        // Restarting replays everything from the buffer without touching the source.
+        let all: Vec<_> = results.restart().collect();
+        assert_eq!(4, pulled.get());
+        assert_eq!(4, results.buffered_len());
+        assert_eq!(first_two[..], all[..2]);
+        assert_eq!(rest[..], all[2..]);
+
+        assert_eq!(rest, results.collect::<Vec<_>>());
+        assert_eq!(4, pulled.get());
+    }
+}
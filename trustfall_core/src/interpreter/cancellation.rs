@@ -0,0 +1,228 @@
+//! Cancelling a running query from another thread, via a shared flag.
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::ir::{indexed::IndexedQuery, EdgeParameters, FieldValue};
+
+use super::{
+    error::QueryArgumentsError, execution::interpret_ir, Adapter, ContextIterator,
+    ContextOutcomeIterator, QueryInfo, VertexIterator,
+};
+
+/// Stops producing items once the cancellation flag is set, dropping the wrapped iterator.
+struct CancellableIter<I> {
+    inner: Option<I>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<I> CancellableIter<I> {
+    fn new(inner: I, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            inner: Some(inner),
+            cancelled,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for CancellableIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            self.inner = None;
+        }
+        self.inner.as_mut()?.next()
+    }
+}
+
+/// Passes all calls through to the wrapped adapter, but stops producing vertices
+/// once the query is cancelled.
+///
+/// Only vertex iterators are cut short: a query with no more vertices to process
+/// has no more work to do, whereas other adapter iterators must produce an item
+/// for every context they are given.
+struct CancellableAdapter<AdapterT> {
+    inner: Rc<RefCell<AdapterT>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for CancellableAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        let vertices = self
+            .inner
+            .borrow_mut()
+            .resolve_starting_vertices(edge_name, parameters, query_info);
+        Box::new(CancellableIter::new(vertices, self.cancelled.clone()))
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        self.inner
+            .borrow_mut()
+            .resolve_property(contexts, type_name, property_name, query_info)
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        let neighbors = self
+            .inner
+            .borrow_mut()
+            .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info);
+        let cancelled = self.cancelled.clone();
+        Box::new(neighbors.map(move |(ctx, neighbors)| {
+            let neighbors: VertexIterator<'vertex, Self::Vertex> =
+                Box::new(CancellableIter::new(neighbors, cancelled.clone()));
+            (ctx, neighbors)
+        }))
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        self.inner
+            .borrow_mut()
+            .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+    }
+}
+
+/// Like [`interpret_ir`], but stops producing results once `cancelled` is set.
+///
+/// The flag may be set from any thread. It is checked before producing each result row,
+/// and before each vertex is pulled from the iterators returned by the adapter's
+/// [`Adapter::resolve_starting_vertices`] and [`Adapter::resolve_neighbors`] methods.
+/// Once it is set, the result iterator returns `None`, and the query's in-flight iterators,
+/// including those returned by the adapter, are dropped.
+///
+/// Cancellation can't interrupt an adapter call that is already in progress: if the adapter
+/// blocks while producing a vertex or property value, the query stops only after that
+/// call returns. Clearing the flag afterward doesn't resume a cancelled query.
+#[allow(clippy::type_complexity)]
+pub fn interpret_ir_with_cancellation<'query, AdapterT>(
+    adapter: Rc<RefCell<AdapterT>>,
+    indexed_query: Arc<IndexedQuery>,
+    arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+    cancelled: Arc<AtomicBool>,
+) -> Result<Box<dyn Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query>, QueryArgumentsError>
+where
+    AdapterT: Adapter<'query> + 'query,
+{
+    let cancellable_adapter = Rc::new(RefCell::new(CancellableAdapter {
+        inner: adapter,
+        cancelled: cancelled.clone(),
+    }));
+
+    let results = interpret_ir(cancellable_adapter, indexed_query, arguments)?;
+    Ok(Box::new(CancellableIter::new(results, cancelled)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        num::NonZeroUsize,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use crate::{
+        interpreter::{Adapter, InterpretedQuery, QueryInfo},
+        ir::{EdgeParameters, FieldValue, Vid},
+        numbers_interpreter::{numbers_adapter, parse_numbers_query},
+    };
+
+    use super::{interpret_ir_with_cancellation, CancellableAdapter};
+
+    #[test]
+    fn cancellation_stops_results() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 10) {
+        value @output
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let adapter = numbers_adapter();
+        let mut results =
+            interpret_ir_with_cancellation(adapter, query, arguments, cancelled.clone()).unwrap();
+        assert!(results.next().is_some());
+        assert!(results.next().is_some());
+
+        let flag = cancelled.clone();
+        std::thread::spawn(move || flag.store(true, Ordering::Relaxed))
+            .join()
+            .unwrap();
+        assert_eq!(None, results.next());
+
+        // Clearing the flag doesn't resume the query.
+        cancelled.store(false, Ordering::Relaxed);
+        assert_eq!(None, results.next());
+    }
+
+    #[test]
+    fn cancellation_stops_adapter_vertices() {
+        let query = parse_numbers_query("{ Number(min: 1, max: 10) { value @output } }");
+        let query_info = QueryInfo::new(
+            InterpretedQuery::from_query_and_arguments(query, Default::default()).unwrap(),
+            Vid::new(NonZeroUsize::new(1).unwrap()),
+            None,
+        );
+
+        // Cancellation takes effect even while the result iterator is busy
+        // pulling vertices from the adapter, such as when filtering out many of them.
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut adapter = CancellableAdapter {
+            inner: numbers_adapter(),
+            cancelled: cancelled.clone(),
+        };
+        let mut vertices = adapter.resolve_starting_vertices(
+            &Arc::from("Number"),
+            &EdgeParameters::new(Arc::new(btreemap! {
+                "min".into() => FieldValue::Int64(1),
+                "max".into() => FieldValue::Int64(10),
+            })),
+            &query_info,
+        );
+        assert!(vertices.next().is_some());
+
+        cancelled.store(true, Ordering::Relaxed);
+        assert!(vertices.next().is_none());
+    }
+}
@@ -0,0 +1,483 @@
+//! An adapter that combines two adapters over the same vertex type.
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    ir::{EdgeParameters, FieldValue},
+    schema::Schema,
+};
+
+use super::{Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo, VertexIterator};
+
+/// One of the two adapters combined by a [`CompositeAdapter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubAdapter {
+    First,
+    Second,
+}
+
+/// A [`CompositeAdapter`] was asked to resolve something that neither of its adapters
+/// was registered to handle.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UnhandledByCompositeAdapter {
+    #[error("Starting edge \"{0}\" is not handled by either adapter of the composite adapter")]
+    StartingEdge(Arc<str>),
+
+    #[error(
+        "Property \"{property_name}\" on type \"{type_name}\" is not handled by either adapter \
+        of the composite adapter"
+    )]
+    Property {
+        type_name: Arc<str>,
+        property_name: Arc<str>,
+    },
+
+    #[error(
+        "Edge \"{edge_name}\" on type \"{type_name}\" is not handled by either adapter \
+        of the composite adapter"
+    )]
+    Edge {
+        type_name: Arc<str>,
+        edge_name: Arc<str>,
+    },
+
+    #[error(
+        "Coercion from type \"{type_name}\" to \"{coerce_to_type}\" is not handled by either \
+        adapter of the composite adapter"
+    )]
+    Coercion {
+        type_name: Arc<str>,
+        coerce_to_type: Arc<str>,
+    },
+}
+
+/// Combines two adapters with the same vertex type, dispatching each call
+/// to whichever of them is registered to handle it.
+///
+/// This allows a schema's data to come from several independently-written adapters,
+/// e.g. with one adapter handling most of the schema and another adding a few edges
+/// that lead to a different data source.
+///
+/// Registrations are by the exact type name that the query passes to the adapter:
+/// a type that implements an interface doesn't inherit the interface's registrations.
+/// Properties and edges are dispatched to the adapter registered for that specific
+/// field with [`handle_field`](Self::handle_field) if any, and otherwise to the adapter
+/// registered for the whole type with [`handle_type`](Self::handle_type).
+/// Coercions are dispatched the same way, using [`handle_coercion`](Self::handle_coercion)
+/// for specific coercions. Later registrations replace earlier ones for the same name.
+///
+/// Use [`check`](Self::check) to find everything in a schema that isn't handled
+/// before running any queries. Adapters have no way to report errors, so a call
+/// that no adapter is registered to handle still causes a panic whose message describes
+/// the [`UnhandledByCompositeAdapter`] error.
+#[derive(Debug)]
+pub struct CompositeAdapter<FirstT, SecondT> {
+    first: FirstT,
+    second: SecondT,
+    starting_edges: BTreeMap<Arc<str>, SubAdapter>,
+    types: BTreeMap<Arc<str>, SubAdapter>,
+    fields: BTreeMap<(Arc<str>, Arc<str>), SubAdapter>,
+    coercions: BTreeMap<(Arc<str>, Arc<str>), SubAdapter>,
+}
+
+impl<FirstT, SecondT> CompositeAdapter<FirstT, SecondT> {
+    /// Combine two adapters, neither of which handles anything until registered to do so.
+    pub fn new(first: FirstT, second: SecondT) -> Self {
+        Self {
+            first,
+            second,
+            starting_edges: Default::default(),
+            types: Default::default(),
+            fields: Default::default(),
+            coercions: Default::default(),
+        }
+    }
+
+    /// Resolve the named starting edge using the given adapter.
+    pub fn handle_starting_edge(mut self, edge_name: &str, adapter: SubAdapter) -> Self {
+        self.starting_edges.insert(Arc::from(edge_name), adapter);
+        self
+    }
+
+    /// Resolve all properties, edges, and coercions of the named type using the given adapter,
+    /// except those registered more specifically.
+    pub fn handle_type(mut self, type_name: &str, adapter: SubAdapter) -> Self {
+        self.types.insert(Arc::from(type_name), adapter);
+        self
+    }
+
+    /// Resolve the named property or edge of the named type using the given adapter.
+    pub fn handle_field(mut self, type_name: &str, field_name: &str, adapter: SubAdapter) -> Self {
+        self.fields
+            .insert((Arc::from(type_name), Arc::from(field_name)), adapter);
+        self
+    }
+
+    /// Resolve coercions from one named type to the other using the given adapter.
+    pub fn handle_coercion(
+        mut self,
+        type_name: &str,
+        coerce_to_type: &str,
+        adapter: SubAdapter,
+    ) -> Self {
+        self.coercions
+            .insert((Arc::from(type_name), Arc::from(coerce_to_type)), adapter);
+        self
+    }
+
+    /// Check that every starting edge, property, edge, and coercion in the schema
+    /// is handled by one of the two adapters, returning everything that isn't.
+    ///
+    /// Queries over a schema that passes this check never cause the composite adapter
+    /// to panic due to an unhandled call.
+    pub fn check(&self, schema: &Schema) -> Result<(), Vec<UnhandledByCompositeAdapter>> {
+        let mut errors = vec![];
+
+        for edge in schema.starting_edge_completions().edges {
+            if !self.starting_edges.contains_key(&edge.name) {
+                errors.push(UnhandledByCompositeAdapter::StartingEdge(edge.name));
+            }
+        }
+
+        let mut type_names: Vec<&str> = schema
+            .vertex_types
+            .keys()
+            .map(|name| name.as_ref())
+            .filter(|name| *name != schema.query_type_name())
+            .collect();
+        type_names.sort_unstable();
+        for type_name in type_names {
+            let completions = schema
+                .completions(type_name)
+                .expect("type name came from the schema");
+            let type_name = completions.type_name;
+
+            for property in completions.properties {
+                if self.field_handler(&type_name, &property.name).is_none() {
+                    errors.push(UnhandledByCompositeAdapter::Property {
+                        type_name: type_name.clone(),
+                        property_name: property.name,
+                    });
+                }
+            }
+            for edge in completions.edges {
+                if self.field_handler(&type_name, &edge.name).is_none() {
+                    errors.push(UnhandledByCompositeAdapter::Edge {
+                        type_name: type_name.clone(),
+                        edge_name: edge.name,
+                    });
+                }
+            }
+            for coerce_to_type in completions.coercions {
+                if self.coercion_handler(&type_name, &coerce_to_type).is_none() {
+                    errors.push(UnhandledByCompositeAdapter::Coercion {
+                        type_name: type_name.clone(),
+                        coerce_to_type,
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn into_inner(self) -> (FirstT, SecondT) {
+        (self.first, self.second)
+    }
+
+    fn field_handler(&self, type_name: &Arc<str>, field_name: &Arc<str>) -> Option<SubAdapter> {
+        self.fields
+            .get(&(type_name.clone(), field_name.clone()))
+            .or_else(|| self.types.get(type_name))
+            .copied()
+    }
+
+    fn coercion_handler(
+        &self,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+    ) -> Option<SubAdapter> {
+        self.coercions
+            .get(&(type_name.clone(), coerce_to_type.clone()))
+            .or_else(|| self.types.get(type_name))
+            .copied()
+    }
+}
+
+impl<'vertex, VertexT, FirstT, SecondT> Adapter<'vertex> for CompositeAdapter<FirstT, SecondT>
+where
+    VertexT: Clone + std::fmt::Debug + 'vertex,
+    FirstT: Adapter<'vertex, Vertex = VertexT>,
+    SecondT: Adapter<'vertex, Vertex = VertexT>,
+{
+    type Vertex = VertexT;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        match self.starting_edges.get(edge_name) {
+            Some(SubAdapter::First) => self
+                .first
+                .resolve_starting_vertices(edge_name, parameters, query_info),
+            Some(SubAdapter::Second) => self
+                .second
+                .resolve_starting_vertices(edge_name, parameters, query_info),
+            None => {
+                let err = UnhandledByCompositeAdapter::StartingEdge(edge_name.clone());
+                panic!("{err}");
+            }
+        }
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        match self.field_handler(type_name, property_name) {
+            Some(SubAdapter::First) => {
+                self.first
+                    .resolve_property(contexts, type_name, property_name, query_info)
+            }
+            Some(SubAdapter::Second) => {
+                self.second
+                    .resolve_property(contexts, type_name, property_name, query_info)
+            }
+            None => {
+                let err = UnhandledByCompositeAdapter::Property {
+                    type_name: type_name.clone(),
+                    property_name: property_name.clone(),
+                };
+                panic!("{err}");
+            }
+        }
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        match self.field_handler(type_name, edge_name) {
+            Some(SubAdapter::First) => self
+                .first
+                .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info),
+            Some(SubAdapter::Second) => self
+                .second
+                .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info),
+            None => {
+                let err = UnhandledByCompositeAdapter::Edge {
+                    type_name: type_name.clone(),
+                    edge_name: edge_name.clone(),
+                };
+                panic!("{err}");
+            }
+        }
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        match self.coercion_handler(type_name, coerce_to_type) {
+            Some(SubAdapter::First) => {
+                self.first
+                    .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+            }
+            Some(SubAdapter::Second) => {
+                self.second
+                    .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+            }
+            None => {
+                let err = UnhandledByCompositeAdapter::Coercion {
+                    type_name: type_name.clone(),
+                    coerce_to_type: coerce_to_type.clone(),
+                };
+                panic!("{err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+    use crate::{
+        interpreter::{
+            execution::interpret_ir, helpers::resolve_property_with, Adapter, ContextIterator,
+            ContextOutcomeIterator, QueryInfo, Typename, VertexIterator,
+        },
+        ir::{EdgeParameters, FieldValue},
+        numbers_interpreter::{numbers_schema, parse_numbers_query, NumbersAdapter, NumbersVertex},
+    };
+
+    use super::{CompositeAdapter, SubAdapter, UnhandledByCompositeAdapter};
+
+    /// Names numbers after their type, and handles nothing else.
+    struct TypeNamesAdapter;
+
+    impl Adapter<'static> for TypeNamesAdapter {
+        type Vertex = NumbersVertex;
+
+        fn resolve_starting_vertices(
+            &mut self,
+            _edge_name: &Arc<str>,
+            _parameters: &EdgeParameters,
+            _query_info: &QueryInfo,
+        ) -> VertexIterator<'static, Self::Vertex> {
+            unreachable!()
+        }
+
+        fn resolve_property(
+            &mut self,
+            contexts: ContextIterator<'static, Self::Vertex>,
+            _type_name: &Arc<str>,
+            property_name: &Arc<str>,
+            _query_info: &QueryInfo,
+        ) -> ContextOutcomeIterator<'static, Self::Vertex, FieldValue> {
+            assert_eq!("name", property_name.as_ref());
+            resolve_property_with(contexts, |vertex| vertex.typename().to_lowercase().into())
+        }
+
+        fn resolve_neighbors(
+            &mut self,
+            _contexts: ContextIterator<'static, Self::Vertex>,
+            _type_name: &Arc<str>,
+            _edge_name: &Arc<str>,
+            _parameters: &EdgeParameters,
+            _query_info: &QueryInfo,
+        ) -> ContextOutcomeIterator<'static, Self::Vertex, VertexIterator<'static, Self::Vertex>>
+        {
+            unreachable!()
+        }
+
+        fn resolve_coercion(
+            &mut self,
+            _contexts: ContextIterator<'static, Self::Vertex>,
+            _type_name: &Arc<str>,
+            _coerce_to_type: &Arc<str>,
+            _query_info: &QueryInfo,
+        ) -> ContextOutcomeIterator<'static, Self::Vertex, bool> {
+            unreachable!()
+        }
+    }
+
+    fn run_query(
+        adapter: CompositeAdapter<NumbersAdapter, TypeNamesAdapter>,
+    ) -> Vec<BTreeMap<Arc<str>, FieldValue>> {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 2, max: 4) {
+        value @output
+        name @output
+
+        successor {
+            successor_name: name @output
+        }
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        interpret_ir(Rc::new(RefCell::new(adapter)), query, arguments)
+            .unwrap()
+            .collect()
+    }
+
+    #[test]
+    fn calls_are_dispatched_to_registered_adapters() {
+        let adapter = CompositeAdapter::new(NumbersAdapter::new(), TypeNamesAdapter)
+            .handle_starting_edge("Number", SubAdapter::First)
+            .handle_type("Number", SubAdapter::First)
+            .handle_field("Number", "name", SubAdapter::Second);
+
+        let results = run_query(adapter);
+        let expected: Vec<BTreeMap<Arc<str>, FieldValue>> = vec![
+            btreemap! {
+                "value".into() => 2.into(),
+                "name".into() => "prime".into(),
+                "successor_name".into() => "prime".into(),
+            },
+            btreemap! {
+                "value".into() => 3.into(),
+                "name".into() => "prime".into(),
+                "successor_name".into() => "composite".into(),
+            },
+            btreemap! {
+                "value".into() => 4.into(),
+                "name".into() => "composite".into(),
+                "successor_name".into() => "prime".into(),
+            },
+        ];
+        assert_eq!(expected, results);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Edge \"successor\" on type \"Number\" is not handled by either adapter \
+        of the composite adapter"
+    )]
+    fn unhandled_calls_are_reported() {
+        let adapter = CompositeAdapter::new(NumbersAdapter::new(), TypeNamesAdapter)
+            .handle_starting_edge("Number", SubAdapter::First)
+            .handle_field("Number", "value", SubAdapter::First)
+            .handle_field("Number", "name", SubAdapter::Second);
+
+        run_query(adapter);
+    }
+
+    #[test]
+    fn check_reports_everything_unhandled() {
+        let mut adapter = CompositeAdapter::new(NumbersAdapter::new(), TypeNamesAdapter);
+        for edge in ["Number", "Zero", "One", "Two", "Four"] {
+            adapter = adapter.handle_starting_edge(edge, SubAdapter::First);
+        }
+        for type_name in ["Named", "Neither", "Prime", "Composite", "Letter"] {
+            adapter = adapter.handle_type(type_name, SubAdapter::First);
+        }
+        for field in ["value", "vowelsInName", "predecessor", "successor"] {
+            adapter = adapter.handle_field("Number", field, SubAdapter::First);
+        }
+        let adapter = adapter
+            .handle_field("Number", "name", SubAdapter::Second)
+            .handle_coercion("Number", "Prime", SubAdapter::First);
+
+        let expected = vec![
+            UnhandledByCompositeAdapter::StartingEdge("NumberImplicitNullDefault".into()),
+            UnhandledByCompositeAdapter::Edge {
+                type_name: "Number".into(),
+                edge_name: "multiple".into(),
+            },
+            UnhandledByCompositeAdapter::Coercion {
+                type_name: "Number".into(),
+                coerce_to_type: "Composite".into(),
+            },
+            UnhandledByCompositeAdapter::Coercion {
+                type_name: "Number".into(),
+                coerce_to_type: "Neither".into(),
+            },
+        ];
+        assert_eq!(Err(expected), adapter.check(&numbers_schema()));
+
+        let adapter = adapter
+            .handle_starting_edge("NumberImplicitNullDefault", SubAdapter::First)
+            .handle_type("Number", SubAdapter::First);
+        assert_eq!(Ok(()), adapter.check(&numbers_schema()));
+    }
+}
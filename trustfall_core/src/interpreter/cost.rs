@@ -0,0 +1,352 @@
+//! Coarse, heuristic estimates of how much work a query will take to execute.
+use async_graphql_parser::types::BaseType;
+
+use crate::{
+    ir::{
+        indexed::IndexedQuery, Argument, EdgeParameters, Eid, IREdge, IRFold, IRQueryComponent,
+        LocalField, Operation, Vid,
+    },
+    schema::Schema,
+};
+
+/// Number of vertices assumed to be produced by a starting edge,
+/// when the [`StatsProvider`] has no estimate for it.
+pub const DEFAULT_STARTING_VERTICES: f64 = 1000.0;
+
+/// Number of neighbors per vertex assumed for edges whose schema type is a list,
+/// when the [`StatsProvider`] has no estimate for them.
+/// Edges whose type isn't a list are assumed to have exactly one neighbor.
+pub const DEFAULT_LIST_EDGE_FANOUT: f64 = 10.0;
+
+/// Fraction of vertices assumed to pass an `=`, `one_of`, or `is_null` filter,
+/// when the [`StatsProvider`] has no estimate for it.
+pub const DEFAULT_EQUALITY_SELECTIVITY: f64 = 0.1;
+
+/// Fraction of vertices assumed to pass any other filter, a type coercion,
+/// or the post-fold filters of a `@fold`, when there's no better estimate.
+pub const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+/// Statistics about the data behind an adapter, used to refine cost estimates.
+///
+/// Every method returns `None` by default, in which case a default assumption is used.
+/// Providers only need to implement the methods for which they have useful data.
+pub trait StatsProvider {
+    /// The expected number of vertices produced by the named starting edge.
+    fn starting_vertices(&self, edge_name: &str, parameters: &EdgeParameters) -> Option<f64> {
+        let _ = (edge_name, parameters);
+        None
+    }
+
+    /// The expected number of neighbors, per vertex of the given type, along the named edge.
+    fn neighbors_per_vertex(
+        &self,
+        type_name: &str,
+        edge_name: &str,
+        parameters: &EdgeParameters,
+    ) -> Option<f64> {
+        let _ = (type_name, edge_name, parameters);
+        None
+    }
+
+    /// The expected fraction of vertices of the given type that pass the filter.
+    fn filter_selectivity(
+        &self,
+        type_name: &str,
+        filter: &Operation<LocalField, Argument>,
+    ) -> Option<f64> {
+        let _ = (type_name, filter);
+        None
+    }
+}
+
+/// A [`StatsProvider`] without any statistics, so that only default assumptions are used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoStats;
+
+impl StatsProvider for NoStats {}
+
+/// A coarse estimate of the work needed to execute a query.
+///
+/// Estimates are only meaningful relative to each other, e.g. to compare
+/// alternative ways of writing the same query, and shouldn't be taken as predictions.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CostEstimate {
+    /// The expected number of query results.
+    pub results: f64,
+
+    /// The expected number of vertices the adapter will need to produce,
+    /// including vertices that are later discarded by filters.
+    pub vertices: f64,
+}
+
+/// Estimate the cost of executing the query, using statistics from the provider
+/// where available and default assumptions otherwise.
+///
+/// The query is walked from its starting edge, keeping track of the expected number
+/// of partial results at each vertex:
+/// - filters and type coercions scale it by their selectivity;
+/// - required edges multiply it by the expected number of neighbors per vertex,
+///   and optional edges do the same but never decrease it;
+/// - `@recurse` edges multiply it by the expected number of vertices reached
+///   at all recursion depths, including the starting vertex at depth zero;
+/// - `@fold` edges don't multiply it, since they aggregate their neighbors
+///   into a single result, but any filters on the fold's count scale it
+///   by [`DEFAULT_SELECTIVITY`] each.
+///
+/// Every vertex produced along the way, including inside folds, counts toward
+/// [`CostEstimate::vertices`]. Filter operands aren't examined: filters with the same
+/// operation are assumed equally selective, unless the provider knows better.
+pub fn estimate_cost(
+    query: &IndexedQuery,
+    schema: &Schema,
+    stats_provider: &impl StatsProvider,
+) -> CostEstimate {
+    let ir_query = &query.ir_query;
+    let starting_vertices = stats_provider
+        .starting_vertices(&ir_query.root_name, &ir_query.root_parameters)
+        .unwrap_or_else(|| {
+            edge_fanout(schema, schema.query_type_name(), &ir_query.root_name)
+                .unwrap_or(DEFAULT_STARTING_VERTICES)
+        });
+
+    let mut estimator = Estimator {
+        schema,
+        stats_provider,
+        vertices: 0.0,
+    };
+    let component = &ir_query.root_component;
+    let results = estimator.visit(component, component.root, starting_vertices);
+
+    CostEstimate {
+        results,
+        vertices: estimator.vertices,
+    }
+}
+
+/// The number of neighbors implied by the edge's schema type: exactly one for non-list edges,
+/// or `None` for list edges since the schema doesn't say how many neighbors they have.
+fn edge_fanout(schema: &Schema, type_name: &str, edge_name: &str) -> Option<f64> {
+    let field = schema.fields.get(&(type_name.into(), edge_name.into()))?;
+    match field.ty.node.base {
+        BaseType::List(_) => None,
+        BaseType::Named(_) => Some(1.0),
+    }
+}
+
+enum Step<'a> {
+    Edge(&'a IREdge),
+    Fold(&'a IRFold),
+}
+
+impl Step<'_> {
+    fn eid(&self) -> Eid {
+        match self {
+            Step::Edge(edge) => edge.eid,
+            Step::Fold(fold) => fold.eid,
+        }
+    }
+}
+
+struct Estimator<'a, StatsT> {
+    schema: &'a Schema,
+    stats_provider: &'a StatsT,
+    vertices: f64,
+}
+
+impl<StatsT: StatsProvider> Estimator<'_, StatsT> {
+    fn fanout(&self, type_name: &str, edge_name: &str, parameters: &EdgeParameters) -> f64 {
+        self.stats_provider
+            .neighbors_per_vertex(type_name, edge_name, parameters)
+            .or_else(|| edge_fanout(self.schema, type_name, edge_name))
+            .unwrap_or(DEFAULT_LIST_EDGE_FANOUT)
+    }
+
+    /// Given the expected number of vertices produced at `vid`, returns the expected
+    /// number of partial results remaining once `vid` and everything reached from it
+    /// within the component has been processed.
+    fn visit(&mut self, component: &IRQueryComponent, vid: Vid, produced: f64) -> f64 {
+        self.vertices += produced;
+
+        let vertex = &component.vertices[&vid];
+        let mut rows = produced;
+        if vertex.coerced_from_type.is_some() {
+            rows *= DEFAULT_SELECTIVITY;
+        }
+        for filter in &vertex.filters {
+            let selectivity = self
+                .stats_provider
+                .filter_selectivity(&vertex.type_name, filter)
+                .unwrap_or(match filter {
+                    Operation::Equals(..) | Operation::OneOf(..) | Operation::IsNull(..) => {
+                        DEFAULT_EQUALITY_SELECTIVITY
+                    }
+                    _ => DEFAULT_SELECTIVITY,
+                });
+            rows *= selectivity;
+        }
+
+        // Edges and folds are processed in the same order as in the interpreter.
+        let edges = component
+            .edges
+            .values()
+            .filter(|edge| edge.from_vid == vid)
+            .map(|edge| Step::Edge(edge));
+        let folds = component
+            .folds
+            .values()
+            .filter(|fold| fold.from_vid == vid)
+            .map(|fold| Step::Fold(fold));
+        let mut steps: Vec<_> = edges.chain(folds).collect();
+        steps.sort_unstable_by_key(|step| step.eid());
+
+        for step in steps {
+            match step {
+                Step::Edge(edge) => {
+                    let fanout = self.fanout(&vertex.type_name, &edge.edge_name, &edge.parameters);
+                    let multiplier = match &edge.recursive {
+                        Some(recursive) => (0..=recursive.depth.get())
+                            .map(|depth| fanout.powi(depth as i32))
+                            .sum(),
+                        None => fanout,
+                    };
+
+                    let remaining = self.visit(component, edge.to_vid, rows * multiplier);
+                    rows = if edge.optional {
+                        remaining.max(rows)
+                    } else {
+                        remaining
+                    };
+                }
+                Step::Fold(fold) => {
+                    let fanout = self.fanout(&vertex.type_name, &fold.edge_name, &fold.parameters);
+                    let fold_component = &fold.component;
+                    self.visit(fold_component, fold_component.root, rows * fanout);
+                    rows *= DEFAULT_SELECTIVITY.powi(fold.post_filters.len() as i32);
+                }
+            }
+        }
+
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        frontend::parse,
+        ir::{Argument, EdgeParameters, LocalField, Operation},
+        numbers_interpreter::numbers_schema,
+    };
+
+    use super::{
+        estimate_cost, CostEstimate, NoStats, StatsProvider, DEFAULT_EQUALITY_SELECTIVITY,
+        DEFAULT_LIST_EDGE_FANOUT, DEFAULT_SELECTIVITY,
+    };
+
+    struct FixedStats;
+
+    impl StatsProvider for FixedStats {
+        fn starting_vertices(&self, edge_name: &str, _parameters: &EdgeParameters) -> Option<f64> {
+            (edge_name == "Number").then_some(100.0)
+        }
+
+        fn neighbors_per_vertex(
+            &self,
+            _type_name: &str,
+            edge_name: &str,
+            _parameters: &EdgeParameters,
+        ) -> Option<f64> {
+            (edge_name == "multiple").then_some(4.0)
+        }
+
+        fn filter_selectivity(
+            &self,
+            _type_name: &str,
+            filter: &Operation<LocalField, Argument>,
+        ) -> Option<f64> {
+            (filter.left().field_name.as_ref() == "name").then_some(0.25)
+        }
+    }
+
+    #[test]
+    fn estimate_with_and_without_stats() {
+        let schema = numbers_schema();
+        let query = parse(
+            &schema,
+            r#"
+{
+    Number(max: 100) {
+        value @filter(op: "=", value: ["$value"])
+        name @filter(op: "is_not_null")
+
+        successor {
+            value @output
+        }
+        multiple(max: 10) {
+            multiples: value @output
+        }
+        predecessor @optional {
+            ... on Prime {
+                prime: value @output
+            }
+        }
+        multiple(max: 10) @fold @transform(op: "count") @filter(op: ">", value: ["$min"]) {
+            __typename
+        }
+    }
+}"#,
+        )
+        .unwrap();
+
+        let start = 1000.0;
+        let filtered = start * DEFAULT_EQUALITY_SELECTIVITY * DEFAULT_SELECTIVITY;
+        let multiples = filtered * DEFAULT_LIST_EDGE_FANOUT;
+        let expected = CostEstimate {
+            results: multiples * DEFAULT_SELECTIVITY,
+            vertices: start
+                + filtered
+                + multiples
+                + multiples
+                + multiples * DEFAULT_LIST_EDGE_FANOUT,
+        };
+        assert_eq!(expected, estimate_cost(&query, &schema, &NoStats));
+
+        let start = 100.0;
+        let filtered = start * DEFAULT_EQUALITY_SELECTIVITY * 0.25;
+        let multiples = filtered * 4.0;
+        let expected = CostEstimate {
+            results: multiples * DEFAULT_SELECTIVITY,
+            vertices: start + filtered + multiples + multiples + multiples * 4.0,
+        };
+        assert_eq!(expected, estimate_cost(&query, &schema, &FixedStats));
+    }
+
+    #[test]
+    fn recursion_and_singular_starting_edges() {
+        let schema = numbers_schema();
+        let query = parse(
+            &schema,
+            r#"
+{
+    Two {
+        successor @recurse(depth: 2) {
+            value @output
+        }
+    }
+}"#,
+        )
+        .unwrap();
+
+        // `Two` and `successor` both have exactly one vertex per the schema,
+        // and recursing to depth 2 reaches the starting vertex plus one vertex per depth.
+        assert_eq!(
+            CostEstimate {
+                results: 3.0,
+                vertices: 4.0,
+            },
+            estimate_cost(&query, &schema, &NoStats)
+        );
+    }
+}
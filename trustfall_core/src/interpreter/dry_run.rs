@@ -0,0 +1,166 @@
+//! Dry runs of queries, reporting the adapter calls a query would make without resolving data.
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+use crate::ir::{indexed::IndexedQuery, EdgeParameters, FieldValue};
+
+use super::{
+    error::QueryArgumentsError,
+    execution::{interpret_ir, plan_fold_calls},
+    trace::FunctionCall,
+    Adapter, ContextIterator, ContextOutcomeIterator, InterpretedQuery, QueryInfo, VertexIterator,
+};
+
+/// Stands in for a real adapter: records each call made to it, and resolves no data.
+#[derive(Debug, Default)]
+struct PlanningAdapter {
+    calls: Vec<FunctionCall>,
+}
+
+impl<'vertex> Adapter<'vertex> for PlanningAdapter {
+    type Vertex = ();
+
+    fn resolve_starting_vertices(
+        &mut self,
+        _edge_name: &Arc<str>,
+        _parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        self.calls.push(FunctionCall::ResolveStartingVertices(
+            query_info.origin_vid(),
+        ));
+        Box::new(std::iter::empty())
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        self.calls.push(FunctionCall::ResolveProperty(
+            query_info.origin_vid(),
+            type_name.clone(),
+            property_name.clone(),
+        ));
+        Box::new(contexts.map(|ctx| (ctx, FieldValue::Null)))
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        _edge_name: &Arc<str>,
+        _parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        self.calls.push(FunctionCall::ResolveNeighbors(
+            query_info.origin_vid(),
+            type_name.clone(),
+            query_info
+                .origin_crossing_eid()
+                .expect("no edge being crossed while resolving neighbors"),
+        ));
+        Box::new(contexts.map(|ctx| {
+            let neighbors: VertexIterator<'vertex, Self::Vertex> = Box::new(std::iter::empty());
+            (ctx, neighbors)
+        }))
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        self.calls.push(FunctionCall::ResolveCoercion(
+            query_info.origin_vid(),
+            type_name.clone(),
+            coerce_to_type.clone(),
+        ));
+        Box::new(contexts.map(|ctx| (ctx, false)))
+    }
+}
+
+/// Report the adapter calls that executing the query would make, without making any of them.
+///
+/// This is useful for estimating the load a query would place on the data source,
+/// or for checking that an adapter implements every type, property, and edge it needs to.
+///
+/// Calls are reported in the order the query's evaluation would make them. The calls needed
+/// to evaluate the contents of a `@fold` are reported after all the others, and a real
+/// execution makes them once per group of folded vertices rather than just once.
+pub fn dry_run(
+    indexed_query: Arc<IndexedQuery>,
+    arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+) -> Result<Vec<FunctionCall>, QueryArgumentsError> {
+    let query =
+        InterpretedQuery::from_query_and_arguments(indexed_query.clone(), arguments.clone())?;
+    let adapter = Rc::new(RefCell::new(PlanningAdapter::default()));
+
+    // The adapter produces no vertices, so consuming the results makes no further calls.
+    interpret_ir(adapter.clone(), indexed_query, arguments)?.for_each(drop);
+
+    // Nested folds are planned as part of the fold that contains them.
+    for fold in query.indexed_query.ir_query.root_component.folds.values() {
+        plan_fold_calls(adapter.clone(), &query, fold);
+    }
+
+    let calls = std::mem::take(&mut adapter.borrow_mut().calls);
+    Ok(calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{
+        interpreter::trace::FunctionCall,
+        ir::{Eid, FieldValue, Vid},
+        numbers_interpreter::parse_numbers_query,
+    };
+
+    use super::dry_run;
+
+    #[test]
+    fn dry_run_reports_calls() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 3) {
+        ... on Prime {
+            value @output @filter(op: ">", value: ["$min"])
+
+            successor @fold {
+                successor: value @output
+            }
+        }
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> =
+            Arc::new(btreemap! { "min".into() => 1.into() });
+
+        let calls = dry_run(query, arguments).unwrap();
+        let vid = |id: usize| Vid::new(id.try_into().unwrap());
+        assert_eq!(
+            vec![
+                FunctionCall::ResolveStartingVertices(vid(1)),
+                FunctionCall::ResolveCoercion(vid(1), "Number".into(), "Prime".into()),
+                // for the filter
+                FunctionCall::ResolveProperty(vid(1), "Prime".into(), "value".into()),
+                FunctionCall::ResolveNeighbors(
+                    vid(1),
+                    "Prime".into(),
+                    Eid::new(1.try_into().unwrap())
+                ),
+                // for the output
+                FunctionCall::ResolveProperty(vid(1), "Prime".into(), "value".into()),
+                // inside the fold
+                FunctionCall::ResolveProperty(vid(2), "Number".into(), "value".into()),
+            ],
+            calls,
+        );
+    }
+}
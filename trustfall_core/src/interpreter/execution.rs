@@ -6,28 +6,25 @@ use std::{
     sync::Arc,
 };
 
+use itertools::Itertools;
 use regex::Regex;
 
 use crate::{
     interpreter::{
-        filtering::{
-            contains, equals, greater_than, greater_than_or_equal, has_prefix, has_substring,
-            has_suffix, less_than, less_than_or_equal, one_of, regex_matches_optimized,
-            regex_matches_slow_path,
-        },
+        filtering::{constant_filter_outcome, filter_passes, regex_matches_optimized},
         ValueOrVec,
     },
     ir::{
         indexed::IndexedQuery, Argument, ContextField, EdgeParameters, Eid, FieldRef, FieldValue,
-        FoldSpecificFieldKind, IREdge, IRFold, IRQueryComponent, IRVertex, LocalField, Operation,
-        Recursive, Vid,
+        FoldSort, FoldSpecificFieldKind, IREdge, IRFold, IRQueryComponent, IRVertex, LocalField,
+        Operation, Recursive, Vid,
     },
     util::BTreeMapTryInsertExt,
 };
 
 use super::{
-    error::QueryArgumentsError, hints::QueryInfo, Adapter, ContextIterator, DataContext,
-    InterpretedQuery, VertexIterator,
+    error::QueryArgumentsError, hints::QueryInfo, memory::MemoryAccount, Adapter, ContextIterator,
+    DataContext, InterpretedQuery, VertexIterator,
 };
 
 #[allow(clippy::type_complexity)]
@@ -60,6 +57,106 @@ where
     Ok(construct_outputs(adapter.as_ref(), &query, iterator))
 }
 
+/// Skip result rows that are identical to an earlier row, keeping only the first occurrence
+/// of each distinct row. The relative order of the rows that are kept is unchanged.
+///
+/// Queries with `@optional` edges, or that don't output enough properties to distinguish
+/// the vertices they match, may produce such duplicate rows.
+///
+/// Every distinct row is stored until the returned iterator is dropped, so memory use grows
+/// with the number of distinct rows produced so far.
+pub fn distinct_results<'query>(
+    results: impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query,
+) -> impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query {
+    results.unique()
+}
+
+/// Reorder result rows by the score that `score` assigns to each row, highest score first.
+///
+/// Rows with equal scores keep their relative order, and rows scored as NaN come last.
+/// Every row must be scored before the highest-scoring one is known, so all rows
+/// are produced and buffered as soon as the first one is requested.
+pub fn rank_results<'query>(
+    results: impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query,
+    score: impl Fn(&BTreeMap<Arc<str>, FieldValue>) -> f64 + 'query,
+) -> impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query {
+    let mut results = Some(results);
+    let mut ranked: Option<std::vec::IntoIter<(f64, _)>> = None;
+    std::iter::from_fn(move || {
+        ranked
+            .get_or_insert_with(|| {
+                let mut scored: Vec<_> = results
+                    .take()
+                    .expect("results were already ranked")
+                    .map(|row| (score(&row), row))
+                    .collect();
+                scored.sort_by(|(left, _), (right, _)| {
+                    left.is_nan()
+                        .cmp(&right.is_nan())
+                        .then_with(|| right.total_cmp(left))
+                });
+                scored.into_iter()
+            })
+            .next()
+            .map(|(_, row)| row)
+    })
+}
+
+/// Produce at most `max_results` result rows, recording whether any rows were left out.
+///
+/// This is a guardrail against queries producing more results than a caller is willing
+/// to handle. Once the limit is reached, one more row is requested from `results` to check
+/// whether the limit cut the results short, and `results` is then dropped without
+/// producing any further rows. Use [`LimitedResults::was_truncated`] after the rows run out
+/// to find out whether that happened, e.g. by iterating over [`Iterator::by_ref`].
+pub fn limited_results<'query>(
+    results: impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query,
+    max_results: usize,
+) -> LimitedResults<'query> {
+    LimitedResults {
+        results: Some(Box::new(results)),
+        remaining: max_results,
+        truncated: false,
+    }
+}
+
+/// Result rows produced by [`limited_results`].
+pub struct LimitedResults<'query> {
+    results: Option<Box<dyn Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query>>,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl LimitedResults<'_> {
+    /// Whether there were more result rows than the limit allowed.
+    ///
+    /// Only final once this iterator has returned `None`. Before then, it's always `false`.
+    pub fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Iterator for LimitedResults<'_> {
+    type Item = BTreeMap<Arc<str>, FieldValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let results = self.results.as_mut()?;
+        if self.remaining > 0 {
+            let row = results.next();
+            if row.is_some() {
+                self.remaining -= 1;
+            } else {
+                self.results = None;
+            }
+            row
+        } else {
+            self.truncated = results.next().is_some();
+            self.results = None;
+            None
+        }
+    }
+}
+
 fn coerce_if_needed<'query, Vertex>(
     adapter: &RefCell<impl Adapter<'query, Vertex = Vertex> + 'query>,
     query: &InterpretedQuery,
@@ -233,6 +330,17 @@ fn construct_outputs<'query, Vertex: Clone + Debug + 'query>(
         }));
     }
 
+    // Recursion depths are tracked by the interpreter itself, without the adapter's involvement.
+    let depth_outputs: Vec<(Arc<str>, Eid, Vid)> = ir_query
+        .root_component
+        .edges
+        .values()
+        .filter_map(|edge| {
+            let name = edge.recursive.as_ref()?.depth_output.as_ref()?;
+            Some((name.clone(), edge.eid, edge.to_vid))
+        })
+        .collect();
+
     let expected_output_names: BTreeSet<_> = query.indexed_query.outputs.keys().cloned().collect();
 
     Box::new(output_iterator.map(move |mut context| {
@@ -249,6 +357,16 @@ fn construct_outputs<'query, Vertex: Clone + Debug + 'query>(
             assert!(existing.is_none());
         }
 
+        for (output_name, eid, vid) in &depth_outputs {
+            // If the recursion started from a nonexistent vertex, there's no depth to report.
+            let output_value = match context.vertices[vid] {
+                Some(_) => FieldValue::Uint64(context.recursion_depths[eid] as u64),
+                None => FieldValue::Null,
+            };
+            let existing = output.insert(output_name.clone(), output_value);
+            assert!(existing.is_none());
+        }
+
         debug_assert_eq!(expected_output_names, output.keys().cloned().collect());
 
         output
@@ -299,11 +417,111 @@ fn get_max_fold_count_limit(query: &InterpretedQuery, fold: &IRFold) -> Option<u
     result
 }
 
+/// If the only use of this IRFold's elements is to compute the count checked by its post-fold
+/// filters, return the number of elements after which pulling further elements can't change
+/// the query results. The fold then only needs to materialize that many elements.
+/// Otherwise, return None.
+///
+/// This requires that the fold has no outputs (including in nested folds), and that its count
+/// is not tagged for use elsewhere in the query. Then the count is only observable through
+/// the post-fold filters, and each filter that compares it against a query variable
+/// has a threshold at or above which its outcome no longer depends on the count:
+/// - `>= k` and `< k` have threshold `k`, since for larger counts they are always
+///   true and false respectively;
+/// - `> k`, `<= k`, `= k`, and `!= k` have threshold `k + 1`;
+/// - `one_of` and `not_one_of` have threshold `m + 1`, where `m` is the largest list element;
+/// - `is_null` and `is_not_null` have threshold `0`, since the count is never null.
+///
+/// The fold's threshold is the largest of its filters' thresholds. If the fold has at least
+/// that many elements, using the threshold in place of the true count gives the same outcome
+/// for every filter, so materializing more elements is pointless. If any filter's threshold
+/// can't be determined, e.g. because its operand is a tag or a negative number, return None:
+/// this is an optimization, and it's always safe to materialize the whole fold.
+fn get_min_fold_count_short_circuit(query: &InterpretedQuery, fold: &IRFold) -> Option<usize> {
+    if fold.post_filters.is_empty()
+        || !fold.fold_specific_outputs.is_empty()
+        || component_has_outputs(&fold.component)
+        || is_fold_count_used_as_tag(&query.indexed_query.ir_query.root_component, fold.eid)
+    {
+        return None;
+    }
+
+    let variable_value = |argument: &Argument| match argument {
+        Argument::Variable(var_ref) => Some(&query.arguments[&var_ref.variable_name]),
+        Argument::Tag(_) => None,
+    };
+
+    let mut result = 0usize;
+    for post_fold_filter in fold.post_filters.iter() {
+        let threshold = match post_fold_filter {
+            Operation::IsNull(FoldSpecificFieldKind::Count)
+            | Operation::IsNotNull(FoldSpecificFieldKind::Count) => 0,
+            Operation::GreaterThanOrEqual(FoldSpecificFieldKind::Count, argument)
+            | Operation::LessThan(FoldSpecificFieldKind::Count, argument) => {
+                variable_value(argument)?.as_usize()?
+            }
+            Operation::GreaterThan(FoldSpecificFieldKind::Count, argument)
+            | Operation::LessThanOrEqual(FoldSpecificFieldKind::Count, argument)
+            | Operation::Equals(FoldSpecificFieldKind::Count, argument)
+            | Operation::NotEquals(FoldSpecificFieldKind::Count, argument) => {
+                variable_value(argument)?.as_usize()?.checked_add(1)?
+            }
+            Operation::OneOf(FoldSpecificFieldKind::Count, argument)
+            | Operation::NotOneOf(FoldSpecificFieldKind::Count, argument) => {
+                let mut max = 0usize;
+                for value in variable_value(argument)?.as_slice()? {
+                    max = max.max(value.as_usize()?);
+                }
+                max.checked_add(1)?
+            }
+            _ => return None,
+        };
+        result = result.max(threshold);
+    }
+
+    Some(result)
+}
+
+fn component_has_outputs(component: &IRQueryComponent) -> bool {
+    !component.outputs.is_empty()
+        || component.folds.values().any(|fold| {
+            !fold.fold_specific_outputs.is_empty() || component_has_outputs(&fold.component)
+        })
+}
+
+fn is_fold_count_used_as_tag(component: &IRQueryComponent, fold_eid: Eid) -> bool {
+    let is_this_fold_count = |argument: Option<&Argument>| {
+        matches!(
+            argument,
+            Some(Argument::Tag(FieldRef::FoldSpecificField(field))) if field.fold_eid == fold_eid
+        )
+    };
+
+    component
+        .vertices
+        .values()
+        .flat_map(|vertex| vertex.filters.iter())
+        .any(|filter| is_this_fold_count(filter.right()))
+        || component.folds.values().any(|fold| {
+            fold.post_filters
+                .iter()
+                .any(|filter| is_this_fold_count(filter.right()))
+                || is_fold_count_used_as_tag(&fold.component, fold_eid)
+        })
+}
+
 fn collect_fold_elements<'query, Vertex: Clone + Debug + 'query>(
     mut iterator: ContextIterator<'query, Vertex>,
     max_fold_count_limit: &Option<usize>,
+    min_fold_count_short_circuit: &Option<usize>,
 ) -> Option<Vec<DataContext<Vertex>>> {
-    if let Some(max_fold_count_limit) = max_fold_count_limit {
+    if let Some(min_fold_count_short_circuit) = min_fold_count_short_circuit {
+        // The fold's elements are only used to compute the count checked by
+        // the post-fold filters. Once we've found `min_fold_count_short_circuit` of them,
+        // we can stop pulling elements: the post-fold filters have the same outcome
+        // regardless of how many more elements there might be.
+        Some(iterator.take(*min_fold_count_short_circuit).collect())
+    } else if let Some(max_fold_count_limit) = max_fold_count_limit {
         // If this fold has more than `max_fold_count_limit` elements,
         // it will get filtered out by a post-fold filter.
         // Pulling elements from `iterator` causes computations and data fetches to happen,
@@ -416,13 +634,16 @@ fn compute_fold<'query, Vertex: Clone + Debug + 'query>(
     let fold_component = fold.component.clone();
     let fold_eid = fold.eid;
     let max_fold_size = get_max_fold_count_limit(query, fold.as_ref());
+    let min_fold_size_short_circuit = get_min_fold_count_short_circuit(query, fold.as_ref());
     let moved_fold = fold.clone();
     let folded_iterator = edge_iterator.filter_map(move |(mut context, neighbors)| {
         let imported_tags = context.imported_tags.clone();
+        let memory_account = context.memory.as_ref().map(|token| token.account().clone());
 
         let neighbor_contexts = Box::new(neighbors.map(move |x| {
             let mut ctx = DataContext::new(Some(x));
             ctx.imported_tags = imported_tags.clone();
+            ctx.memory = memory_account.as_ref().map(MemoryAccount::token);
             ctx
         }));
 
@@ -433,7 +654,25 @@ fn compute_fold<'query, Vertex: Clone + Debug + 'query>(
             neighbor_contexts,
         );
 
-        let fold_elements = match collect_fold_elements(computed_iterator, &max_fold_size) {
+        // Apply the fold's sort and limit, if any, before any other processing of its elements:
+        // outputs, counts, and post-fold filters all only see the elements that were kept.
+        let computed_iterator = match (&moved_fold.sort, moved_fold.limit) {
+            (Some(sort), _) => sort_fold_elements(
+                cloned_adapter.as_ref(),
+                &cloned_query,
+                &moved_fold,
+                sort,
+                computed_iterator,
+            ),
+            (None, Some(limit)) => Box::new(computed_iterator.take(limit.get())),
+            (None, None) => computed_iterator,
+        };
+
+        let fold_elements = match collect_fold_elements(
+            computed_iterator,
+            &max_fold_size,
+            &min_fold_size_short_circuit,
+        ) {
             None => {
                 // We were able to discard this fold early.
                 return None;
@@ -528,31 +767,13 @@ fn compute_fold<'query, Vertex: Clone + Debug + 'query>(
             }
         } else {
             // Iterate through the elements of the fold and get the values we need.
-            let mut output_iterator: VertexIterator<'query, DataContext<Vertex>> =
-                Box::new(fold_elements.clone().into_iter());
-            for output_name in output_names.iter() {
-                let context_field = &fold.component.outputs[output_name.as_ref()];
-                let vertex_id = context_field.vertex_id;
-                let moved_iterator = Box::new(output_iterator.map(move |context| {
-                    let new_vertex = context.vertices[&vertex_id].clone();
-                    context.move_to_vertex(new_vertex)
-                }));
-
-                let mut adapter_ref = cloned_adapter.borrow_mut();
-                let query_info = QueryInfo::new(cloned_query.clone(), vertex_id, None);
-                let field_data_iterator = adapter_ref.resolve_property(
-                    moved_iterator,
-                    &fold.component.vertices[&vertex_id].type_name,
-                    &context_field.field_name,
-                    &query_info,
-                );
-                drop(adapter_ref);
-
-                output_iterator = Box::new(field_data_iterator.map(|(mut context, value)| {
-                    context.values.push(value);
-                    context
-                }));
-            }
+            let output_iterator = resolve_fold_outputs(
+                cloned_adapter.as_ref(),
+                &cloned_query,
+                &fold,
+                &output_names,
+                Box::new(fold_elements.clone().into_iter()),
+            );
 
             for mut folded_context in output_iterator {
                 for (key, value) in folded_context.folded_values {
@@ -599,6 +820,127 @@ fn compute_fold<'query, Vertex: Clone + Debug + 'query>(
     Box::new(final_iterator)
 }
 
+/// Sort the fold's elements by the given property of the fold's root vertex,
+/// keeping no more than the fold's limit of them.
+///
+/// All the fold's elements have to be materialized before they can be sorted.
+fn sort_fold_elements<'query, Vertex: Clone + Debug + 'query>(
+    adapter: &RefCell<impl Adapter<'query, Vertex = Vertex> + 'query>,
+    query: &InterpretedQuery,
+    fold: &IRFold,
+    sort: &FoldSort,
+    iterator: ContextIterator<'query, Vertex>,
+) -> ContextIterator<'query, Vertex> {
+    let fold_root_vid = fold.to_vid;
+    let activated_vertex_iterator: ContextIterator<'query, Vertex> =
+        Box::new(iterator.map(move |x| x.activate_vertex(&fold_root_vid)));
+
+    let mut adapter_ref = adapter.borrow_mut();
+    let query_info = QueryInfo::new(query.clone(), fold_root_vid, None);
+    let context_and_value_iterator = adapter_ref.resolve_property(
+        activated_vertex_iterator,
+        &fold.component.vertices[&fold_root_vid].type_name,
+        &sort.field_name,
+        &query_info,
+    );
+    drop(adapter_ref);
+
+    // The sort must be stable, so equal values keep the order the adapter produced them in.
+    let mut elements: Vec<_> = context_and_value_iterator.collect();
+    if sort.descending {
+        elements.sort_by(|(_, left), (_, right)| right.cmp(left));
+    } else {
+        elements.sort_by(|(_, left), (_, right)| left.cmp(right));
+    }
+    if let Some(limit) = fold.limit {
+        elements.truncate(limit.get());
+    }
+
+    Box::new(elements.into_iter().map(|(context, _)| context))
+}
+
+/// Resolve the given outputs of the fold's component, pushing their values
+/// onto each context's value stack in the order the outputs are given.
+fn resolve_fold_outputs<'query, Vertex: Clone + Debug + 'query>(
+    adapter: &RefCell<impl Adapter<'query, Vertex = Vertex> + 'query>,
+    query: &InterpretedQuery,
+    fold: &IRFold,
+    output_names: &[Arc<str>],
+    mut output_iterator: VertexIterator<'query, DataContext<Vertex>>,
+) -> VertexIterator<'query, DataContext<Vertex>> {
+    for output_name in output_names.iter() {
+        let context_field = &fold.component.outputs[output_name.as_ref()];
+        let vertex_id = context_field.vertex_id;
+        let moved_iterator = Box::new(output_iterator.map(move |context| {
+            let new_vertex = context.vertices[&vertex_id].clone();
+            context.move_to_vertex(new_vertex)
+        }));
+
+        let mut adapter_ref = adapter.borrow_mut();
+        let query_info = QueryInfo::new(query.clone(), vertex_id, None);
+        let field_data_iterator = adapter_ref.resolve_property(
+            moved_iterator,
+            &fold.component.vertices[&vertex_id].type_name,
+            &context_field.field_name,
+            &query_info,
+        );
+        drop(adapter_ref);
+
+        output_iterator = Box::new(field_data_iterator.map(|(mut context, value)| {
+            context.values.push(value);
+            context
+        }));
+    }
+
+    output_iterator
+}
+
+/// Make the adapter calls needed to evaluate the fold's contents, without any vertices
+/// to evaluate them on.
+///
+/// Those calls are only made once per group of folded vertices, so evaluating a query
+/// whose adapter produces no data never makes them. This makes them anyway,
+/// including the calls for any nested folds.
+pub(super) fn plan_fold_calls<'query, Vertex>(
+    adapter: Rc<RefCell<impl Adapter<'query, Vertex = Vertex> + 'query>>,
+    query: &InterpretedQuery,
+    fold: &IRFold,
+) where
+    Vertex: Clone + Debug + 'query,
+{
+    compute_component(
+        adapter.clone(),
+        query,
+        &fold.component,
+        Box::new(std::iter::empty()),
+    )
+    .for_each(drop);
+    for inner_fold in fold.component.folds.values() {
+        plan_fold_calls(adapter.clone(), query, inner_fold);
+    }
+    if let Some(sort) = &fold.sort {
+        sort_fold_elements(
+            adapter.as_ref(),
+            query,
+            fold,
+            sort,
+            Box::new(std::iter::empty()),
+        )
+        .for_each(drop);
+    }
+
+    let mut output_names: Vec<Arc<str>> = fold.component.outputs.keys().cloned().collect();
+    output_names.sort_unstable();
+    resolve_fold_outputs(
+        adapter.as_ref(),
+        query,
+        fold,
+        &output_names,
+        Box::new(std::iter::empty()),
+    )
+    .for_each(drop);
+}
+
 /// Check whether a tagged value that is being used in a filter originates from
 /// a scope that is optional and missing, and therefore the filter should pass.
 ///
@@ -623,46 +965,6 @@ fn is_tag_optional_and_missing<'query, Vertex: Clone + Debug + 'query>(
     matches!(context.vertices.get(&vid), Some(None))
 }
 
-macro_rules! implement_filter {
-    ( $iter: ident, $right: ident, $func: ident ) => {
-        Box::new($iter.filter_map(move |mut context| {
-            let right_value = context.values.pop().unwrap();
-            let left_value = context.values.pop().unwrap();
-            if let Argument::Tag(field) = &$right {
-                if is_tag_optional_and_missing(&context, field) {
-                    return Some(context);
-                }
-            }
-
-            if $func(&left_value, &right_value) {
-                Some(context)
-            } else {
-                None
-            }
-        }))
-    };
-}
-
-macro_rules! implement_negated_filter {
-    ( $iter: ident, $right: ident, $func: ident ) => {
-        Box::new($iter.filter_map(move |mut context| {
-            let right_value = context.values.pop().unwrap();
-            let left_value = context.values.pop().unwrap();
-            if let Argument::Tag(field) = &$right {
-                if is_tag_optional_and_missing(&context, field) {
-                    return Some(context);
-                }
-            }
-
-            if $func(&left_value, &right_value) {
-                None
-            } else {
-                Some(context)
-            }
-        }))
-    };
-}
-
 fn apply_local_field_filter<'query, Vertex: Clone + Debug + 'query>(
     adapter_ref: &RefCell<impl Adapter<'query, Vertex = Vertex> + 'query>,
     query: &InterpretedQuery,
@@ -671,6 +973,10 @@ fn apply_local_field_filter<'query, Vertex: Clone + Debug + 'query>(
     filter: &Operation<LocalField, Argument>,
     iterator: ContextIterator<'query, Vertex>,
 ) -> ContextIterator<'query, Vertex> {
+    if let Some(passes) = constant_filter_outcome(filter, &query.arguments) {
+        return apply_constant_filter(passes, iterator);
+    }
+
     let local_field = filter.left();
     let field_iterator = compute_local_field(
         adapter_ref,
@@ -700,6 +1006,10 @@ fn apply_fold_specific_filter<'query, Vertex: Clone + Debug + 'query>(
     filter: &Operation<FoldSpecificFieldKind, Argument>,
     iterator: ContextIterator<'query, Vertex>,
 ) -> ContextIterator<'query, Vertex> {
+    if let Some(passes) = constant_filter_outcome(filter, &query.arguments) {
+        return apply_constant_filter(passes, iterator);
+    }
+
     let fold_specific_field = filter.left();
     let field_iterator = compute_fold_specific_field(fold.eid, fold_specific_field, iterator);
 
@@ -713,7 +1023,25 @@ fn apply_fold_specific_filter<'query, Vertex: Clone + Debug + 'query>(
     )
 }
 
-fn apply_filter<'query, Vertex: Clone + Debug + 'query, LeftT: Debug + Clone + PartialEq + Eq>(
+/// Apply a filter whose outcome doesn't depend on the filtered value, without computing
+/// that value. If the filter never passes, the returned iterator is empty without pulling
+/// any contexts from `iterator`, so none of the work that produces them happens either.
+fn apply_constant_filter<'query, Vertex: Clone + Debug + 'query>(
+    passes: bool,
+    iterator: ContextIterator<'query, Vertex>,
+) -> ContextIterator<'query, Vertex> {
+    if passes {
+        iterator
+    } else {
+        Box::new(std::iter::empty())
+    }
+}
+
+fn apply_filter<
+    'query,
+    Vertex: Clone + Debug + 'query,
+    LeftT: Debug + Clone + PartialEq + Eq + 'query,
+>(
     adapter_ref: &RefCell<impl Adapter<'query, Vertex = Vertex> + 'query>,
     query: &InterpretedQuery,
     component: &IRQueryComponent,
@@ -778,115 +1106,54 @@ fn apply_filter<'query, Vertex: Clone + Debug + 'query, LeftT: Debug + Clone + P
         None => iterator,
     };
 
-    match filter.clone() {
-        Operation::IsNull(_) => {
-            let output_iter = expression_iterator.filter_map(move |mut context| {
-                let last_value = context.values.pop().unwrap();
-                match last_value {
-                    FieldValue::Null => Some(context),
-                    _ => None,
+    match filter {
+        Operation::RegexMatches(_, Argument::Variable(var))
+        | Operation::NotRegexMatches(_, Argument::Variable(var)) => {
+            // Compile the regex only once, instead of once per value being filtered.
+            let negated = matches!(filter, Operation::NotRegexMatches(..));
+            let variable_value = &query.arguments[var.variable_name.as_ref()];
+            let pattern = Regex::new(variable_value.as_str().unwrap()).unwrap();
+
+            Box::new(expression_iterator.filter_map(move |mut context| {
+                let _ = context.values.pop().unwrap();
+                let left_value = context.values.pop().unwrap();
+
+                if regex_matches_optimized(&left_value, &pattern) != negated {
+                    Some(context)
+                } else {
+                    None
                 }
-            });
-            Box::new(output_iter)
+            }))
         }
-        Operation::IsNotNull(_) => {
-            let output_iter = expression_iterator.filter_map(move |mut context| {
-                let last_value = context.values.pop().unwrap();
-                match last_value {
-                    FieldValue::Null => None,
-                    _ => Some(context),
+        Operation::IsNull(_) | Operation::IsNotNull(_) => {
+            let filter = filter.clone();
+            Box::new(expression_iterator.filter_map(move |mut context| {
+                let left_value = context.values.pop().unwrap();
+                if filter_passes(&filter, &left_value, None) {
+                    Some(context)
+                } else {
+                    None
                 }
-            });
-            Box::new(output_iter)
-        }
-        Operation::Equals(_, right) => {
-            implement_filter!(expression_iterator, right, equals)
-        }
-        Operation::NotEquals(_, right) => {
-            implement_negated_filter!(expression_iterator, right, equals)
-        }
-        Operation::GreaterThan(_, right) => {
-            implement_filter!(expression_iterator, right, greater_than)
-        }
-        Operation::GreaterThanOrEqual(_, right) => {
-            implement_filter!(expression_iterator, right, greater_than_or_equal)
-        }
-        Operation::LessThan(_, right) => {
-            implement_filter!(expression_iterator, right, less_than)
-        }
-        Operation::LessThanOrEqual(_, right) => {
-            implement_filter!(expression_iterator, right, less_than_or_equal)
-        }
-        Operation::HasSubstring(_, right) => {
-            implement_filter!(expression_iterator, right, has_substring)
-        }
-        Operation::NotHasSubstring(_, right) => {
-            implement_negated_filter!(expression_iterator, right, has_substring)
-        }
-        Operation::OneOf(_, right) => {
-            implement_filter!(expression_iterator, right, one_of)
-        }
-        Operation::NotOneOf(_, right) => {
-            implement_negated_filter!(expression_iterator, right, one_of)
-        }
-        Operation::Contains(_, right) => {
-            implement_filter!(expression_iterator, right, contains)
-        }
-        Operation::NotContains(_, right) => {
-            implement_negated_filter!(expression_iterator, right, contains)
-        }
-        Operation::HasPrefix(_, right) => {
-            implement_filter!(expression_iterator, right, has_prefix)
-        }
-        Operation::NotHasPrefix(_, right) => {
-            implement_negated_filter!(expression_iterator, right, has_prefix)
-        }
-        Operation::HasSuffix(_, right) => {
-            implement_filter!(expression_iterator, right, has_suffix)
-        }
-        Operation::NotHasSuffix(_, right) => {
-            implement_negated_filter!(expression_iterator, right, has_suffix)
+            }))
         }
-        Operation::RegexMatches(_, right) => match &right {
-            Argument::Tag(_) => {
-                implement_filter!(expression_iterator, right, regex_matches_slow_path)
-            }
-            Argument::Variable(var) => {
-                let variable_value = &query.arguments[var.variable_name.as_ref()];
-                let pattern = Regex::new(variable_value.as_str().unwrap()).unwrap();
-
-                Box::new(expression_iterator.filter_map(move |mut context| {
-                    let _ = context.values.pop().unwrap();
-                    let left_value = context.values.pop().unwrap();
-
-                    if regex_matches_optimized(&left_value, &pattern) {
-                        Some(context)
-                    } else {
-                        None
-                    }
-                }))
-            }
-        },
-        Operation::NotRegexMatches(_, right) => match &right {
-            Argument::Tag(_) => {
-                implement_negated_filter!(expression_iterator, right, regex_matches_slow_path)
-            }
-            Argument::Variable(var) => {
-                let variable_value = &query.arguments[var.variable_name.as_ref()];
-                let pattern = Regex::new(variable_value.as_str().unwrap()).unwrap();
-
-                Box::new(expression_iterator.filter_map(move |mut context| {
-                    let _ = context.values.pop().unwrap();
-                    let left_value = context.values.pop().unwrap();
-
-                    if !regex_matches_optimized(&left_value, &pattern) {
-                        Some(context)
-                    } else {
-                        None
+        _ => {
+            let filter = filter.clone();
+            Box::new(expression_iterator.filter_map(move |mut context| {
+                let right_value = context.values.pop().unwrap();
+                let left_value = context.values.pop().unwrap();
+                if let Some(Argument::Tag(field)) = filter.right() {
+                    if is_tag_optional_and_missing(&context, field) {
+                        return Some(context);
                     }
-                }))
-            }
-        },
+                }
+
+                if filter_passes(&filter, &left_value, Some(&right_value)) {
+                    Some(context)
+                } else {
+                    None
+                }
+            }))
+        }
     }
 }
 
@@ -1165,6 +1432,7 @@ fn expand_recursive_edge<'query, Vertex: Clone + Debug + 'query>(
     iterator: ContextIterator<'query, Vertex>,
 ) -> ContextIterator<'query, Vertex> {
     let expanding_from_vid = expanding_from.vid;
+    let track_depth = recursive.depth_output.is_some();
     let mut recursion_iterator: ContextIterator<'query, Vertex> =
         Box::new(iterator.map(move |mut context| {
             if context.active_vertex.is_none() {
@@ -1172,6 +1440,10 @@ fn expand_recursive_edge<'query, Vertex: Clone + Debug + 'query>(
                 // so the later unsuspend() call should restore it to such a state later.
                 context.suspended_vertices.push(None);
             }
+            if track_depth {
+                // The vertex we're recursing from is at depth zero.
+                context.recursion_depths.insert(edge_id, 0);
+            }
             context.activate_vertex(&expanding_from_vid)
         }));
 
@@ -1186,6 +1458,7 @@ fn expand_recursive_edge<'query, Vertex: Clone + Debug + 'query>(
         edge_id,
         edge_name,
         edge_parameters,
+        track_depth,
         recursion_iterator,
     );
 
@@ -1228,6 +1501,7 @@ fn expand_recursive_edge<'query, Vertex: Clone + Debug + 'query>(
             edge_id,
             edge_name,
             edge_parameters,
+            track_depth,
             recursion_iterator,
         );
     }
@@ -1246,6 +1520,7 @@ fn perform_one_recursive_edge_expansion<'query, Vertex: Clone + Debug + 'query>(
     edge_id: Eid,
     edge_name: &Arc<str>,
     edge_parameters: &EdgeParameters,
+    track_depth: bool,
     iterator: ContextIterator<'query, Vertex>,
 ) -> ContextIterator<'query, Vertex> {
     let query_info = QueryInfo::new(query.clone(), expanding_from.vid, Some(edge_id));
@@ -1261,7 +1536,7 @@ fn perform_one_recursive_edge_expansion<'query, Vertex: Clone + Debug + 'query>(
 
     let result_iterator: ContextIterator<'query, Vertex> =
         Box::new(edge_iterator.flat_map(move |(context, neighbor_iterator)| {
-            RecursiveEdgeExpander::new(context, neighbor_iterator)
+            RecursiveEdgeExpander::new(context, neighbor_iterator, track_depth.then_some(edge_id))
         }));
 
     result_iterator
@@ -1273,12 +1548,16 @@ struct RecursiveEdgeExpander<'query, Vertex: Clone + Debug + 'query> {
     neighbors: VertexIterator<'query, Vertex>,
     has_neighbors: bool,
     neighbors_ended: bool,
+
+    /// The recursive edge whose recursion depth needs to be tracked, if any.
+    depth_tracked_edge: Option<Eid>,
 }
 
 impl<'query, Vertex: Clone + Debug + 'query> RecursiveEdgeExpander<'query, Vertex> {
     pub fn new(
         context: DataContext<Vertex>,
         neighbors: VertexIterator<'query, Vertex>,
+        depth_tracked_edge: Option<Eid>,
     ) -> RecursiveEdgeExpander<'query, Vertex> {
         RecursiveEdgeExpander {
             context: Some(context),
@@ -1286,6 +1565,7 @@ impl<'query, Vertex: Clone + Debug + 'query> RecursiveEdgeExpander<'query, Verte
             neighbors,
             has_neighbors: false,
             neighbors_ended: false,
+            depth_tracked_edge,
         }
     }
 }
@@ -1301,10 +1581,15 @@ impl<'query, Vertex: Clone + Debug + 'query> Iterator for RecursiveEdgeExpander<
                 if let Some(context) = self.context.take() {
                     // Prep a neighbor base context for future use, since we're moving
                     // the "self" context out.
-                    self.neighbor_base = Some(context.split_and_move_to_vertex(None));
+                    let mut neighbor_base = context.split_and_move_to_vertex(None);
+                    if let Some(eid) = self.depth_tracked_edge {
+                        // Neighbors are one level deeper into the recursion than this vertex.
+                        *neighbor_base.recursion_depths.get_mut(&eid).unwrap() += 1;
+                    }
+                    let neighbor_base = self.neighbor_base.insert(neighbor_base);
 
                     // Attach the "self" context as a piggyback rider on the neighbor.
-                    let mut neighbor_context = context.split_and_move_to_vertex(Some(vertex));
+                    let mut neighbor_context = neighbor_base.split_and_move_to_vertex(Some(vertex));
                     neighbor_context
                         .piggyback
                         .get_or_insert_with(Default::default)
@@ -1386,8 +1671,9 @@ mod tests {
     use trustfall_filetests_macros::parameterize;
 
     use crate::{
-        interpreter::{error::QueryArgumentsError, InterpretedQuery},
+        interpreter::{error::QueryArgumentsError, execution::interpret_ir, InterpretedQuery},
         ir::{indexed::IndexedQuery, FieldValue},
+        numbers_interpreter::{numbers_adapter, parse_numbers_query},
         util::TestIRQueryResult,
     };
 
@@ -1420,4 +1706,202 @@ mod tests {
 
         assert_eq!(check_parsed, constructed_test_item);
     }
+
+    #[test]
+    fn constant_filters_are_evaluated_once() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 4) {
+        value @filter(op: "not_one_of", value: ["$excluded"])
+        name @filter(op: "one_of", value: ["$names"]) @output
+    }
+}"#,
+        );
+
+        let run = |names: FieldValue| {
+            let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Arc::new(btreemap! {
+                "excluded".into() => FieldValue::List(vec![]),
+                "names".into() => names,
+            });
+            let adapter = numbers_adapter();
+            let (results, stats) = crate::interpreter::stats::interpret_ir_with_stats(
+                adapter,
+                query.clone(),
+                arguments,
+            )
+            .unwrap();
+            (results.count(), stats)
+        };
+
+        // Excluding nothing always passes, so `value` is never resolved.
+        let (count, stats) = run(vec!["two", "four", "five"].into());
+        assert_eq!(2, count);
+        assert_eq!(4 + 2, stats.properties_read());
+
+        // Matching no names never passes, so the query does no work at all.
+        let (count, stats) = run(FieldValue::List(vec![]));
+        assert_eq!(0, count);
+        assert_eq!(0, stats.vertices_resolved());
+        assert_eq!(0, stats.properties_read());
+    }
+
+    #[test]
+    fn fold_count_filters_stop_expanding_at_threshold() {
+        // The number 3 has 9 multiples: 6, 9, ..., 30.
+        // The expected vertices include the starting vertex.
+        for (op, k, expected_vertices, expected_rows) in [
+            (">=", FieldValue::Int64(3), 1 + 3, 1),
+            ("<", FieldValue::Int64(3), 1 + 3, 0),
+            (">", FieldValue::Int64(3), 1 + 4, 1),
+            ("<=", FieldValue::Int64(3), 1 + 4, 0),
+            ("=", FieldValue::Int64(3), 1 + 4, 0),
+            ("=", FieldValue::Int64(9), 1 + 9, 1),
+            ("!=", FieldValue::Int64(3), 1 + 4, 1),
+            ("one_of", vec![1i64, 4].into(), 1 + 5, 0),
+            ("not_one_of", vec![1i64, 4].into(), 1 + 5, 1),
+        ] {
+            let query = parse_numbers_query(&format!(
+                r#"
+{{
+    Number(min: 3, max: 3) {{
+        value @output
+
+        multiple(max: 10) @fold @transform(op: "count") @filter(op: "{op}", value: ["$k"])
+    }}
+}}"#
+            ));
+            let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> =
+                Arc::new(btreemap! { "k".into() => k });
+
+            let adapter = numbers_adapter();
+            let (results, stats) =
+                crate::interpreter::stats::interpret_ir_with_stats(adapter, query, arguments)
+                    .unwrap();
+            assert_eq!(expected_rows, results.count(), "{op}");
+            assert_eq!(expected_vertices, stats.vertices_resolved(), "{op}");
+        }
+    }
+
+    #[test]
+    fn fold_with_outputs_is_fully_expanded() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 3, max: 3) {
+        multiple(max: 10) @fold @transform(op: "count") @filter(op: ">=", value: ["$k"]) {
+            value @output
+        }
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> =
+            Arc::new(btreemap! { "k".into() => FieldValue::Int64(3) });
+
+        let adapter = numbers_adapter();
+        let (results, stats) =
+            crate::interpreter::stats::interpret_ir_with_stats(adapter, query, arguments).unwrap();
+        let rows: Vec<_> = results.collect();
+        assert_eq!(1, rows.len());
+        assert_eq!(
+            FieldValue::List((2..=10).map(|m| FieldValue::Int64(3 * m)).collect()),
+            rows[0]["value"],
+        );
+        assert_eq!(1 + 9, stats.vertices_resolved());
+    }
+
+    #[test]
+    fn rank_results() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 8) {
+        value @output
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter = numbers_adapter();
+        let results = interpret_ir(adapter, query, arguments).unwrap();
+
+        // Rank by distance from 4.5, with ties kept in their original order.
+        // The value 8 gets a NaN score, which ranks last: even after the value 1,
+        // whose score is negative infinity.
+        let values: Vec<_> = super::rank_results(results, |row| {
+            let value = row["value"].as_i64().unwrap() as f64;
+            if value == 8.0 {
+                f64::NAN
+            } else if value == 1.0 {
+                f64::NEG_INFINITY
+            } else {
+                (value - 4.5).abs()
+            }
+        })
+        .map(|row| row["value"].as_i64().unwrap())
+        .collect();
+        assert_eq!(vec![2, 7, 3, 6, 4, 5, 1, 8], values);
+    }
+
+    #[test]
+    fn limited_results() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 5) {
+        value @output
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        for (max_results, expected_values, truncated) in [
+            (0, vec![], true),
+            (3, vec![1, 2, 3], true),
+            (5, vec![1, 2, 3, 4, 5], false),
+            (8, vec![1, 2, 3, 4, 5], false),
+        ] {
+            let adapter = numbers_adapter();
+            let results = interpret_ir(adapter, query.clone(), arguments.clone()).unwrap();
+
+            let mut limited = super::limited_results(results, max_results);
+            let values: Vec<_> = limited
+                .by_ref()
+                .map(|row| row["value"].as_i64().unwrap())
+                .collect();
+            assert_eq!(expected_values, values);
+            assert_eq!(truncated, limited.was_truncated(), "{max_results}");
+
+            // Once exhausted, the iterator stays exhausted.
+            assert_eq!(None, limited.next());
+            assert_eq!(truncated, limited.was_truncated(), "{max_results}");
+        }
+    }
+
+    #[test]
+    fn distinct_results() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 0, max: 5) {
+        __typename @output
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter = numbers_adapter();
+        let results = interpret_ir(adapter, query, arguments).unwrap();
+        let typenames: Vec<_> = super::distinct_results(results)
+            .map(|row| row["__typename"].clone())
+            .collect();
+        assert_eq!(
+            vec![
+                FieldValue::from("Neither"),
+                "Prime".into(),
+                "Composite".into()
+            ],
+            typenames,
+        );
+    }
 }
@@ -1,11 +1,11 @@
-use std::mem;
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Debug, mem, sync::Arc};
 
 use regex::Regex;
 
-use crate::ir::FieldValue;
+use crate::ir::{Argument, FieldValue, Operation};
 
 #[inline(always)]
-pub(super) fn equals(left: &FieldValue, right: &FieldValue) -> bool {
+pub(crate) fn equals(left: &FieldValue, right: &FieldValue) -> bool {
     if mem::discriminant(left) == mem::discriminant(right) {
         match (left, right) {
             (FieldValue::List(l), FieldValue::List(r)) => {
@@ -15,24 +15,10 @@ pub(super) fn equals(left: &FieldValue, right: &FieldValue) -> bool {
         }
     } else {
         match (left, right) {
-            (&FieldValue::Uint64(l), &FieldValue::Int64(r)) => {
-                if let Ok(l) = i64::try_from(l) {
-                    l == r
-                } else if let Ok(r) = u64::try_from(r) {
-                    l == r
-                } else {
-                    false
-                }
-            }
-            (&FieldValue::Int64(l), &FieldValue::Uint64(r)) => {
-                if let Ok(l) = u64::try_from(l) {
-                    l == r
-                } else if let Ok(r) = i64::try_from(r) {
-                    l == r
-                } else {
-                    false
-                }
-            }
+            (
+                FieldValue::Uint64(_) | FieldValue::Int64(_),
+                FieldValue::Uint64(_) | FieldValue::Int64(_),
+            ) => left.numeric_key() == right.numeric_key(),
             _ => false,
         }
     }
@@ -50,6 +36,9 @@ macro_rules! make_comparison_op_func {
                 (FieldValue::Int64(l), FieldValue::Int64(r)) => l $op r,
                 (FieldValue::Uint64(l), FieldValue::Uint64(r)) => l $op r,
                 (FieldValue::Float64(l), FieldValue::Float64(r)) => l $op r,
+                (FieldValue::List(l), FieldValue::List(r)) => {
+                    compare_lists(l, r).is_some_and(|ordering| ordering $op Ordering::Equal)
+                }
                 _ => $slow_path_handler(left, right),
             }
         }
@@ -122,6 +111,48 @@ macro_rules! make_less_than_func_slow_path {
     };
 }
 
+/// Compares list values lexicographically, for the range filter operators.
+///
+/// Elements are compared the same way as those operators compare values, so integers
+/// are compared numerically regardless of their representation. Unlike in those operators,
+/// a null element is comparable: it's less than any non-null element and equal to another null.
+/// If one list is a prefix of the other, the shorter list is less. Returns `None`
+/// if the lists first differ at elements that can't be compared, such as a string and an integer.
+fn compare_lists(left: &[FieldValue], right: &[FieldValue]) -> Option<Ordering> {
+    for (l, r) in left.iter().zip(right.iter()) {
+        let ordering = match (l, r) {
+            (FieldValue::Null, FieldValue::Null) => Ordering::Equal,
+            (FieldValue::Null, _) => Ordering::Less,
+            (_, FieldValue::Null) => Ordering::Greater,
+            (FieldValue::List(l), FieldValue::List(r)) => compare_lists(l, r)?,
+            (FieldValue::String(_), FieldValue::String(_))
+            | (FieldValue::DateTimeUtc(_), FieldValue::DateTimeUtc(_))
+            | (FieldValue::Float64(_), FieldValue::Float64(_))
+            | (
+                FieldValue::Int64(_) | FieldValue::Uint64(_),
+                FieldValue::Int64(_) | FieldValue::Uint64(_),
+            ) => {
+                if less_than(l, r) {
+                    Ordering::Less
+                } else if greater_than(l, r) {
+                    Ordering::Greater
+                } else if equals(l, r) {
+                    Ordering::Equal
+                } else {
+                    return None;
+                }
+            }
+            _ if equals(l, r) => Ordering::Equal,
+            _ => return None,
+        };
+        if ordering != Ordering::Equal {
+            return Some(ordering);
+        }
+    }
+
+    Some(left.len().cmp(&right.len()))
+}
+
 make_greater_than_func_slow_path!(slow_path_greater_than, >);
 make_comparison_op_func!(greater_than, >, slow_path_greater_than);
 make_greater_than_func_slow_path!(slow_path_greater_than_or_equal, >=);
@@ -164,13 +195,17 @@ pub(super) fn has_suffix(left: &FieldValue, right: &FieldValue) -> bool {
     }
 }
 
+/// Whether `left` is an element of the `right` list.
+///
+/// Elements are compared the same way as by an `=` filter: a null element in the list
+/// matches a null `left` value, and integers match regardless of their signedness.
 #[inline(always)]
 pub(super) fn one_of(left: &FieldValue, right: &FieldValue) -> bool {
     match right {
         FieldValue::Null => false,
         FieldValue::List(v) => {
             for value in v.iter() {
-                if left == value {
+                if equals(left, value) {
                     return true;
                 }
             }
@@ -218,15 +253,190 @@ pub(super) fn regex_matches_optimized(left: &FieldValue, regex: &Regex) -> bool
     }
 }
 
+/// Whether the `left` value passes the filter operation, given the value of its operand.
+///
+/// The operand must be `Some` for every operation other than `is_null` and `is_not_null`.
+/// The operation's own operand is ignored, so that the caller can resolve it however it needs to.
+///
+/// This defines the semantics of every filter operation, both for the interpreter
+/// and for [`evaluate_filter`](super::helpers::evaluate_filter). Regex filters here compile
+/// their pattern on each call; callers that apply the same pattern many times can precompile it
+/// and use [`regex_matches_optimized`] instead, which is equivalent for valid patterns.
+pub(super) fn filter_passes<LeftT, RightT>(
+    filter: &Operation<LeftT, RightT>,
+    left: &FieldValue,
+    right: Option<&FieldValue>,
+) -> bool
+where
+    LeftT: Debug + Clone + PartialEq + Eq,
+    RightT: Debug + Clone + PartialEq + Eq,
+{
+    let right = || right.unwrap_or_else(|| panic!("no operand value for filter {filter:?}"));
+    match filter {
+        Operation::IsNull(_) => matches!(left, FieldValue::Null),
+        Operation::IsNotNull(_) => !matches!(left, FieldValue::Null),
+        Operation::Equals(..) => equals(left, right()),
+        Operation::NotEquals(..) => !equals(left, right()),
+        Operation::LessThan(..) => less_than(left, right()),
+        Operation::LessThanOrEqual(..) => less_than_or_equal(left, right()),
+        Operation::GreaterThan(..) => greater_than(left, right()),
+        Operation::GreaterThanOrEqual(..) => greater_than_or_equal(left, right()),
+        Operation::Contains(..) => contains(left, right()),
+        Operation::NotContains(..) => !contains(left, right()),
+        Operation::OneOf(..) => one_of(left, right()),
+        Operation::NotOneOf(..) => !one_of(left, right()),
+        Operation::HasPrefix(..) => has_prefix(left, right()),
+        Operation::NotHasPrefix(..) => !has_prefix(left, right()),
+        Operation::HasSuffix(..) => has_suffix(left, right()),
+        Operation::NotHasSuffix(..) => !has_suffix(left, right()),
+        Operation::HasSubstring(..) => has_substring(left, right()),
+        Operation::NotHasSubstring(..) => !has_substring(left, right()),
+        Operation::RegexMatches(..) => regex_matches_slow_path(left, right()),
+        Operation::NotRegexMatches(..) => !regex_matches_slow_path(left, right()),
+    }
+}
+
+/// The outcome of the filter for every possible value being filtered, if it's the same for all
+/// of them given the query's arguments, or `None` if the outcome depends on the value.
+///
+/// Only filters whose operand is a variable can have such constant outcomes. For example,
+/// ordering comparisons and string filters against a null operand never pass,
+/// nor do `one_of` filters against an empty list. This lets the interpreter evaluate
+/// such filters once per query, instead of once per value.
+pub(super) fn constant_filter_outcome<LeftT>(
+    filter: &Operation<LeftT, Argument>,
+    arguments: &BTreeMap<Arc<str>, FieldValue>,
+) -> Option<bool>
+where
+    LeftT: Debug + Clone + PartialEq + Eq,
+{
+    let operand = match filter.right() {
+        Some(Argument::Variable(var)) => &arguments[var.variable_name.as_ref()],
+        _ => return None,
+    };
+
+    match (filter, operand) {
+        (
+            Operation::LessThan(..)
+            | Operation::LessThanOrEqual(..)
+            | Operation::GreaterThan(..)
+            | Operation::GreaterThanOrEqual(..)
+            | Operation::HasPrefix(..)
+            | Operation::HasSuffix(..)
+            | Operation::HasSubstring(..)
+            | Operation::RegexMatches(..)
+            | Operation::OneOf(..),
+            FieldValue::Null,
+        ) => Some(false),
+        (
+            Operation::NotHasPrefix(..)
+            | Operation::NotHasSuffix(..)
+            | Operation::NotHasSubstring(..)
+            | Operation::NotRegexMatches(..)
+            | Operation::NotOneOf(..),
+            FieldValue::Null,
+        ) => Some(true),
+        (Operation::OneOf(..), FieldValue::List(values)) if values.is_empty() => Some(false),
+        (Operation::NotOneOf(..), FieldValue::List(values)) if values.is_empty() => Some(true),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{cmp::Ordering, collections::BTreeMap, sync::Arc};
+
+    use async_graphql_parser::types::Type;
+
     use crate::{
-        interpreter::filtering::{equals, greater_than_or_equal, less_than, less_than_or_equal},
-        ir::FieldValue,
+        interpreter::filtering::{
+            contains, equals, greater_than_or_equal, less_than, less_than_or_equal, one_of,
+        },
+        ir::{Argument, FieldValue, LocalField, Operation, VariableRef},
     };
 
     use super::greater_than;
 
+    #[test]
+    fn test_constant_filter_outcomes() {
+        let field = LocalField {
+            field_name: "value".into(),
+            field_type: Type::new("Int").unwrap(),
+        };
+        let var = Argument::Variable(VariableRef {
+            variable_name: "arg".into(),
+            variable_type: Type::new("[Int]").unwrap(),
+        });
+        let test_data = [
+            (
+                Operation::LessThan(field.clone(), var.clone()),
+                FieldValue::Null,
+                Some(false),
+            ),
+            (
+                Operation::LessThan(field.clone(), var.clone()),
+                FieldValue::Int64(1),
+                None,
+            ),
+            (
+                Operation::NotHasPrefix(field.clone(), var.clone()),
+                FieldValue::Null,
+                Some(true),
+            ),
+            (
+                Operation::OneOf(field.clone(), var.clone()),
+                FieldValue::List(vec![]),
+                Some(false),
+            ),
+            (
+                Operation::NotOneOf(field.clone(), var.clone()),
+                FieldValue::Null,
+                Some(true),
+            ),
+            (
+                Operation::OneOf(field.clone(), var.clone()),
+                vec![1i64].into(),
+                None,
+            ),
+            (
+                Operation::Equals(field.clone(), var.clone()),
+                FieldValue::Null,
+                None,
+            ),
+            (
+                Operation::Contains(field.clone(), var.clone()),
+                FieldValue::Null,
+                None,
+            ),
+        ];
+
+        for (filter, operand, expected) in test_data {
+            let arguments: BTreeMap<Arc<str>, FieldValue> = btreemap! {
+                "arg".into() => operand.clone(),
+            };
+            assert_eq!(
+                expected,
+                super::constant_filter_outcome(&filter, &arguments),
+                "{filter:?} {operand:?}"
+            );
+
+            // The constant outcome must match the outcome of evaluating the filter.
+            if let Some(expected) = expected {
+                let values = match &filter {
+                    Operation::NotHasPrefix(..) => vec![FieldValue::Null, "".into(), "a".into()],
+                    _ => vec![FieldValue::Null, FieldValue::Int64(0), FieldValue::Int64(1)],
+                };
+                for value in values {
+                    assert_eq!(
+                        expected,
+                        super::filter_passes(&filter, &value, Some(&operand)),
+                        "{filter:?} {value:?} {operand:?}"
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_integer_strict_inequality_comparisons() {
         let test_data = vec![
@@ -291,6 +501,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_list_inequality_comparisons() {
+        let list = |values: &[&str]| FieldValue::from(values.to_vec());
+        let test_data = vec![
+            (list(&[]), list(&[]), false),
+            (list(&[]), list(&["a"]), true),
+            (list(&["a"]), list(&["a", "b"]), true),
+            (list(&["a", "b"]), list(&["a"]), false),
+            (list(&["a", "z"]), list(&["b"]), true),
+            (list(&["b"]), list(&["a", "z"]), false),
+        ];
+
+        for (left, right, expected_outcome) in test_data {
+            assert_eq!(
+                expected_outcome,
+                less_than(&left, &right),
+                "{left:?} < {right:?}",
+            );
+            assert_eq!(
+                expected_outcome,
+                greater_than(&right, &left),
+                "{right:?} > {left:?}",
+            );
+            assert_eq!(
+                !expected_outcome,
+                greater_than_or_equal(&left, &right),
+                "{left:?} >= {right:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_mixed_list_inequality_comparisons() {
+        let test_data = vec![
+            // Integers compare numerically regardless of their representation.
+            (
+                vec![FieldValue::Int64(1), FieldValue::Uint64(5)],
+                vec![FieldValue::Uint64(1), FieldValue::Int64(7)],
+                Some(Ordering::Less),
+            ),
+            (
+                vec![FieldValue::Uint64(u64::MAX)],
+                vec![FieldValue::Int64(-1)],
+                Some(Ordering::Greater),
+            ),
+            (
+                vec![FieldValue::Int64(3), FieldValue::Int64(2)],
+                vec![FieldValue::Uint64(3), FieldValue::Uint64(2)],
+                Some(Ordering::Equal),
+            ),
+            // Nulls are less than any other element, and equal to each other.
+            (
+                vec![FieldValue::Null, FieldValue::Int64(9)],
+                vec![FieldValue::Int64(-5)],
+                Some(Ordering::Less),
+            ),
+            (
+                vec![FieldValue::Null, FieldValue::Int64(1)],
+                vec![FieldValue::Null, FieldValue::Uint64(2)],
+                Some(Ordering::Less),
+            ),
+            (
+                vec![FieldValue::Null],
+                vec![FieldValue::Null],
+                Some(Ordering::Equal),
+            ),
+            // Nested lists compare the same way.
+            (
+                vec![FieldValue::List(vec![
+                    FieldValue::Int64(1),
+                    FieldValue::Null,
+                ])],
+                vec![FieldValue::List(vec![FieldValue::Uint64(1)])],
+                Some(Ordering::Greater),
+            ),
+            // Elements of different types can't be compared, unless they are never reached.
+            (
+                vec![FieldValue::Int64(1), FieldValue::String("a".into())],
+                vec![FieldValue::Int64(1), FieldValue::Float64(1.5)],
+                None,
+            ),
+            (
+                vec![FieldValue::Int64(1), FieldValue::String("a".into())],
+                vec![FieldValue::Int64(2), FieldValue::Float64(1.5)],
+                Some(Ordering::Less),
+            ),
+        ];
+
+        for (left, right, expected) in test_data {
+            let (left, right) = (FieldValue::List(left), FieldValue::List(right));
+            for (l, r, expected) in [
+                (&left, &right, expected),
+                (&right, &left, expected.map(Ordering::reverse)),
+            ] {
+                assert_eq!(
+                    expected == Some(Ordering::Less),
+                    less_than(l, r),
+                    "{l:?} < {r:?}"
+                );
+                assert_eq!(
+                    matches!(expected, Some(Ordering::Less | Ordering::Equal)),
+                    less_than_or_equal(l, r),
+                    "{l:?} <= {r:?}"
+                );
+                assert_eq!(
+                    expected == Some(Ordering::Greater),
+                    greater_than(l, r),
+                    "{l:?} > {r:?}"
+                );
+                assert_eq!(
+                    matches!(expected, Some(Ordering::Greater | Ordering::Equal)),
+                    greater_than_or_equal(l, r),
+                    "{l:?} >= {r:?}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_integer_equality_comparisons() {
         let test_data = vec![
@@ -419,4 +747,37 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_mixed_integer_list_membership() {
+        let list = FieldValue::List(vec![
+            FieldValue::Uint64(5),
+            FieldValue::Int64(-1),
+            FieldValue::List(vec![FieldValue::Int64(7)]),
+            FieldValue::Null,
+        ]);
+        let test_data = vec![
+            (FieldValue::Int64(5), true),
+            (FieldValue::Uint64(5), true),
+            (FieldValue::Int64(-1), true),
+            (FieldValue::List(vec![FieldValue::Uint64(7)]), true),
+            (FieldValue::Null, true),
+            (FieldValue::Int64(6), false),
+            (FieldValue::Uint64(u64::MAX), false),
+            (FieldValue::Float64(5.0), false),
+        ];
+
+        for (element, expected_outcome) in test_data {
+            assert_eq!(
+                expected_outcome,
+                contains(&list, &element),
+                "{list:?} contains {element:?}",
+            );
+            assert_eq!(
+                expected_outcome,
+                one_of(&element, &list),
+                "{element:?} one_of {list:?}",
+            );
+        }
+    }
 }
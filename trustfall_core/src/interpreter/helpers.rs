@@ -1,8 +1,27 @@
-use std::fmt::Debug;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    hash::Hash,
+    num::NonZeroUsize,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::{ir::FieldValue, schema::Schema};
+use crate::{
+    ir::{Argument, EdgeParameters, FieldValue, LocalField, Operation},
+    schema::Schema,
+};
 
-use super::{ContextIterator, ContextOutcomeIterator, Typename, VertexIterator};
+use super::{
+    filtering::filter_passes,
+    stats::{FanOutIter, FanOutStats},
+    ContextIterator, ContextOutcomeIterator, DataContext, Typename, VertexIterator,
+};
 
 /// Helper for implementing [`BasicAdapter::resolve_property`] and equivalents.
 ///
@@ -52,6 +71,409 @@ pub fn resolve_neighbors_with<'vertex, Vertex: Debug + Clone + 'vertex>(
     }))
 }
 
+/// Like [`resolve_neighbors_with`], but also records how many neighbors each vertex had
+/// in the given [`FanOutStats`].
+///
+/// Neighbors are counted as the query consumes them, and each vertex's count is recorded
+/// once the query is done with that vertex's neighbors. The query doesn't always need
+/// all of them, e.g. when checking whether an edge exists at all, so the recorded counts
+/// reflect the neighbors the query actually used, which is what the adapter had to produce.
+/// Contexts without an active vertex are not recorded.
+///
+/// [`BasicAdapter::resolve_neighbors`]: super::basic_adapter::BasicAdapter::resolve_neighbors
+pub fn resolve_neighbors_with_fan_out<'vertex, Vertex: Debug + Clone + 'vertex>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    mut resolver: impl FnMut(&Vertex) -> VertexIterator<'vertex, Vertex> + 'static,
+    stats: &FanOutStats,
+) -> ContextOutcomeIterator<'vertex, Vertex, VertexIterator<'vertex, Vertex>> {
+    let stats = stats.clone();
+    resolve_neighbors_with(contexts, move |vertex| {
+        Box::new(FanOutIter::new(resolver(vertex), stats.clone()))
+    })
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_neighbors`] and equivalents,
+/// for edges whose neighbors are derived from a list of values stored on the vertex.
+///
+/// For each vertex in the input context iterator, `values` retrieves the list of values,
+/// and `make_vertex` converts each value into a neighboring vertex. For example,
+/// a vertex holding a list of child IDs can expose an edge to vertices wrapping each ID.
+///
+/// [`BasicAdapter::resolve_neighbors`]: super::basic_adapter::BasicAdapter::resolve_neighbors
+pub fn resolve_neighbors_from_property<'vertex, Vertex: Debug + Clone + 'vertex>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    mut values: impl FnMut(&Vertex) -> Vec<FieldValue> + 'static,
+    make_vertex: impl Fn(FieldValue) -> Vertex + 'static,
+) -> ContextOutcomeIterator<'vertex, Vertex, VertexIterator<'vertex, Vertex>> {
+    let make_vertex = Rc::new(make_vertex);
+    resolve_neighbors_with(contexts, move |vertex| {
+        let make_vertex = make_vertex.clone();
+        Box::new(
+            values(vertex)
+                .into_iter()
+                .map(move |value| make_vertex(value)),
+        )
+    })
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_neighbors`] and equivalents,
+/// for adapters whose neighbor lookups are independent of each other and can run concurrently.
+///
+/// Pulls up to `batch_size` contexts at a time from the input iterator, and resolves
+/// the neighbors of all vertices in that batch in parallel, using at most `max_threads`
+/// threads that each resolve one vertex at a time until the batch is done.
+/// Outputs are always produced in the same order as the input contexts, so query results
+/// are the same as if [`resolve_neighbors_with`] had been used instead.
+///
+/// This is useful when each neighbor lookup is a separate blocking I/O operation,
+/// such as a network request. Since the interpreter pulls contexts lazily, larger batches
+/// allow more concurrency at the expense of loading more data than strictly necessary
+/// if the query doesn't end up needing all of its results.
+///
+/// Running the resolver on other threads imposes some extra requirements:
+/// - the vertex type must be [`Send`] + [`Sync`], since vertices are shared with
+///   the resolver threads and the resolved neighbors are sent back from them;
+/// - the resolver function must be [`Sync`], since it is called from multiple threads at once.
+///
+/// All threads are finished by the time each batch's outputs are produced. If the resolver
+/// panics, the panic is propagated to the caller once the batch's other threads are done.
+///
+/// [`BasicAdapter::resolve_neighbors`]: super::basic_adapter::BasicAdapter::resolve_neighbors
+pub fn resolve_neighbors_parallel_with<'vertex, Vertex>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    batch_size: NonZeroUsize,
+    max_threads: NonZeroUsize,
+    resolver: impl Fn(&Vertex) -> Vec<Vertex> + Send + Sync + 'static,
+) -> ContextOutcomeIterator<'vertex, Vertex, VertexIterator<'vertex, Vertex>>
+where
+    Vertex: Debug + Clone + Send + Sync + 'vertex,
+{
+    Box::new(ParallelNeighborsIter {
+        contexts,
+        batch_size,
+        max_threads,
+        resolver,
+        resolved: Default::default(),
+    })
+}
+
+struct ParallelNeighborsIter<'vertex, Vertex: Debug + Clone + 'vertex, F> {
+    contexts: ContextIterator<'vertex, Vertex>,
+    batch_size: NonZeroUsize,
+    max_threads: NonZeroUsize,
+    resolver: F,
+    resolved: VecDeque<(DataContext<Vertex>, Vec<Vertex>)>,
+}
+
+/// Apply `resolver` to each of `items` using at most `max_threads` scoped threads,
+/// returning the outputs in the same order as the items.
+///
+/// If the resolver panics, the panic is resumed on the calling thread
+/// after all threads have finished.
+fn parallel_map<T: Sync, U: Send>(
+    items: &[T],
+    max_threads: NonZeroUsize,
+    resolver: &(impl Fn(&T) -> U + Sync),
+) -> Vec<U> {
+    let next_index = AtomicUsize::new(0);
+    let thread_count = max_threads.get().min(items.len());
+    let results = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..thread_count)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outputs = vec![];
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break outputs;
+                        };
+                        outputs.push((index, resolver(item)));
+                    }
+                })
+            })
+            .collect();
+
+        // Join every thread before resuming any panic, so none are left running.
+        let joined: Vec<_> = handles.into_iter().map(|handle| handle.join()).collect();
+        joined
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|payload| std::panic::resume_unwind(payload)))
+            .collect::<Vec<_>>()
+    });
+
+    let mut outputs: Vec<(usize, U)> = results.into_iter().flatten().collect();
+    outputs.sort_unstable_by_key(|(index, _)| *index);
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+impl<'vertex, Vertex, F> Iterator for ParallelNeighborsIter<'vertex, Vertex, F>
+where
+    Vertex: Debug + Clone + Send + Sync + 'vertex,
+    F: Fn(&Vertex) -> Vec<Vertex> + Send + Sync,
+{
+    type Item = (DataContext<Vertex>, VertexIterator<'vertex, Vertex>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.resolved.is_empty() {
+            let batch: Vec<DataContext<Vertex>> =
+                self.contexts.by_ref().take(self.batch_size.get()).collect();
+
+            let resolver = &self.resolver;
+            let neighbors: Vec<Vec<Vertex>> =
+                parallel_map(&batch, self.max_threads, &|ctx: &DataContext<Vertex>| {
+                    ctx.active_vertex.as_ref().map(resolver).unwrap_or_default()
+                });
+
+            self.resolved.extend(batch.into_iter().zip(neighbors));
+        }
+
+        let (ctx, neighbors) = self.resolved.pop_front()?;
+        let neighbors: VertexIterator<'vertex, Vertex> = Box::new(neighbors.into_iter());
+        Some((ctx, neighbors))
+    }
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_neighbors`] and equivalents,
+/// for edges whose neighbors are identified by IDs and can be fetched many at a time.
+///
+/// Pulls up to `batch_size` contexts at a time from the input iterator, and uses `neighbor_ids`
+/// to get the IDs of the neighbors of each vertex in that batch. All those IDs are then
+/// fetched at once with a single call to `bulk_fetch`, and the fetched vertices are
+/// distributed back to the contexts that referenced them. This replaces one fetch
+/// per neighbor with one fetch per batch, which is usually much faster when each fetch
+/// is a separate request to a database or API.
+///
+/// Each `bulk_fetch` call receives every ID referenced in the batch exactly once, in the order
+/// they were first referenced, and returns the vertices it found keyed by their ID.
+/// IDs missing from the returned map are assumed to not exist, and are skipped.
+/// If the same ID is referenced by multiple vertices, each of them gets a clone
+/// of the fetched vertex.
+///
+/// Since the interpreter pulls contexts lazily, larger batches make fewer `bulk_fetch` calls
+/// at the expense of loading more data than strictly necessary if the query doesn't end up
+/// needing all of its results.
+///
+/// [`BasicAdapter::resolve_neighbors`]: super::basic_adapter::BasicAdapter::resolve_neighbors
+pub fn resolve_neighbors_by_ids<'vertex, Vertex, Id>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    batch_size: NonZeroUsize,
+    neighbor_ids: impl FnMut(&Vertex) -> Vec<Id> + 'vertex,
+    bulk_fetch: impl FnMut(&[Id]) -> HashMap<Id, Vertex> + 'vertex,
+) -> ContextOutcomeIterator<'vertex, Vertex, VertexIterator<'vertex, Vertex>>
+where
+    Vertex: Debug + Clone + 'vertex,
+    Id: Hash + Eq + Clone + 'vertex,
+{
+    Box::new(BulkNeighborsIter {
+        contexts,
+        batch_size,
+        neighbor_ids,
+        bulk_fetch,
+        resolved: Default::default(),
+    })
+}
+
+struct BulkNeighborsIter<'vertex, Vertex: Debug + Clone + 'vertex, IdsF, FetchF> {
+    contexts: ContextIterator<'vertex, Vertex>,
+    batch_size: NonZeroUsize,
+    neighbor_ids: IdsF,
+    bulk_fetch: FetchF,
+    resolved: VecDeque<(DataContext<Vertex>, Vec<Vertex>)>,
+}
+
+impl<'vertex, Vertex, Id, IdsF, FetchF> Iterator
+    for BulkNeighborsIter<'vertex, Vertex, IdsF, FetchF>
+where
+    Vertex: Debug + Clone + 'vertex,
+    Id: Hash + Eq + Clone,
+    IdsF: FnMut(&Vertex) -> Vec<Id>,
+    FetchF: FnMut(&[Id]) -> HashMap<Id, Vertex>,
+{
+    type Item = (DataContext<Vertex>, VertexIterator<'vertex, Vertex>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.resolved.is_empty() {
+            let batch: Vec<(DataContext<Vertex>, Vec<Id>)> = self
+                .contexts
+                .by_ref()
+                .take(self.batch_size.get())
+                .map(|ctx| {
+                    let ids = match ctx.active_vertex.as_ref() {
+                        None => vec![],
+                        Some(vertex) => (self.neighbor_ids)(vertex),
+                    };
+                    (ctx, ids)
+                })
+                .collect();
+
+            let mut seen_ids: HashSet<&Id> = Default::default();
+            let unique_ids: Vec<Id> = batch
+                .iter()
+                .flat_map(|(_, ids)| ids.iter())
+                .filter(|id| seen_ids.insert(*id))
+                .cloned()
+                .collect();
+            let fetched = if unique_ids.is_empty() {
+                Default::default()
+            } else {
+                (self.bulk_fetch)(&unique_ids)
+            };
+
+            self.resolved.extend(batch.into_iter().map(|(ctx, ids)| {
+                let neighbors = ids
+                    .iter()
+                    .filter_map(|id| fetched.get(id).cloned())
+                    .collect();
+                (ctx, neighbors)
+            }));
+        }
+
+        let (ctx, neighbors) = self.resolved.pop_front()?;
+        let neighbors: VertexIterator<'vertex, Vertex> = Box::new(neighbors.into_iter());
+        Some((ctx, neighbors))
+    }
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_property`] and equivalents,
+/// for properties whose resolution may hang, e.g. due to slow I/O.
+///
+/// Like [`resolve_property_with`], but vertices' properties are resolved on a pool of
+/// worker threads, and each vertex is given at most `timeout` to produce a value.
+/// If the resolver takes longer than that, the property's value is [`FieldValue::Null`]
+/// instead. This keeps a single hung resolution from stalling the entire query, so it's
+/// only suitable for nullable properties, and for queries where a missing value is
+/// preferable to a delay.
+///
+/// Threads can't be interrupted, so a resolution that times out keeps its worker busy
+/// until the resolver returns, and its result is then discarded. The pool starts with
+/// one worker, and another is added whenever a resolution times out, up to `max_threads`
+/// workers. Once every worker is busy with a hung resolution, the remaining vertices
+/// time out as well, so the total delay grows with their count. Workers skip
+/// the resolutions that timed out before they got to them, so the resolver isn't called
+/// for values that would be discarded. Idle workers stop
+/// when the returned iterator is dropped, and busy ones stop once their resolution
+/// returns. Since a worker may outlive the query, the resolver gets its own copy of
+/// the vertex, which must therefore be [`Send`] and `'static`. The resolver is shared
+/// with the workers, so it must be [`Send`] + [`Sync`].
+///
+/// Vertices are resolved one at a time, in order. If the resolver panics before
+/// the resolution times out, the panic is propagated to the caller.
+///
+/// [`BasicAdapter::resolve_property`]: super::basic_adapter::BasicAdapter::resolve_property
+pub fn resolve_property_with_timeout<'vertex, Vertex>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    timeout: Duration,
+    max_threads: NonZeroUsize,
+    resolver: impl Fn(&Vertex) -> FieldValue + Send + Sync + 'static,
+) -> ContextOutcomeIterator<'vertex, Vertex, FieldValue>
+where
+    Vertex: Debug + Clone + Send + 'static,
+{
+    let (jobs, job_receiver) = mpsc::channel();
+    Box::new(TimeoutPropertyIter {
+        contexts,
+        timeout,
+        max_threads,
+        resolver: Arc::new(resolver),
+        jobs,
+        job_receiver: Arc::new(Mutex::new(job_receiver)),
+        workers: 0,
+    })
+}
+
+/// A vertex whose property to resolve, the time after which the value is no longer needed,
+/// and where to send the value.
+type PropertyJob<Vertex> = (
+    Vertex,
+    Instant,
+    mpsc::Sender<std::thread::Result<FieldValue>>,
+);
+
+struct TimeoutPropertyIter<'vertex, Vertex: Debug + Clone + 'vertex, F> {
+    contexts: ContextIterator<'vertex, Vertex>,
+    timeout: Duration,
+    max_threads: NonZeroUsize,
+    resolver: Arc<F>,
+    jobs: mpsc::Sender<PropertyJob<Vertex>>,
+    job_receiver: Arc<Mutex<mpsc::Receiver<PropertyJob<Vertex>>>>,
+    workers: usize,
+}
+
+impl<'vertex, Vertex, F> TimeoutPropertyIter<'vertex, Vertex, F>
+where
+    Vertex: Debug + Clone + Send + 'static,
+    F: Fn(&Vertex) -> FieldValue + Send + Sync + 'static,
+{
+    fn spawn_worker(&mut self) {
+        let resolver = self.resolver.clone();
+        let job_receiver = self.job_receiver.clone();
+        std::thread::spawn(move || loop {
+            // Release the lock before resolving, so other workers can take jobs meanwhile.
+            let job = job_receiver
+                .lock()
+                .expect("no worker panics while holding the lock")
+                .recv();
+            let Ok((vertex, deadline, sender)) = job else {
+                // The iterator was dropped, so no more jobs will arrive.
+                break;
+            };
+            if Instant::now() >= deadline {
+                // The resolution already timed out while waiting for a worker.
+                continue;
+            }
+            let value =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| resolver(&vertex)));
+
+            // The receiver is gone if the resolution timed out. That's fine.
+            let _ = sender.send(value);
+        });
+        self.workers += 1;
+    }
+}
+
+impl<'vertex, Vertex, F> Iterator for TimeoutPropertyIter<'vertex, Vertex, F>
+where
+    Vertex: Debug + Clone + Send + 'static,
+    F: Fn(&Vertex) -> FieldValue + Send + Sync + 'static,
+{
+    type Item = (DataContext<Vertex>, FieldValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ctx = self.contexts.next()?;
+        let vertex = match ctx.active_vertex.as_ref() {
+            None => return Some((ctx, FieldValue::Null)),
+            Some(vertex) => vertex.clone(),
+        };
+
+        if self.workers == 0 {
+            self.spawn_worker();
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let deadline = Instant::now() + self.timeout;
+        self.jobs
+            .send((vertex, deadline, sender))
+            .expect("workers stop only once the iterator is dropped");
+
+        let value = match receiver.recv_timeout(self.timeout) {
+            Ok(Ok(value)) => value,
+            Ok(Err(payload)) => std::panic::resume_unwind(payload),
+            Err(RecvTimeoutError::Timeout) => {
+                // The worker resolving this vertex may be hung, so make sure
+                // another one is available for the remaining vertices.
+                if self.workers < self.max_threads.get() {
+                    self.spawn_worker();
+                }
+                FieldValue::Null
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                unreachable!("workers always reply to the jobs they take")
+            }
+        };
+        Some((ctx, value))
+    }
+}
+
 /// Helper for implementing [`BasicAdapter::resolve_coercion`] and equivalents.
 ///
 /// Takes a coercion-resolver function and applies it over each of the vertices
@@ -71,6 +493,148 @@ pub fn resolve_coercion_with<'vertex, Vertex: Debug + Clone + 'vertex>(
     }))
 }
 
+/// A vertex tagged with its concrete type, as produced by [`resolve_neighbors_typed`].
+///
+/// Its [`Typename`] implementation returns the tagged type without inspecting the vertex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedVertex<Vertex> {
+    vertex: Vertex,
+    typename: &'static str,
+}
+
+impl<Vertex> TypedVertex<Vertex> {
+    pub fn new(vertex: Vertex, typename: &'static str) -> Self {
+        Self { vertex, typename }
+    }
+
+    pub fn vertex(&self) -> &Vertex {
+        &self.vertex
+    }
+
+    pub fn into_inner(self) -> Vertex {
+        self.vertex
+    }
+}
+
+impl<Vertex> Typename for TypedVertex<Vertex> {
+    fn typename(&self) -> &'static str {
+        self.typename
+    }
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_neighbors`] and equivalents,
+/// for adapters whose vertices are tagged with their concrete type.
+///
+/// Like [`resolve_neighbors_with`], except that the neighbor-resolver function returns
+/// each neighboring vertex together with the name of its concrete type.
+/// The neighbors are then wrapped in [`TypedVertex`] so that the type needn't be
+/// recomputed later. This is useful for edges whose neighbors may be of several types,
+/// when the type of each neighbor is known at the time it's loaded.
+///
+/// Using this helper is optional, and doesn't change how the interpreter handles coercions:
+/// the adapter's [`BasicAdapter::resolve_coercion`] is still called as usual.
+/// Implementing it with [`resolve_coercion_by_typename`] answers coercions
+/// directly from each vertex's tagged type.
+///
+/// [`BasicAdapter::resolve_neighbors`]: super::basic_adapter::BasicAdapter::resolve_neighbors
+/// [`BasicAdapter::resolve_coercion`]: super::basic_adapter::BasicAdapter::resolve_coercion
+#[allow(dead_code, clippy::type_complexity)]
+pub fn resolve_neighbors_typed<'vertex, Vertex: Debug + Clone + 'vertex>(
+    contexts: ContextIterator<'vertex, TypedVertex<Vertex>>,
+    mut resolver: impl FnMut(&Vertex) -> Box<dyn Iterator<Item = (Vertex, &'static str)> + 'vertex>
+        + 'static,
+) -> ContextOutcomeIterator<
+    'vertex,
+    TypedVertex<Vertex>,
+    VertexIterator<'vertex, TypedVertex<Vertex>>,
+> {
+    resolve_neighbors_with(contexts, move |vertex| {
+        Box::new(
+            resolver(vertex.vertex())
+                .map(|(neighbor, typename)| TypedVertex::new(neighbor, typename)),
+        )
+    })
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_coercion`] and equivalents,
+/// for vertex types whose [`Typename`] implementation is accurate.
+///
+/// Each vertex can be coerced to `coerce_to_type` if its [`Typename::typename`]
+/// is that type or one of its subtypes, according to the schema.
+///
+/// [`BasicAdapter::resolve_coercion`]: super::basic_adapter::BasicAdapter::resolve_coercion
+pub fn resolve_coercion_by_typename<'vertex, Vertex: Typename + Debug + Clone + 'vertex>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    schema: &Schema,
+    coerce_to_type: &str,
+) -> ContextOutcomeIterator<'vertex, Vertex, bool> {
+    let subtypes: BTreeSet<String> = match schema.subtypes(coerce_to_type) {
+        Some(iter) => iter.map(String::from).collect(),
+        None => panic!("type {coerce_to_type} is not part of this schema"),
+    };
+    resolve_coercion_with(contexts, move |vertex| subtypes.contains(vertex.typename()))
+}
+
+/// Helper for implementing [`BasicAdapter::resolve_property`] and equivalents,
+/// for properties whose implementation depends on the vertex's concrete type.
+///
+/// Takes a table of `(type name, property-resolver function)` pairs. Each vertex
+/// in the input context iterator is resolved with the function whose type name matches
+/// the vertex's [`Typename::typename`]. A property shared by several types may use
+/// the same function in several entries. Vertices whose type isn't in the table
+/// resolve to [`FieldValue::Null`].
+///
+/// [`BasicAdapter::resolve_property`]: super::basic_adapter::BasicAdapter::resolve_property
+#[allow(dead_code, clippy::type_complexity)]
+pub fn resolve_property_by_subtype<'vertex, Vertex: Typename + Debug + Clone + 'vertex>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    resolvers: &[(&'static str, fn(&Vertex) -> FieldValue)],
+) -> ContextOutcomeIterator<'vertex, Vertex, FieldValue> {
+    let resolvers = resolvers.to_vec();
+    Box::new(contexts.map(move |ctx| match ctx.active_vertex.as_ref() {
+        None => (ctx, FieldValue::Null),
+        Some(vertex) => {
+            let typename = vertex.typename();
+            let value = resolvers
+                .iter()
+                .find(|(type_name, _)| *type_name == typename)
+                .map_or(FieldValue::Null, |(_, resolver)| resolver(vertex));
+            (ctx, value)
+        }
+    }))
+}
+
+/// Helper for implementing resolver methods whose outcome depends on the whole [`DataContext`],
+/// not just its active vertex, and whose resolution may fail.
+///
+/// Applies the resolver function to each context in the input context iterator, one at a time.
+/// Since the resolver receives the context itself, it is called for every context,
+/// including ones whose active vertex is `None`. It's up to the resolver to produce
+/// the outcome required for such contexts, such as [`FieldValue::Null`] for properties.
+///
+/// The interpreter does not yet have a way for adapters to report errors, so errors returned
+/// by the resolver are passed to `on_error` together with their context, and the outcome
+/// it produces is used in their place. For example, `on_error` may record the error
+/// for the adapter to report once the query's results are consumed, and produce
+/// [`FieldValue::Null`] for a property or an empty iterator for an edge.
+pub fn resolve_with_context<'vertex, Vertex, OutcomeT, ErrorT>(
+    contexts: ContextIterator<'vertex, Vertex>,
+    mut resolver: impl FnMut(&DataContext<Vertex>) -> Result<OutcomeT, ErrorT> + 'vertex,
+    mut on_error: impl FnMut(&DataContext<Vertex>, ErrorT) -> OutcomeT + 'vertex,
+) -> ContextOutcomeIterator<'vertex, Vertex, OutcomeT>
+where
+    Vertex: Debug + Clone + 'vertex,
+    OutcomeT: 'vertex,
+{
+    Box::new(contexts.map(move |ctx| {
+        let outcome = match resolver(&ctx) {
+            Ok(outcome) => outcome,
+            Err(e) => on_error(&ctx, e),
+        };
+        (ctx, outcome)
+    }))
+}
+
 /// Helper for making property resolver functions based on fields.
 ///
 /// Generally used with [`resolve_property_with`].
@@ -366,16 +930,182 @@ pub fn resolve_typename<'a, Vertex: Typename + Debug + Clone + 'a>(
     }
 }
 
+/// Helper for implementing [`BasicAdapter::resolve_starting_vertices`] and equivalents,
+/// for starting edges that take a list-typed parameter with one element per starting vertex.
+///
+/// For example, given the starting edge `Users(names: [String!]!): [User!]!`, this makes
+/// one call to `resolver` for each of the names in the list, in order, and produces the
+/// vertices it returns. Elements for which `resolver` returns `None`, e.g. because no such
+/// vertex exists, don't produce a vertex. Duplicate list elements are not deduplicated,
+/// so they produce one starting vertex each. `null` list elements, and a `null` or
+/// absent parameter, are skipped without calling `resolver`.
+///
+/// Elements are resolved lazily, as the interpreter pulls starting vertices.
+///
+/// Panics if the parameter's value is neither a list nor `null`. The interpreter
+/// type-checks edge parameters against the schema, so this indicates that the parameter
+/// isn't list-typed in the schema.
+///
+/// [`BasicAdapter::resolve_starting_vertices`]: super::basic_adapter::BasicAdapter::resolve_starting_vertices
+pub fn resolve_starting_vertices_from_list<'vertex, Vertex: Debug + Clone + 'vertex>(
+    parameters: &EdgeParameters,
+    parameter_name: &str,
+    mut resolver: impl FnMut(&FieldValue) -> Option<Vertex> + 'vertex,
+) -> VertexIterator<'vertex, Vertex> {
+    let elements = match parameters.get(parameter_name) {
+        None | Some(FieldValue::Null) => vec![],
+        Some(FieldValue::List(elements)) => elements.clone(),
+        Some(value) => {
+            panic!("edge parameter \"{parameter_name}\" is not a list: {value:?}")
+        }
+    };
+
+    Box::new(
+        elements
+            .into_iter()
+            .filter(|element| !matches!(element, FieldValue::Null))
+            .filter_map(move |element| resolver(&element)),
+    )
+}
+
+/// Check whether a property value passes all the given filters, as the interpreter would.
+///
+/// Filter operands that are query variables are looked up in `arguments`. Filters whose operand
+/// can't be determined this way are assumed to pass: this includes filters that use a tagged
+/// value, and filters whose variable has no value in `arguments`. Adapters that apply filters
+/// themselves, e.g. to avoid fetching data that would be discarded, can use this to ensure
+/// that their filtering matches the interpreter's exactly. The interpreter still applies
+/// all filters to the vertices the adapter produces.
+///
+/// Values are compared exactly as in the interpreter. For example, an `Enum` value never equals
+/// a `String` value with the same contents. Adapters that store enum values as strings
+/// should convert them to `Enum` values, or compare them using [`FieldValue::enum_eq_str`].
+///
+/// Panics if a filter's operand isn't a valid value for that filter operation,
+/// e.g. a `one_of` filter whose variable value isn't a list.
+pub fn evaluate_filter(
+    value: &FieldValue,
+    filters: &[Operation<LocalField, Argument>],
+    arguments: &BTreeMap<Arc<str>, FieldValue>,
+) -> bool {
+    filters.iter().all(|filter| match filter.right() {
+        None => filter_passes(filter, value, None),
+        Some(Argument::Variable(var)) => match arguments.get(&var.variable_name) {
+            Some(operand) => filter_passes(filter, value, Some(operand)),
+            None => true,
+        },
+        Some(Argument::Tag(..)) => true,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fmt::Debug;
+    use std::{
+        cell::RefCell,
+        collections::{BTreeMap, HashMap},
+        fmt::Debug,
+        num::NonZeroUsize,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
 
     use crate::{
-        interpreter::{helpers::resolve_typename, DataContext, Typename},
-        ir::FieldValue,
+        interpreter::{
+            helpers::{
+                evaluate_filter, resolve_coercion_by_typename, resolve_neighbors_by_ids,
+                resolve_neighbors_from_property, resolve_neighbors_parallel_with,
+                resolve_neighbors_typed, resolve_neighbors_with_fan_out,
+                resolve_property_by_subtype, resolve_property_with_timeout,
+                resolve_starting_vertices_from_list, resolve_typename, resolve_with_context,
+                TypedVertex,
+            },
+            stats::FanOutStats,
+            ContextIterator, DataContext, Typename,
+        },
+        ir::{EdgeParameters, FieldValue},
+        numbers_interpreter::parse_numbers_query,
         schema::Schema,
     };
 
+    #[test]
+    fn filter_evaluation() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(max: 10) {
+        value @tag(name: "prior")
+
+        successor {
+            value @output
+                  @filter(op: ">=", value: ["$min"])
+                  @filter(op: "not_one_of", value: ["$excluded"])
+                  @filter(op: "is_not_null")
+                  @filter(op: ">", value: ["%prior"])
+        }
+    }
+}"#,
+        );
+        let successor = query.ir_query.root_component.edges.values().next().unwrap();
+        let filters = &query.ir_query.root_component.vertices[&successor.to_vid].filters;
+        let arguments: BTreeMap<Arc<str>, FieldValue> = btreemap! {
+            "min".into() => FieldValue::Int64(3),
+            "excluded".into() => vec![5i64, 7].into(),
+        };
+
+        // The tagged value is unknown, so that filter is assumed to pass.
+        let passing: Vec<_> = (0i64..10)
+            .filter(|value| evaluate_filter(&FieldValue::Int64(*value), filters, &arguments))
+            .collect();
+        assert_eq!(vec![3, 4, 6, 8, 9], passing);
+        assert!(!evaluate_filter(&FieldValue::Null, filters, &arguments));
+
+        // Filters whose variables have no values are assumed to pass too.
+        let only_min: BTreeMap<Arc<str>, FieldValue> = btreemap! {
+            "min".into() => FieldValue::Uint64(8),
+        };
+        let passing: Vec<_> = (0i64..10)
+            .filter(|value| evaluate_filter(&FieldValue::Int64(*value), filters, &only_min))
+            .collect();
+        assert_eq!(vec![8, 9], passing);
+    }
+
+    #[test]
+    fn starting_vertices_from_list_parameter() {
+        let parameters = EdgeParameters::new(Arc::new(btreemap! {
+            "names".into() => FieldValue::List(vec![
+                "alice".into(),
+                FieldValue::Null,
+                "nobody".into(),
+                "bob".into(),
+                "alice".into(),
+            ]),
+            "missing".into() => FieldValue::Null,
+        }));
+
+        let mut resolved_names = vec![];
+        let vertices: Vec<String> =
+            resolve_starting_vertices_from_list(&parameters, "names", |value| {
+                let name = value.as_str().unwrap().to_owned();
+                resolved_names.push(name.clone());
+                (name != "nobody").then_some(name)
+            })
+            .collect();
+        assert_eq!(vec!["alice", "bob", "alice"], vertices);
+        assert_eq!(vec!["alice", "nobody", "bob", "alice"], resolved_names);
+
+        for absent in ["missing", "nonexistent"] {
+            let mut vertices =
+                resolve_starting_vertices_from_list(&parameters, absent, |_| -> Option<String> {
+                    unreachable!("no elements to resolve")
+                });
+            assert!(vertices.next().is_none());
+        }
+    }
+
     #[test]
     fn typename_resolved_statically() {
         #[derive(Debug, Clone)]
@@ -398,8 +1128,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 
 type RootSchemaQuery {
@@ -419,4 +1149,493 @@ type Vertex {
 
         assert_eq!(vec![FieldValue::from("Vertex")], outputs);
     }
+
+    #[test]
+    fn typed_neighbors_and_coercion() {
+        let schema = Schema::parse(
+            "\
+schema {
+    query: RootSchemaQuery
+}
+directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
+directive @tag(name: String) on FIELD
+directive @output(name: String) on FIELD
+directive @optional on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
+directive @transform(op: String!) on FIELD
+
+type RootSchemaQuery {
+    Node: [Node!]!
+}
+
+interface Node {
+    value: Int
+    neighbor: [Node!]!
+}
+
+type Even implements Node {
+    value: Int
+    neighbor: [Node!]!
+}
+
+type Odd implements Node {
+    value: Int
+    neighbor: [Node!]!
+}",
+        )
+        .expect("failed to parse schema");
+
+        let parity = |n: i64| if n % 2 == 0 { "Even" } else { "Odd" };
+        let contexts = Box::new(
+            [Some(TypedVertex::new(1i64, "Odd")), None]
+                .into_iter()
+                .map(DataContext::new),
+        );
+        let neighbors: Vec<_> = resolve_neighbors_typed(contexts, move |n: &i64| {
+            Box::new((n + 1..=n + 3).map(move |m| (m, parity(m))))
+        })
+        .flat_map(|(_ctx, neighbors)| neighbors)
+        .collect();
+        assert_eq!(
+            vec![
+                TypedVertex::new(2, "Even"),
+                TypedVertex::new(3, "Odd"),
+                TypedVertex::new(4, "Even"),
+            ],
+            neighbors
+        );
+
+        let contexts = Box::new(
+            neighbors
+                .into_iter()
+                .map(Some)
+                .chain([None])
+                .map(DataContext::new),
+        );
+        let outcomes: Vec<_> = resolve_coercion_by_typename(contexts, &schema, "Even")
+            .map(|(ctx, can_coerce)| (ctx.active_vertex().map(|v| *v.vertex()), can_coerce))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(2), true),
+                (Some(3), false),
+                (Some(4), true),
+                (None, false)
+            ],
+            outcomes
+        );
+
+        // Every vertex can be coerced to its supertype.
+        let contexts = Box::new(std::iter::once(DataContext::new(Some(TypedVertex::new(
+            3i64, "Odd",
+        )))));
+        assert!(resolve_coercion_by_typename(contexts, &schema, "Node").all(|(_, outcome)| outcome));
+    }
+
+    #[test]
+    fn parallel_neighbors_preserve_input_order() {
+        let inputs = vec![Some(3u64), None, Some(1), Some(0), Some(5), None, Some(2)];
+        let contexts = Box::new(
+            inputs
+                .clone()
+                .into_iter()
+                .map(DataContext::new)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        );
+
+        let outputs: Vec<_> = resolve_neighbors_parallel_with(
+            contexts,
+            NonZeroUsize::new(3).unwrap(),
+            NonZeroUsize::new(2).unwrap(),
+            |vertex: &u64| {
+                // Make later vertices finish first, to check that ordering is preserved.
+                std::thread::sleep(Duration::from_millis(10 * (5 - vertex.min(&5))));
+                (0..*vertex).collect()
+            },
+        )
+        .map(|(ctx, neighbors)| (ctx.active_vertex().copied(), neighbors.collect::<Vec<_>>()))
+        .collect();
+
+        let expected: Vec<_> = inputs
+            .into_iter()
+            .map(|vertex| (vertex, (0..vertex.unwrap_or_default()).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(expected, outputs);
+    }
+
+    #[test]
+    fn parallel_neighbors_use_bounded_threads() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let contexts = Box::new((0..12u64).map(|vertex| DataContext::new(Some(vertex))));
+
+        let resolver_running = running.clone();
+        let resolver_max_running = max_running.clone();
+        let outputs = resolve_neighbors_parallel_with(
+            contexts,
+            NonZeroUsize::new(8).unwrap(),
+            NonZeroUsize::new(3).unwrap(),
+            move |vertex: &u64| {
+                let now_running = resolver_running.fetch_add(1, Ordering::SeqCst) + 1;
+                resolver_max_running.fetch_max(now_running, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(5));
+                resolver_running.fetch_sub(1, Ordering::SeqCst);
+                vec![*vertex]
+            },
+        )
+        .count();
+
+        assert_eq!(12, outputs);
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "no neighbors for vertex 2")]
+    fn parallel_neighbors_propagate_resolver_panics() {
+        let contexts = Box::new((0..4u64).map(|vertex| DataContext::new(Some(vertex))));
+        let _ = resolve_neighbors_parallel_with(
+            contexts,
+            NonZeroUsize::new(4).unwrap(),
+            NonZeroUsize::new(2).unwrap(),
+            |vertex: &u64| {
+                assert_ne!(*vertex, 2, "no neighbors for vertex {vertex}");
+                vec![]
+            },
+        )
+        .count();
+    }
+
+    #[test]
+    fn neighbor_fan_out_is_recorded() {
+        let stats = FanOutStats::new();
+        assert_eq!(None, stats.average());
+
+        // Vertex `n` has `n` neighbors.
+        let inputs = vec![Some(3u64), None, Some(0), Some(3), Some(1)];
+        let contexts = Box::new(inputs.into_iter().map(DataContext::new));
+        let outputs: Vec<_> =
+            resolve_neighbors_with_fan_out(contexts, |vertex: &u64| Box::new(0..*vertex), &stats)
+                .collect();
+        // Counts are recorded once the neighbors have been consumed.
+        assert_eq!(0, stats.expansions());
+        for (_, neighbors) in outputs {
+            neighbors.count();
+        }
+
+        assert_eq!(btreemap! { 0 => 1, 1 => 1, 3 => 2 }, stats.histogram());
+        assert_eq!(4, stats.expansions());
+        assert_eq!(7, stats.total_neighbors());
+        assert_eq!(Some(1.75), stats.average());
+        assert_eq!(Some(3), stats.max());
+
+        // Only the neighbors that were consumed are counted.
+        let contexts = Box::new(std::iter::once(DataContext::new(Some(10u64))));
+        let clone = stats.clone();
+        for (_, mut neighbors) in
+            resolve_neighbors_with_fan_out(contexts, |vertex: &u64| Box::new(0..*vertex), &clone)
+        {
+            assert_eq!(Some(0), neighbors.next());
+        }
+        assert_eq!(btreemap! { 0 => 1, 1 => 2, 3 => 2 }, stats.histogram());
+    }
+
+    #[test]
+    fn neighbors_fetched_in_bulk_by_id() {
+        // Vertex `n` has neighbors with IDs `n / 2` and `n + 1`, of which only IDs up to 4 exist.
+        let inputs = vec![Some(3u64), None, Some(2), Some(4), Some(6)];
+        let contexts = Box::new(
+            inputs
+                .clone()
+                .into_iter()
+                .map(DataContext::new)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        );
+
+        let fetches = Rc::new(RefCell::new(vec![]));
+        let recorded_fetches = fetches.clone();
+        let outputs: Vec<_> = resolve_neighbors_by_ids(
+            contexts,
+            NonZeroUsize::new(3).unwrap(),
+            |vertex: &u64| vec![vertex / 2, vertex + 1],
+            move |ids: &[u64]| {
+                recorded_fetches.borrow_mut().push(ids.to_vec());
+                ids.iter()
+                    .filter(|id| **id <= 4)
+                    .map(|id| (*id, *id))
+                    .collect::<HashMap<_, _>>()
+            },
+        )
+        .map(|(ctx, neighbors)| (ctx.active_vertex().copied(), neighbors.collect::<Vec<_>>()))
+        .collect();
+
+        assert_eq!(
+            vec![
+                (Some(3), vec![1, 4]),
+                (None, vec![]),
+                (Some(2), vec![1, 3]),
+                (Some(4), vec![2]),
+                (Some(6), vec![3]),
+            ],
+            outputs
+        );
+
+        // One fetch per batch of contexts, without duplicate IDs.
+        assert_eq!(vec![vec![1, 4, 3], vec![2, 5, 3, 7]], *fetches.borrow());
+    }
+
+    #[test]
+    fn property_resolution_times_out() {
+        let inputs = vec![Some(1u64), None, Some(2), Some(3)];
+        let resolve = |max_threads: usize| -> Vec<_> {
+            let contexts = Box::new(
+                inputs
+                    .clone()
+                    .into_iter()
+                    .map(DataContext::new)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            );
+            resolve_property_with_timeout(
+                contexts,
+                Duration::from_millis(200),
+                NonZeroUsize::new(max_threads).unwrap(),
+                |vertex: &u64| {
+                    if *vertex == 2 {
+                        // Simulate a hung resolution.
+                        std::thread::sleep(Duration::from_secs(5));
+                    }
+                    FieldValue::Uint64(vertex * 10)
+                },
+            )
+            .map(|(ctx, value)| (ctx.active_vertex().copied(), value))
+            .collect()
+        };
+
+        // Another worker resolves the vertices after the hung one.
+        assert_eq!(
+            vec![
+                (Some(1), FieldValue::Uint64(10)),
+                (None, FieldValue::Null),
+                (Some(2), FieldValue::Null),
+                (Some(3), FieldValue::Uint64(30)),
+            ],
+            resolve(2),
+        );
+
+        // With its only worker hung, the remaining vertices time out too.
+        assert_eq!(
+            vec![
+                (Some(1), FieldValue::Uint64(10)),
+                (None, FieldValue::Null),
+                (Some(2), FieldValue::Null),
+                (Some(3), FieldValue::Null),
+            ],
+            resolve(1),
+        );
+    }
+
+    #[test]
+    fn timed_out_property_resolutions_are_skipped() {
+        let resolved = Arc::new(Mutex::new(vec![]));
+        let contexts = Box::new((1u64..=3).map(|v| DataContext::new(Some(v))));
+        let outputs: Vec<_> = resolve_property_with_timeout(
+            contexts,
+            Duration::from_millis(50),
+            NonZeroUsize::new(1).unwrap(),
+            {
+                let resolved = resolved.clone();
+                move |vertex: &u64| {
+                    resolved.lock().unwrap().push(*vertex);
+                    if *vertex == 1 {
+                        // Simulate a hung resolution, which blocks the only worker.
+                        std::thread::sleep(Duration::from_millis(500));
+                    }
+                    FieldValue::Uint64(*vertex)
+                }
+            },
+        )
+        .map(|(_, value)| value)
+        .collect();
+        assert_eq!(vec![FieldValue::Null; 3], outputs);
+
+        // Once the hung resolution returns, the worker skips the jobs that timed out
+        // while it was busy, instead of resolving values no one will read.
+        std::thread::sleep(Duration::from_millis(1000));
+        assert_eq!(vec![1], *resolved.lock().unwrap());
+    }
+
+    #[test]
+    fn property_resolution_with_timeout_propagates_panics() {
+        let contexts = Box::new(vec![DataContext::new(Some(1u64))].into_iter());
+        let mut outputs = resolve_property_with_timeout(
+            contexts,
+            Duration::from_secs(5),
+            NonZeroUsize::new(1).unwrap(),
+            |_: &u64| panic!("resolver failed"),
+        );
+
+        let payload =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| outputs.next())).unwrap_err();
+        assert_eq!(Some(&"resolver failed"), payload.downcast_ref::<&str>());
+    }
+
+    #[test]
+    fn neighbors_from_property_values() {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        enum Vertex {
+            Story(Vec<i64>),
+            Comment(i64),
+        }
+
+        let contexts = Box::new(
+            vec![
+                DataContext::new(Some(Vertex::Story(vec![3, 1, 2]))),
+                DataContext::new(None),
+                DataContext::new(Some(Vertex::Story(vec![]))),
+            ]
+            .into_iter(),
+        );
+
+        let outputs: Vec<_> = resolve_neighbors_from_property(
+            contexts,
+            |vertex| match vertex {
+                Vertex::Story(kids) => kids.iter().copied().map(FieldValue::Int64).collect(),
+                Vertex::Comment(_) => unreachable!(),
+            },
+            |value| Vertex::Comment(value.as_i64().unwrap()),
+        )
+        .map(|(_ctx, neighbors)| neighbors.collect::<Vec<_>>())
+        .collect();
+
+        assert_eq!(
+            vec![
+                vec![Vertex::Comment(3), Vertex::Comment(1), Vertex::Comment(2)],
+                vec![],
+                vec![],
+            ],
+            outputs
+        );
+    }
+
+    #[test]
+    fn property_by_subtype() {
+        #[derive(Debug, Clone)]
+        enum Vertex {
+            Story { title: &'static str },
+            Job { title: &'static str },
+            Comment,
+        }
+
+        impl Typename for Vertex {
+            fn typename(&self) -> &'static str {
+                match self {
+                    Vertex::Story { .. } => "Story",
+                    Vertex::Job { .. } => "Job",
+                    Vertex::Comment => "Comment",
+                }
+            }
+        }
+
+        let contexts = Box::new(
+            vec![
+                DataContext::new(Some(Vertex::Story { title: "story" })),
+                DataContext::new(None),
+                DataContext::new(Some(Vertex::Comment)),
+                DataContext::new(Some(Vertex::Job { title: "job" })),
+            ]
+            .into_iter(),
+        );
+
+        let outputs: Vec<_> = resolve_property_by_subtype(
+            contexts,
+            &[
+                ("Story", |vertex| match vertex {
+                    Vertex::Story { title } => (*title).into(),
+                    _ => unreachable!(),
+                }),
+                ("Job", |vertex| match vertex {
+                    Vertex::Job { title } => format!("{title}!").into(),
+                    _ => unreachable!(),
+                }),
+            ],
+        )
+        .map(|(_ctx, value)| value)
+        .collect();
+
+        assert_eq!(
+            vec![
+                FieldValue::from("story"),
+                FieldValue::Null,
+                FieldValue::Null,
+                FieldValue::from("job!"),
+            ],
+            outputs
+        );
+    }
+
+    fn numbered_contexts() -> ContextIterator<'static, u64> {
+        Box::new(
+            vec![
+                DataContext::new(Some(1)),
+                DataContext::new(None),
+                DataContext::new(Some(2)),
+            ]
+            .into_iter(),
+        )
+    }
+
+    #[test]
+    fn resolve_with_whole_context() {
+        let outputs: Vec<_> = resolve_with_context(
+            numbered_contexts(),
+            |ctx| {
+                Ok::<_, String>(match ctx.active_vertex() {
+                    Some(vertex) => FieldValue::Uint64(vertex * 10),
+                    None => FieldValue::Null,
+                })
+            },
+            |_, e| unreachable!("{e}"),
+        )
+        .map(|(ctx, value)| (ctx.active_vertex().copied(), value))
+        .collect();
+
+        assert_eq!(
+            vec![
+                (Some(1), FieldValue::Uint64(10)),
+                (None, FieldValue::Null),
+                (Some(2), FieldValue::Uint64(20)),
+            ],
+            outputs
+        );
+    }
+
+    #[test]
+    fn resolve_with_context_error() {
+        let errors = Rc::new(RefCell::new(vec![]));
+        let recorded_errors = errors.clone();
+        let outputs: Vec<_> = resolve_with_context(
+            numbered_contexts(),
+            |ctx| match ctx.active_vertex() {
+                Some(2) => Err("no data for vertex 2"),
+                _ => Ok(true),
+            },
+            move |ctx, e| {
+                recorded_errors
+                    .borrow_mut()
+                    .push((ctx.active_vertex().copied(), e));
+                false
+            },
+        )
+        .map(|(ctx, outcome)| (ctx.active_vertex().copied(), outcome))
+        .collect();
+
+        assert_eq!(
+            vec![(Some(1), true), (None, true), (Some(2), false)],
+            outputs
+        );
+        assert_eq!(vec![(Some(2), "no data for vertex 2")], *errors.borrow());
+    }
 }
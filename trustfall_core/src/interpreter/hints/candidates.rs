@@ -0,0 +1,708 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt::{self, Debug, Display, Formatter},
+    hash::{BuildHasher, Hasher},
+    ops::Bound,
+};
+
+use crate::{
+    interpreter::filtering::{
+        equals, greater_than, greater_than_or_equal, less_than, less_than_or_equal,
+    },
+    ir::{hash_normalized, FieldValue},
+};
+
+/// Candidate values for a property, as deduced from the query's filters.
+///
+/// Both `Debug` and `Display` use a compact rendering meant for logs:
+/// `Single(5)`, `Multiple([1, 2, 3])`, `Range(>=5, <10)`, `All`, `Impossible`.
+/// `Debug` renders the contained values with their own `Debug` impl,
+/// while `Display` uses their `Display` impl.
+#[non_exhaustive]
+#[derive(Clone, PartialEq, Eq)]
+pub enum CandidateValue<T> {
+    /// No value can satisfy the query's constraints.
+    Impossible,
+
+    /// Only this specific value can satisfy the query's constraints.
+    Single(T),
+
+    /// Only one of these values can satisfy the query's constraints.
+    Multiple(Vec<T>),
+
+    /// Only values within this range can satisfy the query's constraints.
+    Range(Range<T>),
+
+    /// The query does not constrain this property's value.
+    All,
+}
+
+/// A range of values, where each endpoint may be inclusive, exclusive, or unbounded.
+///
+/// Endpoints may be of any type that supports ordering filters like `>=`, such as numbers,
+/// strings, and datetimes. Values are ordered the same way those filters order them,
+/// so strings compare lexicographically and datetimes chronologically.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Range<T> {
+    start: Bound<T>,
+    end: Bound<T>,
+}
+
+impl<T> Range<T> {
+    pub fn new(start: Bound<T>, end: Bound<T>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> Bound<&T> {
+        self.start.as_ref()
+    }
+
+    pub fn end(&self) -> Bound<&T> {
+        self.end.as_ref()
+    }
+
+    fn fmt_with(
+        &self,
+        f: &mut Formatter<'_>,
+        fmt_item: fn(&T, &mut Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        write!(f, "Range(")?;
+        let mut wrote_start = true;
+        match &self.start {
+            Bound::Included(x) => {
+                write!(f, ">=")?;
+                fmt_item(x, f)?;
+            }
+            Bound::Excluded(x) => {
+                write!(f, ">")?;
+                fmt_item(x, f)?;
+            }
+            Bound::Unbounded => wrote_start = false,
+        }
+        match &self.end {
+            Bound::Included(x) => {
+                if wrote_start {
+                    write!(f, ", ")?;
+                }
+                write!(f, "<=")?;
+                fmt_item(x, f)?;
+            }
+            Bound::Excluded(x) => {
+                if wrote_start {
+                    write!(f, ", ")?;
+                }
+                write!(f, "<")?;
+                fmt_item(x, f)?;
+            }
+            Bound::Unbounded if !wrote_start => write!(f, "..")?,
+            Bound::Unbounded => {}
+        }
+        write!(f, ")")
+    }
+}
+
+impl<T> CandidateValue<T> {
+    fn fmt_with(
+        &self,
+        f: &mut Formatter<'_>,
+        fmt_item: fn(&T, &mut Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        match self {
+            CandidateValue::Impossible => write!(f, "Impossible"),
+            CandidateValue::Single(x) => {
+                write!(f, "Single(")?;
+                fmt_item(x, f)?;
+                write!(f, ")")
+            }
+            CandidateValue::Multiple(values) => {
+                write!(f, "Multiple([")?;
+                for (idx, x) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    fmt_item(x, f)?;
+                }
+                write!(f, "])")
+            }
+            CandidateValue::Range(range) => range.fmt_with(f, fmt_item),
+            CandidateValue::All => write!(f, "All"),
+        }
+    }
+}
+
+impl Range<&FieldValue> {
+    fn contains(&self, value: &FieldValue) -> bool {
+        let after_start = match self.start {
+            Bound::Included(start) => greater_than_or_equal(value, start),
+            Bound::Excluded(start) => greater_than(value, start),
+            Bound::Unbounded => true,
+        };
+        let before_end = match self.end {
+            Bound::Included(end) => less_than_or_equal(value, end),
+            Bound::Excluded(end) => less_than(value, end),
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+}
+
+/// Of two lower bounds, the one that admits fewer values.
+fn tighter_start<'a>(
+    left: Bound<&'a FieldValue>,
+    right: Bound<&'a FieldValue>,
+) -> Bound<&'a FieldValue> {
+    match (left, right) {
+        (Bound::Unbounded, other) | (other, Bound::Unbounded) => other,
+        (Bound::Included(l) | Bound::Excluded(l), Bound::Included(r) | Bound::Excluded(r)) => {
+            if greater_than(l, r) {
+                left
+            } else if greater_than(r, l) {
+                right
+            } else if matches!(left, Bound::Excluded(_)) {
+                left
+            } else {
+                right
+            }
+        }
+    }
+}
+
+/// Of two upper bounds, the one that admits fewer values.
+fn tighter_end<'a>(
+    left: Bound<&'a FieldValue>,
+    right: Bound<&'a FieldValue>,
+) -> Bound<&'a FieldValue> {
+    match (left, right) {
+        (Bound::Unbounded, other) | (other, Bound::Unbounded) => other,
+        (Bound::Included(l) | Bound::Excluded(l), Bound::Included(r) | Bound::Excluded(r)) => {
+            if less_than(l, r) {
+                left
+            } else if less_than(r, l) {
+                right
+            } else if matches!(left, Bound::Excluded(_)) {
+                left
+            } else {
+                right
+            }
+        }
+    }
+}
+
+/// A set of values, where values are the same if the `=` filter considers them equal.
+///
+/// Values are bucketed by a hash consistent with that equality, so that checking
+/// membership doesn't require comparing against every value in the set.
+/// This keeps hints cheap to compute even for `one_of` filters with very long lists.
+#[derive(Default)]
+struct ValueSet<'a> {
+    hasher: RandomState,
+    buckets: HashMap<u64, Vec<&'a FieldValue>>,
+}
+
+impl<'a> ValueSet<'a> {
+    fn hash(&self, value: &FieldValue) -> u64 {
+        let mut state = self.hasher.build_hasher();
+        hash_normalized(value, &mut state);
+        state.finish()
+    }
+
+    /// Add the value to the set, returning whether it wasn't already in it.
+    fn insert(&mut self, value: &'a FieldValue) -> bool {
+        let bucket = self.buckets.entry(self.hash(value)).or_default();
+        if bucket.iter().any(|existing| equals(existing, value)) {
+            false
+        } else {
+            bucket.push(value);
+            true
+        }
+    }
+
+    fn contains(&self, value: &FieldValue) -> bool {
+        self.buckets
+            .get(&self.hash(value))
+            .is_some_and(|bucket| bucket.iter().any(|existing| equals(existing, value)))
+    }
+}
+
+impl<'a> FromIterator<&'a FieldValue> for ValueSet<'a> {
+    fn from_iter<T: IntoIterator<Item = &'a FieldValue>>(iter: T) -> Self {
+        let mut set = Self::default();
+        for value in iter {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+impl<'a> CandidateValue<&'a FieldValue> {
+    fn from_values(values: impl IntoIterator<Item = &'a FieldValue>) -> Self {
+        let mut seen = ValueSet::default();
+        let mut deduplicated: Vec<&'a FieldValue> = values
+            .into_iter()
+            .filter(|value| seen.insert(value))
+            .collect();
+        match deduplicated.len() {
+            0 => CandidateValue::Impossible,
+            1 => CandidateValue::Single(deduplicated.pop().unwrap()),
+            _ => CandidateValue::Multiple(deduplicated),
+        }
+    }
+
+    /// Narrow down the candidates to those that are also among the given values.
+    pub(super) fn intersect_values(self, values: impl IntoIterator<Item = &'a FieldValue>) -> Self {
+        match self {
+            CandidateValue::Impossible => CandidateValue::Impossible,
+            CandidateValue::All => Self::from_values(values),
+            CandidateValue::Single(value) => {
+                if values.into_iter().any(|candidate| equals(candidate, value)) {
+                    CandidateValue::Single(value)
+                } else {
+                    CandidateValue::Impossible
+                }
+            }
+            CandidateValue::Multiple(existing) => {
+                let values: ValueSet = values.into_iter().collect();
+                Self::from_values(existing.into_iter().filter(|value| values.contains(value)))
+            }
+            CandidateValue::Range(range) => {
+                Self::from_values(values.into_iter().filter(|value| range.contains(value)))
+            }
+        }
+    }
+
+    /// Narrow down the candidates to those that are also within the given range.
+    pub(super) fn intersect_range(self, range: Range<&'a FieldValue>) -> Self {
+        match self {
+            CandidateValue::Impossible => CandidateValue::Impossible,
+            CandidateValue::All => Self::from_range(range),
+            CandidateValue::Single(value) => {
+                if range.contains(value) {
+                    CandidateValue::Single(value)
+                } else {
+                    CandidateValue::Impossible
+                }
+            }
+            CandidateValue::Multiple(values) => {
+                Self::from_values(values.into_iter().filter(|value| range.contains(value)))
+            }
+            CandidateValue::Range(existing) => Self::from_range(Range::new(
+                tighter_start(existing.start, range.start),
+                tighter_end(existing.end, range.end),
+            )),
+        }
+    }
+
+    fn from_range(range: Range<&'a FieldValue>) -> Self {
+        match (range.start, range.end) {
+            (Bound::Included(start), Bound::Included(end)) if equals(start, end) => {
+                CandidateValue::Single(start)
+            }
+            (
+                Bound::Included(start) | Bound::Excluded(start),
+                Bound::Included(end) | Bound::Excluded(end),
+            ) if !less_than(start, end) => CandidateValue::Impossible,
+            _ => CandidateValue::Range(range),
+        }
+    }
+}
+
+/// All combinations of the candidate values of several properties, such as the properties
+/// that together form a composite key.
+///
+/// Returns `None` if any of the candidates is not enumerable, i.e. is a [`CandidateValue::Range`]
+/// or [`CandidateValue::All`]. Otherwise, returns an iterator over every combination of one value
+/// per candidate, with values in the same order as the candidates they came from.
+/// If any candidate is [`CandidateValue::Impossible`], the iterator is empty.
+pub fn composite_keys<T: Clone>(
+    candidates: impl IntoIterator<Item = CandidateValue<T>>,
+) -> Option<CompositeKeys<T>> {
+    let mut values = vec![];
+    for candidate in candidates {
+        values.push(match candidate {
+            CandidateValue::Impossible => vec![],
+            CandidateValue::Single(value) => vec![value],
+            CandidateValue::Multiple(values) => values,
+            CandidateValue::Range(_) | CandidateValue::All => return None,
+        });
+    }
+
+    let next = if values.iter().any(|v| v.is_empty()) {
+        None
+    } else {
+        Some(vec![0; values.len()])
+    };
+    Some(CompositeKeys { values, next })
+}
+
+/// Iterator over combinations of candidate values, produced by [`composite_keys`].
+#[derive(Debug, Clone)]
+pub struct CompositeKeys<T> {
+    values: Vec<Vec<T>>,
+
+    /// For each candidate, the index of its value in the next combination.
+    /// `None` once all combinations have been produced.
+    next: Option<Vec<usize>>,
+}
+
+impl<T: Clone> Iterator for CompositeKeys<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indexes = self.next.as_mut()?;
+        let key = indexes
+            .iter()
+            .zip(self.values.iter())
+            .map(|(&index, values)| values[index].clone())
+            .collect();
+
+        // Advance the rightmost index that isn't at its last value, resetting those after it.
+        let mut exhausted = true;
+        for (index, values) in indexes.iter_mut().zip(self.values.iter()).rev() {
+            *index += 1;
+            if *index < values.len() {
+                exhausted = false;
+                break;
+            }
+            *index = 0;
+        }
+        if exhausted {
+            self.next = None;
+        }
+
+        Some(key)
+    }
+}
+
+impl<T: Debug> Debug for Range<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, <T as Debug>::fmt)
+    }
+}
+
+impl<T: Display> Display for Range<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, <T as Display>::fmt)
+    }
+}
+
+impl<T: Debug> Debug for CandidateValue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, <T as Debug>::fmt)
+    }
+}
+
+impl<T: Display> Display for CandidateValue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, <T as Display>::fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use crate::ir::FieldValue;
+
+    use super::{CandidateValue, Range};
+
+    #[test]
+    fn compact_display() {
+        let five = FieldValue::Int64(5);
+        let ten = FieldValue::Int64(10);
+        let values = [
+            FieldValue::Int64(1),
+            FieldValue::Int64(2),
+            FieldValue::Int64(3),
+        ];
+        let test_data: Vec<(CandidateValue<&FieldValue>, &str)> = vec![
+            (CandidateValue::Impossible, "Impossible"),
+            (CandidateValue::All, "All"),
+            (CandidateValue::Single(&five), "Single(5)"),
+            (
+                CandidateValue::Multiple(values.iter().collect()),
+                "Multiple([1, 2, 3])",
+            ),
+            (
+                CandidateValue::Range(Range::new(Bound::Included(&five), Bound::Excluded(&ten))),
+                "Range(>=5, <10)",
+            ),
+            (
+                CandidateValue::Range(Range::new(Bound::Excluded(&five), Bound::Unbounded)),
+                "Range(>5)",
+            ),
+            (
+                CandidateValue::Range(Range::new(Bound::Unbounded, Bound::Included(&ten))),
+                "Range(<=10)",
+            ),
+            (
+                CandidateValue::Range(Range::new(Bound::Unbounded, Bound::Unbounded)),
+                "Range(..)",
+            ),
+        ];
+
+        for (candidate, expected) in test_data {
+            assert_eq!(expected, candidate.to_string());
+        }
+    }
+
+    #[test]
+    fn compact_debug() {
+        let five = FieldValue::Int64(5);
+        let ten = FieldValue::Int64(10);
+        let candidate =
+            CandidateValue::Range(Range::new(Bound::Included(&five), Bound::Excluded(&ten)));
+        assert_eq!("Range(>=Int64(5), <Int64(10))", format!("{candidate:?}"));
+
+        let candidate = CandidateValue::Single(&five);
+        assert_eq!("Single(Int64(5))", format!("{candidate:?}"));
+    }
+
+    type Bounds<'a> = (Bound<&'a FieldValue>, Bound<&'a FieldValue>);
+
+    fn intersect<'a>(first: Bounds<'a>, second: Bounds<'a>) -> CandidateValue<&'a FieldValue> {
+        CandidateValue::All
+            .intersect_range(Range::new(first.0, first.1))
+            .intersect_range(Range::new(second.0, second.1))
+    }
+
+    #[test]
+    fn range_intersection_at_shared_endpoint() {
+        let three = FieldValue::Int64(3);
+        let three_unsigned = FieldValue::Uint64(3);
+        let unbounded = Bound::Unbounded;
+
+        // The endpoint is admitted only if both ranges include it.
+        let test_data: Vec<(Bounds, _, &str)> = vec![
+            (
+                (Bound::Included(&three), unbounded),
+                Bound::Included(&three),
+                "Single(3)",
+            ),
+            (
+                (Bound::Included(&three), unbounded),
+                Bound::Excluded(&three),
+                "Impossible",
+            ),
+            (
+                (Bound::Excluded(&three), unbounded),
+                Bound::Included(&three),
+                "Impossible",
+            ),
+            (
+                (Bound::Excluded(&three), unbounded),
+                Bound::Excluded(&three),
+                "Impossible",
+            ),
+            (
+                (Bound::Included(&three_unsigned), unbounded),
+                Bound::Included(&three),
+                "Single(3)",
+            ),
+        ];
+        for (lower, upper, expected) in test_data {
+            let upper = (unbounded, upper);
+            assert_eq!(
+                expected,
+                intersect(lower, upper).to_string(),
+                "{lower:?} {upper:?}"
+            );
+            assert_eq!(
+                expected,
+                intersect(upper, lower).to_string(),
+                "{upper:?} {lower:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn range_intersection_prefers_exclusive_bound_at_same_value() {
+        let three = FieldValue::Int64(3);
+        let five = FieldValue::Int64(5);
+
+        let candidate = intersect(
+            (Bound::Included(&three), Bound::Included(&five)),
+            (Bound::Excluded(&three), Bound::Excluded(&five)),
+        );
+        assert_eq!("Range(>3, <5)", candidate.to_string());
+
+        let candidate = intersect(
+            (Bound::Excluded(&three), Bound::Included(&five)),
+            (Bound::Included(&three), Bound::Included(&five)),
+        );
+        assert_eq!("Range(>3, <=5)", candidate.to_string());
+    }
+
+    #[test]
+    fn string_range_intersection() {
+        let [a, m, mm, n] = ["a", "m", "mm", "n"].map(FieldValue::from);
+
+        let candidate = intersect(
+            (Bound::Included(&a), Bound::Excluded(&n)),
+            (Bound::Included(&m), Bound::Unbounded),
+        );
+        assert_eq!(r#"Range(>="m", <"n")"#, candidate.to_string());
+
+        // "m" is a prefix of "mm", so it sorts first.
+        let candidate = intersect(
+            (Bound::Excluded(&m), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Included(&mm)),
+        );
+        assert_eq!(r#"Range(>"m", <="mm")"#, candidate.to_string());
+
+        let candidate = intersect(
+            (Bound::Included(&m), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Included(&m)),
+        );
+        assert_eq!(r#"Single("m")"#, candidate.to_string());
+
+        let candidate = intersect(
+            (Bound::Excluded(&m), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Included(&m)),
+        );
+        assert_eq!("Impossible", candidate.to_string());
+
+        let candidate = intersect(
+            (Bound::Included(&n), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Excluded(&mm)),
+        );
+        assert_eq!("Impossible", candidate.to_string());
+
+        let values = [&a, &m, &mm, &n];
+        let candidate = CandidateValue::Range(Range::new(Bound::Excluded(&m), Bound::Included(&n)))
+            .intersect_values(values.to_vec());
+        assert_eq!(r#"Multiple(["mm", "n"])"#, candidate.to_string());
+    }
+
+    #[test]
+    fn datetime_range_intersection() {
+        let datetime = |s: &str| FieldValue::DateTimeUtc(s.parse().expect("not a valid datetime"));
+        let new_year = datetime("2024-01-01T00:00:00Z");
+        let spring = datetime("2024-03-20T03:06:00Z");
+        let summer = datetime("2024-06-20T20:51:00Z");
+
+        let candidate = intersect(
+            (Bound::Included(&new_year), Bound::Included(&summer)),
+            (Bound::Excluded(&spring), Bound::Unbounded),
+        );
+        assert_eq!(
+            CandidateValue::Range(Range::new(
+                Bound::Excluded(&spring),
+                Bound::Included(&summer)
+            )),
+            candidate,
+        );
+
+        let candidate = intersect(
+            (Bound::Included(&spring), Bound::Unbounded),
+            (Bound::Excluded(&new_year), Bound::Included(&spring)),
+        );
+        assert_eq!(CandidateValue::Single(&spring), candidate);
+
+        let candidate = intersect(
+            (Bound::Included(&new_year), Bound::Excluded(&spring)),
+            (Bound::Included(&spring), Bound::Included(&summer)),
+        );
+        assert_eq!(CandidateValue::Impossible, candidate);
+
+        let candidate =
+            CandidateValue::Multiple(vec![&new_year, &spring, &summer]).intersect_range(
+                Range::new(Bound::Excluded(&new_year), Bound::Excluded(&summer)),
+            );
+        assert_eq!(CandidateValue::Single(&spring), candidate);
+    }
+
+    #[test]
+    fn values_at_range_endpoints() {
+        let values = [
+            FieldValue::Int64(3),
+            FieldValue::Int64(4),
+            FieldValue::Int64(5),
+        ];
+        let (three, five) = (&values[0], &values[2]);
+
+        let in_range = |start, end| {
+            CandidateValue::Range(Range::new(start, end))
+                .intersect_values(values.iter())
+                .to_string()
+        };
+        assert_eq!(
+            "Multiple([3, 4, 5])",
+            in_range(Bound::Included(three), Bound::Included(five))
+        );
+        assert_eq!(
+            "Multiple([4, 5])",
+            in_range(Bound::Excluded(three), Bound::Included(five))
+        );
+        assert_eq!(
+            "Single(4)",
+            in_range(Bound::Excluded(three), Bound::Excluded(five))
+        );
+
+        let single = CandidateValue::Single(three);
+        assert_eq!(
+            "Impossible",
+            single
+                .clone()
+                .intersect_range(Range::new(Bound::Excluded(three), Bound::Unbounded))
+                .to_string()
+        );
+        assert_eq!(
+            "Single(3)",
+            single
+                .intersect_range(Range::new(Bound::Unbounded, Bound::Included(three)))
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn long_value_lists() {
+        // Each number appears twice: once as `Int64` and once as `Uint64`.
+        // Comparing each value to every other one would take billions of comparisons.
+        let values: Vec<FieldValue> = (0..50_000i64)
+            .map(FieldValue::Int64)
+            .chain((0..50_000u64).map(FieldValue::Uint64))
+            .collect();
+
+        let candidate = CandidateValue::All.intersect_values(values.iter());
+        let CandidateValue::Multiple(deduplicated) = &candidate else {
+            panic!("unexpected candidate: {candidate}");
+        };
+        assert_eq!(values[..50_000].iter().collect::<Vec<_>>(), *deduplicated);
+
+        let others: Vec<FieldValue> = (49_997..100_000i64).map(FieldValue::Int64).collect();
+        assert_eq!(
+            "Multiple([49997, 49998, 49999])",
+            candidate.intersect_values(others.iter()).to_string(),
+        );
+    }
+
+    #[test]
+    fn composite_keys() {
+        let keys: Vec<_> = super::composite_keys([
+            CandidateValue::Multiple(vec![1, 2]),
+            CandidateValue::Single(3),
+            CandidateValue::Multiple(vec![4, 5]),
+        ])
+        .expect("candidates are enumerable")
+        .collect();
+        assert_eq!(
+            vec![vec![1, 3, 4], vec![1, 3, 5], vec![2, 3, 4], vec![2, 3, 5],],
+            keys
+        );
+
+        let no_keys =
+            super::composite_keys([CandidateValue::Single(1), CandidateValue::Impossible])
+                .expect("candidates are enumerable");
+        assert_eq!(0, no_keys.count());
+
+        let empty_key: Vec<Vec<i64>> = super::composite_keys([]).unwrap().collect();
+        assert_eq!(vec![Vec::<i64>::new()], empty_key);
+
+        assert!(super::composite_keys([CandidateValue::Single(1), CandidateValue::All]).is_none());
+        assert!(super::composite_keys([
+            CandidateValue::Range(Range::new(Bound::Included(1), Bound::Unbounded)),
+            CandidateValue::Impossible,
+        ])
+        .is_none());
+    }
+}
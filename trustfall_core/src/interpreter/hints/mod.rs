@@ -1,9 +1,23 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    ops::Bound,
+    sync::Arc,
+};
 
-use crate::ir::{Eid, FieldValue, IRQuery, Vid};
+use crate::ir::{
+    indexed::EdgeKind, Argument, ContextField, EdgeParameters, Eid, FieldRef, FieldValue, FoldSort,
+    FoldSpecificFieldKind, IRFold, IRQuery, IRQueryComponent, Operation, Vid,
+};
+#[cfg(feature = "testing")]
+use crate::{interpreter::error::QueryArgumentsError, ir::indexed::IndexedQuery, schema::Schema};
 
 use super::InterpretedQuery;
 
+mod candidates;
+
+pub use candidates::{composite_keys, CandidateValue, CompositeKeys, Range};
+
 /// Information about the query being processed.
 #[non_exhaustive]
 #[derive(Debug, Clone)]
@@ -26,6 +40,62 @@ impl QueryInfo {
         }
     }
 
+    /// Construct a [`QueryInfo`] positioned at the given vertex of the query, and optionally
+    /// at an edge leaving that vertex, as the interpreter would when calling adapter methods.
+    ///
+    /// Meant for unit-testing adapter code that uses query hints, without having to execute
+    /// a whole query. Returns an error if the arguments aren't valid for the query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query has no vertex with the given [`Vid`], or if the given [`Eid`]
+    /// isn't an edge of the query that leaves that vertex.
+    #[cfg(feature = "testing")]
+    pub fn for_testing(
+        indexed_query: Arc<IndexedQuery>,
+        arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+        vid: Vid,
+        crossing_eid: Option<Eid>,
+    ) -> Result<Self, QueryArgumentsError> {
+        assert!(
+            indexed_query.vids.contains_key(&vid),
+            "{vid:?} is not a vertex in this query"
+        );
+        if let Some(eid) = crossing_eid {
+            let from_vid = match indexed_query.eids.get(&eid) {
+                Some(EdgeKind::Regular(edge)) => edge.from_vid,
+                Some(EdgeKind::Fold(fold)) => fold.from_vid,
+                None => panic!("{eid:?} is not an edge in this query"),
+            };
+            assert_eq!(vid, from_vid, "{eid:?} does not leave {vid:?}");
+        }
+
+        let query = InterpretedQuery::from_query_and_arguments(indexed_query, arguments)?;
+        Ok(Self::new(query, vid, crossing_eid))
+    }
+
+    /// Parse the query and construct a [`QueryInfo`] positioned at the given vertex,
+    /// as [`QueryInfo::for_testing`] does.
+    ///
+    /// Meant for asserting on the hints, such as [`QueryInfo::starting_vertex_candidate`],
+    /// that an adapter would see at that vertex when executing the query.
+    /// Returns an error if the arguments aren't valid for the query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the query isn't valid for the schema, or has no vertex with the given [`Vid`].
+    #[cfg(feature = "testing")]
+    pub fn for_testing_query(
+        schema: &Schema,
+        query: &str,
+        arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+        vid: Vid,
+    ) -> Result<Self, QueryArgumentsError> {
+        let indexed_query = crate::frontend::parse(schema, query)
+            .unwrap_or_else(|e| panic!("query is not valid for the schema: {e:?}"));
+        Self::for_testing(indexed_query, arguments, vid, None)
+    }
+
     #[allow(dead_code)]
     pub(crate) fn ir_query(&self) -> &IRQuery {
         &self.query.indexed_query.ir_query
@@ -45,4 +115,1265 @@ impl QueryInfo {
     pub fn origin_crossing_eid(&self) -> Option<Eid> {
         self.crossing_eid
     }
+
+    /// A stable identifier for the query being executed.
+    ///
+    /// Computed from the query's IR, so it is the same across runs and processes for queries
+    /// that only differ in formatting or in the values of their arguments, and differs
+    /// for queries that differ in any other way. Useful for correlating backend calls
+    /// with the originating query in logs and traces.
+    ///
+    /// The fingerprint is recomputed on every call, so consider caching it
+    /// if it's needed often.
+    pub fn query_fingerprint(&self) -> u64 {
+        let serialized = ron::to_string(self.ir_query()).expect("failed to serialize IR query");
+
+        // 64-bit FNV-1a, which unlike the std hashers is stable across Rust versions.
+        serialized
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    /// The names of the query's variables, in sorted order.
+    pub fn variable_names(&self) -> impl Iterator<Item = &Arc<str>> + '_ {
+        self.ir_query().variables.keys()
+    }
+
+    /// The values of the named property that the query's starting vertices may have,
+    /// as deduced from the filters applied to that property on the starting vertex.
+    ///
+    /// Useful in [`Adapter::resolve_starting_vertices`](super::Adapter::resolve_starting_vertices)
+    /// to look up starting vertices by a key property, instead of enumerating all vertices
+    /// only for most of them to be discarded by the query's filters.
+    ///
+    /// Only `=`, `one_of`, `is_null`, and comparison filters whose operand is a query variable
+    /// are taken into account. Returns `None` if there are no such filters on the property.
+    /// The returned values are only a hint: the filters are still applied
+    /// to the produced vertices, so it's fine for the adapter to produce vertices
+    /// whose property value isn't one of the candidates.
+    ///
+    /// Filters whose variable has no value in the query's arguments are also not taken into
+    /// account. This can't happen for queries being executed, whose arguments are validated
+    /// beforehand, but may happen e.g. when testing hints on a partially-specified query.
+    ///
+    /// The candidates may include [`FieldValue::Null`], either due to an `is_null` filter
+    /// or a null element in the list passed to a `one_of` filter. In both cases, vertices
+    /// whose property value is null satisfy the filter and must not be skipped.
+    pub fn starting_vertex_candidate(
+        &self,
+        property_name: &str,
+    ) -> Option<CandidateValue<&FieldValue>> {
+        static NULL: FieldValue = FieldValue::Null;
+
+        let root_component = &self.ir_query().root_component;
+        let root_vertex = &root_component.vertices[&root_component.root];
+        let arguments = self.arguments();
+
+        let mut candidate = None;
+        for filter in &root_vertex.filters {
+            if filter.left().field_name.as_ref() != property_name {
+                continue;
+            }
+
+            let operand = match filter.right() {
+                Some(Argument::Variable(var)) => match arguments.get(&var.variable_name) {
+                    Some(value) => Some(value),
+                    // Without the variable's value, this filter can't narrow down
+                    // the candidates. Ignoring it still produces a valid hint.
+                    None => continue,
+                },
+                Some(Argument::Tag(_)) => continue,
+                None => None,
+            };
+            let current = candidate.take().unwrap_or(CandidateValue::All);
+            candidate =
+                Some(match (filter, operand) {
+                    (Operation::IsNull(_), _) => current.intersect_values(vec![&NULL]),
+                    (Operation::Equals(..), Some(value)) => current.intersect_values(vec![value]),
+                    (Operation::OneOf(..), Some(FieldValue::List(values))) => {
+                        current.intersect_values(values)
+                    }
+                    (Operation::OneOf(..), Some(_)) => CandidateValue::Impossible,
+                    (Operation::LessThan(..), Some(value)) => current
+                        .intersect_range(Range::new(Bound::Unbounded, Bound::Excluded(value))),
+                    (Operation::LessThanOrEqual(..), Some(value)) => current
+                        .intersect_range(Range::new(Bound::Unbounded, Bound::Included(value))),
+                    (Operation::GreaterThan(..), Some(value)) => current
+                        .intersect_range(Range::new(Bound::Excluded(value), Bound::Unbounded)),
+                    (Operation::GreaterThanOrEqual(..), Some(value)) => current
+                        .intersect_range(Range::new(Bound::Included(value), Bound::Unbounded)),
+                    // This filter doesn't constrain the property to a known set of values.
+                    _ => current,
+                });
+        }
+
+        candidate.filter(|candidate| !matches!(candidate, CandidateValue::All))
+    }
+
+    /// How the query uses the named property of the vertex at this query location.
+    ///
+    /// A property that is [filtered](PropertyUsage::filtered) but neither output nor tagged
+    /// only needs to be resolved in order to apply the filter. An adapter that fully applies
+    /// that filter in its backend doesn't need to resolve the property for any other purpose.
+    pub fn property_usage(&self, property_name: &str) -> PropertyUsage {
+        let vid = self.current_vertex;
+        let indexed_query = &self.query.indexed_query;
+        let component = &indexed_query.vids[&vid];
+
+        let filtered = component.vertices[&vid]
+            .filters
+            .iter()
+            .any(|filter| filter.left().field_name.as_ref() == property_name);
+        let output = component
+            .outputs
+            .values()
+            .any(|field| field.vertex_id == vid && field.field_name.as_ref() == property_name);
+        let tagged = is_property_tagged_in_component(
+            &indexed_query.ir_query.root_component,
+            vid,
+            property_name,
+        );
+
+        PropertyUsage {
+            filtered,
+            output,
+            tagged,
+        }
+    }
+
+    /// The properties that uniquely identify a vertex at this query location, if any.
+    ///
+    /// These are the properties of the vertex's type that the schema marks with
+    /// the `@key` directive. Each of them on its own is unique across all vertices
+    /// of that type, so e.g. a filter requiring one of them to equal a single value
+    /// allows a point lookup instead of a scan, and vertices with equal key values
+    /// are the same vertex.
+    pub fn key_fields(&self) -> impl Iterator<Item = &Arc<str>> + '_ {
+        let vid = self.current_vertex;
+        self.query.indexed_query.vids[&vid].vertices[&vid]
+            .key_fields
+            .iter()
+    }
+
+    /// All edges leaving the vertex at this query location, including folded edges.
+    pub fn outgoing_edges(&self) -> impl Iterator<Item = EdgeInfo> + '_ {
+        self.edges_from(self.current_vertex)
+    }
+
+    /// Information about the vertex reached by following the named edges in order,
+    /// starting from the vertex at this query location.
+    ///
+    /// Returns `None` if any edge in the chain isn't present in the query, or isn't
+    /// a required edge: `@optional`, `@recurse`, and `@fold` edges do not guarantee
+    /// that their destination vertex exists in every result, so they end the chain.
+    /// If the query has several required edges with the same name at some vertex
+    /// (e.g. with different type coercions), the one that appears first is followed.
+    ///
+    /// This allows an adapter to check whether a filter several edges away
+    /// may be pushed down as a join along a path that every result must contain.
+    /// An empty chain returns information about the vertex at this query location.
+    pub fn required_edge_chain(&self, names: &[&str]) -> Option<QueryInfo> {
+        let mut vid = self.current_vertex;
+        for name in names {
+            let edge = self.edges_from(vid).find(|edge| {
+                edge.edge_name.as_ref() == *name
+                    && !edge.optional
+                    && !edge.recursive
+                    && !edge.folded
+            })?;
+            vid = edge.destination_vid;
+        }
+
+        if names.is_empty() {
+            Some(self.clone())
+        } else {
+            Some(QueryInfo::new(self.query.clone(), vid, None))
+        }
+    }
+
+    /// Information about the other vertices reached from the same parent vertex
+    /// as the vertex at this query location, in the order their edges appear in the query.
+    ///
+    /// The parent is the vertex whose edge leads to the vertex at this query location.
+    /// Siblings are the destinations of that parent's other edges, including `@optional`
+    /// and `@recurse` edges, but not `@fold` edges: folded vertices are part of a separate
+    /// component of the query. Vertices at the root of a component, i.e. starting vertices and
+    /// the first vertex inside a fold, have no parent in their component and so no siblings.
+    ///
+    /// This allows an adapter to notice when sibling vertices share properties,
+    /// for example a join key pinned by a filter on a sibling vertex, and coordinate
+    /// how it accesses their data.
+    pub fn sibling_vertices(&self) -> impl Iterator<Item = QueryInfo> + '_ {
+        let vid = self.current_vertex;
+        let component = &self.query.indexed_query.vids[&vid];
+        let parent_vid = component
+            .edges
+            .values()
+            .find(|edge| edge.to_vid == vid)
+            .map(|edge| edge.from_vid);
+
+        component
+            .edges
+            .values()
+            .filter(move |edge| Some(edge.from_vid) == parent_vid && edge.to_vid != vid)
+            .map(|edge| QueryInfo::new(self.query.clone(), edge.to_vid, None))
+    }
+
+    /// The edges leaving the vertex at this query location whose only purpose in the query
+    /// is to require that at least one neighbor exists.
+    ///
+    /// Such edges are required (not `@optional`, `@recurse`, or `@fold`), and nothing
+    /// reachable through them is output or tagged for use elsewhere in the query:
+    /// their neighbors may be filtered, but contribute no values to the results.
+    /// Folds within that part of the query are allowed only if they have no outputs.
+    ///
+    /// A vertex at this query location without a neighbor along such an edge can't be part
+    /// of any result, so the adapter may translate the edge (and any filters beyond it)
+    /// into an `EXISTS`-style check when producing vertices here. The edge is still resolved
+    /// as usual afterward, and each of its matching neighbors still produces its own result.
+    pub fn statically_required_edges_exist(&self) -> impl Iterator<Item = EdgeInfo> + '_ {
+        let component = &self.query.indexed_query.vids[&self.current_vertex];
+        self.outgoing_edges().filter(move |edge| {
+            !edge.optional
+                && !edge.recursive
+                && !edge.folded
+                && is_existence_only(component, edge.destination_vid)
+        })
+    }
+
+    /// How the query uses the `@fold` edge with the given unique ID: the values it outputs
+    /// from within the fold, and the transforms applied to the fold and any filters on them.
+    ///
+    /// Returns `None` if the query has no `@fold` edge with that ID.
+    pub fn fold_info(&self, eid: Eid) -> Option<FoldInfo> {
+        match self.query.indexed_query.eids.get(&eid)? {
+            EdgeKind::Fold(fold) => Some(FoldInfo { fold: fold.clone() }),
+            EdgeKind::Regular(_) => None,
+        }
+    }
+
+    fn edges_from(&self, vid: Vid) -> impl Iterator<Item = EdgeInfo> + '_ {
+        let component = &self.query.indexed_query.vids[&vid];
+
+        let edges = component
+            .edges
+            .values()
+            .filter(move |edge| edge.from_vid == vid)
+            .map(|edge| EdgeInfo {
+                eid: edge.eid,
+                edge_name: edge.edge_name.clone(),
+                parameters: edge.parameters.clone(),
+                destination_vid: edge.to_vid,
+                destination_type: component.vertices[&edge.to_vid].type_name.clone(),
+                optional: edge.optional,
+                recursive: edge.recursive.is_some(),
+                folded: false,
+            });
+        let folds = component
+            .folds
+            .values()
+            .filter(move |fold| fold.from_vid == vid)
+            .map(|fold| EdgeInfo {
+                eid: fold.eid,
+                edge_name: fold.edge_name.clone(),
+                parameters: fold.parameters.clone(),
+                destination_vid: fold.to_vid,
+                destination_type: fold.component.vertices[&fold.to_vid].type_name.clone(),
+                optional: false,
+                recursive: false,
+                folded: true,
+            });
+
+        let mut all_edges: Vec<_> = edges.chain(folds).collect();
+        all_edges.sort_unstable_by_key(|edge| edge.eid);
+        all_edges.into_iter()
+    }
+
+    /// The edges leaving the vertex at this query location whose destination vertex
+    /// has the given type, including any type coercion applied at the destination.
+    ///
+    /// Only exact type name matches are returned: destinations of a supertype or subtype
+    /// of the given type are not included.
+    pub fn edges_to_type<'a>(&'a self, type_name: &'a str) -> impl Iterator<Item = EdgeInfo> + 'a {
+        self.outgoing_edges()
+            .filter(move |edge| edge.destination_type.as_ref() == type_name)
+    }
+}
+
+/// Information about an edge leaving a vertex in the query.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeInfo {
+    eid: Eid,
+    edge_name: Arc<str>,
+    parameters: EdgeParameters,
+    destination_vid: Vid,
+    destination_type: Arc<str>,
+    optional: bool,
+    recursive: bool,
+    folded: bool,
+}
+
+impl EdgeInfo {
+    /// The unique ID of this edge within its query.
+    pub fn eid(&self) -> Eid {
+        self.eid
+    }
+
+    pub fn edge_name(&self) -> &Arc<str> {
+        &self.edge_name
+    }
+
+    pub fn parameters(&self) -> &EdgeParameters {
+        &self.parameters
+    }
+
+    /// The unique ID of the vertex at which this edge arrives.
+    pub fn destination_vid(&self) -> Vid {
+        self.destination_vid
+    }
+
+    /// The type of the vertex at which this edge arrives, after any type coercion it applies.
+    pub fn destination_type(&self) -> &Arc<str> {
+        &self.destination_type
+    }
+
+    /// Whether the edge is marked `@optional`.
+    pub fn optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Whether the edge is marked `@recurse`.
+    pub fn recursive(&self) -> bool {
+        self.recursive
+    }
+
+    /// Whether the edge is marked `@fold`.
+    pub fn folded(&self) -> bool {
+        self.folded
+    }
+}
+
+/// Information about how the query uses a `@fold` edge.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldInfo {
+    fold: Arc<IRFold>,
+}
+
+impl FoldInfo {
+    /// The unique ID of the folded edge within its query.
+    pub fn eid(&self) -> Eid {
+        self.fold.eid
+    }
+
+    pub fn edge_name(&self) -> &Arc<str> {
+        &self.fold.edge_name
+    }
+
+    pub fn parameters(&self) -> &EdgeParameters {
+        &self.fold.parameters
+    }
+
+    /// The unique ID of the vertex at which the folded edge arrives.
+    pub fn destination_vid(&self) -> Vid {
+        self.fold.to_vid
+    }
+
+    /// The part of the query inside the fold, including any folds nested within it.
+    pub fn component(&self) -> &Arc<IRQueryComponent> {
+        &self.fold.component
+    }
+
+    /// The properties output from within the fold, by output name.
+    ///
+    /// Each of these produces a list of values, one per element of the fold.
+    /// Outputs of folds nested inside this one are not included.
+    pub fn outputs(&self) -> &BTreeMap<Arc<str>, ContextField> {
+        &self.fold.component.outputs
+    }
+
+    /// The transformed values of the fold that are output, by output name,
+    /// such as the number of its elements for `@transform(op: "count")`.
+    pub fn transform_outputs(&self) -> &BTreeMap<Arc<str>, FoldSpecificFieldKind> {
+        &self.fold.fold_specific_outputs
+    }
+
+    /// The transforms applied to the fold, whether their values are output, filtered, or both.
+    pub fn transforms(&self) -> BTreeSet<FoldSpecificFieldKind> {
+        self.fold
+            .fold_specific_outputs
+            .values()
+            .copied()
+            .chain(self.fold.post_filters.iter().map(|filter| *filter.left()))
+            .collect()
+    }
+
+    /// The filters applied to the fold's transformed values, such as a filter on its count.
+    pub fn transform_filters(&self) -> &[Operation<FoldSpecificFieldKind, Argument>] {
+        &self.fold.post_filters
+    }
+
+    /// How the fold's elements are sorted, if the query requested it via `@fold(sort_by: ...)`.
+    ///
+    /// Adapters that can produce the fold's elements already in this order may do so,
+    /// though the interpreter sorts them regardless.
+    pub fn sort(&self) -> Option<&FoldSort> {
+        self.fold.sort.as_ref()
+    }
+
+    /// The maximum number of the fold's elements the query uses, after sorting,
+    /// if the query requested it via `@fold(limit: ...)`.
+    pub fn limit(&self) -> Option<NonZeroUsize> {
+        self.fold.limit
+    }
+}
+
+/// How the query uses a particular vertex property.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PropertyUsage {
+    /// The property is used in at least one `@filter` on its own vertex.
+    pub filtered: bool,
+
+    /// The property is `@output`.
+    pub output: bool,
+
+    /// The property is `@tag`-ged and the tag is used in a filter elsewhere in the query.
+    pub tagged: bool,
+}
+
+impl PropertyUsage {
+    /// Whether the property's value is only needed to evaluate filters on its own vertex.
+    pub fn is_filter_only(&self) -> bool {
+        self.filtered && !self.output && !self.tagged
+    }
+}
+
+fn is_property_tagged_in_component(
+    component: &IRQueryComponent,
+    vid: Vid,
+    property_name: &str,
+) -> bool {
+    let is_tag_of_property = |argument: Option<&Argument>| match argument {
+        Some(Argument::Tag(FieldRef::ContextField(field))) => {
+            field.vertex_id == vid && field.field_name.as_ref() == property_name
+        }
+        _ => false,
+    };
+
+    component
+        .vertices
+        .values()
+        .flat_map(|vertex| vertex.filters.iter())
+        .any(|filter| is_tag_of_property(filter.right()))
+        || component.folds.values().any(|fold| {
+            fold.post_filters
+                .iter()
+                .any(|filter| is_tag_of_property(filter.right()))
+                || is_property_tagged_in_component(&fold.component, vid, property_name)
+        })
+}
+
+/// Whether the part of the component reached from the given vertex only constrains
+/// the results, without any of its values being output or tagged for use elsewhere.
+fn is_existence_only(component: &IRQueryComponent, vid: Vid) -> bool {
+    let mut subtree = BTreeSet::from([vid]);
+    let mut stack = vec![vid];
+    while let Some(from_vid) = stack.pop() {
+        for edge in component.edges.values() {
+            if edge.from_vid == from_vid && subtree.insert(edge.to_vid) {
+                stack.push(edge.to_vid);
+            }
+        }
+    }
+
+    let tags_subtree = |argument: Option<&Argument>| match argument {
+        Some(Argument::Tag(FieldRef::ContextField(field))) => subtree.contains(&field.vertex_id),
+        _ => false,
+    };
+
+    let has_outputs = component
+        .outputs
+        .values()
+        .any(|field| subtree.contains(&field.vertex_id))
+        || component.folds.values().any(|fold| {
+            subtree.contains(&fold.from_vid)
+                && (!fold.component.outputs.is_empty() || !fold.fold_specific_outputs.is_empty())
+        });
+    let tagged_elsewhere = component
+        .vertices
+        .values()
+        .filter(|vertex| !subtree.contains(&vertex.vid))
+        .flat_map(|vertex| vertex.filters.iter())
+        .any(|filter| tags_subtree(filter.right()))
+        || component
+            .folds
+            .values()
+            .filter(|fold| !subtree.contains(&fold.from_vid))
+            .any(|fold| {
+                fold.imported_tags.iter().any(|tag| match tag {
+                    FieldRef::ContextField(field) => subtree.contains(&field.vertex_id),
+                    FieldRef::FoldSpecificField(_) => false,
+                })
+            });
+
+    !has_outputs && !tagged_elsewhere
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        num::NonZeroUsize,
+        sync::Arc,
+    };
+
+    use crate::{
+        frontend::parse,
+        interpreter::InterpretedQuery,
+        ir::{FieldValue, FoldSort, FoldSpecificFieldKind, Operation, Vid},
+        numbers_interpreter::numbers_schema,
+        schema::Schema,
+    };
+
+    use super::{PropertyUsage, QueryInfo};
+
+    fn query_info_at(
+        query: &str,
+        vid: usize,
+        arguments: BTreeMap<Arc<str>, FieldValue>,
+    ) -> QueryInfo {
+        let schema = numbers_schema();
+        let indexed_query = parse(&schema, query).unwrap();
+        let query =
+            InterpretedQuery::from_query_and_arguments(indexed_query, Arc::new(arguments)).unwrap();
+        QueryInfo::new(query, Vid(NonZeroUsize::new(vid).unwrap()), None)
+    }
+
+    #[test]
+    fn property_usage() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @filter(op: ">", value: ["$min"])
+        name @output @filter(op: "is_not_null")
+        vowelsInName @tag
+
+        successor {
+            vowelsInName @filter(op: "=", value: ["%vowelsInName"])
+        }
+    }
+}"#;
+        let info = query_info_at(
+            query,
+            1,
+            btreemap! {
+                "min".into() => FieldValue::Int64(2),
+            },
+        );
+
+        let value = info.property_usage("value");
+        assert_eq!(
+            PropertyUsage {
+                filtered: true,
+                output: false,
+                tagged: false,
+            },
+            value
+        );
+        assert!(value.is_filter_only());
+
+        let name = info.property_usage("name");
+        assert!(name.filtered && name.output && !name.tagged);
+        assert!(!name.is_filter_only());
+
+        let vowels = info.property_usage("vowelsInName");
+        assert!(!vowels.filtered && !vowels.output && vowels.tagged);
+
+        assert_eq!(PropertyUsage::default(), info.property_usage("predecessor"));
+
+        let successor_info = query_info_at(
+            query,
+            2,
+            btreemap! {
+                "min".into() => FieldValue::Int64(2),
+            },
+        );
+        assert!(successor_info
+            .property_usage("vowelsInName")
+            .is_filter_only());
+    }
+
+    #[test]
+    fn key_fields() {
+        let schema = Schema::parse(
+            fs::read_to_string("test_data/tests/valid_schemas/key_fields.graphql").unwrap(),
+        )
+        .unwrap();
+        let query = r#"
+{
+    Account {
+        id @filter(op: "=", value: ["$id"])
+
+        friend {
+            email @output
+        }
+    }
+}"#;
+        let indexed_query = parse(&schema, query).unwrap();
+        let query = InterpretedQuery::from_query_and_arguments(
+            indexed_query,
+            Arc::new(btreemap! {
+                "id".into() => FieldValue::Int64(1),
+            }),
+        )
+        .unwrap();
+
+        for vid in [1, 2] {
+            let info = QueryInfo::new(query.clone(), Vid(NonZeroUsize::new(vid).unwrap()), None);
+            assert_eq!(
+                vec!["id", "name"],
+                info.key_fields()
+                    .map(|key| key.as_ref())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        // Types without keys have none.
+        let info = query_info_at("{ Zero { value @output } }", 1, Default::default());
+        assert_eq!(0, info.key_fields().count());
+    }
+
+    #[test]
+    fn edges_to_type() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output
+
+        predecessor @optional {
+            value @output(name: "predecessor")
+        }
+        successor {
+            ... on Prime {
+                value @output(name: "prime_successor")
+            }
+        }
+        multiple(max: 3) @fold {
+            value @output(name: "multiples")
+        }
+        predecessor {
+            ... on Prime {
+                name @output(name: "prime_predecessor")
+            }
+        }
+    }
+}"#;
+        let info = query_info_at(query, 1, Default::default());
+
+        let all_edges: Vec<_> = info
+            .outgoing_edges()
+            .map(|edge| {
+                (
+                    edge.edge_name().to_string(),
+                    edge.destination_type().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("predecessor".to_string(), "Number".to_string()),
+                ("successor".to_string(), "Prime".to_string()),
+                ("multiple".to_string(), "Composite".to_string()),
+                ("predecessor".to_string(), "Prime".to_string()),
+            ],
+            all_edges
+        );
+
+        let to_prime: Vec<_> = info.edges_to_type("Prime").collect();
+        assert_eq!(2, to_prime.len());
+        assert_eq!("successor", to_prime[0].edge_name().as_ref());
+        assert_eq!("predecessor", to_prime[1].edge_name().as_ref());
+        assert!(to_prime
+            .iter()
+            .all(|edge| !edge.optional() && !edge.folded()));
+
+        let to_composite: Vec<_> = info.edges_to_type("Composite").collect();
+        assert_eq!(1, to_composite.len());
+        assert!(to_composite[0].folded());
+        assert_eq!(
+            Some(&FieldValue::Int64(3)),
+            to_composite[0].parameters().get("max")
+        );
+
+        let to_number: Vec<_> = info.edges_to_type("Number").collect();
+        assert_eq!(1, to_number.len());
+        assert!(to_number[0].optional());
+
+        assert_eq!(0, info.edges_to_type("Letter").count());
+    }
+
+    #[test]
+    fn required_edge_chain() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output
+
+        predecessor @optional {
+            successor {
+                value @output(name: "optional_value")
+            }
+        }
+        successor {
+            successor {
+                ... on Prime {
+                    multiple(max: 3) @fold {
+                        value @output(name: "multiples")
+                    }
+                    successor @recurse(depth: 2) {
+                        value @output(name: "recursed")
+                    }
+                }
+            }
+        }
+    }
+}"#;
+        let info = query_info_at(query, 1, Default::default());
+
+        let destination = info
+            .required_edge_chain(&["successor", "successor"])
+            .expect("no required edge chain");
+        assert_eq!(Vid(NonZeroUsize::new(5).unwrap()), destination.origin_vid());
+        assert_eq!(None, destination.origin_crossing_eid());
+        assert_eq!(
+            vec!["multiple", "successor"],
+            destination
+                .outgoing_edges()
+                .map(|edge| edge.edge_name().to_string())
+                .collect::<Vec<_>>(),
+        );
+
+        let same = info.required_edge_chain(&[]).expect("empty chain is valid");
+        assert_eq!(info.origin_vid(), same.origin_vid());
+
+        // Optional, folded, and recursive edges end the chain, as do edges not in the query.
+        assert!(info.required_edge_chain(&["predecessor"]).is_none());
+        assert!(info
+            .required_edge_chain(&["predecessor", "successor"])
+            .is_none());
+        assert!(info
+            .required_edge_chain(&["successor", "successor", "multiple"])
+            .is_none());
+        assert!(info
+            .required_edge_chain(&["successor", "successor", "successor"])
+            .is_none());
+        assert!(info.required_edge_chain(&["multiple"]).is_none());
+    }
+
+    #[test]
+    fn sibling_vertices() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output
+
+        successor {
+            value @output(name: "successor")
+            successor {
+                value @output(name: "second_successor")
+            }
+        }
+        predecessor @optional {
+            value @output(name: "predecessor")
+        }
+        multiple(max: 3) @fold {
+            value @output(name: "multiples")
+            successor {
+                value @output(name: "multiple_successors")
+            }
+        }
+        successor @recurse(depth: 2) {
+            value @output(name: "recursed")
+        }
+    }
+}"#;
+        let siblings_of = |vid: usize| -> Vec<Vid> {
+            query_info_at(query, vid, Default::default())
+                .sibling_vertices()
+                .map(|info| info.origin_vid())
+                .collect()
+        };
+        let vid = |vid: usize| Vid(NonZeroUsize::new(vid).unwrap());
+
+        assert_eq!(vec![vid(4), vid(7)], siblings_of(2));
+        assert_eq!(vec![vid(2), vid(7)], siblings_of(4));
+        assert_eq!(vec![vid(2), vid(4)], siblings_of(7));
+
+        // Only children get siblings, and only from their component.
+        assert!(siblings_of(3).is_empty());
+        assert!(siblings_of(1).is_empty());
+        assert!(siblings_of(5).is_empty());
+        assert!(siblings_of(6).is_empty());
+    }
+
+    #[test]
+    fn statically_required_edges_exist() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output
+
+        successor {
+            value @filter(op: ">", value: ["$min"])
+            predecessor {
+                name @filter(op: "is_not_null")
+            }
+        }
+        predecessor {
+            value @output(name: "predecessor")
+        }
+        multiple(max: 3) {
+            vowelsInName @tag
+            predecessor {
+                vowelsInName @filter(op: "=", value: ["%vowelsInName"])
+            }
+        }
+        multiple(max: 4) {
+            divisor @fold @transform(op: "count") @filter(op: ">", value: ["$min"])
+        }
+        successor {
+            value @filter(op: "<", value: ["$min"])
+            name @tag
+        }
+        predecessor @optional {
+            name @filter(op: "=", value: ["%name"])
+        }
+        predecessor @fold {
+            value @output(name: "folded")
+        }
+        successor @recurse(depth: 2) {
+            value @filter(op: ">", value: ["$min"])
+        }
+    }
+}"#;
+        let info = query_info_at(
+            query,
+            1,
+            btreemap! {
+                "min".into() => FieldValue::Int64(2),
+            },
+        );
+
+        // Nothing beyond these edges is output or tagged for use elsewhere in the query.
+        // The third `successor` edge's tag is used at the optional `predecessor` edge.
+        let edges: Vec<_> = info
+            .statically_required_edges_exist()
+            .map(|edge| edge.edge_name().to_string())
+            .collect();
+        assert_eq!(vec!["successor", "multiple", "multiple"], edges);
+
+        // The edges are relative to the vertex at the query location.
+        let successor_info = query_info_at(
+            query,
+            2,
+            btreemap! {
+                "min".into() => FieldValue::Int64(2),
+            },
+        );
+        assert_eq!(
+            vec!["predecessor"],
+            successor_info
+                .statically_required_edges_exist()
+                .map(|edge| edge.edge_name().to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn fold_info() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output
+
+        multiple(max: 3) @fold @transform(op: "count") @output(name: "count")
+                                @filter(op: ">", value: ["$min"]) {
+            value @output(name: "multiples")
+            divisor @fold(sort_by: "value", descending: true, limit: 2) {
+                value @output(name: "divisors")
+            }
+        }
+        predecessor @fold @transform(op: "count") @filter(op: "<", value: ["$min"])
+        successor {
+            value @output(name: "successor")
+        }
+    }
+}"#;
+        let info = query_info_at(
+            query,
+            1,
+            btreemap! {
+                "min".into() => FieldValue::Int64(2),
+            },
+        );
+        let eid = |name: &str| {
+            info.outgoing_edges()
+                .find(|edge| edge.edge_name().as_ref() == name)
+                .unwrap()
+                .eid()
+        };
+
+        let multiple = info.fold_info(eid("multiple")).expect("no fold info");
+        assert_eq!("multiple", multiple.edge_name().as_ref());
+        assert_eq!(
+            Some(&FieldValue::Int64(3)),
+            multiple.parameters().get("max")
+        );
+        assert_eq!(
+            vec!["multiples"],
+            multiple
+                .outputs()
+                .keys()
+                .map(|name| name.as_ref())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            btreemap! { Arc::from("count") => FoldSpecificFieldKind::Count },
+            *multiple.transform_outputs()
+        );
+        assert_eq!(
+            BTreeSet::from([FoldSpecificFieldKind::Count]),
+            multiple.transforms()
+        );
+        assert_eq!(1, multiple.transform_filters().len());
+        assert_eq!(None, multiple.sort());
+        assert_eq!(None, multiple.limit());
+        assert_eq!(1, multiple.component().folds.len());
+        assert_eq!(
+            "Composite",
+            multiple.component().vertices[&multiple.destination_vid()]
+                .type_name
+                .as_ref()
+        );
+
+        // A transform that is only filtered on, and not output, is still reported.
+        let predecessor = info.fold_info(eid("predecessor")).expect("no fold info");
+        assert!(predecessor.outputs().is_empty());
+        assert!(predecessor.transform_outputs().is_empty());
+        assert_eq!(
+            BTreeSet::from([FoldSpecificFieldKind::Count]),
+            predecessor.transforms()
+        );
+        assert!(matches!(
+            predecessor.transform_filters(),
+            [Operation::LessThan(FoldSpecificFieldKind::Count, _)]
+        ));
+
+        // Nested folds are found too, while non-folded edges have no fold info.
+        let nested_eid = *multiple.component().folds.keys().next().unwrap();
+        let divisor = info.fold_info(nested_eid).expect("no fold info");
+        assert_eq!("divisor", divisor.edge_name().as_ref());
+        assert!(divisor.transforms().is_empty());
+        assert_eq!(
+            Some(&FoldSort {
+                field_name: "value".into(),
+                descending: true,
+            }),
+            divisor.sort()
+        );
+        assert_eq!(NonZeroUsize::new(2), divisor.limit());
+        assert!(info.fold_info(eid("successor")).is_none());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn for_testing() {
+        let schema = numbers_schema();
+        let query = parse(
+            &schema,
+            r#"
+{
+    Number(max: 10) {
+        value @output @filter(op: "=", value: ["$value"])
+
+        successor {
+            successor_value: value @output
+        }
+    }
+}"#,
+        )
+        .unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> =
+            Arc::new(btreemap! { "value".into() => FieldValue::Int64(3) });
+        let vid = |id| Vid::new(NonZeroUsize::new(id).unwrap());
+        let eid = crate::ir::Eid::new(NonZeroUsize::new(1).unwrap());
+
+        let info =
+            QueryInfo::for_testing(query.clone(), arguments.clone(), vid(1), Some(eid)).unwrap();
+        assert_eq!(vid(1), info.origin_vid());
+        assert_eq!(Some(eid), info.origin_crossing_eid());
+        assert_eq!(
+            Some("Single(3)".to_string()),
+            info.starting_vertex_candidate("value")
+                .map(|candidate| candidate.to_string())
+        );
+
+        let info = QueryInfo::for_testing(query.clone(), arguments, vid(2), None).unwrap();
+        assert!(info.property_usage("value").output);
+
+        assert!(QueryInfo::for_testing(query, Default::default(), vid(1), None).is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn for_testing_query() {
+        let schema = numbers_schema();
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output @filter(op: ">=", value: ["$min"]) @filter(op: "<", value: ["$max"])
+
+        successor {
+            name @filter(op: "is_not_null")
+        }
+    }
+}"#;
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Arc::new(btreemap! {
+            "min".into() => FieldValue::Int64(3),
+            "max".into() => FieldValue::Int64(7),
+        });
+        let vid = |id| Vid::new(NonZeroUsize::new(id).unwrap());
+
+        let info = QueryInfo::for_testing_query(&schema, query, arguments.clone(), vid(1)).unwrap();
+        assert_eq!(
+            Some("Range(>=3, <7)".to_string()),
+            info.starting_vertex_candidate("value")
+                .map(|candidate| candidate.to_string())
+        );
+
+        let info = QueryInfo::for_testing_query(&schema, query, arguments.clone(), vid(2)).unwrap();
+        assert!(info.property_usage("name").is_filter_only());
+
+        assert!(QueryInfo::for_testing_query(&schema, query, Default::default(), vid(1)).is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[should_panic(expected = "does not leave")]
+    fn for_testing_rejects_edge_from_other_vertex() {
+        let schema = numbers_schema();
+        let query = parse(&schema, "{ Zero { successor { value @output } } }").unwrap();
+        let _ = QueryInfo::for_testing(
+            query,
+            Default::default(),
+            Vid::new(NonZeroUsize::new(2).unwrap()),
+            Some(crate::ir::Eid::new(NonZeroUsize::new(1).unwrap())),
+        );
+    }
+
+    #[test]
+    fn query_fingerprint_and_variables() {
+        let query = r#"
+{
+    Number(max: 10) {
+        value @output @filter(op: ">", value: ["$min"]) @filter(op: "<", value: ["$max"])
+    }
+}"#;
+        let reformatted = r#"{ Number(max: 10) {
+            value @filter(op: ">", value: ["$min"]) @output @filter(op: "<", value: ["$max"])
+        } }"#;
+        let different = r#"
+{
+    Number(max: 11) {
+        value @output @filter(op: ">", value: ["$min"]) @filter(op: "<", value: ["$max"])
+    }
+}"#;
+        let arguments = |min: i64| {
+            BTreeMap::from([
+                (Arc::from("min"), FieldValue::Int64(min)),
+                (Arc::from("max"), FieldValue::Int64(8)),
+            ])
+        };
+
+        let info = query_info_at(query, 1, arguments(1));
+        assert_eq!(
+            vec!["max", "min"],
+            info.variable_names()
+                .map(|name| name.as_ref())
+                .collect::<Vec<_>>()
+        );
+
+        let fingerprint = info.query_fingerprint();
+        assert_eq!(fingerprint, info.query_fingerprint());
+        assert_eq!(
+            fingerprint,
+            query_info_at(reformatted, 1, arguments(1)).query_fingerprint()
+        );
+        assert_eq!(
+            fingerprint,
+            query_info_at(query, 1, arguments(2)).query_fingerprint()
+        );
+        assert_ne!(
+            fingerprint,
+            query_info_at(different, 1, arguments(1)).query_fingerprint()
+        );
+    }
+
+    #[test]
+    fn starting_vertex_candidate() {
+        let candidate_for = |filters: &str, arguments: Vec<(&str, FieldValue)>| {
+            let query = format!(
+                r#"
+{{
+    Number(max: 100) {{
+        value @output {filters}
+    }}
+}}"#
+            );
+            let arguments = arguments
+                .into_iter()
+                .map(|(name, value)| (Arc::from(name), value))
+                .collect();
+            let info = query_info_at(&query, 1, arguments);
+            info.starting_vertex_candidate("value")
+                .map(|candidate| candidate.to_string())
+        };
+
+        assert_eq!(None, candidate_for("", vec![]));
+        assert_eq!(
+            None,
+            candidate_for(
+                r#"@filter(op: "!=", value: ["$n"])"#,
+                vec![("n", FieldValue::Int64(3))]
+            )
+        );
+        assert_eq!(
+            Some("Single(3)".to_string()),
+            candidate_for(
+                r#"@filter(op: "=", value: ["$n"])"#,
+                vec![("n", FieldValue::Int64(3))]
+            )
+        );
+        assert_eq!(
+            Some("Multiple([4, 2])".to_string()),
+            candidate_for(
+                r#"@filter(op: "one_of", value: ["$ns"]) @filter(op: ">", value: ["$min"])"#,
+                vec![
+                    ("ns", vec![1i64, 4, 2, 4].into()),
+                    ("min", FieldValue::Int64(1))
+                ]
+            )
+        );
+        assert_eq!(
+            Some("Range(>3, <=7)".to_string()),
+            candidate_for(
+                r#"@filter(op: ">=", value: ["$a"]) @filter(op: ">", value: ["$b"])
+                   @filter(op: "<=", value: ["$c"]) @filter(op: "<", value: ["$d"])"#,
+                vec![
+                    ("a", FieldValue::Int64(3)),
+                    ("b", FieldValue::Int64(3)),
+                    ("c", FieldValue::Int64(7)),
+                    ("d", FieldValue::Int64(9)),
+                ]
+            )
+        );
+        assert_eq!(
+            Some("Single(5)".to_string()),
+            candidate_for(
+                r#"@filter(op: ">=", value: ["$a"]) @filter(op: "<=", value: ["$b"])"#,
+                vec![("a", FieldValue::Int64(5)), ("b", FieldValue::Int64(5))]
+            )
+        );
+        assert_eq!(
+            Some("Impossible".to_string()),
+            candidate_for(
+                r#"@filter(op: "=", value: ["$n"]) @filter(op: "<", value: ["$max"])"#,
+                vec![("n", FieldValue::Int64(5)), ("max", FieldValue::Int64(5))]
+            )
+        );
+
+        // A null in a `one_of` list matches vertices whose property value is null.
+        assert_eq!(
+            Some("Multiple([1, null])".to_string()),
+            candidate_for(
+                r#"@filter(op: "one_of", value: ["$ns"])"#,
+                vec![("ns", vec![Some(1i64), None].into())]
+            )
+        );
+        assert_eq!(
+            Some("Single(null)".to_string()),
+            candidate_for(
+                r#"@filter(op: "one_of", value: ["$ns"]) @filter(op: "is_null")"#,
+                vec![("ns", vec![Some(1i64), None].into())]
+            )
+        );
+        assert_eq!(
+            Some("Single(1)".to_string()),
+            candidate_for(
+                r#"@filter(op: "one_of", value: ["$ns"]) @filter(op: ">=", value: ["$min"])"#,
+                vec![
+                    ("ns", vec![Some(1i64), None].into()),
+                    ("min", FieldValue::Int64(0))
+                ]
+            )
+        );
+
+        // Ranges work the same way for other orderable types, like strings.
+        let query = r#"
+{
+    Number(max: 100) {
+        name @filter(op: ">=", value: ["$min"]) @filter(op: "<", value: ["$max"])
+        value @output
+    }
+}"#;
+        let arguments = btreemap! {
+            Arc::from("min") => FieldValue::from("m"),
+            Arc::from("max") => FieldValue::from("t"),
+        };
+        let info = query_info_at(query, 1, arguments);
+        assert_eq!(
+            Some(r#"Range(>="m", <"t")"#.to_string()),
+            info.starting_vertex_candidate("name")
+                .map(|candidate| candidate.to_string())
+        );
+    }
+
+    #[test]
+    fn starting_vertex_candidate_with_missing_argument() {
+        let schema = numbers_schema();
+        let indexed_query = parse(
+            &schema,
+            r#"
+{
+    Number(max: 100) {
+        value @output @filter(op: ">", value: ["$min"]) @filter(op: "<", value: ["$max"])
+    }
+}"#,
+        )
+        .unwrap();
+
+        // Bypass argument validation, which would reject the missing `$max` argument.
+        let query = InterpretedQuery {
+            indexed_query,
+            arguments: Arc::new(btreemap! { Arc::from("min") => FieldValue::Int64(3) }),
+        };
+        let info = QueryInfo::new(query, Vid(NonZeroUsize::new(1).unwrap()), None);
+        assert_eq!(
+            Some("Range(>3)".to_string()),
+            info.starting_vertex_candidate("value")
+                .map(|candidate| candidate.to_string())
+        );
+
+        let query = InterpretedQuery {
+            indexed_query: info.query.indexed_query.clone(),
+            arguments: Default::default(),
+        };
+        let info = QueryInfo::new(query, Vid(NonZeroUsize::new(1).unwrap()), None);
+        assert_eq!(None, info.starting_vertex_candidate("value"));
+    }
 }
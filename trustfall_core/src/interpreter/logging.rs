@@ -0,0 +1,267 @@
+//! Adapter middleware that logs every call made to the wrapped adapter.
+use std::{fmt::Debug, sync::Arc};
+
+use itertools::Itertools;
+use log::{log, Level};
+
+use crate::ir::{EdgeParameters, FieldValue};
+
+use super::{
+    Adapter, ContextIterator, ContextOutcomeIterator, DataContext, QueryInfo, VertexIterator,
+};
+
+/// Passes contexts through unchanged, logging how many there were once they run out.
+struct CountedContexts<'vertex, VertexT: Clone + Debug> {
+    inner: ContextIterator<'vertex, VertexT>,
+    count: usize,
+    exhausted: bool,
+    level: Level,
+    call: String,
+}
+
+impl<'vertex, VertexT: Clone + Debug> Iterator for CountedContexts<'vertex, VertexT> {
+    type Item = DataContext<VertexT>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next();
+        match item {
+            Some(_) => self.count += 1,
+            None if !self.exhausted => {
+                self.exhausted = true;
+                log!(self.level, "{}: {} contexts", self.call, self.count);
+            }
+            None => {}
+        }
+        item
+    }
+}
+
+fn display_parameters(parameters: &EdgeParameters) -> String {
+    format!(
+        "({})",
+        parameters
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .join(", ")
+    )
+}
+
+/// Wraps an adapter, logging each call made to it via the [`log`](mod@log) crate.
+///
+/// Every call is logged when it is made, together with the names of the types, properties,
+/// and edges it involves, any edge parameters, and the query vertex it's for. Since queries
+/// are evaluated lazily, the number of contexts passed to a call is only known once
+/// the call's input iterator is exhausted, and is logged as a separate message at that point.
+///
+/// All messages are emitted at the configured log level, and results are passed through
+/// from the wrapped adapter unchanged. Unlike [tracing](super::trace), this does not record
+/// enough information to replay the query.
+#[derive(Debug)]
+pub struct LoggingAdapter<AdapterT> {
+    inner: AdapterT,
+    level: Level,
+}
+
+impl<AdapterT> LoggingAdapter<AdapterT> {
+    /// Log all calls at [`Level::Debug`].
+    pub fn new(adapter: AdapterT) -> Self {
+        Self::with_level(adapter, Level::Debug)
+    }
+
+    pub fn with_level(adapter: AdapterT, level: Level) -> Self {
+        Self {
+            inner: adapter,
+            level,
+        }
+    }
+
+    pub fn into_inner(self) -> AdapterT {
+        self.inner
+    }
+
+    fn counted<'vertex, VertexT: Clone + Debug + 'vertex>(
+        &self,
+        contexts: ContextIterator<'vertex, VertexT>,
+        call: String,
+    ) -> ContextIterator<'vertex, VertexT> {
+        log!(self.level, "{call}");
+        Box::new(CountedContexts {
+            inner: contexts,
+            count: 0,
+            exhausted: false,
+            level: self.level,
+            call,
+        })
+    }
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for LoggingAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        log!(
+            self.level,
+            "resolve_starting_vertices {edge_name}{} at {:?}",
+            display_parameters(parameters),
+            query_info.origin_vid(),
+        );
+        self.inner
+            .resolve_starting_vertices(edge_name, parameters, query_info)
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        let contexts = self.counted(
+            contexts,
+            format!(
+                "resolve_property {type_name}.{property_name} at {:?}",
+                query_info.origin_vid(),
+            ),
+        );
+        self.inner
+            .resolve_property(contexts, type_name, property_name, query_info)
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        let contexts = self.counted(
+            contexts,
+            format!(
+                "resolve_neighbors {type_name}.{edge_name}{} at {:?}",
+                display_parameters(parameters),
+                query_info.origin_vid(),
+            ),
+        );
+        self.inner
+            .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info)
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        let contexts = self.counted(
+            contexts,
+            format!(
+                "resolve_coercion {type_name} -> {coerce_to_type} at {:?}",
+                query_info.origin_vid(),
+            ),
+        );
+        self.inner
+            .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        collections::BTreeMap,
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+
+    use log::{Level, LevelFilter, Log, Metadata, Record};
+
+    use crate::{
+        interpreter::execution::interpret_ir,
+        ir::FieldValue,
+        numbers_interpreter::{parse_numbers_query, run_numbers_query, NumbersAdapter},
+    };
+
+    use super::LoggingAdapter;
+
+    struct CapturingLogger {
+        messages: Mutex<Vec<(Level, String)>>,
+    }
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger {
+        messages: Mutex::new(vec![]),
+    };
+
+    #[test]
+    fn calls_are_logged_and_results_unchanged() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 3) {
+        value @output
+
+        successor {
+            ... on Prime {
+                prime: value @output
+            }
+        }
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let expected = run_numbers_query(query.clone(), arguments.clone());
+
+        let adapter = LoggingAdapter::with_level(NumbersAdapter::new(), Level::Info);
+        let actual: Vec<_> = interpret_ir(Rc::new(RefCell::new(adapter)), query, arguments)
+            .unwrap()
+            .collect();
+        assert_eq!(expected, actual);
+
+        let messages = LOGGER.messages.lock().unwrap();
+        assert!(messages.iter().all(|(level, _)| *level == Level::Info));
+
+        let messages: Vec<_> = messages.iter().map(|(_, msg)| msg.as_str()).collect();
+        for expected_message in [
+            "resolve_starting_vertices Number(max: 3, min: 1) at Vid(1)",
+            "resolve_neighbors Number.successor() at Vid(1)",
+            "resolve_neighbors Number.successor() at Vid(1): 3 contexts",
+            "resolve_coercion Number -> Prime at Vid(2)",
+            "resolve_coercion Number -> Prime at Vid(2): 3 contexts",
+            "resolve_property Prime.value at Vid(2): 2 contexts",
+            "resolve_property Number.value at Vid(1): 2 contexts",
+        ] {
+            assert!(
+                messages.contains(&expected_message),
+                "{expected_message:?} not in {messages:#?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! Approximate accounting of the memory held by a query's partial results,
+//! for stopping queries that would otherwise use too much of it.
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Debug,
+    mem,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::ir::{indexed::IndexedQuery, EdgeParameters, FieldValue};
+
+use super::{
+    cancellation::interpret_ir_with_cancellation, error::QueryArgumentsError, Adapter,
+    ContextIterator, ContextOutcomeIterator, DataContext, QueryInfo, VertexIterator,
+};
+
+/// A query was stopped because the memory held by its partial results
+/// was estimated to exceed its limit.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Query exceeded its memory limit of {limit_bytes} bytes: its {live_contexts} partial results \
+    held in memory were estimated to use {estimated_bytes} bytes"
+)]
+pub struct MemoryLimitExceeded {
+    pub limit_bytes: usize,
+    pub estimated_bytes: usize,
+    pub live_contexts: usize,
+}
+
+/// The memory limit for [`interpret_ir_with_memory_limit`].
+///
+/// Memory use is estimated as the number of partial results currently held in memory,
+/// such as those buffered while computing a `@fold`, multiplied by an estimate
+/// of the size of each one. By default, that estimate is the inline size of a partial result,
+/// which doesn't include any heap memory owned by its vertices and property values.
+/// Queries over vertex types that own significant heap memory should set a larger estimate
+/// with [`MemoryLimit::with_bytes_per_context`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimit {
+    max_bytes: usize,
+    bytes_per_context: Option<usize>,
+}
+
+impl MemoryLimit {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            bytes_per_context: None,
+        }
+    }
+
+    /// Estimate that each partial result held in memory uses the given number of bytes.
+    pub fn with_bytes_per_context(self, bytes_per_context: usize) -> Self {
+        Self {
+            bytes_per_context: Some(bytes_per_context),
+            ..self
+        }
+    }
+}
+
+/// The memory accounting shared by all partial results of a query.
+#[derive(Debug)]
+pub(super) struct MemoryAccount {
+    limit_bytes: usize,
+    bytes_per_context: usize,
+    live_contexts: AtomicUsize,
+    peak_contexts: AtomicUsize,
+    exceeded: Mutex<Option<MemoryLimitExceeded>>,
+
+    /// Set once the limit is exceeded, cancelling the query.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl MemoryAccount {
+    /// Count one more partial result as held in memory, until the returned token is dropped.
+    pub(super) fn token(self: &Arc<Self>) -> ContextMemoryToken {
+        let live_contexts = self.live_contexts.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_contexts
+            .fetch_max(live_contexts, Ordering::Relaxed);
+
+        let estimated_bytes = live_contexts.saturating_mul(self.bytes_per_context);
+        if estimated_bytes > self.limit_bytes && !self.cancelled.swap(true, Ordering::Relaxed) {
+            *self.exceeded.lock().unwrap() = Some(MemoryLimitExceeded {
+                limit_bytes: self.limit_bytes,
+                estimated_bytes,
+                live_contexts,
+            });
+        }
+
+        ContextMemoryToken(self.clone())
+    }
+}
+
+/// Counts a partial result against its query's memory limit for as long as it exists.
+///
+/// Clones of a partial result are held in memory separately, so they are counted separately.
+pub(super) struct ContextMemoryToken(Arc<MemoryAccount>);
+
+impl ContextMemoryToken {
+    pub(super) fn account(&self) -> &Arc<MemoryAccount> {
+        &self.0
+    }
+}
+
+impl Clone for ContextMemoryToken {
+    fn clone(&self) -> Self {
+        self.0.token()
+    }
+}
+
+impl Drop for ContextMemoryToken {
+    fn drop(&mut self) {
+        self.0.live_contexts.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Debug for ContextMemoryToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextMemoryToken").finish_non_exhaustive()
+    }
+}
+
+/// The estimated memory use of a query, as returned by [`interpret_ir_with_memory_limit`].
+///
+/// Query execution is lazy, so the estimates are updated as the query's result iterator
+/// is consumed. All clones of a `MemoryUsage` share the same estimates.
+#[derive(Debug, Clone)]
+pub struct MemoryUsage {
+    account: Arc<MemoryAccount>,
+}
+
+impl MemoryUsage {
+    /// The number of the query's partial results currently held in memory.
+    pub fn live_contexts(&self) -> usize {
+        self.account.live_contexts.load(Ordering::Relaxed)
+    }
+
+    /// The estimated memory currently held by the query's partial results, in bytes.
+    pub fn estimated_bytes(&self) -> usize {
+        self.live_contexts()
+            .saturating_mul(self.account.bytes_per_context)
+    }
+
+    /// The highest estimated memory held by the query's partial results at any one time,
+    /// in bytes.
+    pub fn peak_estimated_bytes(&self) -> usize {
+        self.account
+            .peak_contexts
+            .load(Ordering::Relaxed)
+            .saturating_mul(self.account.bytes_per_context)
+    }
+
+    /// The error that stopped the query, if it exceeded its memory limit.
+    pub fn limit_exceeded(&self) -> Option<MemoryLimitExceeded> {
+        self.account.exceeded.lock().unwrap().clone()
+    }
+}
+
+/// Passes all calls through to the wrapped adapter, counting the contexts it produces
+/// against the query's memory limit.
+///
+/// Contexts derived from a counted context, such as when expanding an edge, are counted too.
+struct MemoryAccountedAdapter<AdapterT> {
+    inner: Rc<RefCell<AdapterT>>,
+    account: Arc<MemoryAccount>,
+}
+
+impl<AdapterT> MemoryAccountedAdapter<AdapterT> {
+    fn account_for<'vertex, Vertex, OutcomeT>(
+        &self,
+        outcomes: ContextOutcomeIterator<'vertex, Vertex, OutcomeT>,
+    ) -> ContextOutcomeIterator<'vertex, Vertex, OutcomeT>
+    where
+        Vertex: Clone + Debug + 'vertex,
+        OutcomeT: 'vertex,
+    {
+        let account = self.account.clone();
+        Box::new(outcomes.map(move |(mut ctx, outcome)| {
+            if ctx.memory.is_none() {
+                ctx.memory = Some(account.token());
+            }
+            (ctx, outcome)
+        }))
+    }
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for MemoryAccountedAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        self.inner
+            .borrow_mut()
+            .resolve_starting_vertices(edge_name, parameters, query_info)
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        let values = self.inner.borrow_mut().resolve_property(
+            contexts,
+            type_name,
+            property_name,
+            query_info,
+        );
+        self.account_for(values)
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        let neighbors = self
+            .inner
+            .borrow_mut()
+            .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info);
+        self.account_for(neighbors)
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        let outcomes = self.inner.borrow_mut().resolve_coercion(
+            contexts,
+            type_name,
+            coerce_to_type,
+            query_info,
+        );
+        self.account_for(outcomes)
+    }
+}
+
+/// Yields the query's results until the memory limit is exceeded, then yields
+/// the [`MemoryLimitExceeded`] error once and ends.
+struct MemoryLimitedResults<I> {
+    inner: Option<I>,
+    account: Arc<MemoryAccount>,
+}
+
+impl<I> Iterator for MemoryLimitedResults<I>
+where
+    I: Iterator<Item = BTreeMap<Arc<str>, FieldValue>>,
+{
+    type Item = Result<BTreeMap<Arc<str>, FieldValue>, MemoryLimitExceeded>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.inner.as_mut()?.next();
+
+        // A row produced while the query was being stopped may be missing data
+        // from the vertices that were cut off, so it's replaced by the error.
+        if let Some(exceeded) = self.account.exceeded.lock().unwrap().clone() {
+            self.inner = None;
+            return Some(Err(exceeded));
+        }
+        row.map(Ok)
+    }
+}
+
+/// Like [`interpret_ir`](super::execution::interpret_ir), but stops producing results
+/// once the memory held by the query's partial results is estimated to exceed `limit`.
+///
+/// This is a safety valve against queries whose partial results would use too much memory,
+/// such as ones with a `@fold` over a very large number of vertices. The estimate is coarse:
+/// see [`MemoryLimit`] for how it's computed. Partial results are counted once they reach
+/// the adapter, so a starting vertex that hasn't yet been passed to the adapter isn't counted.
+///
+/// A query that exceeds its limit is stopped the same way as a query cancelled with
+/// [`interpret_ir_with_cancellation`], and its result iterator then yields
+/// a [`MemoryLimitExceeded`] error as its last item. Every `Ok` row is a complete result.
+/// Since execution is lazy, a query's memory use depends on how its results are consumed,
+/// and results may have been produced before the limit was exceeded.
+#[allow(clippy::type_complexity)]
+pub fn interpret_ir_with_memory_limit<'query, AdapterT>(
+    adapter: Rc<RefCell<AdapterT>>,
+    indexed_query: Arc<IndexedQuery>,
+    arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+    limit: MemoryLimit,
+) -> Result<
+    (
+        Box<
+            dyn Iterator<Item = Result<BTreeMap<Arc<str>, FieldValue>, MemoryLimitExceeded>>
+                + 'query,
+        >,
+        MemoryUsage,
+    ),
+    QueryArgumentsError,
+>
+where
+    AdapterT: Adapter<'query> + 'query,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let account = Arc::new(MemoryAccount {
+        limit_bytes: limit.max_bytes,
+        bytes_per_context: limit
+            .bytes_per_context
+            .unwrap_or(mem::size_of::<DataContext<AdapterT::Vertex>>()),
+        live_contexts: AtomicUsize::new(0),
+        peak_contexts: AtomicUsize::new(0),
+        exceeded: Mutex::new(None),
+        cancelled: cancelled.clone(),
+    });
+    let accounted_adapter = Rc::new(RefCell::new(MemoryAccountedAdapter {
+        inner: adapter,
+        account: account.clone(),
+    }));
+
+    let results =
+        interpret_ir_with_cancellation(accounted_adapter, indexed_query, arguments, cancelled)?;
+    let results = MemoryLimitedResults {
+        inner: Some(results),
+        account: account.clone(),
+    };
+    Ok((Box::new(results), MemoryUsage { account }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{
+        frontend::parse,
+        ir::FieldValue,
+        numbers_interpreter::{numbers_adapter, numbers_schema},
+    };
+
+    use super::{interpret_ir_with_memory_limit, MemoryLimit, MemoryLimitExceeded};
+
+    const FOLD_QUERY: &str = r#"
+{
+    Number(min: 1, max: 10) {
+        value @output
+
+        multiple(max: 20) @fold {
+            multiples: value @output
+        }
+    }
+}"#;
+
+    #[allow(clippy::type_complexity)]
+    fn run_with_limit(
+        limit: MemoryLimit,
+    ) -> (
+        Vec<Result<BTreeMap<Arc<str>, FieldValue>, MemoryLimitExceeded>>,
+        super::MemoryUsage,
+    ) {
+        let schema = numbers_schema();
+        let query = parse(&schema, FOLD_QUERY).unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter = numbers_adapter();
+        let (results, usage) =
+            interpret_ir_with_memory_limit(adapter, query, arguments, limit).unwrap();
+        (results.collect(), usage)
+    }
+
+    #[test]
+    fn queries_within_the_limit_are_unaffected() {
+        let (rows, usage) = run_with_limit(MemoryLimit::new(1000).with_bytes_per_context(1));
+        assert_eq!(10, rows.len());
+        assert!(rows.iter().all(Result::is_ok), "{rows:?}");
+        assert_eq!(None, usage.limit_exceeded());
+
+        // The folded partial results were held in memory, and have all been released.
+        assert!(usage.peak_estimated_bytes() >= 19, "{usage:?}");
+        assert_eq!(0, usage.live_contexts());
+        assert_eq!(0, usage.estimated_bytes());
+    }
+
+    #[test]
+    fn queries_over_the_limit_are_stopped() {
+        let (mut rows, usage) = run_with_limit(MemoryLimit::new(10).with_bytes_per_context(2));
+        let expected = MemoryLimitExceeded {
+            limit_bytes: 10,
+            estimated_bytes: 12,
+            live_contexts: 6,
+        };
+        assert_eq!(Some(Err(expected.clone())), rows.pop());
+        assert!(rows.len() < 10, "{rows:?}");
+        assert!(rows.iter().all(Result::is_ok), "{rows:?}");
+        assert_eq!(Some(expected), usage.limit_exceeded());
+        assert_eq!(0, usage.live_contexts());
+    }
+}
@@ -4,26 +4,42 @@ use async_graphql_parser::types::Type;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    ir::{
-        indexed::IndexedQuery, types::is_argument_type_valid, EdgeParameters, Eid, FieldRef,
-        FieldValue, Vid,
-    },
-    util::BTreeMapTryInsertExt,
+use crate::ir::{
+    indexed::IndexedQuery, types::is_argument_type_valid, EdgeParameters, Eid, FieldRef,
+    FieldValue, Vid,
 };
 
-use self::error::QueryArgumentsError;
+use self::{error::QueryArgumentsError, vertex_store::VertexStore};
 
 pub mod basic_adapter;
+pub mod buffered;
+pub mod cancellation;
+pub mod composite;
+pub mod cost;
+pub mod dry_run;
 pub mod error;
 pub mod execution;
-mod filtering;
+pub(crate) mod filtering;
 pub mod helpers;
 mod hints;
+#[cfg(feature = "log")]
+pub mod logging;
+pub mod memory;
+pub mod nullability;
+pub mod rate_limit;
 pub mod replay;
+pub mod retry;
+pub mod stats;
+#[cfg(feature = "async")]
+pub mod stream;
 pub mod trace;
+pub mod type_check;
+mod vertex_store;
 
-pub use hints::QueryInfo;
+pub use hints::{
+    composite_keys, CandidateValue, CompositeKeys, EdgeInfo, FoldInfo, PropertyUsage, QueryInfo,
+    Range,
+};
 
 /// An iterator of vertices representing data points we are querying.
 pub type VertexIterator<'vertex, VertexT> = Box<dyn Iterator<Item = VertexT> + 'vertex>;
@@ -61,13 +77,17 @@ pub trait Typename {
 #[derive(Debug, Clone)]
 pub struct DataContext<Vertex: Clone + Debug> {
     active_vertex: Option<Vertex>,
-    vertices: BTreeMap<Vid, Option<Vertex>>,
+    vertices: VertexStore<Vertex>,
     values: Vec<FieldValue>,
     suspended_vertices: Vec<Option<Vertex>>,
     folded_contexts: BTreeMap<Eid, Vec<DataContext<Vertex>>>,
     folded_values: BTreeMap<(Eid, Arc<str>), Option<ValueOrVec>>,
     piggyback: Option<Vec<DataContext<Vertex>>>,
     imported_tags: BTreeMap<FieldRef, FieldValue>,
+    recursion_depths: BTreeMap<Eid, usize>,
+
+    /// Counts this context against the query's memory limit, if it has one.
+    memory: Option<memory::ContextMemoryToken>,
 }
 
 impl<Vertex: Clone + Debug> DataContext<Vertex> {
@@ -139,6 +159,10 @@ where
     /// Tagged values imported from an ancestor component of the one currently being evaluated.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     imported_tags: BTreeMap<FieldRef, FieldValue>,
+
+    /// The recursion depth reached along each recursive edge whose depth is a query output.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    recursion_depths: BTreeMap<Eid, usize>,
 }
 
 impl<Vertex> From<SerializableContext<Vertex>> for DataContext<Vertex>
@@ -149,13 +173,15 @@ where
     fn from(context: SerializableContext<Vertex>) -> Self {
         Self {
             active_vertex: context.active_vertex,
-            vertices: context.vertices,
+            vertices: context.vertices.into(),
             values: context.values,
             suspended_vertices: context.suspended_vertices,
             folded_contexts: context.folded_contexts,
             folded_values: context.folded_values,
             piggyback: context.piggyback,
             imported_tags: context.imported_tags,
+            recursion_depths: context.recursion_depths,
+            memory: None,
         }
     }
 }
@@ -168,13 +194,14 @@ where
     fn from(context: DataContext<Vertex>) -> Self {
         Self {
             active_vertex: context.active_vertex,
-            vertices: context.vertices,
+            vertices: context.vertices.into(),
             values: context.values,
             suspended_vertices: context.suspended_vertices,
             folded_contexts: context.folded_contexts,
             folded_values: context.folded_values,
             piggyback: context.piggyback,
             imported_tags: context.imported_tags,
+            recursion_depths: context.recursion_depths,
         }
     }
 }
@@ -190,13 +217,13 @@ impl<Vertex: Clone + Debug> DataContext<Vertex> {
             folded_contexts: Default::default(),
             folded_values: Default::default(),
             imported_tags: Default::default(),
+            recursion_depths: Default::default(),
+            memory: None,
         }
     }
 
     fn record_vertex(&mut self, vid: Vid) {
-        self.vertices
-            .insert_or_error(vid, self.active_vertex.clone())
-            .unwrap();
+        self.vertices.insert(vid, self.active_vertex.clone());
     }
 
     fn activate_vertex(self, vid: &Vid) -> DataContext<Vertex> {
@@ -209,6 +236,8 @@ impl<Vertex: Clone + Debug> DataContext<Vertex> {
             folded_values: self.folded_values,
             piggyback: self.piggyback,
             imported_tags: self.imported_tags,
+            recursion_depths: self.recursion_depths,
+            memory: self.memory,
         }
     }
 
@@ -222,6 +251,8 @@ impl<Vertex: Clone + Debug> DataContext<Vertex> {
             folded_values: self.folded_values.clone(),
             piggyback: None,
             imported_tags: self.imported_tags.clone(),
+            recursion_depths: self.recursion_depths.clone(),
+            memory: self.memory.clone(),
         }
     }
 
@@ -235,6 +266,8 @@ impl<Vertex: Clone + Debug> DataContext<Vertex> {
             folded_values: self.folded_values,
             piggyback: self.piggyback,
             imported_tags: self.imported_tags,
+            recursion_depths: self.recursion_depths,
+            memory: self.memory,
         }
     }
 
@@ -250,6 +283,8 @@ impl<Vertex: Clone + Debug> DataContext<Vertex> {
                 folded_values: self.folded_values,
                 piggyback: self.piggyback,
                 imported_tags: self.imported_tags,
+                recursion_depths: self.recursion_depths,
+                memory: self.memory,
             }
         } else {
             self
@@ -269,6 +304,8 @@ impl<Vertex: Clone + Debug> DataContext<Vertex> {
                     folded_values: self.folded_values,
                     piggyback: self.piggyback,
                     imported_tags: self.imported_tags,
+                    recursion_depths: self.recursion_depths,
+                    memory: self.memory,
                 }
             }
             Some(_) => self,
@@ -285,6 +322,7 @@ impl<Vertex: Debug + Clone + PartialEq> PartialEq for DataContext<Vertex> {
             && self.folded_contexts == other.folded_contexts
             && self.piggyback == other.piggyback
             && self.imported_tags == other.imported_tags
+            && self.recursion_depths == other.recursion_depths
     }
 }
 
@@ -332,9 +370,16 @@ impl InterpretedQuery {
     ) -> Result<Self, QueryArgumentsError> {
         let mut errors = vec![];
 
+        let ir_query = &indexed_query.ir_query;
         let mut missing_arguments = vec![];
-        for (variable_name, variable_type) in &indexed_query.ir_query.variables {
-            match arguments.get(variable_name) {
+        let mut defaulted_arguments = vec![];
+        for (variable_name, variable_type) in &ir_query.variables {
+            let argument_value = arguments.get(variable_name).or_else(|| {
+                let default_value = ir_query.variable_defaults.get(variable_name)?;
+                defaulted_arguments.push((variable_name.clone(), default_value.clone()));
+                Some(default_value)
+            });
+            match argument_value {
                 Some(argument_value) => {
                     // Ensure the provided argument value is valid for the variable's inferred type.
                     if let Err(e) = validate_argument_type(
@@ -362,7 +407,7 @@ impl InterpretedQuery {
         let unused_arguments = arguments
             .keys()
             .map(|x| x.as_ref())
-            .filter(|arg| !indexed_query.ir_query.variables.contains_key(*arg))
+            .filter(|arg| !ir_query.variables.contains_key(*arg))
             .collect_vec();
         if !unused_arguments.is_empty() {
             errors.push(QueryArgumentsError::UnusedArguments(
@@ -374,6 +419,15 @@ impl InterpretedQuery {
         }
 
         if errors.is_empty() {
+            // Variables without a supplied value take their default value, if they have one.
+            let arguments = if defaulted_arguments.is_empty() {
+                arguments
+            } else {
+                let mut arguments = arguments.as_ref().clone();
+                arguments.extend(defaulted_arguments);
+                Arc::new(arguments)
+            };
+
             Ok(Self {
                 indexed_query,
                 arguments,
@@ -404,6 +458,34 @@ fn validate_argument_type(
 ///
 /// Simpler variants of this trait exist, at the expense of some flexibility.
 /// See [`BasicAdapter`](self::basic_adapter::BasicAdapter) for details.
+///
+/// Only [`resolve_starting_vertices`](Adapter::resolve_starting_vertices) and
+/// [`resolve_property`](Adapter::resolve_property) must be implemented.
+/// The remaining methods have default implementations suitable for schemas
+/// without edges (other than starting edges) or without subtyping, respectively.
+///
+/// # Request-scoped state
+///
+/// Each query execution takes its own adapter, so the intended way to give resolvers
+/// access to connection pools, API clients, credentials, and similar state is to store it
+/// in the adapter, constructing a new adapter for each query. There is no separate context
+/// object threaded through the interpreter: the adapter is that context.
+///
+/// Keep adapter construction cheap by putting resources shared across queries behind an `Arc`,
+/// and storing per-request state, such as the requesting user's auth token, directly.
+/// The iterators returned by the resolver methods may outlive the `&mut self` borrow,
+/// so resolvers should clone what they need out of `self` instead of borrowing it:
+/// ```ignore
+/// struct MyAdapter {
+///     pool: Arc<ConnectionPool>,  // shared by all queries
+///     auth_token: String,         // specific to this request
+/// }
+///
+/// // In `resolve_starting_vertices()`:
+/// let pool = self.pool.clone();
+/// let auth_token = self.auth_token.clone();
+/// Box::new(ids.into_iter().map(move |id| pool.get(&auth_token, id)))
+/// ```
 pub trait Adapter<'vertex> {
     /// The type of vertices in the dataset this adapter queries.
     /// It's frequently a good idea to use an Rc<...> type for cheaper cloning here.
@@ -470,7 +552,9 @@ pub trait Adapter<'vertex> {
     /// This function resolves the neighboring vertices for that active vertex.
     ///
     /// If the schema this adapter covers has no edges aside from starting edges,
-    /// then this method will never be called and may be implemented as `unreachable!()`.
+    /// then this method will never be called. In that case, the default implementation
+    /// may be used: it panics with a message naming the edge, since being called means
+    /// the adapter is missing an implementation for an edge in its schema.
     ///
     /// The caller guarantees that:
     /// - `type_name` is a type or interface defined in the schema.
@@ -487,12 +571,17 @@ pub trait Adapter<'vertex> {
     /// - When a context's active vertex is None, it has an empty neighbors iterator.
     fn resolve_neighbors(
         &mut self,
-        contexts: ContextIterator<'vertex, Self::Vertex>,
+        _contexts: ContextIterator<'vertex, Self::Vertex>,
         type_name: &Arc<str>,
         edge_name: &Arc<str>,
-        parameters: &EdgeParameters,
-        query_info: &QueryInfo,
-    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>>;
+        _parameters: &EdgeParameters,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        unimplemented!(
+            "resolve_neighbors() is not implemented by this adapter, but was called \
+            for edge \"{edge_name}\" on type \"{type_name}\""
+        )
+    }
 
     /// Attempt to coerce vertices to a subtype, over an iterator of query contexts.
     ///
@@ -516,7 +605,8 @@ pub trait Adapter<'vertex> {
     /// This function checks whether the active vertex is of the specified subtype.
     ///
     /// If this adapter's schema contains no subtyping, then no type coercions are possible:
-    /// this method will never be called and may be implemented as `unreachable!()`.
+    /// this method will never be called. In that case, the default implementation may be used:
+    /// it reports that coercion failed (`false`) for every context.
     ///
     /// The caller guarantees that:
     /// - `type_name` is an interface defined in the schema.
@@ -533,8 +623,10 @@ pub trait Adapter<'vertex> {
     fn resolve_coercion(
         &mut self,
         contexts: ContextIterator<'vertex, Self::Vertex>,
-        type_name: &Arc<str>,
-        coerce_to_type: &Arc<str>,
-        query_info: &QueryInfo,
-    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool>;
+        _type_name: &Arc<str>,
+        _coerce_to_type: &Arc<str>,
+        _query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        Box::new(contexts.map(|ctx| (ctx, false)))
+    }
 }
@@ -0,0 +1,238 @@
+//! Adapter middleware that checks that non-nullable properties are never null.
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    ir::{EdgeParameters, FieldValue, Vid},
+    schema::Schema,
+};
+
+use super::{Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo, VertexIterator};
+
+/// An adapter produced a null value for a property that the schema declares as non-nullable.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Property \"{property_name}\" on type \"{type_name}\" is non-nullable, but the adapter \
+    produced a null value for it at vertex {vid:?}"
+)]
+pub struct NonNullViolation {
+    pub type_name: Arc<str>,
+    pub property_name: Arc<str>,
+    pub vid: Vid,
+}
+
+/// Wraps an adapter, checking that every property value it produces is non-null
+/// if the schema declares that property as non-nullable.
+///
+/// Without this check, such null values are passed through to the query as-is:
+/// they are output as nulls and compared as nulls in filters, producing results
+/// that no query against that schema should be able to produce.
+///
+/// This is meant for catching adapter bugs, e.g. when developing an adapter against
+/// a strict schema. Adapters have no way to report errors to the query, so the offending
+/// null values are still passed through, and each one is recorded as a [`NonNullViolation`]
+/// that can be read with [`NonNullCheckedAdapter::violations`]. Query execution is lazy,
+/// so violations are recorded as the query's result iterator is consumed.
+#[derive(Debug)]
+pub struct NonNullCheckedAdapter<AdapterT> {
+    inner: AdapterT,
+    schema: Arc<Schema>,
+    violations: Arc<Mutex<Vec<NonNullViolation>>>,
+}
+
+impl<AdapterT> NonNullCheckedAdapter<AdapterT> {
+    pub fn new(adapter: AdapterT, schema: Arc<Schema>) -> Self {
+        Self {
+            inner: adapter,
+            schema,
+            violations: Default::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> AdapterT {
+        self.inner
+    }
+
+    /// The violations found so far, in the order they were found.
+    pub fn violations(&self) -> Vec<NonNullViolation> {
+        self.violations.lock().unwrap().clone()
+    }
+
+    /// Whether the schema declares the given property as non-nullable.
+    ///
+    /// Meta-fields like `__typename` are not part of the schema's declared fields,
+    /// and are never checked.
+    fn is_non_nullable(&self, type_name: &str, property_name: &str) -> bool {
+        self.schema
+            .fields
+            .get(&(Arc::from(type_name), Arc::from(property_name)))
+            .map(|field| !field.ty.node.nullable)
+            .unwrap_or(false)
+    }
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for NonNullCheckedAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        self.inner
+            .resolve_starting_vertices(edge_name, parameters, query_info)
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        let values = self
+            .inner
+            .resolve_property(contexts, type_name, property_name, query_info);
+        if !self.is_non_nullable(type_name, property_name) {
+            return values;
+        }
+
+        let type_name = type_name.clone();
+        let property_name = property_name.clone();
+        let vid = query_info.origin_vid();
+        let violations = self.violations.clone();
+        Box::new(values.inspect(move |(ctx, value)| {
+            // Contexts without an active vertex always get a null value,
+            // e.g. when an `@optional` edge doesn't exist.
+            if ctx.active_vertex().is_some() && matches!(value, FieldValue::Null) {
+                violations.lock().unwrap().push(NonNullViolation {
+                    type_name: type_name.clone(),
+                    property_name: property_name.clone(),
+                    vid,
+                });
+            }
+        }))
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        self.inner
+            .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info)
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        self.inner
+            .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeMap, num::NonZeroUsize, rc::Rc, sync::Arc};
+
+    use crate::{
+        frontend::parse,
+        interpreter::execution::interpret_ir,
+        ir::{FieldValue, Vid},
+        numbers_interpreter::NumbersAdapter,
+        schema::Schema,
+    };
+
+    use super::{NonNullCheckedAdapter, NonNullViolation};
+
+    /// The numbers schema, except that number names are declared non-nullable
+    /// even though the numbers adapter only names the smallest numbers.
+    fn strict_numbers_schema() -> Arc<Schema> {
+        let schema_text = include_str!("../../test_data/schemas/numbers.graphql")
+            .replace("name: String\n", "name: String!\n");
+        Arc::new(Schema::parse(schema_text).expect("schema is not valid"))
+    }
+
+    #[test]
+    fn non_null_values_are_unaffected() {
+        let schema = strict_numbers_schema();
+        let query = parse(
+            &schema,
+            r#"
+{
+    Number(min: 1, max: 3) {
+        name @output
+
+        predecessor @optional {
+            predecessor_name: name @output
+        }
+    }
+}"#,
+        )
+        .unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter = Rc::new(RefCell::new(NonNullCheckedAdapter::new(
+            NumbersAdapter::new(),
+            schema,
+        )));
+        let results: Vec<_> = interpret_ir(adapter.clone(), query, arguments)
+            .unwrap()
+            .collect();
+        assert_eq!(3, results.len());
+        assert!(adapter.borrow().violations().is_empty());
+    }
+
+    #[test]
+    fn null_for_non_nullable_property_is_reported() {
+        let schema = strict_numbers_schema();
+        let query = parse(
+            &schema,
+            r#"
+{
+    Number(min: 1000, max: 1001) {
+        name @output
+    }
+}"#,
+        )
+        .unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter = Rc::new(RefCell::new(NonNullCheckedAdapter::new(
+            NumbersAdapter::new(),
+            schema,
+        )));
+        let results: Vec<_> = interpret_ir(adapter.clone(), query, arguments)
+            .unwrap()
+            .collect();
+
+        // The null values are passed through, and each of them is reported.
+        assert_eq!(2, results.len());
+        assert!(results.iter().all(|row| row["name"] == FieldValue::Null));
+
+        let violation = NonNullViolation {
+            type_name: "Number".into(),
+            property_name: "name".into(),
+            vid: Vid::new(NonZeroUsize::new(1).unwrap()),
+        };
+        assert_eq!(
+            vec![violation.clone(), violation.clone()],
+            adapter.borrow().violations()
+        );
+        assert_eq!(
+            "Property \"name\" on type \"Number\" is non-nullable, but the adapter \
+            produced a null value for it at vertex Vid(1)",
+            violation.to_string(),
+        );
+    }
+}
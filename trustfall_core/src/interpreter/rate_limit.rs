@@ -0,0 +1,215 @@
+//! Adapter middleware that limits how often the wrapped adapter is asked for data.
+use std::{
+    cell::RefCell,
+    num::NonZeroU32,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::ir::{EdgeParameters, FieldValue};
+
+use super::{Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo, VertexIterator};
+
+/// A token bucket: holds up to `capacity` tokens, refilled continuously at `refill_rate`
+/// tokens per second. Each rate-limited operation consumes one token.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    refill_rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_second: NonZeroU32, burst: NonZeroU32) -> Self {
+        let capacity = f64::from(burst.get());
+        Self {
+            capacity,
+            refill_rate: f64::from(per_second.get()),
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take one token, blocking the current thread until one is available.
+    fn acquire(&mut self) {
+        self.refill();
+        if self.tokens < 1.0 {
+            let missing = 1.0 - self.tokens;
+            std::thread::sleep(Duration::from_secs_f64(missing / self.refill_rate));
+            self.refill();
+        }
+        self.tokens = (self.tokens - 1.0).max(0.0);
+    }
+}
+
+struct RateLimitedIter<I> {
+    inner: I,
+    bucket: Rc<RefCell<TokenBucket>>,
+}
+
+impl<I: Iterator> Iterator for RateLimitedIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.bucket.borrow_mut().acquire();
+        self.inner.next()
+    }
+}
+
+/// Wraps an adapter, throttling how quickly data is requested from it.
+///
+/// Uses a token bucket that refills at the configured number of operations per second,
+/// and holds up to the configured burst size. The following operations each use one token,
+/// blocking the current thread until a token is available:
+/// - each [`Adapter::resolve_starting_vertices`] call;
+/// - each context pulled through the iterators returned by [`Adapter::resolve_property`]
+///   and [`Adapter::resolve_neighbors`], since adapters usually make one backend request
+///   per vertex when those iterators are advanced.
+///
+/// Type coercions are not rate-limited, since they rarely require a backend request.
+///
+/// All results are passed through from the wrapped adapter unchanged.
+#[derive(Debug)]
+pub struct RateLimitedAdapter<AdapterT> {
+    inner: AdapterT,
+    bucket: Rc<RefCell<TokenBucket>>,
+}
+
+impl<AdapterT> RateLimitedAdapter<AdapterT> {
+    /// Allow up to `per_second` operations per second, without bursts above that rate.
+    pub fn new(adapter: AdapterT, per_second: NonZeroU32) -> Self {
+        Self::with_burst(adapter, per_second, NonZeroU32::new(1).unwrap())
+    }
+
+    /// Allow up to `per_second` operations per second on average, with up to `burst`
+    /// operations in quick succession after a period of inactivity.
+    pub fn with_burst(adapter: AdapterT, per_second: NonZeroU32, burst: NonZeroU32) -> Self {
+        Self {
+            inner: adapter,
+            bucket: Rc::new(RefCell::new(TokenBucket::new(per_second, burst))),
+        }
+    }
+
+    pub fn into_inner(self) -> AdapterT {
+        self.inner
+    }
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for RateLimitedAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        self.bucket.borrow_mut().acquire();
+        self.inner
+            .resolve_starting_vertices(edge_name, parameters, query_info)
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        Box::new(RateLimitedIter {
+            inner: self
+                .inner
+                .resolve_property(contexts, type_name, property_name, query_info),
+            bucket: self.bucket.clone(),
+        })
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        Box::new(RateLimitedIter {
+            inner: self
+                .inner
+                .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info),
+            bucket: self.bucket.clone(),
+        })
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        self.inner
+            .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        collections::BTreeMap,
+        num::NonZeroU32,
+        rc::Rc,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        interpreter::execution::interpret_ir,
+        ir::FieldValue,
+        numbers_interpreter::{parse_numbers_query, run_numbers_query, NumbersAdapter},
+    };
+
+    use super::RateLimitedAdapter;
+
+    #[test]
+    fn results_unchanged_but_throttled() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(max: 4) {
+        value @output
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let expected = run_numbers_query(query.clone(), arguments.clone());
+        assert_eq!(5, expected.len());
+
+        // One starting vertices call, plus one token per context pulled for the property:
+        // five vertices, plus the final pull that finds no more contexts.
+        // The first token is available immediately, and the rest take 20ms each.
+        let limited = Rc::new(RefCell::new(RateLimitedAdapter::new(
+            NumbersAdapter::new(),
+            NonZeroU32::new(50).unwrap(),
+        )));
+        let start = Instant::now();
+        let actual: Vec<_> = interpret_ir(limited, query, arguments).unwrap().collect();
+        let elapsed = start.elapsed();
+
+        assert_eq!(expected, actual);
+        assert!(elapsed >= Duration::from_millis(100), "{elapsed:?}");
+    }
+}
@@ -0,0 +1,164 @@
+//! Retrying backend operations that fail with transient errors.
+//!
+//! Adapter methods return iterators rather than `Result`s, so errors from an adapter's backend
+//! never reach the interpreter, and an adapter wrapper has no errors on which to base
+//! a decision to retry. Instead, adapters use a [`RetryPolicy`] around the individual
+//! backend calls they make while resolving vertices, properties, and edges.
+use std::{num::NonZeroU32, time::Duration};
+
+/// How many times to attempt a fallible backend operation, and how long to wait
+/// between attempts.
+///
+/// The wait before the first retry is the initial backoff, and doubles before each
+/// subsequent retry up to the maximum backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: NonZeroU32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts, waiting `initial_backoff` before the first retry
+    /// and doubling the wait before each subsequent one, without limit.
+    pub fn new(max_attempts: NonZeroU32, initial_backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff: Duration::MAX,
+        }
+    }
+
+    /// Never wait longer than `max_backoff` between attempts.
+    pub fn with_max_backoff(self, max_backoff: Duration) -> Self {
+        Self {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Run `operation` until it succeeds, fails with an error that `is_transient`
+    /// doesn't consider transient, or the maximum number of attempts is reached.
+    ///
+    /// Returns the result of the last attempt. The current thread is blocked while waiting
+    /// between attempts.
+    pub fn retry<T, E>(
+        &self,
+        is_transient: impl Fn(&E) -> bool,
+        operation: impl FnMut() -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.retry_with_sleep(is_transient, operation, std::thread::sleep)
+    }
+
+    /// Like [`RetryPolicy::retry`], but waits between attempts by calling `sleep`
+    /// with the duration of each wait, instead of blocking the current thread.
+    pub fn retry_with_sleep<T, E>(
+        &self,
+        is_transient: impl Fn(&E) -> bool,
+        mut operation: impl FnMut() -> Result<T, E>,
+        mut sleep: impl FnMut(Duration),
+    ) -> Result<T, E> {
+        let mut backoff = self.initial_backoff.min(self.max_backoff);
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Err(e) if attempt < self.max_attempts.get() && is_transient(&e) => {
+                    sleep(backoff);
+                    backoff = backoff.saturating_mul(2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU32, time::Duration};
+
+    use super::RetryPolicy;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum BackendError {
+        Timeout,
+        NotFound,
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(NonZeroU32::new(max_attempts).unwrap(), Duration::ZERO)
+    }
+
+    fn is_transient(e: &BackendError) -> bool {
+        *e == BackendError::Timeout
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        let mut attempts = 0;
+        let result = policy(3).retry(is_transient, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(BackendError::Timeout)
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(Ok(3), result);
+    }
+
+    #[test]
+    fn retries_stop_at_max_attempts() {
+        let mut attempts = 0;
+        let result: Result<(), _> = policy(3).retry(is_transient, || {
+            attempts += 1;
+            Err(BackendError::Timeout)
+        });
+        assert_eq!(Err(BackendError::Timeout), result);
+        assert_eq!(3, attempts);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let mut attempts = 0;
+        let result: Result<(), _> = policy(3).retry(is_transient, || {
+            attempts += 1;
+            Err(BackendError::NotFound)
+        });
+        assert_eq!(Err(BackendError::NotFound), result);
+        assert_eq!(1, attempts);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let ms = Duration::from_millis;
+        let policy = RetryPolicy::new(NonZeroU32::new(5).unwrap(), ms(10)).with_max_backoff(ms(25));
+
+        let mut waits = vec![];
+        let result: Result<(), _> = policy.retry_with_sleep(
+            is_transient,
+            || Err(BackendError::Timeout),
+            |wait| waits.push(wait),
+        );
+        assert_eq!(Err(BackendError::Timeout), result);
+        assert_eq!(vec![ms(10), ms(20), ms(25), ms(25)], waits);
+
+        // No waits after the last attempt, or after non-transient errors.
+        let mut waits = vec![];
+        let mut attempts = 0;
+        let result: Result<(), _> = policy.retry_with_sleep(
+            is_transient,
+            || {
+                attempts += 1;
+                Err(if attempts < 3 {
+                    BackendError::Timeout
+                } else {
+                    BackendError::NotFound
+                })
+            },
+            |wait| waits.push(wait),
+        );
+        assert_eq!(Err(BackendError::NotFound), result);
+        assert_eq!(vec![ms(10), ms(20)], waits);
+    }
+}
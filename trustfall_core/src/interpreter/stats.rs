@@ -0,0 +1,337 @@
+//! Lightweight execution statistics, collected as query results are consumed.
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::ir::{indexed::IndexedQuery, EdgeParameters, FieldValue};
+
+use super::{
+    error::QueryArgumentsError, execution::interpret_ir, Adapter, ContextIterator,
+    ContextOutcomeIterator, QueryInfo, VertexIterator,
+};
+
+#[derive(Debug, Default)]
+struct Counters {
+    vertices_resolved: Cell<u64>,
+    neighbor_expansions: Cell<u64>,
+    properties_read: Cell<u64>,
+    result_rows: Cell<u64>,
+}
+
+fn increment(counter: &Cell<u64>) {
+    counter.set(counter.get() + 1);
+}
+
+/// Aggregate statistics about a query's execution, as returned by [`interpret_ir_with_stats`].
+///
+/// Query execution is lazy, so the statistics are updated as the query's result iterator
+/// is consumed. All clones of an `ExecutionStats` share the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionStats {
+    counters: Rc<Counters>,
+}
+
+impl ExecutionStats {
+    /// The number of vertices produced by the adapter, whether as starting vertices
+    /// or as neighbors of other vertices.
+    pub fn vertices_resolved(&self) -> u64 {
+        self.counters.vertices_resolved.get()
+    }
+
+    /// The number of vertices whose neighbors along some edge were requested from the adapter.
+    pub fn neighbor_expansions(&self) -> u64 {
+        self.counters.neighbor_expansions.get()
+    }
+
+    /// The number of property values produced by the adapter.
+    pub fn properties_read(&self) -> u64 {
+        self.counters.properties_read.get()
+    }
+
+    /// The number of result rows produced by the query so far.
+    pub fn result_rows(&self) -> u64 {
+        self.counters.result_rows.get()
+    }
+}
+
+/// How many neighbors each vertex had along an edge, as recorded by
+/// [`resolve_neighbors_with_fan_out`](super::helpers::resolve_neighbors_with_fan_out).
+///
+/// Keep one `FanOutStats` per edge of interest, e.g. in the adapter, to find the edges
+/// whose high fan-out makes them worth optimizing with bulk fetching.
+/// Query execution is lazy, so the statistics are updated as the query's result iterator
+/// is consumed. All clones of a `FanOutStats` share the same histogram.
+#[derive(Debug, Clone, Default)]
+pub struct FanOutStats {
+    histogram: Rc<RefCell<BTreeMap<usize, u64>>>,
+}
+
+impl FanOutStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// For each number of neighbors, how many vertices had that many neighbors.
+    pub fn histogram(&self) -> BTreeMap<usize, u64> {
+        self.histogram.borrow().clone()
+    }
+
+    /// The number of vertices whose neighbors were recorded.
+    pub fn expansions(&self) -> u64 {
+        self.histogram.borrow().values().sum()
+    }
+
+    /// The number of neighbors recorded across all vertices.
+    pub fn total_neighbors(&self) -> u64 {
+        self.histogram
+            .borrow()
+            .iter()
+            .map(|(neighbors, vertices)| *neighbors as u64 * vertices)
+            .sum()
+    }
+
+    /// The average number of neighbors per vertex, or `None` if nothing was recorded yet.
+    pub fn average(&self) -> Option<f64> {
+        let expansions = self.expansions();
+        (expansions > 0).then(|| self.total_neighbors() as f64 / expansions as f64)
+    }
+
+    /// The largest number of neighbors recorded for any vertex.
+    pub fn max(&self) -> Option<usize> {
+        self.histogram.borrow().keys().next_back().copied()
+    }
+
+    fn record(&self, neighbors: usize) {
+        *self.histogram.borrow_mut().entry(neighbors).or_default() += 1;
+    }
+}
+
+/// Counts the items an iterator produces, and records the count in a [`FanOutStats`]
+/// when the iterator is dropped.
+pub(super) struct FanOutIter<I> {
+    inner: I,
+    neighbors: usize,
+    stats: FanOutStats,
+}
+
+impl<I> FanOutIter<I> {
+    pub(super) fn new(inner: I, stats: FanOutStats) -> Self {
+        Self {
+            inner,
+            neighbors: 0,
+            stats,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for FanOutIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next();
+        if item.is_some() {
+            self.neighbors += 1;
+        }
+        item
+    }
+}
+
+impl<I> Drop for FanOutIter<I> {
+    fn drop(&mut self) {
+        self.stats.record(self.neighbors);
+    }
+}
+
+struct CountingIter<I> {
+    inner: I,
+    stats: ExecutionStats,
+    counter: fn(&Counters) -> &Cell<u64>,
+}
+
+impl<I: Iterator> Iterator for CountingIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next();
+        if item.is_some() {
+            increment((self.counter)(&self.stats.counters));
+        }
+        item
+    }
+}
+
+/// Passes all calls through to the wrapped adapter, counting what they produce.
+struct StatsAdapter<AdapterT> {
+    inner: Rc<RefCell<AdapterT>>,
+    stats: ExecutionStats,
+}
+
+impl<AdapterT> StatsAdapter<AdapterT> {
+    fn count<I: Iterator>(
+        &self,
+        inner: I,
+        counter: fn(&Counters) -> &Cell<u64>,
+    ) -> CountingIter<I> {
+        CountingIter {
+            inner,
+            stats: self.stats.clone(),
+            counter,
+        }
+    }
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for StatsAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        let vertices = self
+            .inner
+            .borrow_mut()
+            .resolve_starting_vertices(edge_name, parameters, query_info);
+        Box::new(self.count(vertices, |c| &c.vertices_resolved))
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        let values = self.inner.borrow_mut().resolve_property(
+            contexts,
+            type_name,
+            property_name,
+            query_info,
+        );
+        Box::new(self.count(values, |c| &c.properties_read))
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        let neighbors = self
+            .inner
+            .borrow_mut()
+            .resolve_neighbors(contexts, type_name, edge_name, parameters, query_info);
+        let stats = self.stats.clone();
+        Box::new(
+            self.count(neighbors, |c| &c.neighbor_expansions)
+                .map(move |(ctx, neighbors)| {
+                    let neighbors: VertexIterator<'vertex, Self::Vertex> = Box::new(CountingIter {
+                        inner: neighbors,
+                        stats: stats.clone(),
+                        counter: |c| &c.vertices_resolved,
+                    });
+                    (ctx, neighbors)
+                }),
+        )
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        self.inner
+            .borrow_mut()
+            .resolve_coercion(contexts, type_name, coerce_to_type, query_info)
+    }
+}
+
+/// Like [`interpret_ir`], but also returns statistics about the query's execution.
+///
+/// Counting is much cheaper than recording a full [trace](super::trace),
+/// so this is suitable for profiling production query workloads.
+/// The statistics are updated as the returned result iterator is consumed.
+#[allow(clippy::type_complexity)]
+pub fn interpret_ir_with_stats<'query, AdapterT>(
+    adapter: Rc<RefCell<AdapterT>>,
+    indexed_query: Arc<IndexedQuery>,
+    arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+) -> Result<
+    (
+        Box<dyn Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'query>,
+        ExecutionStats,
+    ),
+    QueryArgumentsError,
+>
+where
+    AdapterT: Adapter<'query> + 'query,
+{
+    let stats = ExecutionStats::default();
+    let stats_adapter = Rc::new(RefCell::new(StatsAdapter {
+        inner: adapter,
+        stats: stats.clone(),
+    }));
+
+    let results = interpret_ir(stats_adapter, indexed_query, arguments)?;
+    let results = Box::new(CountingIter {
+        inner: results,
+        stats: stats.clone(),
+        counter: |c| &c.result_rows,
+    });
+    Ok((results, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{
+        ir::FieldValue,
+        numbers_interpreter::{numbers_adapter, parse_numbers_query},
+    };
+
+    use super::interpret_ir_with_stats;
+
+    #[test]
+    fn stats_track_consumed_results() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(min: 1, max: 3) {
+        value @output
+
+        successor {
+            successor_value: value @output
+        }
+    }
+}"#,
+        );
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter = numbers_adapter();
+        let (mut results, stats) = interpret_ir_with_stats(adapter, query, arguments).unwrap();
+        assert_eq!(0, stats.result_rows());
+
+        assert!(results.next().is_some());
+        assert_eq!(1, stats.result_rows());
+
+        assert_eq!(2, results.count());
+        assert_eq!(3, stats.result_rows());
+        // Three starting vertices, each with one successor.
+        assert_eq!(6, stats.vertices_resolved());
+        assert_eq!(3, stats.neighbor_expansions());
+        // Two output properties per result row.
+        assert_eq!(6, stats.properties_read());
+    }
+}
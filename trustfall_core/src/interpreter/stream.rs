@@ -0,0 +1,108 @@
+//! Query results as an async [`Stream`], for consumers that apply backpressure.
+use std::{
+    collections::BTreeMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::ir::FieldValue;
+
+type ResultRow = BTreeMap<Arc<str>, FieldValue>;
+
+/// A [`Stream`] over a query's results, computing each row only when it's polled.
+///
+/// Wraps a query's result iterator, such as the one returned by
+/// [`interpret_ir`](super::execution::interpret_ir). The query only makes progress
+/// when the stream is polled, so a consumer that stops polling, e.g. because it's waiting
+/// for a slow client to receive the rows it already has, also stops query execution
+/// and the adapter calls it makes. No rows are buffered: memory use doesn't grow
+/// with the number of results, regardless of how fast they are consumed.
+///
+/// # Composing with synchronous adapters
+///
+/// Adapters are synchronous, so this stream is only async on the outside. Each call to
+/// [`Stream::poll_next`] runs the query until it produces its next row, which includes
+/// any adapter calls needed to produce that row, and then returns [`Poll::Ready`].
+/// It never returns [`Poll::Pending`]. Consequences of this include:
+/// - The executor thread is blocked for as long as it takes to produce each row.
+///   Adapters that block on slow I/O are better run on a dedicated thread, such as
+///   with `tokio::task::spawn_blocking`, sending rows through a bounded channel:
+///   the channel's capacity then bounds how far the query may run ahead of the consumer.
+/// - Like the result iterator, the stream is neither [`Send`] nor [`Sync`] when the adapter
+///   isn't, e.g. when it's shared via `Rc<RefCell<_>>` as in [`interpret_ir`]. Such streams
+///   must be polled on the thread that created them, e.g. in a `tokio::task::LocalSet`.
+/// - Since rows are always ready, a consumer that polls in a loop without otherwise
+///   awaiting doesn't yield to other tasks until the query is done.
+///
+/// [`interpret_ir`]: super::execution::interpret_ir
+pub struct ResultStream<'query> {
+    results: Option<Box<dyn Iterator<Item = ResultRow> + 'query>>,
+}
+
+impl<'query> ResultStream<'query> {
+    pub fn new(results: Box<dyn Iterator<Item = ResultRow> + 'query>) -> Self {
+        Self {
+            results: Some(results),
+        }
+    }
+}
+
+impl<'query> Stream for ResultStream<'query> {
+    type Item = ResultRow;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let row = this.results.as_mut().and_then(|results| results.next());
+        if row.is_none() {
+            // Release the query and its adapter as soon as the results are exhausted.
+            this.results = None;
+        }
+        Poll::Ready(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.results {
+            Some(results) => results.size_hint(),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+    use futures::{executor::block_on, Stream, StreamExt};
+
+    use crate::ir::FieldValue;
+
+    use super::ResultStream;
+
+    #[test]
+    fn rows_are_computed_only_when_polled() {
+        let pulled = Rc::new(Cell::new(0usize));
+        let pulled_ref = pulled.clone();
+        let source = Box::new((0..4i64).map(move |value| {
+            pulled_ref.set(pulled_ref.get() + 1);
+            BTreeMap::from([(Arc::from("value"), FieldValue::Int64(value))])
+        }));
+
+        let mut stream = ResultStream::new(source);
+        assert_eq!(0, pulled.get());
+
+        let first = block_on(stream.next()).expect("no first row");
+        assert_eq!(FieldValue::Int64(0), first["value"]);
+        assert_eq!(1, pulled.get());
+
+        let rest: Vec<_> = block_on(stream.by_ref().collect());
+        assert_eq!(3, rest.len());
+        assert_eq!(4, pulled.get());
+
+        // Exhausted streams stay exhausted.
+        assert!(block_on(stream.next()).is_none());
+        assert_eq!((0, Some(0)), stream.size_hint());
+    }
+}
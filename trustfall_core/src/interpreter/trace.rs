@@ -1,9 +1,17 @@
 use std::{
-    cell::RefCell, collections::BTreeMap, fmt::Debug, marker::PhantomData, num::NonZeroUsize,
-    rc::Rc, sync::Arc,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug},
+    marker::PhantomData,
+    num::NonZeroUsize,
+    rc::Rc,
+    sync::Arc,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{self, value::EnumAccessDeserializer, EnumAccess, IgnoredAny, VariantAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use crate::{
     interpreter::{Adapter, DataContext},
@@ -16,6 +24,185 @@ use super::{ContextIterator, ContextOutcomeIterator, QueryInfo, VertexIterator};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Opid(pub NonZeroUsize); // operation ID
 
+/// An enum in a trace, whose newer versions may have variants this version doesn't know.
+///
+/// Such enums derive their serde impls with `#[serde(remote = "Self")]`, and implement
+/// [`Serialize`] and [`Deserialize`] with [`forward_compatible_enum!`]: known variants
+/// use the derived impls, and unknown ones are deserialized with [`Self::unknown`].
+trait ForwardCompatibleEnum<'de>: Sized {
+    /// The variants whose names the derived [`Deserialize`] impl recognizes.
+    const KNOWN_VARIANTS: &'static [&'static str];
+
+    fn deserialize_known<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+
+    fn unknown(variant_name: Arc<str>) -> Self;
+}
+
+macro_rules! forward_compatible_enum {
+    ( FunctionCall, [ $( $variant: literal ),* $(,)? ] ) => {
+        impl Serialize for FunctionCall {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                FunctionCall::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for FunctionCall {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_enum(
+                    "FunctionCall",
+                    &[],
+                    ForwardCompatibleVisitor(PhantomData),
+                )
+            }
+        }
+
+        impl<'de> ForwardCompatibleEnum<'de> for FunctionCall {
+            const KNOWN_VARIANTS: &'static [&'static str] = &[ $( $variant ),* ];
+
+            fn deserialize_known<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                FunctionCall::deserialize(deserializer)
+            }
+
+            fn unknown(variant_name: Arc<str>) -> Self {
+                FunctionCall::Unknown(variant_name)
+            }
+        }
+    };
+    ( $name: ident < Vertex >, [ $( $variant: literal ),* $(,)? ] ) => {
+        impl<Vertex> Serialize for $name<Vertex>
+        where
+            Vertex: Clone + Debug + PartialEq + Eq + Serialize,
+            for<'de2> Vertex: Deserialize<'de2>,
+        {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $name::serialize(self, serializer)
+            }
+        }
+
+        impl<'de, Vertex> Deserialize<'de> for $name<Vertex>
+        where
+            Vertex: Clone + Debug + PartialEq + Eq + Serialize,
+            for<'de2> Vertex: Deserialize<'de2>,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_enum(
+                    stringify!($name),
+                    &[],
+                    ForwardCompatibleVisitor(PhantomData),
+                )
+            }
+        }
+
+        impl<'de, Vertex> ForwardCompatibleEnum<'de> for $name<Vertex>
+        where
+            Vertex: Clone + Debug + PartialEq + Eq + Serialize,
+            for<'de2> Vertex: Deserialize<'de2>,
+        {
+            const KNOWN_VARIANTS: &'static [&'static str] = &[ $( $variant ),* ];
+
+            fn deserialize_known<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $name::deserialize(deserializer)
+            }
+
+            fn unknown(variant_name: Arc<str>) -> Self {
+                $name::Unknown(variant_name)
+            }
+        }
+    };
+}
+
+/// The name of an enum variant, as read from the serialized enum's tag.
+struct VariantName(String);
+
+impl<'de> Deserialize<'de> for VariantName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VariantNameVisitor;
+
+        impl<'de> Visitor<'de> for VariantNameVisitor {
+            type Value = VariantName;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an enum variant name")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(VariantName(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(VariantNameVisitor)
+    }
+}
+
+/// Hands an enum's already-read tag, together with the access to its contents,
+/// to the derived [`Deserialize`] impl of a [`ForwardCompatibleEnum`].
+struct KnownVariantAccess<A> {
+    variant_name: String,
+    variant: A,
+}
+
+impl<'de, A: VariantAccess<'de>> EnumAccess<'de> for KnownVariantAccess<A> {
+    type Error = A::Error;
+    type Variant = A;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let name = de::value::StringDeserializer::new(self.variant_name);
+        Ok((seed.deserialize(name)?, self.variant))
+    }
+}
+
+struct ForwardCompatibleVisitor<T>(PhantomData<T>);
+
+impl<'de, T: ForwardCompatibleEnum<'de>> Visitor<'de> for ForwardCompatibleVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an enum")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (VariantName(variant_name), variant) = data.variant()?;
+        if T::KNOWN_VARIANTS.contains(&variant_name.as_str()) {
+            T::deserialize_known(EnumAccessDeserializer::new(KnownVariantAccess {
+                variant_name,
+                variant,
+            }))
+        } else {
+            // Variants added in newer versions always hold exactly one value.
+            variant.newtype_variant::<IgnoredAny>()?;
+            Ok(T::unknown(variant_name.into()))
+        }
+    }
+}
+
+/// A record of the adapter operations performed while executing a query.
+///
+/// # Compatibility of serialized traces
+///
+/// Traces are serialized using the serde representations of their contents,
+/// including [`FieldValue`] and the query's IR. Those types evolve according to
+/// the following policy, so that previously-recorded traces remain usable:
+/// - New enum variants, such as new kinds of [`FieldValue`], may be added.
+///   Traces recorded before a variant existed don't use it, so they keep deserializing.
+/// - New struct fields are only added together with a default value used when
+///   the field is missing, so traces recorded before the field existed keep deserializing.
+/// - Existing variants and fields are never renamed, and their contents never change shape.
+///
+/// Traces recorded by newer versions can be read as far as possible:
+/// - Unknown struct fields are ignored.
+/// - Unknown kinds of operations, calls, and yielded values are deserialized as
+///   the `Unknown` variant of [`TraceOpContent`], [`FunctionCall`], and [`YieldValue`]
+///   respectively, which holds the unknown variant's name. Their contents are skipped.
+///   For this to work, new variants of those enums always hold exactly one value.
+///   Traces containing `Unknown` variants can't be serialized again, and replaying them
+///   stops at the first unknown operation: a lenient replay returns
+///   [`ReplayError::UnknownOperation`](super::replay::ReplayError::UnknownOperation),
+///   and a strict one panics with the same message.
+/// - Unknown kinds of [`FieldValue`] fail to deserialize, with an error naming the variant.
+///   Values are the data that traces check, so they are never replaced with some other value.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(bound = "Vertex: Serialize, for<'de2> Vertex: Deserialize<'de2>")]
 pub struct Trace<Vertex>
@@ -56,6 +243,194 @@ where
         self.ops.insert_or_error(next_opid, op).unwrap();
         next_opid
     }
+
+    /// Combine two traces of the same query, recorded separately, into a single trace.
+    ///
+    /// This is meant for setups where different adapters handle different parts
+    /// of the same query, each recording its own trace. The traces must satisfy
+    /// the following constraints, or an error is returned:
+    /// - both must be traces of the same query, executed with the same arguments;
+    /// - their scopes must not overlap: no query vertex may have adapter calls in both traces.
+    ///
+    /// The resulting trace contains all of this trace's operations, followed by
+    /// all of `other`'s operations. Operations from `other` are assigned new [`Opid`]s
+    /// that follow this trace's, and their parent references are updated to match.
+    /// Since the two streams of operations are not interleaved as in a single execution,
+    /// the result is suitable for analysis, but not for replaying the query.
+    pub fn concat(mut self, other: Self) -> Result<Self, TraceMergeError> {
+        if self.ir_query != other.ir_query {
+            return Err(TraceMergeError::DifferentQueries);
+        }
+        if self.arguments != other.arguments {
+            return Err(TraceMergeError::DifferentArguments);
+        }
+
+        let own_vids = self.called_vids();
+        let overlapping: Vec<Vid> = other
+            .called_vids()
+            .intersection(&own_vids)
+            .copied()
+            .collect();
+        if !overlapping.is_empty() {
+            return Err(TraceMergeError::OverlappingScopes(overlapping));
+        }
+
+        let offset = self.ops.keys().next_back().map_or(0, |opid| opid.0.get());
+        let renumber = |opid: Opid| Opid(opid.0.checked_add(offset).unwrap());
+        for op in other.ops.into_values() {
+            let opid = renumber(op.opid);
+            let op = TraceOp {
+                opid,
+                parent_opid: op.parent_opid.map(renumber),
+                content: op.content,
+            };
+            self.ops.insert_or_error(opid, op).unwrap();
+        }
+
+        Ok(self)
+    }
+
+    /// A sub-trace containing only the adapter calls made for the given query vertex,
+    /// together with all the operations recorded within those calls.
+    ///
+    /// Calls are made for a vertex when resolving its starting vertices, properties,
+    /// or type coercions, or the edges that originate at it.
+    ///
+    /// See [`Trace::filter_by_eid`] for the properties of the resulting trace.
+    pub fn filter_by_vid(&self, vid: Vid) -> Self {
+        self.filter_calls(|call| match call {
+            FunctionCall::ResolveStartingVertices(call_vid)
+            | FunctionCall::ResolveProperty(call_vid, ..)
+            | FunctionCall::ResolveNeighbors(call_vid, ..)
+            | FunctionCall::ResolveCoercion(call_vid, ..) => *call_vid == vid,
+            FunctionCall::Unknown(_) => false,
+        })
+    }
+
+    /// A sub-trace containing only the adapter calls that resolve the given query edge,
+    /// together with all the operations recorded within those calls.
+    ///
+    /// The sub-trace keeps the [`Opid`]s and parent references of the operations it contains,
+    /// so they can be looked up in the full trace. Operations not recorded within
+    /// a matching call, such as produced query results, are omitted. Since most of
+    /// the query's execution is missing, the sub-trace is only suitable for inspection:
+    /// it can't be replayed, and operations shouldn't be recorded into it.
+    pub fn filter_by_eid(&self, eid: Eid) -> Self {
+        self.filter_calls(|call| match call {
+            FunctionCall::ResolveNeighbors(_, _, call_eid) => *call_eid == eid,
+            _ => false,
+        })
+    }
+
+    fn filter_calls(&self, mut predicate: impl FnMut(&FunctionCall) -> bool) -> Self {
+        // Parents are always recorded before their children, so they are visited first.
+        let mut ops: BTreeMap<Opid, TraceOp<Vertex>> = Default::default();
+        for (opid, op) in &self.ops {
+            let keep = match (&op.parent_opid, &op.content) {
+                (Some(parent), _) => ops.contains_key(parent),
+                (None, TraceOpContent::Call(call)) => predicate(call),
+                (None, _) => false,
+            };
+            if keep {
+                ops.insert(*opid, op.clone());
+            }
+        }
+
+        Self {
+            ops,
+            ir_query: self.ir_query.clone(),
+            arguments: self.arguments.clone(),
+        }
+    }
+
+    /// The query vertices for which this trace contains adapter calls.
+    fn called_vids(&self) -> BTreeSet<Vid> {
+        self.ops
+            .values()
+            .filter_map(|op| match &op.content {
+                TraceOpContent::Call(
+                    FunctionCall::ResolveStartingVertices(vid)
+                    | FunctionCall::ResolveProperty(vid, ..)
+                    | FunctionCall::ResolveNeighbors(vid, ..)
+                    | FunctionCall::ResolveCoercion(vid, ..),
+                ) => Some(*vid),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The query results recorded in this trace, in the order they were produced.
+    ///
+    /// The results are read directly from the trace's [`TraceOpContent::ProduceQueryResult`]
+    /// operations, without replaying the trace. No checks are made that the trace is
+    /// consistent, or that replaying it would produce the same results.
+    pub fn recorded_results(&self) -> impl Iterator<Item = &BTreeMap<Arc<str>, FieldValue>> {
+        self.ops.values().filter_map(|op| match &op.content {
+            TraceOpContent::ProduceQueryResult(result) => Some(result),
+            _ => None,
+        })
+    }
+
+    /// Serialize the trace as MessagePack, a more compact alternative to RON or JSON.
+    ///
+    /// Struct fields are encoded by name, so the output is self-describing.
+    #[cfg(feature = "rmp-serde")]
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Deserialize a trace from MessagePack produced by [`Trace::to_msgpack`].
+    #[cfg(feature = "rmp-serde")]
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+
+    /// Serialize the trace as RON, compressed with zstd at the given compression level.
+    ///
+    /// Level 0 selects zstd's default level. The compressed data is written
+    /// to `writer` as it is produced.
+    #[cfg(feature = "zstd")]
+    pub fn write_zstd<W: std::io::Write>(
+        &self,
+        writer: W,
+        level: i32,
+    ) -> Result<(), CompressedTraceError> {
+        let mut encoder = zstd::Encoder::new(writer, level)?;
+        ron::ser::to_writer(&mut encoder, self)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Deserialize a trace produced by [`Trace::write_zstd`], decompressing it on the fly.
+    #[cfg(feature = "zstd")]
+    pub fn read_zstd<R: std::io::Read>(reader: R) -> Result<Self, CompressedTraceError> {
+        let decoder = zstd::Decoder::new(reader)?;
+        Ok(ron::de::from_reader(decoder)?)
+    }
+}
+
+/// Reasons why two traces cannot be combined with [`Trace::concat`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TraceMergeError {
+    #[error("The traces are of different queries")]
+    DifferentQueries,
+
+    #[error("The traces were recorded with different query arguments")]
+    DifferentArguments,
+
+    #[error("Both traces contain adapter calls for the same query vertices: {0:?}")]
+    OverlappingScopes(Vec<Vid>),
+}
+
+/// Errors from reading or writing zstd-compressed traces.
+#[cfg(feature = "zstd")]
+#[derive(Debug, thiserror::Error)]
+pub enum CompressedTraceError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("RON error: {0}")]
+    Ron(#[from] ron::Error),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -71,8 +446,12 @@ where
     pub content: TraceOpContent<Vertex>,
 }
 
+#[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(bound = "Vertex: Serialize, for<'de2> Vertex: Deserialize<'de2>")]
+#[serde(
+    remote = "Self",
+    bound = "Vertex: Serialize, for<'de2> Vertex: Deserialize<'de2>"
+)]
 pub enum TraceOpContent<Vertex>
 where
     Vertex: Clone + Debug + PartialEq + Eq + Serialize,
@@ -89,20 +468,57 @@ where
     OutputIteratorExhausted,
 
     ProduceQueryResult(BTreeMap<Arc<str>, FieldValue>),
+
+    /// An operation of a kind added in a newer version, with the given variant name.
+    #[serde(skip)]
+    Unknown(Arc<str>),
 }
 
+forward_compatible_enum!(
+    TraceOpContent<Vertex>,
+    [
+        "Call",
+        "AdvanceInputIterator",
+        "YieldInto",
+        "YieldFrom",
+        "InputIteratorExhausted",
+        "OutputIteratorExhausted",
+        "ProduceQueryResult",
+    ]
+);
+
 #[allow(clippy::enum_variant_names)] // the variant names match the functions they represent
+#[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub enum FunctionCall {
     ResolveStartingVertices(Vid),             // vertex ID
     ResolveProperty(Vid, Arc<str>, Arc<str>), // vertex ID + type name + name of the property
     ResolveNeighbors(Vid, Arc<str>, Eid),     // vertex ID + type name + edge ID
     ResolveCoercion(Vid, Arc<str>, Arc<str>), // vertex ID + current type + coerced-to type
+
+    /// A call to an adapter function added in a newer version, with the given variant name.
+    #[serde(skip)]
+    Unknown(Arc<str>),
 }
 
+forward_compatible_enum!(
+    FunctionCall,
+    [
+        "ResolveStartingVertices",
+        "ResolveProperty",
+        "ResolveNeighbors",
+        "ResolveCoercion",
+    ]
+);
+
 #[allow(clippy::enum_variant_names)] // the variant names match the functions they represent
+#[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(bound = "Vertex: Serialize, for<'de2> Vertex: Deserialize<'de2>")]
+#[serde(
+    remote = "Self",
+    bound = "Vertex: Serialize, for<'de2> Vertex: Deserialize<'de2>"
+)]
 pub enum YieldValue<Vertex>
 where
     Vertex: Clone + Debug + PartialEq + Eq + Serialize,
@@ -113,8 +529,24 @@ where
     ResolveNeighborsOuter(DataContext<Vertex>),
     ResolveNeighborsInner(usize, Vertex), // iterable index + produced element
     ResolveCoercion(DataContext<Vertex>, bool),
+
+    /// A value yielded by an adapter function added in a newer version,
+    /// with the given variant name.
+    #[serde(skip)]
+    Unknown(Arc<str>),
 }
 
+forward_compatible_enum!(
+    YieldValue<Vertex>,
+    [
+        "ResolveStartingVertices",
+        "ResolveProperty",
+        "ResolveNeighborsOuter",
+        "ResolveNeighborsInner",
+        "ResolveCoercion",
+    ]
+);
+
 pub struct OnIterEnd<T, I: Iterator<Item = T>, F: FnOnce()> {
     inner: I,
     on_end_func: Option<F>,
@@ -209,8 +641,8 @@ where
     }
 }
 
-#[allow(dead_code)]
-pub(crate) fn tap_results<'vertex, AdapterT>(
+#[doc(hidden)]
+pub fn tap_results<'vertex, AdapterT>(
     adapter_tap: Rc<RefCell<AdapterTap<'vertex, AdapterT>>>,
     result_iter: impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'vertex,
 ) -> impl Iterator<Item = BTreeMap<Arc<str>, FieldValue>> + 'vertex
@@ -510,3 +942,310 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, num::NonZeroUsize};
+
+    use crate::{
+        ir::{Eid, FieldValue, Vid},
+        numbers_interpreter::NumbersVertex,
+        util::TestInterpreterOutputTrace,
+    };
+
+    use super::{FunctionCall, Opid, Trace, TraceMergeError, TraceOpContent, YieldValue};
+
+    #[test]
+    fn concat_renumbers_ops() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+        let ir_query = test_data.trace.ir_query;
+        let arguments = test_data.trace.arguments;
+
+        let vid = |id: usize| Vid::new(NonZeroUsize::new(id).unwrap());
+        let opid = |id: usize| Opid(NonZeroUsize::new(id).unwrap());
+        let call = |vid, type_name: &str| {
+            TraceOpContent::Call(FunctionCall::ResolveProperty(
+                vid,
+                type_name.into(),
+                "value".into(),
+            ))
+        };
+
+        let mut first = Trace::<NumbersVertex>::new(ir_query.clone(), arguments.clone());
+        let first_call = first.record(call(vid(1), "Composite"), None);
+        first.record(TraceOpContent::OutputIteratorExhausted, Some(first_call));
+
+        let mut second = Trace::<NumbersVertex>::new(ir_query, arguments);
+        let second_call = second.record(call(vid(2), "Number"), None);
+        second.record(TraceOpContent::InputIteratorExhausted, Some(second_call));
+
+        let merged = first.clone().concat(second.clone()).unwrap();
+        let ops: Vec<_> = merged
+            .ops
+            .iter()
+            .map(|(id, op)| (*id, op.opid, op.parent_opid, op.content.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (opid(1), opid(1), None, call(vid(1), "Composite")),
+                (
+                    opid(2),
+                    opid(2),
+                    Some(opid(1)),
+                    TraceOpContent::OutputIteratorExhausted
+                ),
+                (opid(3), opid(3), None, call(vid(2), "Number")),
+                (
+                    opid(4),
+                    opid(4),
+                    Some(opid(3)),
+                    TraceOpContent::InputIteratorExhausted
+                ),
+            ],
+            ops,
+        );
+
+        // New operations can be recorded into the merged trace.
+        let mut merged = merged;
+        assert_eq!(
+            opid(5),
+            merged.record(TraceOpContent::OutputIteratorExhausted, None)
+        );
+
+        assert_eq!(
+            Err(TraceMergeError::OverlappingScopes(vec![vid(1)])),
+            first.clone().concat(first.clone()),
+        );
+
+        let mut different_arguments = second.clone();
+        different_arguments
+            .arguments
+            .insert("two".into(), FieldValue::Uint64(3));
+        assert_eq!(
+            Err(TraceMergeError::DifferentArguments),
+            first.clone().concat(different_arguments),
+        );
+
+        let mut different_query = second;
+        different_query.ir_query.root_name = "Four".into();
+        assert_eq!(
+            Err(TraceMergeError::DifferentQueries),
+            first.concat(different_query),
+        );
+    }
+
+    #[test]
+    fn filter_by_vid_and_eid() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+        let trace = test_data.trace;
+
+        let vid = |id: usize| Vid::new(NonZeroUsize::new(id).unwrap());
+        let eid = |id: usize| Eid::new(NonZeroUsize::new(id).unwrap());
+        let calls = |trace: &Trace<NumbersVertex>| -> Vec<FunctionCall> {
+            trace
+                .ops
+                .values()
+                .filter_map(|op| match &op.content {
+                    TraceOpContent::Call(call) => Some(call.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let by_vid = trace.filter_by_vid(vid(2));
+        assert_eq!(
+            vec![FunctionCall::ResolveProperty(
+                vid(2),
+                "Prime".into(),
+                "value".into()
+            )],
+            calls(&by_vid)
+        );
+
+        let by_eid = trace.filter_by_eid(eid(1));
+        assert_eq!(
+            vec![FunctionCall::ResolveNeighbors(
+                vid(1),
+                "Composite".into(),
+                eid(1)
+            )],
+            calls(&by_eid)
+        );
+
+        for filtered in [&by_vid, &by_eid] {
+            assert!(filtered.ops.len() > 1);
+            assert!(filtered.recorded_results().next().is_none());
+            for (opid, op) in &filtered.ops {
+                assert_eq!(&trace.ops[opid], op);
+                if let Some(parent) = op.parent_opid {
+                    assert!(filtered.ops.contains_key(&parent));
+                }
+            }
+        }
+
+        assert!(trace.filter_by_eid(eid(2)).ops.is_empty());
+    }
+
+    #[test]
+    fn recorded_results() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+
+        let recorded: Vec<_> = test_data.trace.recorded_results().cloned().collect();
+        assert!(!recorded.is_empty());
+        assert_eq!(test_data.results, recorded);
+    }
+
+    #[test]
+    fn unknown_value_variants_are_reported() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+
+        // Fields added after a trace was recorded have defaults, so they may be missing.
+        let without_arguments = input_data.replacen("arguments: {", "ignored_field: {", 1);
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&without_arguments).unwrap();
+        assert!(test_data.trace.arguments.is_empty());
+
+        // Values of kinds the reader doesn't know about are errors, not silently replaced.
+        let unknown_variant = input_data.replacen("Int64(2)", "Decimal(\"2.0\")", 1);
+        let err = ron::from_str::<TestInterpreterOutputTrace<NumbersVertex>>(&unknown_variant)
+            .unwrap_err();
+        assert!(err.to_string().contains("Decimal"), "{err}");
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+
+        // The trace uses every kind of operation, call, and yielded value,
+        // so none of them may be mistaken for an unknown variant.
+        let encoded = ron::to_string(&test_data.trace).unwrap();
+        let decoded: Trace<NumbersVertex> = ron::from_str(&encoded).unwrap();
+        assert_eq!(test_data.trace, decoded);
+    }
+
+    #[test]
+    fn unknown_fields_and_operations_are_skipped() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+
+        // Simulate a trace recorded by a newer version, with a new field
+        // and new kinds of operations, calls, and yielded values.
+        let newer_data = input_data
+            .replacen(
+                "opid: Opid(1),",
+                "opid: Opid(1),\n        new_field: [1, 2],",
+                1,
+            )
+            .replacen(
+                "content: AdvanceInputIterator,",
+                "content: NewOp((1, \"x\", [Int64(2)])),",
+                1,
+            )
+            .replacen(
+                "Call(ResolveCoercion(Vid(1), \"Number\", \"Composite\"))",
+                "Call(ResolveNewThing(Vid(1)))",
+                1,
+            )
+            .replacen(
+                "YieldFrom(ResolveStartingVertices(",
+                "YieldFrom(ResolveNewThing(",
+                1,
+            );
+        let newer: TestInterpreterOutputTrace<NumbersVertex> = ron::from_str(&newer_data).unwrap();
+
+        let opid = |id: usize| Opid(NonZeroUsize::new(id).unwrap());
+        let content =
+            |trace: &Trace<NumbersVertex>, id: usize| trace.ops[&opid(id)].content.clone();
+        assert_eq!(
+            TraceOpContent::Call(FunctionCall::Unknown("ResolveNewThing".into())),
+            content(&newer.trace, 2),
+        );
+        assert_eq!(
+            TraceOpContent::Unknown("NewOp".into()),
+            content(&newer.trace, 5),
+        );
+        let first_yield = test_data
+            .trace
+            .ops
+            .values()
+            .find(|op| {
+                matches!(
+                    op.content,
+                    TraceOpContent::YieldFrom(YieldValue::ResolveStartingVertices(_))
+                )
+            })
+            .unwrap()
+            .opid;
+        assert_eq!(
+            TraceOpContent::YieldFrom(YieldValue::Unknown("ResolveNewThing".into())),
+            newer.trace.ops[&first_yield].content,
+        );
+
+        // Everything else is read as usual.
+        assert_eq!(test_data.trace.ops.len(), newer.trace.ops.len());
+        let changed = [opid(2), opid(5), first_yield];
+        for (id, op) in &test_data.trace.ops {
+            if !changed.contains(id) {
+                assert_eq!(op, &newer.trace.ops[id]);
+            }
+        }
+        assert_eq!(test_data.results, newer.results);
+
+        // Unknown variants can't be serialized again.
+        assert!(ron::to_string(&newer.trace).is_err());
+    }
+
+    #[cfg(feature = "rmp-serde")]
+    #[test]
+    fn msgpack_round_trip() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+
+        let encoded = test_data.trace.to_msgpack().unwrap();
+        assert!(encoded.len() < input_data.len());
+
+        let decoded = Trace::<NumbersVertex>::from_msgpack(&encoded).unwrap();
+        assert_eq!(test_data.trace, decoded);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trip() {
+        let input_data =
+            fs::read_to_string("test_data/tests/valid_queries/fold_count_filter.trace.ron")
+                .unwrap();
+        let test_data: TestInterpreterOutputTrace<NumbersVertex> =
+            ron::from_str(&input_data).unwrap();
+
+        let mut encoded = vec![];
+        test_data.trace.write_zstd(&mut encoded, 0).unwrap();
+        assert!(encoded.len() < input_data.len());
+
+        let decoded = Trace::<NumbersVertex>::read_zstd(encoded.as_slice()).unwrap();
+        assert_eq!(test_data.trace, decoded);
+    }
+}
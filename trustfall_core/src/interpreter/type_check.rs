@@ -0,0 +1,322 @@
+//! Adapter middleware that checks that vertices have a type permitted by the schema
+//! at their position in the query.
+use std::sync::Arc;
+
+use crate::{
+    ir::{types::get_base_named_type, EdgeParameters, FieldValue},
+    schema::Schema,
+};
+
+use super::{
+    Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo, Typename, VertexIterator,
+};
+
+/// A vertex produced by an adapter has a type that the schema does not permit
+/// at that vertex's position in the query.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TypePositionError {
+    #[error(
+        "Starting edge \"{edge_name}\" produced a vertex of type \"{actual}\", which is not \
+        a subtype of the edge's declared type \"{expected}\""
+    )]
+    StartingVertex {
+        edge_name: Arc<str>,
+        expected: Arc<str>,
+        actual: &'static str,
+    },
+
+    #[error(
+        "Edge \"{edge_name}\" on type \"{type_name}\" produced a neighbor of type \"{actual}\", \
+        which is not a subtype of the edge's declared type \"{expected}\""
+    )]
+    Neighbor {
+        type_name: Arc<str>,
+        edge_name: Arc<str>,
+        expected: Arc<str>,
+        actual: &'static str,
+    },
+
+    #[error(
+        "Coercion of a vertex of type \"{actual}\" from \"{type_name}\" to \"{coerce_to_type}\" \
+        produced {outcome}, but the schema requires {}", !outcome
+    )]
+    Coercion {
+        type_name: Arc<str>,
+        coerce_to_type: Arc<str>,
+        actual: &'static str,
+        outcome: bool,
+    },
+}
+
+/// Wraps an adapter, checking that every vertex it produces has a type that the schema
+/// permits at that vertex's position in the query:
+/// - starting vertices must be subtypes of their starting edge's declared type;
+/// - neighbor vertices must be subtypes of their edge's declared type;
+/// - type coercions must succeed exactly when the vertex's type is a subtype
+///   of the coercion's target type.
+///
+/// Vertex types are determined using their [`Typename`] implementation.
+///
+/// This is meant for catching adapter bugs, e.g. when writing tests for a new adapter.
+/// Adapters have no way to report errors, so any mismatch causes a panic
+/// whose message describes the offending [`TypePositionError`].
+#[derive(Debug)]
+pub struct TypeCheckedAdapter<AdapterT> {
+    inner: AdapterT,
+    schema: Arc<Schema>,
+}
+
+impl<AdapterT> TypeCheckedAdapter<AdapterT> {
+    pub fn new(adapter: AdapterT, schema: Arc<Schema>) -> Self {
+        Self {
+            inner: adapter,
+            schema,
+        }
+    }
+
+    pub fn into_inner(self) -> AdapterT {
+        self.inner
+    }
+
+    /// The name of the type that the schema declares for the given edge.
+    fn edge_destination_type(&self, type_name: &str, edge_name: &str) -> Arc<str> {
+        let field = &self.schema.fields[&(Arc::from(type_name), Arc::from(edge_name))];
+        Arc::from(get_base_named_type(&field.ty.node))
+    }
+}
+
+impl<'vertex, AdapterT> Adapter<'vertex> for TypeCheckedAdapter<AdapterT>
+where
+    AdapterT: Adapter<'vertex>,
+    AdapterT::Vertex: Typename,
+{
+    type Vertex = AdapterT::Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        let expected = self.edge_destination_type(self.schema.query_type_name(), edge_name);
+        let schema = self.schema.clone();
+        let edge_name = edge_name.clone();
+
+        let vertices = self
+            .inner
+            .resolve_starting_vertices(&edge_name, parameters, query_info);
+        Box::new(vertices.inspect(move |vertex| {
+            let actual = vertex.typename();
+            if !schema.is_named_type_subtype(&expected, actual) {
+                let err = TypePositionError::StartingVertex {
+                    edge_name: edge_name.clone(),
+                    expected: expected.clone(),
+                    actual,
+                };
+                panic!("{err}");
+            }
+        }))
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        self.inner
+            .resolve_property(contexts, type_name, property_name, query_info)
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        let expected = self.edge_destination_type(type_name, edge_name);
+        let schema = self.schema.clone();
+        let type_name = type_name.clone();
+        let edge_name = edge_name.clone();
+
+        let neighbors = self
+            .inner
+            .resolve_neighbors(contexts, &type_name, &edge_name, parameters, query_info);
+        Box::new(neighbors.map(move |(ctx, neighbors)| {
+            let schema = schema.clone();
+            let type_name = type_name.clone();
+            let edge_name = edge_name.clone();
+            let expected = expected.clone();
+            let neighbors: VertexIterator<'vertex, Self::Vertex> =
+                Box::new(neighbors.inspect(move |vertex| {
+                    let actual = vertex.typename();
+                    if !schema.is_named_type_subtype(&expected, actual) {
+                        let err = TypePositionError::Neighbor {
+                            type_name: type_name.clone(),
+                            edge_name: edge_name.clone(),
+                            expected: expected.clone(),
+                            actual,
+                        };
+                        panic!("{err}");
+                    }
+                }));
+            (ctx, neighbors)
+        }))
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        query_info: &QueryInfo,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        let schema = self.schema.clone();
+        let type_name = type_name.clone();
+        let coerce_to_type = coerce_to_type.clone();
+
+        let outcomes =
+            self.inner
+                .resolve_coercion(contexts, &type_name, &coerce_to_type, query_info);
+        Box::new(outcomes.inspect(move |(ctx, outcome)| {
+            if let Some(vertex) = ctx.active_vertex() {
+                let actual = vertex.typename();
+                if *outcome != schema.is_named_type_subtype(&coerce_to_type, actual) {
+                    let err = TypePositionError::Coercion {
+                        type_name: type_name.clone(),
+                        coerce_to_type: coerce_to_type.clone(),
+                        actual,
+                        outcome: *outcome,
+                    };
+                    panic!("{err}");
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Arc};
+
+    use crate::{
+        frontend::parse,
+        interpreter::{
+            execution::interpret_ir, Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo,
+            VertexIterator,
+        },
+        ir::{EdgeParameters, FieldValue},
+        numbers_interpreter::{numbers_schema, run_numbers_query, NumbersAdapter, NumbersVertex},
+    };
+
+    use super::{TypeCheckedAdapter, TypePositionError};
+
+    /// Returns prime numbers from the `Four` starting edge, which the schema declares
+    /// as producing a `Composite` vertex.
+    struct WrongStartingTypeAdapter(NumbersAdapter);
+
+    impl Adapter<'static> for WrongStartingTypeAdapter {
+        type Vertex = NumbersVertex;
+
+        fn resolve_starting_vertices(
+            &mut self,
+            edge_name: &Arc<str>,
+            parameters: &EdgeParameters,
+            query_info: &QueryInfo,
+        ) -> VertexIterator<'static, Self::Vertex> {
+            let edge_name: Arc<str> = match edge_name.as_ref() {
+                "Four" => "Two".into(),
+                _ => edge_name.clone(),
+            };
+            self.0
+                .resolve_starting_vertices(&edge_name, parameters, query_info)
+        }
+
+        fn resolve_property(
+            &mut self,
+            contexts: ContextIterator<'static, Self::Vertex>,
+            type_name: &Arc<str>,
+            property_name: &Arc<str>,
+            query_info: &QueryInfo,
+        ) -> ContextOutcomeIterator<'static, Self::Vertex, FieldValue> {
+            self.0
+                .resolve_property(contexts, type_name, property_name, query_info)
+        }
+    }
+
+    #[test]
+    fn well_typed_adapter_is_unaffected() {
+        let schema = Arc::new(numbers_schema());
+        let query = parse(
+            &schema,
+            r#"
+{
+    Number(max: 6) {
+        value @output
+
+        successor {
+            ... on Prime {
+                prime: value @output
+            }
+        }
+    }
+}"#,
+        )
+        .unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let expected = run_numbers_query(query.clone(), arguments.clone());
+
+        let adapter = TypeCheckedAdapter::new(NumbersAdapter::new(), schema);
+        let actual: Vec<_> = interpret_ir(Rc::new(RefCell::new(adapter)), query, arguments)
+            .unwrap()
+            .collect();
+
+        assert!(!actual.is_empty());
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Starting edge \"Four\" produced a vertex of type \"Prime\", which is not \
+        a subtype of the edge's declared type \"Composite\""
+    )]
+    fn wrong_starting_vertex_type_is_reported() {
+        let schema = Arc::new(numbers_schema());
+        let query = parse(
+            &schema,
+            r#"
+{
+    Four {
+        value @output
+    }
+}"#,
+        )
+        .unwrap();
+        let arguments: Arc<BTreeMap<Arc<str>, FieldValue>> = Default::default();
+
+        let adapter =
+            TypeCheckedAdapter::new(WrongStartingTypeAdapter(NumbersAdapter::new()), schema);
+        let _ = interpret_ir(Rc::new(RefCell::new(adapter)), query, arguments)
+            .unwrap()
+            .count();
+    }
+
+    #[test]
+    fn coercion_error_message() {
+        let err = TypePositionError::Coercion {
+            type_name: "Number".into(),
+            coerce_to_type: "Prime".into(),
+            actual: "Composite",
+            outcome: true,
+        };
+        assert_eq!(
+            "Coercion of a vertex of type \"Composite\" from \"Number\" to \"Prime\" \
+            produced true, but the schema requires false",
+            err.to_string(),
+        );
+    }
+}
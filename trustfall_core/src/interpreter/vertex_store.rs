@@ -0,0 +1,168 @@
+//! Storage for the vertices that a [`DataContext`](super::DataContext) has recorded so far.
+//!
+//! Each context owns one such store, which is cloned every time the context
+//! is split across a vertex's neighbors. By default, the store is a `BTreeMap`.
+//! With the `dense-vertex-store` feature, it's a `Vec` indexed by [`Vid`] instead:
+//! since vertex IDs are small consecutive integers, this makes cloning the store a single
+//! allocation and lookups a direct index, at the cost of space for vertices not yet recorded.
+use std::{collections::BTreeMap, fmt::Debug, ops::Index};
+
+use crate::ir::Vid;
+
+#[cfg(not(feature = "dense-vertex-store"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct VertexStore<Vertex> {
+    vertices: BTreeMap<Vid, Option<Vertex>>,
+}
+
+#[cfg(not(feature = "dense-vertex-store"))]
+impl<Vertex> VertexStore<Vertex> {
+    /// Record the vertex at the given [`Vid`].
+    ///
+    /// Panics if a vertex was already recorded there.
+    pub(super) fn insert(&mut self, vid: Vid, vertex: Option<Vertex>) {
+        let existing = self.vertices.insert(vid, vertex);
+        assert!(existing.is_none(), "{vid:?} was already recorded");
+    }
+
+    /// The vertex recorded at the given [`Vid`], if any.
+    ///
+    /// `Some(None)` means that the context recorded the absence of a vertex there,
+    /// such as at an `@optional` edge without neighbors.
+    pub(super) fn get(&self, vid: &Vid) -> Option<&Option<Vertex>> {
+        self.vertices.get(vid)
+    }
+}
+
+#[cfg(not(feature = "dense-vertex-store"))]
+impl<Vertex> From<VertexStore<Vertex>> for BTreeMap<Vid, Option<Vertex>> {
+    fn from(store: VertexStore<Vertex>) -> Self {
+        store.vertices
+    }
+}
+
+#[cfg(not(feature = "dense-vertex-store"))]
+impl<Vertex> From<BTreeMap<Vid, Option<Vertex>>> for VertexStore<Vertex> {
+    fn from(vertices: BTreeMap<Vid, Option<Vertex>>) -> Self {
+        Self { vertices }
+    }
+}
+
+#[cfg(feature = "dense-vertex-store")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct VertexStore<Vertex> {
+    /// The entry for each [`Vid`] is at index `vid - 1`, since vertex IDs start at 1.
+    /// The outer `Option` is `None` if nothing was recorded at that [`Vid`].
+    vertices: Vec<Option<Option<Vertex>>>,
+}
+
+#[cfg(feature = "dense-vertex-store")]
+impl<Vertex> VertexStore<Vertex> {
+    fn index_of(vid: Vid) -> usize {
+        vid.0.get() - 1
+    }
+
+    /// Record the vertex at the given [`Vid`].
+    ///
+    /// Panics if a vertex was already recorded there.
+    pub(super) fn insert(&mut self, vid: Vid, vertex: Option<Vertex>) {
+        let index = Self::index_of(vid);
+        if index >= self.vertices.len() {
+            self.vertices.resize_with(index + 1, || None);
+        }
+        let existing = self.vertices[index].replace(vertex);
+        assert!(existing.is_none(), "{vid:?} was already recorded");
+    }
+
+    /// The vertex recorded at the given [`Vid`], if any.
+    ///
+    /// `Some(None)` means that the context recorded the absence of a vertex there,
+    /// such as at an `@optional` edge without neighbors.
+    pub(super) fn get(&self, vid: &Vid) -> Option<&Option<Vertex>> {
+        self.vertices.get(Self::index_of(*vid))?.as_ref()
+    }
+}
+
+#[cfg(feature = "dense-vertex-store")]
+impl<Vertex> From<VertexStore<Vertex>> for BTreeMap<Vid, Option<Vertex>> {
+    fn from(store: VertexStore<Vertex>) -> Self {
+        store
+            .vertices
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let vid = Vid::new(std::num::NonZeroUsize::new(index + 1).unwrap());
+                entry.map(|vertex| (vid, vertex))
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "dense-vertex-store")]
+impl<Vertex> From<BTreeMap<Vid, Option<Vertex>>> for VertexStore<Vertex> {
+    fn from(vertices: BTreeMap<Vid, Option<Vertex>>) -> Self {
+        let mut store = Self::default();
+        for (vid, vertex) in vertices {
+            store.insert(vid, vertex);
+        }
+        store
+    }
+}
+
+impl<Vertex> Default for VertexStore<Vertex> {
+    fn default() -> Self {
+        Self {
+            vertices: Default::default(),
+        }
+    }
+}
+
+impl<Vertex> Index<&Vid> for VertexStore<Vertex> {
+    type Output = Option<Vertex>;
+
+    fn index(&self, vid: &Vid) -> &Self::Output {
+        self.get(vid)
+            .unwrap_or_else(|| panic!("no vertex recorded at {vid:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, num::NonZeroUsize};
+
+    use crate::ir::Vid;
+
+    use super::VertexStore;
+
+    fn vid(id: usize) -> Vid {
+        Vid::new(NonZeroUsize::new(id).unwrap())
+    }
+
+    #[test]
+    fn records_and_looks_up_vertices() {
+        let mut store: VertexStore<&str> = Default::default();
+        store.insert(vid(1), Some("first"));
+        store.insert(vid(3), None);
+
+        assert_eq!(Some(&Some("first")), store.get(&vid(1)));
+        assert_eq!(None, store.get(&vid(2)));
+        assert_eq!(Some(&None), store.get(&vid(3)));
+        assert_eq!(None, store.get(&vid(4)));
+        assert_eq!(&Some("first"), &store[&vid(1)]);
+
+        let map: BTreeMap<_, _> = store.clone().into();
+        assert_eq!(
+            BTreeMap::from([(vid(1), Some("first")), (vid(3), None)]),
+            map
+        );
+        assert_eq!(store, VertexStore::from(map));
+    }
+
+    #[test]
+    #[should_panic(expected = "already recorded")]
+    fn rejects_recording_vid_twice() {
+        let mut store: VertexStore<&str> = Default::default();
+        store.insert(vid(2), None);
+        store.insert(vid(2), Some("second"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Canonical forms of query IR, for recognizing queries that differ only superficially.
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    convert::Infallible,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use super::{
+    Argument, Eid, FieldValue, IRFold, IRQuery, IRQueryComponent, IRVertex, Operation, Vid,
+};
+
+/// A query's IR in canonical form, as produced by [`canonicalize_ir`].
+///
+/// Equality and hashing only consider the canonical IR, so this is suitable as the key
+/// of a cache shared by queries that differ only in the ways [`canonicalize_ir`] normalizes.
+#[derive(Debug, Clone)]
+pub struct CanonicalIRQuery {
+    ir: IRQuery,
+
+    /// The canonical name of each of the original query's variables.
+    variable_names: BTreeMap<Arc<str>, Arc<str>>,
+
+    /// A hash of the canonical IR, computed once since hashing it is expensive.
+    ir_hash: u64,
+}
+
+impl CanonicalIRQuery {
+    /// The canonical IR, which may be executed like any other query's IR.
+    pub fn ir(&self) -> &IRQuery {
+        &self.ir
+    }
+
+    pub fn into_ir(self) -> IRQuery {
+        self.ir
+    }
+
+    /// The canonical name of the original query's variable with the given name, if any.
+    pub fn canonical_variable_name(&self, original_name: &str) -> Option<&Arc<str>> {
+        self.variable_names.get(original_name)
+    }
+
+    /// Rename arguments meant for the original query, so they can be used
+    /// to execute the canonical IR instead.
+    ///
+    /// Arguments that don't correspond to any of the original query's variables are dropped.
+    pub fn canonicalize_arguments(
+        &self,
+        arguments: &BTreeMap<Arc<str>, FieldValue>,
+    ) -> BTreeMap<Arc<str>, FieldValue> {
+        arguments
+            .iter()
+            .filter_map(|(name, value)| {
+                let canonical_name = self.variable_names.get(name)?;
+                Some((canonical_name.clone(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+impl PartialEq for CanonicalIRQuery {
+    fn eq(&self, other: &Self) -> bool {
+        self.ir_hash == other.ir_hash && self.ir == other.ir
+    }
+}
+
+impl Eq for CanonicalIRQuery {}
+
+impl Hash for CanonicalIRQuery {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ir_hash.hash(state)
+    }
+}
+
+fn hash_ir(ir: &IRQuery) -> u64 {
+    // The IR is made of ordered collections, so its serialization is deterministic,
+    // and equal IR always serializes the same way.
+    let mut hasher = DefaultHasher::new();
+    ron::to_string(ir)
+        .expect("failed to serialize IR query")
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Normalize a query's IR so that queries that differ only superficially
+/// have equal canonical forms.
+///
+/// The following differences are normalized away:
+/// - the names of variables: variables are renamed `var0`, `var1`, etc. in the order
+///   in which they are first used by the query's filters, visiting vertices in [`Vid`] order
+///   and then the filters applied after each `@fold` in [`Eid`] order;
+/// - the order of the filters applied to the same vertex, or after the same `@fold`,
+///   none of which affects the query's results.
+///
+/// Outputs are keyed by name in the IR, so the order in which a query's outputs are written
+/// doesn't affect its IR to begin with. Differences that affect vertex and edge numbering,
+/// such as the order in which a vertex's edges are written, are not normalized, so such
+/// queries are equivalent but have different canonical forms.
+pub fn canonicalize_ir(query: &IRQuery) -> CanonicalIRQuery {
+    let mut ir = query.clone();
+
+    sort_filters(Arc::make_mut(&mut ir.root_component));
+
+    let mut vertices = BTreeMap::new();
+    let mut folds = BTreeMap::new();
+    collect_vertices_and_folds(&ir.root_component, &mut vertices, &mut folds);
+    let first_uses = vertices
+        .values()
+        .flat_map(|vertex| vertex.filters.iter().filter_map(|op| op.right()))
+        .chain(
+            folds
+                .values()
+                .flat_map(|fold| fold.post_filters.iter().filter_map(|op| op.right())),
+        )
+        .filter_map(|argument| match argument {
+            Argument::Variable(variable) => Some(&variable.variable_name),
+            Argument::Tag(_) => None,
+        });
+
+    let mut variable_names: BTreeMap<Arc<str>, Arc<str>> = BTreeMap::new();
+    // All variables are used by some filter, but name any that aren't just in case.
+    for name in first_uses.chain(ir.variables.keys()) {
+        if !variable_names.contains_key(name) {
+            let canonical_name = Arc::from(format!("var{}", variable_names.len()));
+            variable_names.insert(name.clone(), canonical_name);
+        }
+    }
+
+    rename_variables(Arc::make_mut(&mut ir.root_component), &variable_names);
+    ir.variables = std::mem::take(&mut ir.variables)
+        .into_iter()
+        .map(|(name, ty)| (variable_names[&name].clone(), ty))
+        .collect();
+    ir.variable_defaults = std::mem::take(&mut ir.variable_defaults)
+        .into_iter()
+        .map(|(name, value)| (variable_names[&name].clone(), value))
+        .collect();
+
+    let ir_hash = hash_ir(&ir);
+    CanonicalIRQuery {
+        ir,
+        variable_names,
+        ir_hash,
+    }
+}
+
+fn collect_vertices_and_folds<'a>(
+    component: &'a IRQueryComponent,
+    vertices: &mut BTreeMap<Vid, &'a IRVertex>,
+    folds: &mut BTreeMap<Eid, &'a IRFold>,
+) {
+    vertices.extend(
+        component
+            .vertices
+            .iter()
+            .map(|(vid, vertex)| (*vid, vertex)),
+    );
+    for (eid, fold) in &component.folds {
+        folds.insert(*eid, fold.as_ref());
+        collect_vertices_and_folds(&fold.component, vertices, folds);
+    }
+}
+
+fn sort_filters(component: &mut IRQueryComponent) {
+    for vertex in component.vertices.values_mut() {
+        sort_by_anonymized_form(&mut vertex.filters);
+    }
+    for fold in component.folds.values_mut() {
+        let fold = Arc::make_mut(fold);
+        sort_by_anonymized_form(&mut fold.post_filters);
+        sort_filters(Arc::make_mut(&mut fold.component));
+    }
+}
+
+/// Sort filters by their serialized form with variable names erased, so that the order
+/// doesn't depend on the variable names that [`canonicalize_ir`] is about to replace.
+fn sort_by_anonymized_form<LeftT>(filters: &mut [Operation<LeftT, Argument>])
+where
+    LeftT: Debug + Clone + PartialEq + Eq + serde::Serialize,
+{
+    filters.sort_by_cached_key(|filter| {
+        let anonymized = map_arguments(filter, |argument| match argument {
+            Argument::Variable(variable) => {
+                let mut variable = variable.clone();
+                variable.variable_name = Arc::from("");
+                Argument::Variable(variable)
+            }
+            Argument::Tag(_) => argument.clone(),
+        });
+        ron::to_string(&anonymized).expect("failed to serialize filter")
+    });
+}
+
+fn rename_variables(component: &mut IRQueryComponent, names: &BTreeMap<Arc<str>, Arc<str>>) {
+    let rename = |argument: &Argument| match argument {
+        Argument::Variable(variable) => {
+            let mut variable = variable.clone();
+            variable.variable_name = names[&variable.variable_name].clone();
+            Argument::Variable(variable)
+        }
+        Argument::Tag(_) => argument.clone(),
+    };
+
+    for vertex in component.vertices.values_mut() {
+        for filter in &mut vertex.filters {
+            *filter = map_arguments(filter, rename);
+        }
+    }
+    for fold in component.folds.values_mut() {
+        let fold = Arc::make_mut(fold);
+        for filter in &mut fold.post_filters {
+            *filter = map_arguments(filter, rename);
+        }
+        rename_variables(Arc::make_mut(&mut fold.component), names);
+    }
+}
+
+fn map_arguments<LeftT>(
+    filter: &Operation<LeftT, Argument>,
+    map: impl FnOnce(&Argument) -> Argument,
+) -> Operation<LeftT, Argument>
+where
+    LeftT: Debug + Clone + PartialEq + Eq,
+{
+    let mapped = filter
+        .try_map::<_, _, _, _, Infallible>(|left| Ok(left.clone()), |argument| Ok(map(argument)));
+    match mapped {
+        Ok(filter) => filter,
+        Err(never) => match never {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::{
+        frontend::parse,
+        ir::FieldValue,
+        numbers_interpreter::{numbers_schema, parse_numbers_query},
+    };
+
+    use super::canonicalize_ir;
+
+    #[test]
+    fn alpha_equivalent_queries_are_canonicalized_identically() {
+        let first = parse_numbers_query(
+            r#"
+{
+    Number(max: 10) {
+        value @filter(op: ">", value: ["$low"])
+              @filter(op: "<", value: ["$high"])
+              @output
+
+        multiple(max: 3) @fold @transform(op: "count") @filter(op: "=", value: ["$count"]) {
+            name @filter(op: "one_of", value: ["$names"])
+        }
+    }
+}"#,
+        );
+        let second = parse_numbers_query(
+            r#"
+{
+    Number(max: 10) {
+        value @output
+              @filter(op: "<", value: ["$max"])
+              @filter(op: ">", value: ["$min"])
+
+        multiple(max: 3) @fold @transform(op: "count") @filter(op: "=", value: ["$a"]) {
+            name @filter(op: "one_of", value: ["$b"])
+        }
+    }
+}"#,
+        );
+        assert_ne!(first.ir_query, second.ir_query);
+
+        let first = canonicalize_ir(&first.ir_query);
+        let second = canonicalize_ir(&second.ir_query);
+        assert_eq!(first, second);
+
+        // The same argument values reach the same filters, whatever the query called them.
+        let first_arguments: BTreeMap<Arc<str>, FieldValue> = btreemap! {
+            "low".into() => FieldValue::Int64(2),
+            "high".into() => FieldValue::Int64(8),
+            "count".into() => FieldValue::Int64(1),
+            "names".into() => FieldValue::List(vec![]),
+        };
+        let second_arguments: BTreeMap<Arc<str>, FieldValue> = btreemap! {
+            "min".into() => FieldValue::Int64(2),
+            "max".into() => FieldValue::Int64(8),
+            "a".into() => FieldValue::Int64(1),
+            "b".into() => FieldValue::List(vec![]),
+            "unused".into() => FieldValue::Null,
+        };
+        assert_eq!(
+            first.canonicalize_arguments(&first_arguments),
+            second.canonicalize_arguments(&second_arguments),
+        );
+        assert_eq!(
+            first.canonical_variable_name("low"),
+            second.canonical_variable_name("min"),
+        );
+    }
+
+    #[test]
+    fn queries_with_different_filters_remain_distinct() {
+        let schema = numbers_schema();
+        let query = |op: &str| {
+            let query = parse(
+                &schema,
+                format!(
+                    r#"
+{{
+    Number(max: 10) {{
+        value @filter(op: "{op}", value: ["$bound"]) @output
+    }}
+}}"#
+                ),
+            )
+            .unwrap();
+            canonicalize_ir(&query.ir_query)
+        };
+
+        assert_eq!(query("<"), query("<"));
+        assert_ne!(query("<"), query(">"));
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use chrono::{TimeZone, Utc};
+
+use super::{value_eq_normalized, FieldValue, NormalizedValue};
+
+/// Check that [`FieldValue`]'s equality, ordering, and hashing are consistent
+/// with each other, and with [`value_eq_normalized`] and [`NormalizedValue`].
+///
+/// Checks all pairs and triples of values drawn from a built-in set of tricky values,
+/// such as numbers that are equal across variants, together with any values given
+/// in `extra_values`. The following must hold for all such values `a`, `b`, and `c`:
+/// - `a == b` exactly when `a.cmp(&b)` is [`Ordering::Equal`], and `a.partial_cmp(&b)`
+///   is always `Some(a.cmp(&b))`;
+/// - `a.cmp(&b)` is the reverse of `b.cmp(&a)`;
+/// - if `a <= b` and `b <= c`, then `a <= c`;
+/// - if `a == b`, then `a` and `b` hash the same way, and `value_eq_normalized(a, b)`;
+/// - `value_eq_normalized` is symmetric and transitive, and values it considers equal
+///   hash the same way when wrapped in [`NormalizedValue`].
+///
+/// Use it through [`assert_field_value_contract!`](crate::assert_field_value_contract).
+///
+/// # Panics
+///
+/// Panics, naming the offending values, if any of the above doesn't hold.
+#[doc(hidden)]
+pub fn check_field_value_contract(extra_values: &[FieldValue]) {
+    let mut values = contract_test_values();
+    values.extend_from_slice(extra_values);
+
+    for a in &values {
+        for b in &values {
+            check_pair(a, b);
+            for c in &values {
+                check_triple(a, b, c);
+            }
+        }
+    }
+}
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn check_pair(a: &FieldValue, b: &FieldValue) {
+    let ordering = a.cmp(b);
+    assert_eq!(
+        a == b,
+        ordering == Ordering::Equal,
+        "== and cmp() disagree: {a:?} {b:?}"
+    );
+    assert_eq!(
+        Some(ordering),
+        a.partial_cmp(b),
+        "cmp() and partial_cmp() disagree: {a:?} {b:?}"
+    );
+    assert_eq!(
+        ordering.reverse(),
+        b.cmp(a),
+        "cmp() is not antisymmetric: {a:?} {b:?}"
+    );
+
+    let normalized_eq = value_eq_normalized(a, b);
+    assert_eq!(
+        normalized_eq,
+        value_eq_normalized(b, a),
+        "value_eq_normalized() is not symmetric: {a:?} {b:?}"
+    );
+    if a == b {
+        assert_eq!(
+            hash_of(a),
+            hash_of(b),
+            "equal values hash differently: {a:?} {b:?}"
+        );
+        assert!(
+            normalized_eq,
+            "equal values are unequal under value_eq_normalized(): {a:?} {b:?}"
+        );
+    }
+    if normalized_eq {
+        assert_eq!(
+            hash_of(&NormalizedValue(a.clone())),
+            hash_of(&NormalizedValue(b.clone())),
+            "values equal under value_eq_normalized() hash differently: {a:?} {b:?}"
+        );
+    }
+}
+
+fn check_triple(a: &FieldValue, b: &FieldValue, c: &FieldValue) {
+    if a <= b && b <= c {
+        assert!(a <= c, "cmp() is not transitive: {a:?} {b:?} {c:?}");
+    }
+    if value_eq_normalized(a, b) && value_eq_normalized(b, c) {
+        assert!(
+            value_eq_normalized(a, c),
+            "value_eq_normalized() is not transitive: {a:?} {b:?} {c:?}"
+        );
+    }
+}
+
+/// Values whose comparisons are easy to get wrong: numbers that are equal across
+/// numeric variants, numbers at the edges of each variant's range, signed zeros,
+/// values of different variants with the same contents, and nested lists.
+fn contract_test_values() -> Vec<FieldValue> {
+    let mut values = vec![
+        FieldValue::Null,
+        FieldValue::Int64(0),
+        FieldValue::Int64(1),
+        FieldValue::Int64(-1),
+        FieldValue::Int64(i64::MIN),
+        FieldValue::Int64(i64::MAX),
+        FieldValue::Int64(1 << 53),
+        FieldValue::Int64((1 << 53) + 1),
+        FieldValue::Uint64(0),
+        FieldValue::Uint64(1),
+        FieldValue::Uint64(i64::MAX as u64),
+        FieldValue::Uint64(1 << 63),
+        FieldValue::Uint64(u64::MAX),
+        FieldValue::Float64(0.0),
+        FieldValue::Float64(-0.0),
+        FieldValue::Float64(0.5),
+        FieldValue::Float64(1.0),
+        FieldValue::Float64(-1.0),
+        FieldValue::Float64(9007199254740992.0),
+        FieldValue::Float64(9223372036854775808.0),
+        FieldValue::Float64(f64::MAX),
+        FieldValue::Float64(f64::MIN),
+        FieldValue::String("".into()),
+        FieldValue::String("a".into()),
+        FieldValue::String("b".into()),
+        FieldValue::Enum("a".into()),
+        FieldValue::Boolean(false),
+        FieldValue::Boolean(true),
+        FieldValue::DateTimeUtc(Utc.timestamp_opt(0, 0).unwrap()),
+        FieldValue::DateTimeUtc(Utc.timestamp_opt(1, 0).unwrap()),
+        FieldValue::List(vec![]),
+        FieldValue::List(vec![FieldValue::Null]),
+    ];
+
+    let lists: Vec<_> = [
+        vec![FieldValue::Int64(1)],
+        vec![FieldValue::Uint64(1)],
+        vec![FieldValue::Float64(1.0)],
+        vec![FieldValue::Int64(1), FieldValue::Int64(2)],
+        vec![FieldValue::Int64(2)],
+        vec![FieldValue::List(vec![FieldValue::Float64(-0.0)])],
+        vec![FieldValue::List(vec![FieldValue::Int64(0)])],
+    ]
+    .into_iter()
+    .map(FieldValue::List)
+    .collect();
+    values.extend(lists);
+    values
+}
+
+/// Assert that [`FieldValue`](crate::ir::FieldValue)'s equality, ordering, and hashing
+/// are consistent with each other, and with
+/// [`value_eq_normalized`](crate::ir::value_eq_normalized).
+///
+/// Meant for the test suites of adapters that build their own comparisons on top of
+/// `FieldValue`, such as for pushing filters down to a backend. Runs the same checks that
+/// this crate uses on a built-in set of tricky values, optionally extended with values
+/// that the adapter produces. Panics, naming the offending values, if any check fails.
+///
+/// # Examples
+///
+/// ```rust
+/// # use trustfall_core::{assert_field_value_contract, ir::FieldValue};
+/// // Check only the built-in values.
+/// assert_field_value_contract!();
+///
+/// // Also check values that an adapter produces.
+/// assert_field_value_contract!([
+///     FieldValue::Int64(42),
+///     FieldValue::Float64(42.0),
+///     FieldValue::List(vec![FieldValue::Uint64(42)]),
+/// ]);
+/// ```
+#[macro_export]
+macro_rules! assert_field_value_contract {
+    () => {
+        $crate::ir::check_field_value_contract(&[])
+    };
+    ($values:expr $(,)?) => {
+        $crate::ir::check_field_value_contract(&$values)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::FieldValue;
+
+    #[test]
+    fn field_value_contract_holds() {
+        assert_field_value_contract!();
+        assert_field_value_contract!([
+            FieldValue::Int64(-3),
+            FieldValue::Float64(-3.0),
+            FieldValue::List(vec![FieldValue::Int64(-3), FieldValue::Null]),
+        ]);
+    }
+}
@@ -6,7 +6,8 @@ use serde::{Deserialize, Serialize};
 use crate::util::BTreeMapTryInsertExt;
 
 use super::{
-    types::is_scalar_only_subtype, Argument, Eid, IREdge, IRFold, IRQuery, IRQueryComponent, Vid,
+    types::is_scalar_only_subtype, Argument, Eid, FieldValue, IREdge, IRFold, IRQuery,
+    IRQueryComponent, TypedValue, Vid,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,6 +19,15 @@ pub struct IndexedQuery {
     pub eids: BTreeMap<Eid, EdgeKind>,
 
     pub outputs: BTreeMap<Arc<str>, Output>,
+
+    /// The names of the query's outputs, in the order in which the query declares them.
+    ///
+    /// Queries parsed from their text record their declaration order, except that
+    /// `@recurse` depth outputs come after all other outputs. Queries converted from IR,
+    /// which doesn't record the declaration order, order their outputs by the vertex
+    /// at which they are output and then by name.
+    #[serde(default)]
+    pub output_order: Vec<Arc<str>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -36,6 +46,85 @@ pub enum InvalidIRQueryError {
     GetBetterVariant(i32),
 }
 
+impl IndexedQuery {
+    /// Pair each value in a result row of this query with the declared type of its output,
+    /// so that nulls may be serialized together with their type.
+    ///
+    /// Returns `None` if the row contains a value that isn't an output of this query.
+    pub fn typed_row<'a>(
+        &'a self,
+        row: &'a BTreeMap<Arc<str>, FieldValue>,
+    ) -> Option<BTreeMap<&'a str, TypedValue<'a>>> {
+        row.iter()
+            .map(|(name, value)| {
+                let output = self.outputs.get(name)?;
+                Some((name.as_ref(), TypedValue::new(value, &output.value_type)))
+            })
+            .collect()
+    }
+
+    /// The values in a result row of this query, in the order in which the query
+    /// declares its outputs. Serializing the returned value produces a map whose fields
+    /// appear in that order, unlike the result row itself whose fields are sorted by name.
+    ///
+    /// Outputs missing from the row are skipped.
+    pub fn ordered_row<'a>(&'a self, row: &'a BTreeMap<Arc<str>, FieldValue>) -> OrderedRow<'a> {
+        OrderedRow {
+            output_order: &self.output_order,
+            row,
+        }
+    }
+
+    /// Use the given output order in place of the one derived from the IR.
+    ///
+    /// Names that aren't outputs of this query are ignored, and outputs
+    /// whose names aren't given keep their prior relative order after all the given ones.
+    pub(crate) fn with_output_order(mut self, declared_order: Vec<Arc<str>>) -> Self {
+        let mut output_order: Vec<_> = declared_order
+            .into_iter()
+            .filter(|name| self.outputs.contains_key(name))
+            .collect();
+        let remaining: Vec<_> = self
+            .output_order
+            .iter()
+            .filter(|name| !output_order.contains(name))
+            .cloned()
+            .collect();
+        output_order.extend(remaining);
+
+        self.output_order = output_order;
+        self
+    }
+}
+
+/// A result row whose values are in the order in which the query declares its outputs.
+///
+/// Constructed with [`IndexedQuery::ordered_row`].
+#[derive(Debug, Clone, Copy)]
+pub struct OrderedRow<'a> {
+    output_order: &'a [Arc<str>],
+    row: &'a BTreeMap<Arc<str>, FieldValue>,
+}
+
+impl<'a> OrderedRow<'a> {
+    /// The row's output names and values, in the query's output order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a Arc<str>, &'a FieldValue)> + 'a {
+        let row = self.row;
+        self.output_order
+            .iter()
+            .filter_map(move |name| row.get_key_value(name))
+    }
+}
+
+impl Serialize for OrderedRow<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
 impl TryFrom<IRQuery> for IndexedQuery {
     type Error = InvalidIRQueryError;
 
@@ -74,11 +163,15 @@ impl TryFrom<IRQuery> for IndexedQuery {
             0,
         )?;
 
+        let mut output_order: Vec<_> = outputs.keys().cloned().collect();
+        output_order.sort_by_key(|name| outputs[name].vid);
+
         Ok(Self {
             ir_query,
             vids,
             eids,
             outputs,
+            output_order,
         })
     }
 }
@@ -191,6 +284,25 @@ fn add_data_from_component(
         if existing.is_some() {
             return Err(InvalidIRQueryError::GetBetterVariant(9));
         }
+
+        // Recursion depth outputs are only supported outside of folds.
+        if let Some(recursive) = &edge.recursive {
+            if let Some(name) = &recursive.depth_output {
+                if fold_depth != 0 {
+                    return Err(InvalidIRQueryError::GetBetterVariant(16));
+                }
+                outputs
+                    .insert_or_error(
+                        name.clone(),
+                        Output {
+                            name: name.clone(),
+                            value_type: recursive.depth_output_type().clone(),
+                            vid: edge.to_vid,
+                        },
+                    )
+                    .map_err(|_| InvalidIRQueryError::GetBetterVariant(17))?;
+            }
+        }
     }
 
     let new_fold_depth = fold_depth + 1;
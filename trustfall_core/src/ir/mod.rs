@@ -1,8 +1,11 @@
 //! Trustfall intermediate representation (IR)
 #![allow(dead_code)]
 
+mod canonical;
+mod contract;
 pub mod indexed;
 pub mod serialization;
+mod typed_builder;
 pub mod types;
 pub mod value;
 
@@ -16,10 +19,20 @@ use serde::{Deserialize, Serialize};
 
 use crate::frontend::error::FilterTypeError;
 
+pub use self::canonical::{canonicalize_ir, CanonicalIRQuery};
+#[doc(hidden)]
+pub use self::contract::check_field_value_contract;
+pub use self::typed_builder::{FieldValueTypeError, TypedFieldValueBuilder};
 use self::types::{
     are_base_types_equal_ignoring_nullability, is_base_type_orderable, NamedTypedValue,
 };
-pub use self::value::{FieldValue, TransparentValue};
+pub(crate) use self::value::hash_normalized;
+#[cfg(feature = "semver")]
+pub use self::value::semver_compare;
+pub use self::value::{
+    value_eq_normalized, FieldValue, FieldValueConversionError, NormalizedValue, TransparentValue,
+    TypedValue,
+};
 
 pub(crate) const TYPENAME_META_FIELD: &str = "__typename";
 
@@ -58,6 +71,14 @@ impl Eid {
 /// all edge parameters marked as required in the schema are included in
 /// the [`EdgeParameters`] value.
 ///
+/// List-typed parameters are passed as a single [`FieldValue::List`] value, exactly as
+/// the query specified them: the interpreter doesn't fan out over their elements
+/// the way it does over the vertices of a list-typed edge. It's up to the adapter to decide
+/// what the list means, such as producing one starting vertex per element as
+/// [`resolve_starting_vertices_from_list`] does. Like scalar parameters, each element
+/// is type-checked against the schema, including its nullability.
+///
+/// [`resolve_starting_vertices_from_list`]: crate::interpreter::helpers::resolve_starting_vertices_from_list
 /// [`Adapter::resolve_starting_vertices`]: crate::interpreter::Adapter::resolve_neighbors
 /// [`Adapter::resolve_neighbors`]: crate::interpreter::Adapter::resolve_neighbors
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -138,6 +159,11 @@ pub struct IRQuery {
         deserialize_with = "crate::ir::serialization::serde_variables_deserializer"
     )]
     pub variables: BTreeMap<Arc<str>, Type>,
+
+    /// Values for variables that the query declares with a default,
+    /// used when the query is executed without a value for that variable.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variable_defaults: BTreeMap<Arc<str>, FieldValue>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -174,11 +200,29 @@ pub struct Recursive {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub coerce_to: Option<Arc<str>>,
+
+    /// The name of the output reporting the recursion depth at which each vertex was reached,
+    /// if the query requested one via `@recurse(depth_output: "...")`.
+    ///
+    /// The starting vertex of the recursion is at depth zero. The output is `null`
+    /// if the recursion started from a vertex that doesn't exist, e.g. inside an `@optional`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_output: Option<Arc<str>>,
 }
 
 impl Recursive {
     pub fn new(depth: NonZeroUsize, coerce_to: Option<Arc<str>>) -> Self {
-        Self { depth, coerce_to }
+        Self {
+            depth,
+            coerce_to,
+            depth_output: None,
+        }
+    }
+
+    /// The type of the recursion depth output: an integer, which may be `null`
+    /// when the recursion is inside an `@optional` scope that didn't match.
+    pub fn depth_output_type(&self) -> &Type {
+        &NULLABLE_INT_TYPE
     }
 }
 
@@ -196,6 +240,10 @@ pub struct IRVertex {
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub filters: Vec<Operation<LocalField, Argument>>,
+
+    /// The properties of the vertex's type that the schema marks as unique keys.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_fields: Vec<Arc<str>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -220,6 +268,30 @@ pub struct IRFold {
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub post_filters: Vec<Operation<FoldSpecificFieldKind, Argument>>,
+
+    /// How to sort the fold's elements, if the query requested it
+    /// via `@fold(sort_by: "...")`. Sorting happens before any `limit` is applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<FoldSort>,
+
+    /// The maximum number of the fold's elements to keep, if the query requested it
+    /// via `@fold(limit: ...)`. Outputs, counts, and post-fold filters only see
+    /// the elements that were kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<NonZeroUsize>,
+}
+
+/// Sorting of a `@fold`'s elements by a property of the fold's root vertex.
+///
+/// The sort is stable: elements with equal property values keep the order in which
+/// the adapter produced them. Null values are ordered before all other values,
+/// so they come first when sorting in ascending order and last in descending order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoldSort {
+    pub field_name: Arc<str>,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub descending: bool,
 }
 
 #[non_exhaustive]
@@ -233,6 +305,10 @@ lazy_static! {
         base: BaseType::Named(Name::new("Int")),
         nullable: false,
     };
+    static ref NULLABLE_INT_TYPE: Type = Type {
+        base: BaseType::Named(Name::new("Int")),
+        nullable: true,
+    };
 }
 
 impl FoldSpecificFieldKind {
@@ -0,0 +1,320 @@
+//! Construction of [FieldValue]s that are checked against their declared schema type.
+use async_graphql_parser::types::{BaseType, Type};
+use chrono::{DateTime, Utc};
+
+use crate::schema::Schema;
+
+use super::FieldValue;
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FieldValueTypeError {
+    #[error("Expected a value of type {0}, but got null.")]
+    UnexpectedNull(String),
+
+    #[error("Expected a value of type {0}, but got a value of type {1}.")]
+    TypeMismatch(String, String),
+
+    #[error("Expected a value of type {0}, but got non-finite float value {1}.")]
+    NonFiniteFloat(String, String),
+
+    #[error("List element at index {0} is invalid: {1}")]
+    InvalidListElement(usize, Box<FieldValueTypeError>),
+
+    #[error("Type {0} is not a custom scalar type defined in the schema.")]
+    NotCustomScalar(String),
+}
+
+/// Builds [FieldValue]s of a particular schema [Type], rejecting values of any other type.
+///
+/// Each method checks its value against the type as the value is constructed,
+/// including the type's nullability. This surfaces type mismatches at the exact place
+/// in an adapter's resolver where the wrong value was produced.
+///
+/// ```
+/// # use async_graphql_parser::types::Type;
+/// # use trustfall_core::ir::{FieldValue, TypedFieldValueBuilder};
+/// let ty = Type::new("[String!]").unwrap();
+/// let builder = TypedFieldValueBuilder::new(&ty);
+///
+/// let value = builder.list(["a", "b"], |element, s| element.string(s)).unwrap();
+/// assert_eq!(FieldValue::List(vec!["a".into(), "b".into()]), value);
+///
+/// // Null is allowed for the list itself, but not for its elements.
+/// assert!(builder.null().is_ok());
+/// let elements: [Option<&str>; 1] = [None];
+/// let result = builder.list(elements, |element, s| element.optional(s, |b, s| b.string(s)));
+/// assert!(result.is_err());
+///
+/// // Values of the wrong type are rejected.
+/// assert!(builder.int(42).is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TypedFieldValueBuilder<'a> {
+    ty: &'a Type,
+}
+
+impl<'a> TypedFieldValueBuilder<'a> {
+    pub fn new(ty: &'a Type) -> Self {
+        Self { ty }
+    }
+
+    /// The type of values this builder produces.
+    pub fn target_type(&self) -> &'a Type {
+        self.ty
+    }
+
+    pub fn null(&self) -> Result<FieldValue, FieldValueTypeError> {
+        if self.ty.nullable {
+            Ok(FieldValue::Null)
+        } else {
+            Err(FieldValueTypeError::UnexpectedNull(self.ty.to_string()))
+        }
+    }
+
+    /// Build the value produced by `build` if one is present, or null otherwise.
+    pub fn optional<T>(
+        &self,
+        value: Option<T>,
+        build: impl FnOnce(&Self, T) -> Result<FieldValue, FieldValueTypeError>,
+    ) -> Result<FieldValue, FieldValueTypeError> {
+        match value {
+            Some(value) => build(self, value),
+            None => self.null(),
+        }
+    }
+
+    pub fn string(&self, value: impl Into<String>) -> Result<FieldValue, FieldValueTypeError> {
+        self.check_named(&["String", "ID"], "String!")?;
+        Ok(FieldValue::String(value.into()))
+    }
+
+    pub fn int(&self, value: i64) -> Result<FieldValue, FieldValueTypeError> {
+        self.check_named(&["Int"], "Int!")?;
+        Ok(FieldValue::Int64(value))
+    }
+
+    pub fn uint(&self, value: u64) -> Result<FieldValue, FieldValueTypeError> {
+        self.check_named(&["Int"], "Int!")?;
+        Ok(FieldValue::Uint64(value))
+    }
+
+    /// Float values must be finite: NaN and infinities are rejected.
+    pub fn float(&self, value: f64) -> Result<FieldValue, FieldValueTypeError> {
+        self.check_named(&["Float"], "Float!")?;
+        if value.is_finite() {
+            Ok(FieldValue::Float64(value))
+        } else {
+            Err(FieldValueTypeError::NonFiniteFloat(
+                self.ty.to_string(),
+                value.to_string(),
+            ))
+        }
+    }
+
+    pub fn boolean(&self, value: bool) -> Result<FieldValue, FieldValueTypeError> {
+        self.check_named(&["Boolean"], "Boolean!")?;
+        Ok(FieldValue::Boolean(value))
+    }
+
+    pub fn datetime(&self, value: DateTime<Utc>) -> Result<FieldValue, FieldValueTypeError> {
+        self.check_named(&["DateTime"], "DateTime!")?;
+        Ok(FieldValue::DateTimeUtc(value))
+    }
+
+    /// Build a value of a custom scalar type, i.e. one defined with a `scalar` definition
+    /// in the `schema`, such as `scalar Url`.
+    ///
+    /// The schema doesn't constrain how custom scalar values are represented, so any
+    /// non-list value is accepted, and null is accepted if the type is nullable.
+    /// Schemas can't define enum types, so there is no corresponding method for enums.
+    pub fn custom_scalar(
+        &self,
+        schema: &Schema,
+        value: impl Into<FieldValue>,
+    ) -> Result<FieldValue, FieldValueTypeError> {
+        let name = match &self.ty.base {
+            BaseType::Named(name) => name.as_str(),
+            BaseType::List(_) => {
+                return Err(FieldValueTypeError::NotCustomScalar(self.ty.to_string()))
+            }
+        };
+        if !schema.scalars.contains_key(name) {
+            return Err(FieldValueTypeError::NotCustomScalar(self.ty.to_string()));
+        }
+
+        match value.into() {
+            FieldValue::Null => self.null(),
+            FieldValue::List(_) => Err(FieldValueTypeError::TypeMismatch(
+                self.ty.to_string(),
+                "List!".to_string(),
+            )),
+            value => Ok(value),
+        }
+    }
+
+    /// Build a list value, using `build_element` to build each of the list's elements
+    /// with a builder for the list's element type.
+    pub fn list<T>(
+        &self,
+        elements: impl IntoIterator<Item = T>,
+        mut build_element: impl FnMut(&Self, T) -> Result<FieldValue, FieldValueTypeError>,
+    ) -> Result<FieldValue, FieldValueTypeError> {
+        let element_type = match &self.ty.base {
+            BaseType::List(inner) => inner.as_ref(),
+            BaseType::Named(_) => {
+                return Err(FieldValueTypeError::TypeMismatch(
+                    self.ty.to_string(),
+                    "List!".to_string(),
+                ))
+            }
+        };
+        let element_builder = TypedFieldValueBuilder::new(element_type);
+
+        elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| {
+                build_element(&element_builder, element)
+                    .map_err(|e| FieldValueTypeError::InvalidListElement(index, Box::new(e)))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(FieldValue::List)
+    }
+
+    fn check_named(
+        &self,
+        accepted_names: &[&str],
+        value_type: &str,
+    ) -> Result<(), FieldValueTypeError> {
+        match &self.ty.base {
+            BaseType::Named(name) if accepted_names.contains(&name.as_str()) => Ok(()),
+            _ => Err(FieldValueTypeError::TypeMismatch(
+                self.ty.to_string(),
+                value_type.to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql_parser::types::Type;
+
+    use super::{FieldValueTypeError, TypedFieldValueBuilder};
+    use crate::{ir::FieldValue, schema::Schema};
+
+    #[test]
+    fn nullability_is_enforced_at_every_list_level() {
+        let ty = Type::new("[[Int]!]").unwrap();
+        let builder = TypedFieldValueBuilder::new(&ty);
+
+        assert_eq!(Ok(FieldValue::Null), builder.null());
+        assert_eq!(
+            Ok(FieldValue::List(vec![FieldValue::List(vec![
+                FieldValue::Int64(1),
+                FieldValue::Null
+            ])])),
+            builder.list([vec![Some(1), None]], |inner, values| {
+                inner.list(values, |b, v| b.optional(v, |b, v| b.int(v)))
+            })
+        );
+        assert_eq!(
+            Err(FieldValueTypeError::InvalidListElement(
+                1,
+                Box::new(FieldValueTypeError::UnexpectedNull("[Int]!".to_string()))
+            )),
+            builder.list([Some(vec![]), None], |inner, values: Option<Vec<i64>>| {
+                inner.optional(values, |b, v| b.list(v, |b, v| b.int(v)))
+            })
+        );
+    }
+
+    #[test]
+    fn scalar_type_mismatches_are_rejected() {
+        let ty = Type::new("String!").unwrap();
+        let builder = TypedFieldValueBuilder::new(&ty);
+
+        assert_eq!(Ok(FieldValue::String("abc".into())), builder.string("abc"));
+        assert_eq!(
+            Err(FieldValueTypeError::UnexpectedNull("String!".to_string())),
+            builder.null()
+        );
+        assert_eq!(
+            Err(FieldValueTypeError::TypeMismatch(
+                "String!".to_string(),
+                "Int!".to_string()
+            )),
+            builder.uint(1)
+        );
+        assert_eq!(
+            Err(FieldValueTypeError::TypeMismatch(
+                "String!".to_string(),
+                "List!".to_string()
+            )),
+            builder.list(["abc"], |b, s| b.string(s))
+        );
+
+        let float_ty = Type::new("Float").unwrap();
+        let float_builder = TypedFieldValueBuilder::new(&float_ty);
+        assert_eq!(Ok(FieldValue::Float64(1.5)), float_builder.float(1.5));
+        assert!(matches!(
+            float_builder.float(f64::NAN),
+            Err(FieldValueTypeError::NonFiniteFloat(..))
+        ));
+    }
+
+    #[test]
+    fn custom_scalars_are_checked_against_the_schema() {
+        let schema = Schema::parse(
+            r#"
+schema {
+    query: RootSchemaQuery
+}
+
+scalar Url
+
+type RootSchemaQuery {
+    Page: [Page!]!
+}
+
+type Page {
+    title: String
+}"#,
+        )
+        .unwrap();
+
+        let url_ty = Type::new("Url!").unwrap();
+        let builder = TypedFieldValueBuilder::new(&url_ty);
+        assert_eq!(
+            Ok(FieldValue::String("https://example.com".into())),
+            builder.custom_scalar(&schema, "https://example.com")
+        );
+        assert_eq!(
+            Err(FieldValueTypeError::UnexpectedNull("Url!".to_string())),
+            builder.custom_scalar(&schema, FieldValue::Null)
+        );
+        assert_eq!(
+            Err(FieldValueTypeError::TypeMismatch(
+                "Url!".to_string(),
+                "List!".to_string()
+            )),
+            builder.custom_scalar(&schema, FieldValue::List(vec![]))
+        );
+
+        let nullable_url_ty = Type::new("Url").unwrap();
+        assert_eq!(
+            Ok(FieldValue::Null),
+            TypedFieldValueBuilder::new(&nullable_url_ty).custom_scalar(&schema, FieldValue::Null)
+        );
+
+        // Built-in scalars, vertex types, and unknown types aren't custom scalars.
+        for type_name in ["String", "Page", "Nonexistent", "[Url]"] {
+            let ty = Type::new(type_name).unwrap();
+            assert_eq!(
+                Err(FieldValueTypeError::NotCustomScalar(type_name.to_string())),
+                TypedFieldValueBuilder::new(&ty).custom_scalar(&schema, "abc"),
+            );
+        }
+    }
+}
@@ -241,6 +241,18 @@ pub fn is_argument_type_valid(variable_type: &Type, argument_value: &FieldValue)
             }
         }
         FieldValue::Enum(_) => todo!(),
+        #[cfg(feature = "serde_json")]
+        FieldValue::Json(_) => {
+            // JSON values are instances of custom scalars, so this is a valid value only if
+            // the type is not a list and not one of the built-in scalars, ignoring nullability.
+            match &variable_type.base {
+                BaseType::Named(n) => !matches!(
+                    n.as_str(),
+                    "Int" | "Float" | "String" | "Boolean" | "ID" | "DateTime"
+                ),
+                BaseType::List(_) => false,
+            }
+        }
     }
 }
 
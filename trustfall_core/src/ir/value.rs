@@ -1,11 +1,34 @@
 /// IR of the values of Trustfall fields.
-use async_graphql_value::{ConstValue, Number, Value};
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    num::{
+        NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64,
+        NonZeroU8,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_graphql_parser::types::{BaseType, Type};
+use async_graphql_value::{ConstValue, Name, Number, Value};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Serialize,
+};
+
+use crate::interpreter::filtering::equals;
 
 /// Values of fields in Trustfall.
 ///
 /// For version that is serialized as an untagged enum, see [TransparentValue].
+///
+/// New variants may be added over time, and some variants only exist when a cargo feature
+/// is enabled, so matches on this type outside this crate need a wildcard arm.
+/// For how new variants affect previously-serialized values, see the compatibility policy
+/// of [`Trace`](crate::interpreter::trace::Trace).
+#[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FieldValue {
     // Order may matter here! Deserialization, if ever configured for untagged serialization,
@@ -17,19 +40,29 @@ pub enum FieldValue {
     /// AKA integer
     Int64(i64),
     Uint64(u64),
-    /// AKA Float, and also not allowed to be NaN
-    Float64(f64),
+    /// AKA Float, and also not allowed to be NaN or infinite
+    Float64(#[serde(deserialize_with = "deserialize_finite_f64")] f64),
     String(String),
     Boolean(bool),
     DateTimeUtc(DateTime<Utc>),
     Enum(String),
     List(Vec<FieldValue>),
+    /// Opaque JSON, for semi-structured data that the schema doesn't model.
+    ///
+    /// Values are carried through queries untouched, and compared structurally:
+    /// object keys are unordered, and numbers are equal only if they are represented
+    /// the same way, so `1` and `1.0` are different values. Filters can check these values
+    /// for presence with `is_null`/`is_not_null` and for equality with `=`, `!=`, and `one_of`,
+    /// but they aren't orderable, and the string and list operators don't apply to them.
+    #[cfg(feature = "serde_json")]
+    Json(serde_json::Value),
 }
 
 /// Values of fields in GraphQL types.
 ///
 /// Same as [FieldValue], but serialized as an untagged enum,
 /// which may be more suitable e.g. when serializing to JSON.
+#[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum TransparentValue {
@@ -41,12 +74,19 @@ pub enum TransparentValue {
     Null,
     Int64(i64), // AKA Integer
     Uint64(u64),
-    Float64(f64), // AKA Float, and also not allowed to be NaN
+    // AKA Float, and also not allowed to be NaN or infinite
+    Float64(#[serde(deserialize_with = "deserialize_finite_f64")] f64),
     String(String),
     Boolean(bool),
     DateTimeUtc(DateTime<Utc>),
     Enum(String),
     List(Vec<TransparentValue>),
+    /// Serialized as native JSON, as opposed to a string containing JSON.
+    ///
+    /// Only JSON objects deserialize into this variant, since all other JSON values
+    /// are represented by one of the variants above.
+    #[cfg(feature = "serde_json")]
+    Json(#[serde(deserialize_with = "deserialize_json_object")] serde_json::Value),
 }
 
 impl From<FieldValue> for TransparentValue {
@@ -63,6 +103,8 @@ impl From<FieldValue> for TransparentValue {
             FieldValue::List(x) => {
                 TransparentValue::List(x.into_iter().map(|v| v.into()).collect())
             }
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(x) => TransparentValue::Json(x),
         }
     }
 }
@@ -81,6 +123,8 @@ impl From<TransparentValue> for FieldValue {
             TransparentValue::List(x) => {
                 FieldValue::List(x.into_iter().map(|v| v.into()).collect())
             }
+            #[cfg(feature = "serde_json")]
+            TransparentValue::Json(x) => FieldValue::Json(x),
         }
     }
 }
@@ -97,6 +141,8 @@ impl FieldValue {
             | FieldValue::DateTimeUtc(_)
             | FieldValue::List(_)
             | FieldValue::Enum(_) => None,
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => None,
         }
     }
 
@@ -111,6 +157,8 @@ impl FieldValue {
             | FieldValue::DateTimeUtc(_)
             | FieldValue::List(_)
             | FieldValue::Enum(_) => None,
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => None,
         }
     }
 
@@ -125,9 +173,46 @@ impl FieldValue {
             | FieldValue::DateTimeUtc(_)
             | FieldValue::List(_)
             | FieldValue::Enum(_) => None,
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => None,
+        }
+    }
+
+    /// The value as an `f64`, if it's a float or an integer that `f64` represents exactly.
+    ///
+    /// Integers with magnitude above 2^53 are only exactly representable if they are
+    /// a multiple of a large enough power of two. For any other such integer this returns `None`
+    /// instead of silently rounding it. To get the nearest `f64` regardless, match the value
+    /// and cast the integer with `as f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::Float64(f) => Some(*f),
+            FieldValue::Int64(i) => {
+                let f = *i as f64;
+                // Compare as i128, since casting `f` back to i64 saturates on overflow.
+                (f as i128 == *i as i128).then_some(f)
+            }
+            FieldValue::Uint64(u) => {
+                let f = *u as f64;
+                (f as u128 == *u as u128).then_some(f)
+            }
+            FieldValue::Null
+            | FieldValue::String(_)
+            | FieldValue::Boolean(_)
+            | FieldValue::DateTimeUtc(_)
+            | FieldValue::List(_)
+            | FieldValue::Enum(_) => None,
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => None,
         }
     }
 
+    /// The value as a [`FiniteF64`], under the same conditions as [`FieldValue::as_f64`]
+    /// and additionally only if the value is neither infinite nor NaN.
+    pub fn as_finite_f64(&self) -> Option<FiniteF64> {
+        FiniteF64::try_from(self.as_f64()?).ok()
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         match self {
             FieldValue::String(s) => Some(s.as_str()),
@@ -142,6 +227,377 @@ impl FieldValue {
         }
     }
 
+    /// Interpret the value as a boolean, also accepting common non-boolean encodings.
+    ///
+    /// Some data stores have no native boolean type, and represent booleans
+    /// as the integers `0` and `1` or as the strings `"true"` and `"false"`.
+    /// This method maps those representations to `bool`, and returns `None` for anything else.
+    /// For strict boolean-only conversion, use [`FieldValue::as_bool`] instead.
+    pub fn as_bool_lenient(&self) -> Option<bool> {
+        match self {
+            FieldValue::Boolean(b) => Some(*b),
+            FieldValue::Int64(0) | FieldValue::Uint64(0) => Some(false),
+            FieldValue::Int64(1) | FieldValue::Uint64(1) => Some(true),
+            FieldValue::String(s) => match s.as_str() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the value is "truthy", as in languages and data stores without strict booleans.
+    ///
+    /// The following values are falsy, and all others are truthy:
+    /// - `Null`;
+    /// - `Boolean(false)`;
+    /// - numeric zero of any variant, including `-0.0`;
+    /// - the empty string;
+    /// - the empty list.
+    ///
+    /// In particular, datetimes, enum values, and JSON values are always truthy, as are lists
+    /// containing only falsy elements. Filters in queries never use these rules:
+    /// this is only a convenience for adapters that translate queries into backends
+    /// with truthiness-based semantics.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            FieldValue::Null => false,
+            FieldValue::Int64(n) => *n != 0,
+            FieldValue::Uint64(n) => *n != 0,
+            FieldValue::Float64(f) => *f != 0.0,
+            FieldValue::String(s) => !s.is_empty(),
+            FieldValue::Boolean(b) => *b,
+            FieldValue::DateTimeUtc(_) | FieldValue::Enum(_) => true,
+            FieldValue::List(values) => !values.is_empty(),
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => true,
+        }
+    }
+
+    /// Equality that treats floats within `epsilon` of each other as equal.
+    ///
+    /// If either side is a `Float64` and the other is numeric, both are compared as `f64`
+    /// and considered equal if they differ by at most `epsilon`. Lists are compared
+    /// element-wise using the same rule. All other values, including integers compared
+    /// with integers, fall back to exact equality.
+    ///
+    /// Useful for comparing computed float values, such as coordinates, that may carry
+    /// rounding errors.
+    pub fn approx_eq(&self, other: &FieldValue, epsilon: f64) -> bool {
+        match (self, other) {
+            (FieldValue::Float64(_), _) | (_, FieldValue::Float64(_)) => {
+                match (self.as_f64_lossy(), other.as_f64_lossy()) {
+                    (Some(l), Some(r)) => (l - r).abs() <= epsilon,
+                    _ => false,
+                }
+            }
+            (FieldValue::List(l), FieldValue::List(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.approx_eq(r, epsilon))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Equality that treats an `Enum` value as equal to a `String` with the same contents.
+    ///
+    /// `Enum("X")` and `String("X")` are distinct values: they compare unequal with `==`,
+    /// and filters in queries never match one against the other. Adapters whose backends store
+    /// enum values as plain strings can use this to compare them against enum values anyway.
+    /// Lists are compared element-wise using the same rule. All other values use `==`.
+    pub fn enum_eq_str(&self, other: &FieldValue) -> bool {
+        match (self, other) {
+            (FieldValue::Enum(l), FieldValue::String(r))
+            | (FieldValue::String(l), FieldValue::Enum(r)) => l == r,
+            (FieldValue::List(l), FieldValue::List(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.enum_eq_str(r))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Equality that treats a `String` holding a number as equal to that number.
+    ///
+    /// If one side is numeric and the other is a string, the string is parsed as a number
+    /// and the two are compared regardless of their numeric variants, so `String("5")`
+    /// is equal to `Int64(5)`, `Uint64(5)`, and `Float64(5.0)`, and `String("5.0")`
+    /// is equal to `Int64(5)`. Strings that don't parse as finite numbers, including ones
+    /// with surrounding whitespace, are never equal to a numeric value.
+    /// Lists are compared element-wise using the same rule. All other values are compared
+    /// the same way as by the `=` filter operator, so `Int64(5)` is equal to `Uint64(5)`.
+    ///
+    /// Useful for adapters over stringly-typed backends, such as CSV files, whose string values
+    /// need to match numeric query arguments.
+    pub fn lenient_eq(&self, other: &FieldValue) -> bool {
+        match (self, other) {
+            (FieldValue::String(s), number) | (number, FieldValue::String(s))
+                if number.numeric_key().is_some() =>
+            {
+                parse_number(s).and_then(|parsed| parsed.numeric_key()) == number.numeric_key()
+            }
+            (FieldValue::List(l), FieldValue::List(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.lenient_eq(r))
+            }
+            _ => equals(self, other),
+        }
+    }
+
+    /// Equality that compares lists as multisets, ignoring the order of their elements.
+    ///
+    /// Two lists are equal if they contain the same elements the same number of times,
+    /// in any order, so `[1, 2, 2]` equals `[2, 1, 2]` but not `[1, 1, 2]`. This applies
+    /// to nested lists too: `[[1, 2], [3]]` equals `[[3], [2, 1]]`. All other values use `==`.
+    ///
+    /// Useful for properties that are logically sets, whose element order carries no meaning.
+    pub fn set_eq(&self, other: &FieldValue) -> bool {
+        match (self, other) {
+            (FieldValue::List(_), FieldValue::List(_)) => {
+                self.clone().sort_deep() == other.clone().sort_deep()
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Sort lists, including nested ones, so that lists that are [`FieldValue::set_eq`]
+    /// to each other become identical.
+    fn sort_deep(self) -> Self {
+        self.map_deep(&mut |value| match value {
+            FieldValue::List(mut values) => {
+                values.sort_unstable();
+                FieldValue::List(values)
+            }
+            _ => value,
+        })
+    }
+
+    /// Convert floats with integral values into integers, where that's lossless.
+    ///
+    /// Such floats become `Int64` if they fit, or `Uint64` if they are too large for `Int64`
+    /// but fit in `Uint64`. For example, a `Float64(1e10)` becomes `Int64(10000000000)`.
+    /// Floats with fractional parts or outside the range of both integer types are unchanged,
+    /// as are all non-float values. Lists are normalized element-wise.
+    pub fn normalize_numeric(self) -> Self {
+        // 2^63 and 2^64, both exactly representable as f64.
+        const I64_END: f64 = 9223372036854775808.0;
+        const U64_END: f64 = 18446744073709551616.0;
+
+        match self {
+            FieldValue::Float64(f) if f.fract() == 0.0 && (-I64_END..I64_END).contains(&f) => {
+                FieldValue::Int64(f as i64)
+            }
+            FieldValue::Float64(f) if f.fract() == 0.0 && (0.0..U64_END).contains(&f) => {
+                FieldValue::Uint64(f as u64)
+            }
+            FieldValue::List(values) => {
+                FieldValue::List(values.into_iter().map(Self::normalize_numeric).collect())
+            }
+            _ => self,
+        }
+    }
+
+    /// Transform this value and every value nested inside it, bottom-up.
+    ///
+    /// The elements of a list are transformed first, then the transform is applied
+    /// to the list made of the transformed elements. Non-list values are passed
+    /// to the transform directly. The transform is called on values in the order
+    /// they appear, with each list's elements visited before the list itself.
+    pub fn map_deep(self, f: &mut impl FnMut(FieldValue) -> FieldValue) -> FieldValue {
+        let value = match self {
+            FieldValue::List(values) => {
+                FieldValue::List(values.into_iter().map(|value| value.map_deep(f)).collect())
+            }
+            _ => self,
+        };
+        f(value)
+    }
+
+    /// The canonical representation of a numeric value, regardless of its variant.
+    ///
+    /// All equality checks that treat different numeric variants as equal must go through
+    /// this function, so that they all agree with each other and are transitive.
+    pub(crate) fn numeric_key(&self) -> Option<NumericKey> {
+        // 2^127, exactly representable as f64.
+        const I128_END: f64 = 170141183460469231731687303715884105728.0;
+
+        match self {
+            FieldValue::Int64(i) => Some(NumericKey::Integer(i128::from(*i))),
+            FieldValue::Uint64(u) => Some(NumericKey::Integer(i128::from(*u))),
+            FieldValue::Float64(f) if f.fract() == 0.0 && (-I128_END..I128_END).contains(f) => {
+                // Exact, since the value is integral and in range. Also maps -0.0 to 0.
+                Some(NumericKey::Integer(*f as i128))
+            }
+            FieldValue::Float64(f) => Some(NumericKey::Float(f.to_bits())),
+            _ => None,
+        }
+    }
+
+    /// The position of the value's variant in the enum's declaration order.
+    fn variant_order(&self) -> u8 {
+        match self {
+            FieldValue::Null => 0,
+            FieldValue::Int64(_) => 1,
+            FieldValue::Uint64(_) => 2,
+            FieldValue::Float64(_) => 3,
+            FieldValue::String(_) => 4,
+            FieldValue::Boolean(_) => 5,
+            FieldValue::DateTimeUtc(_) => 6,
+            FieldValue::Enum(_) => 7,
+            FieldValue::List(_) => 8,
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => 9,
+        }
+    }
+
+    /// The name of this value's variant, for use in error messages.
+    fn variant_name(&self) -> &'static str {
+        match self {
+            FieldValue::Null => "Null",
+            FieldValue::Int64(_) => "Int64",
+            FieldValue::Uint64(_) => "Uint64",
+            FieldValue::Float64(_) => "Float64",
+            FieldValue::String(_) => "String",
+            FieldValue::Boolean(_) => "Boolean",
+            FieldValue::DateTimeUtc(_) => "DateTimeUtc",
+            FieldValue::Enum(_) => "Enum",
+            FieldValue::List(_) => "List",
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(_) => "Json",
+        }
+    }
+
+    fn as_f64_lossy(&self) -> Option<f64> {
+        match self {
+            FieldValue::Float64(f) => Some(*f),
+            FieldValue::Int64(i) => Some(*i as f64),
+            FieldValue::Uint64(u) => Some(*u as f64),
+            _ => None,
+        }
+    }
+
+    /// The IP address held by a string value, as represented by [`FieldValue::from`]
+    /// or in any other format accepted by [IpAddr]'s `FromStr`.
+    ///
+    /// Strings in the representation used by [`FieldValue::from`] are decoded directly
+    /// without going through the general-purpose address parser. IPv4-mapped IPv6 addresses
+    /// are indistinguishable from IPv4 addresses in that representation,
+    /// so they are returned as IPv4 addresses.
+    pub fn as_ip(&self) -> Option<IpAddr> {
+        let value = self.as_str()?;
+        match decode_ip(value) {
+            Some(ip) => Some(ip),
+            None => value.parse().ok(),
+        }
+    }
+
+    /// The first and last addresses of the network with the given address and prefix length,
+    /// such as `10.0.0.0/8`, or `None` if the prefix is longer than the address.
+    ///
+    /// An address is in the network if it's between the two bounds, inclusive,
+    /// so a network can be queried with a pair of `>=` and `<=` filters on an address property.
+    /// Adapters receive such filters as a [range hint](crate::interpreter::CandidateValue::Range),
+    /// whose bounds can be decoded with [`FieldValue::as_ip`] to push the network
+    /// down to their data source.
+    pub fn ip_network_bounds(network: IpAddr, prefix_len: u8) -> Option<(FieldValue, FieldValue)> {
+        match network {
+            IpAddr::V4(network) => {
+                let mask = u32::MAX.checked_shl(32u32.checked_sub(prefix_len.into())?);
+                let mask = mask.unwrap_or_default();
+                let first = u32::from(network) & mask;
+                Some((
+                    Ipv4Addr::from(first).into(),
+                    Ipv4Addr::from(first | !mask).into(),
+                ))
+            }
+            IpAddr::V6(network) => {
+                let mask = u128::MAX.checked_shl(128u32.checked_sub(prefix_len.into())?);
+                let mask = mask.unwrap_or_default();
+                let first = u128::from(network) & mask;
+                Some((
+                    Ipv6Addr::from(first).into(),
+                    Ipv6Addr::from(first | !mask).into(),
+                ))
+            }
+        }
+    }
+
+    /// Parse a string value as a UUID, in any format accepted by [uuid::Uuid::parse_str].
+    #[cfg(feature = "uuid")]
+    pub fn as_uuid(&self) -> Option<uuid::Uuid> {
+        uuid::Uuid::parse_str(self.as_str()?).ok()
+    }
+
+    /// Parse a string value as a semantic version, such as `1.2.3-alpha.1`.
+    #[cfg(feature = "semver")]
+    pub fn as_semver(&self) -> Option<semver::Version> {
+        semver::Version::parse(self.as_str()?).ok()
+    }
+
+    /// Serialize the value as MessagePack, using the same variant-tagged representation
+    /// as the value's [`Serialize`] implementation.
+    #[cfg(feature = "rmp-serde")]
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Deserialize a value from MessagePack produced by [`FieldValue::to_msgpack`].
+    #[cfg(feature = "rmp-serde")]
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+
+    /// A `DateTimeUtc` value for the given number of milliseconds since the Unix epoch.
+    ///
+    /// Returns `None` if the timestamp is outside the range of representable datetimes.
+    pub fn from_unix_millis(ms: i64) -> Option<Self> {
+        Utc.timestamp_millis_opt(ms).single().map(Self::DateTimeUtc)
+    }
+
+    /// A `DateTimeUtc` value for the given number of seconds since the Unix epoch.
+    ///
+    /// Returns `None` if the timestamp is outside the range of representable datetimes.
+    pub fn from_unix_seconds(secs: i64) -> Option<Self> {
+        Utc.timestamp_opt(secs, 0).single().map(Self::DateTimeUtc)
+    }
+
+    /// Interpret a time-like value as a whole number of seconds since the Unix epoch.
+    ///
+    /// `DateTimeUtc` values are converted, discarding any fractional seconds.
+    /// Integer values are assumed to already be Unix timestamps in seconds, and are returned
+    /// as-is if they fit in `i64`. All other values produce `None`.
+    ///
+    /// Schemas may represent times either as `DateTime` or as integer Unix timestamps,
+    /// like the `unixTime: Int!` properties of the HackerNews example schema.
+    /// An adapter that pushes time filters down to its data source can call this on
+    /// the values in a property's [candidate values](crate::interpreter::CandidateValue)
+    /// to get the bounds of a time range in the same form regardless of the representation.
+    pub fn as_unix_seconds(&self) -> Option<i64> {
+        match self {
+            FieldValue::DateTimeUtc(dt) => Some(dt.timestamp()),
+            _ => self.as_i64(),
+        }
+    }
+
+    /// Interpret a time-like value as a whole number of milliseconds since the Unix epoch.
+    ///
+    /// Like [`FieldValue::as_unix_seconds`], except that integer values are assumed
+    /// to be Unix timestamps in milliseconds.
+    pub fn as_unix_millis(&self) -> Option<i64> {
+        match self {
+            FieldValue::DateTimeUtc(dt) => Some(dt.timestamp_millis()),
+            _ => self.as_i64(),
+        }
+    }
+
+    /// The elements of a list value, borrowed without allocating.
+    ///
+    /// Returns `None` if the value is not a list.
+    pub fn as_slice(&self) -> Option<&[FieldValue]> {
+        match self {
+            FieldValue::List(l) => Some(l.as_slice()),
+            _ => None,
+        }
+    }
+
     pub fn as_vec<'a, T>(&'a self, inner: impl Fn(&'a FieldValue) -> Option<T>) -> Option<Vec<T>> {
         match self {
             FieldValue::List(l) => {
@@ -151,6 +607,130 @@ impl FieldValue {
             _ => None,
         }
     }
+
+    /// Whether this is a list that contains the given value,
+    /// as checked by the `contains` filter operator.
+    pub fn contains_value(&self, needle: &FieldValue) -> bool {
+        match self {
+            FieldValue::List(l) => l.iter().any(|element| equals(element, needle)),
+            _ => false,
+        }
+    }
+
+    /// A range of values, represented as a two-element list of its bounds.
+    ///
+    /// There is no dedicated range variant. By convention, a range such as a reservation's
+    /// time window or a numeric interval is a list `[start, end]`, declared in the schema
+    /// as a list of the bounds' type, e.g. `validity: [DateTime]`. Ranges are half-open:
+    /// they include their start but not their end. A null bound leaves that side unbounded.
+    ///
+    /// Query filters treat such values like any other list. Adapters can use
+    /// [`FieldValue::range_contains`] and [`FieldValue::range_overlaps`] to evaluate
+    /// range conditions themselves, e.g. when pushing them down to their data source.
+    pub fn range(start: impl Into<FieldValue>, end: impl Into<FieldValue>) -> Self {
+        FieldValue::List(vec![start.into(), end.into()])
+    }
+
+    /// The `(start, end)` bounds of a range, as represented by [`FieldValue::range`].
+    ///
+    /// Returns `None` if the value is not a two-element list.
+    pub fn as_range(&self) -> Option<(&FieldValue, &FieldValue)> {
+        match self.as_slice()? {
+            [start, end] => Some((start, end)),
+            _ => None,
+        }
+    }
+
+    /// Whether this [range](FieldValue::range) contains the given value.
+    ///
+    /// Returns `false` if this isn't a range, if the value is null, or if the value
+    /// can't be compared with the range's bounds. Integers and floats of any variant
+    /// are comparable with each other, and strings and datetimes only with their own kind.
+    pub fn range_contains(&self, value: &FieldValue) -> bool {
+        let Some((start, end)) = self.as_range() else {
+            return false;
+        };
+        !matches!(value, FieldValue::Null)
+            && bound_before(start, value, true)
+            && bound_before(value, end, false)
+    }
+
+    /// Whether this [range](FieldValue::range) and another one share at least one value.
+    ///
+    /// Ranges are half-open, so ranges that only touch, like `[1, 5]` and `[5, 9]`,
+    /// don't overlap. Empty ranges, whose end is not after their start, overlap nothing.
+    /// Returns `false` if either value isn't a range, or if their bounds can't be compared
+    /// as described in [`FieldValue::range_contains`].
+    pub fn range_overlaps(&self, other: &FieldValue) -> bool {
+        let (Some((start, end)), Some((other_start, other_end))) =
+            (self.as_range(), other.as_range())
+        else {
+            return false;
+        };
+        bound_before(start, end, false)
+            && bound_before(other_start, other_end, false)
+            && bound_before(start, other_end, false)
+            && bound_before(other_start, end, false)
+    }
+
+    /// Deserialize a list one element at a time, checking whether it contains the given value,
+    /// without materializing the whole list as a `Vec<FieldValue>`.
+    ///
+    /// List elements are deserialized as [TransparentValue], so this is suitable for
+    /// formats like JSON. At most one element is held in memory at a time. Once a match
+    /// is found, the remaining elements are skipped without being converted into values.
+    /// Elements are matched like [`FieldValue::contains_value`] matches them.
+    /// A null list contains no values.
+    pub fn deserialize_list_contains<'de, D>(
+        deserializer: D,
+        needle: &FieldValue,
+    ) -> Result<bool, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_option(ListContainsVisitor { needle })
+    }
+}
+
+struct ListContainsVisitor<'a> {
+    needle: &'a FieldValue,
+}
+
+impl<'de> serde::de::Visitor<'de> for ListContainsVisitor<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of values, or null")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<bool, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<bool, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        while let Some(element) = seq.next_element::<TransparentValue>()? {
+            if equals(&FieldValue::from(element), self.needle) {
+                // Formats like JSON report an error if the rest of the list isn't consumed.
+                while seq.next_element::<serde::de::IgnoredAny>()?.is_some() {}
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
 }
 
 impl PartialEq for FieldValue {
@@ -168,6 +748,8 @@ impl PartialEq for FieldValue {
             (Self::DateTimeUtc(l0), Self::DateTimeUtc(r0)) => l0 == r0,
             (Self::List(l0), Self::List(r0)) => l0 == r0,
             (Self::Enum(l0), Self::Enum(r0)) => l0 == r0,
+            #[cfg(feature = "serde_json")]
+            (Self::Json(l0), Self::Json(r0)) => l0 == r0,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
@@ -175,113 +757,626 @@ impl PartialEq for FieldValue {
 
 impl Eq for FieldValue {}
 
-impl AsRef<FieldValue> for FieldValue {
-    fn as_ref(&self) -> &FieldValue {
-        self
+/// A total ordering of values, used e.g. for sorting.
+///
+/// Values of the same variant compare in their natural order. Lists compare lexicographically,
+/// element by element: the first pair of unequal elements decides the order, and if one list
+/// is a prefix of the other, the shorter list is less. So `[] < [1] < [1, 2] < [2]`.
+///
+/// Numbers compare numerically, regardless of their variant: `Int64(-1) < Uint64(0)`,
+/// and `Uint64(1) < Float64(1.5) < Int64(2)`. Comparisons between integers and floats
+/// are exact, without converting the integer to a possibly-rounded `f64`. Numbers of different
+/// variants are never equal, since [`PartialEq`] considers them distinct: numerically equal
+/// numbers of different variants are ordered by variant, `Int64` < `Uint64` < `Float64`.
+/// Floats are compared with [`f64::total_cmp`], except that `-0.0` and `0.0` are equal.
+///
+/// Values of other kinds are ordered by kind, in the order in which the variants
+/// are declared: `Null` is less than every other value, then numbers, then strings, booleans,
+/// datetimes, enum values, and lists. So `List` is greater than every non-list value.
+///
+/// JSON values are greater than all other values. Among themselves, they are ordered
+/// first by kind: `null`, booleans, numbers, strings, arrays, and then objects.
+/// This order is arbitrary but consistent with equality: it only exists so that
+/// JSON values can be sorted and deduplicated along with other values.
+impl Ord for FieldValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Null, Self::Null) => Ordering::Equal,
+            (Self::Int64(l), Self::Int64(r)) => l.cmp(r),
+            (Self::Uint64(l), Self::Uint64(r)) => l.cmp(r),
+            (Self::Float64(l), Self::Float64(r)) => float_cmp(*l, *r),
+            (Self::String(l), Self::String(r)) => l.cmp(r),
+            (Self::Boolean(l), Self::Boolean(r)) => l.cmp(r),
+            (Self::DateTimeUtc(l), Self::DateTimeUtc(r)) => l.cmp(r),
+            (Self::Enum(l), Self::Enum(r)) => l.cmp(r),
+            (Self::List(l), Self::List(r)) => l.cmp(r),
+            #[cfg(feature = "serde_json")]
+            (Self::Json(l), Self::Json(r)) => json_cmp(l, r),
+            (
+                Self::Int64(_) | Self::Uint64(_) | Self::Float64(_),
+                Self::Int64(_) | Self::Uint64(_) | Self::Float64(_),
+            ) => numeric_cmp(self, other)
+                .then_with(|| self.variant_order().cmp(&other.variant_order())),
+            _ => self.variant_order().cmp(&other.variant_order()),
+        }
     }
 }
 
-impl From<String> for FieldValue {
-    fn from(v: String) -> Self {
-        Self::String(v)
-    }
+/// Compare floats with [`f64::total_cmp`], except that `-0.0` and `0.0` are equal
+/// as they are according to [`PartialEq`].
+fn float_cmp(left: f64, right: f64) -> Ordering {
+    let normalize = |f: f64| if f == 0.0 { 0.0 } else { f };
+    normalize(left).total_cmp(&normalize(right))
 }
 
-impl From<&String> for FieldValue {
-    fn from(v: &String) -> Self {
-        Self::String(v.clone())
+/// Compare numbers of any numeric variants by their exact numeric values.
+fn numeric_cmp(left: &FieldValue, right: &FieldValue) -> Ordering {
+    fn as_integer(value: &FieldValue) -> Option<i128> {
+        match value {
+            FieldValue::Int64(i) => Some(i128::from(*i)),
+            FieldValue::Uint64(u) => Some(i128::from(*u)),
+            _ => None,
+        }
     }
-}
 
-impl From<&str> for FieldValue {
-    fn from(v: &str) -> Self {
-        Self::from(v.to_owned())
+    /// Compare an integer with a float, exactly.
+    fn integer_float_cmp(integer: i128, float: f64) -> Ordering {
+        // 2^127, exactly representable as f64. Every `Int64` and `Uint64` is smaller.
+        const I128_END: f64 = 170141183460469231731687303715884105728.0;
+
+        if float.is_nan() {
+            // Consistent with `f64::total_cmp`, which orders NaN by its sign bit.
+            return if float.is_sign_negative() {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+        if float >= I128_END {
+            return Ordering::Less;
+        }
+        if float < -I128_END {
+            return Ordering::Greater;
+        }
+
+        // Exact, since the truncated value is integral and in range.
+        let truncated = float.trunc();
+        integer.cmp(&(truncated as i128)).then_with(|| {
+            // The integer equals the float's integral part, so the fractional part decides.
+            0.0.partial_cmp(&(float - truncated))
+                .expect("non-NaN fractional part")
+        })
     }
-}
 
-impl From<bool> for FieldValue {
-    fn from(v: bool) -> Self {
-        Self::Boolean(v)
+    match (as_integer(left), as_integer(right)) {
+        (Some(l), Some(r)) => l.cmp(&r),
+        (Some(l), None) => {
+            let FieldValue::Float64(r) = right else {
+                unreachable!("{right:?}")
+            };
+            integer_float_cmp(l, *r)
+        }
+        (None, Some(r)) => {
+            let FieldValue::Float64(l) = left else {
+                unreachable!("{left:?}")
+            };
+            integer_float_cmp(r, *l).reverse()
+        }
+        (None, None) => match (left, right) {
+            (FieldValue::Float64(l), FieldValue::Float64(r)) => float_cmp(*l, *r),
+            _ => unreachable!("{left:?} {right:?}"),
+        },
     }
 }
 
-/// Represents a finite (non-infinite, not-NaN) [f64] value
-pub struct FiniteF64(f64);
-impl From<FiniteF64> for FieldValue {
-    fn from(f: FiniteF64) -> FieldValue {
-        FieldValue::Float64(f.0)
+impl PartialOrd for FieldValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-macro_rules! impl_finite_f64_try_from_float {
-    ( $( $Float: ident )+ ) => {
-        $(
-            impl TryFrom<$Float> for FiniteF64 {
-                type Error = ($Float, &'static str);
-
-                fn try_from(v: $Float) -> Result<Self, Self::Error> {
-                    if v.is_finite() {
-                        Ok(Self(v.into()))
-                    } else {
-                        Err((v, "not a finite (non-infinite, not-NaN) value"))
-                    }
-                }
+impl Hash for FieldValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Null => {}
+            Self::Int64(x) => x.hash(state),
+            Self::Uint64(x) => x.hash(state),
+            Self::Float64(x) => {
+                // `0.0 == -0.0`, so they must hash the same way.
+                let x = if *x == 0.0 { 0.0f64 } else { *x };
+                x.to_bits().hash(state)
             }
-        )+
+            Self::String(x) => x.hash(state),
+            Self::Boolean(x) => x.hash(state),
+            Self::DateTimeUtc(x) => x.hash(state),
+            Self::Enum(x) => x.hash(state),
+            Self::List(x) => x.hash(state),
+            #[cfg(feature = "serde_json")]
+            Self::Json(x) => hash_json(x, state),
+        }
     }
 }
 
-impl_finite_f64_try_from_float!(f32 f64);
-
-macro_rules! impl_field_value_from_int {
-    ( $( $Int: ident )+ ) => {
-        $(
-            impl From<$Int> for FieldValue {
-                fn from(v: $Int) -> Self {
-                    Self::Int64(v.into())
-                }
-            }
-        )+
+/// The position of the JSON value's kind in the order used by [`json_cmp`].
+#[cfg(feature = "serde_json")]
+fn json_kind_order(value: &serde_json::Value) -> u8 {
+    match value {
+        serde_json::Value::Null => 0,
+        serde_json::Value::Bool(_) => 1,
+        serde_json::Value::Number(_) => 2,
+        serde_json::Value::String(_) => 3,
+        serde_json::Value::Array(_) => 4,
+        serde_json::Value::Object(_) => 5,
     }
 }
 
-macro_rules! impl_field_value_from_uint {
-    ( $( $Uint: ident )+ ) => {
-        $(
-            impl From<$Uint> for FieldValue {
-                fn from(v: $Uint) -> Self {
-                    Self::Uint64(v.into())
-                }
-            }
-        )+
+/// The position of the JSON number's representation, since numbers are only equal
+/// if they are represented the same way.
+#[cfg(feature = "serde_json")]
+fn json_number_order(number: &serde_json::Number) -> u8 {
+    if number.is_u64() {
+        0
+    } else if number.is_i64() {
+        1
+    } else {
+        2
     }
 }
 
-impl_field_value_from_int!(i8 i16 i32 i64);
-impl_field_value_from_uint!(u8 u16 u32 u64);
+/// Object entries in key order, regardless of the order in which the map stores them.
+#[cfg(feature = "serde_json")]
+fn sorted_json_entries(
+    map: &serde_json::Map<String, serde_json::Value>,
+) -> Vec<(&String, &serde_json::Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|(key, _)| *key);
+    entries
+}
 
-impl From<DateTime<Utc>> for FieldValue {
-    fn from(v: DateTime<Utc>) -> Self {
-        Self::DateTimeUtc(v)
+/// A total order of JSON values, consistent with their structural equality.
+#[cfg(feature = "serde_json")]
+fn json_cmp(left: &serde_json::Value, right: &serde_json::Value) -> Ordering {
+    use serde_json::Value;
+
+    match (left, right) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Bool(l), Value::Bool(r)) => l.cmp(r),
+        (Value::Number(l), Value::Number(r)) => json_number_order(l)
+            .cmp(&json_number_order(r))
+            .then_with(|| match (l.as_u64(), r.as_u64()) {
+                (Some(l), Some(r)) => l.cmp(&r),
+                _ => match (l.as_i64(), r.as_i64()) {
+                    (Some(l), Some(r)) => l.cmp(&r),
+                    _ => {
+                        let (l, r) = (l.as_f64(), r.as_f64());
+                        l.partial_cmp(&r).expect("JSON numbers are always finite")
+                    }
+                },
+            }),
+        (Value::String(l), Value::String(r)) => l.cmp(r),
+        (Value::Array(l), Value::Array(r)) => l
+            .iter()
+            .zip(r)
+            .map(|(l, r)| json_cmp(l, r))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| l.len().cmp(&r.len())),
+        (Value::Object(l), Value::Object(r)) => {
+            let (l, r) = (sorted_json_entries(l), sorted_json_entries(r));
+            l.iter()
+                .zip(&r)
+                .map(|((lk, lv), (rk, rv))| lk.cmp(rk).then_with(|| json_cmp(lv, rv)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len()))
+        }
+        _ => json_kind_order(left).cmp(&json_kind_order(right)),
     }
 }
 
-impl TryFrom<Option<f32>> for FieldValue {
-    type Error = (f32, &'static str);
+/// Hash a JSON value consistently with its structural equality,
+/// which ignores the order of object keys.
+#[cfg(feature = "serde_json")]
+fn hash_json<H: Hasher>(value: &serde_json::Value, state: &mut H) {
+    use serde_json::Value;
 
-    fn try_from(value: Option<f32>) -> Result<Self, Self::Error> {
-        match value {
-            None => Ok(FieldValue::Null),
-            Some(v) => {
-                let finite_f64 = FiniteF64::try_from(v);
-                finite_f64.map(|x| x.into())
+    json_kind_order(value).hash(state);
+    match value {
+        Value::Null => {}
+        Value::Bool(x) => x.hash(state),
+        Value::Number(x) => x.hash(state),
+        Value::String(x) => x.hash(state),
+        Value::Array(x) => {
+            x.len().hash(state);
+            for element in x {
+                hash_json(element, state);
+            }
+        }
+        Value::Object(x) => {
+            x.len().hash(state);
+            for (key, element) in sorted_json_entries(x) {
+                key.hash(state);
+                hash_json(element, state);
             }
         }
     }
 }
 
-impl TryFrom<Option<f64>> for FieldValue {
-    type Error = (f64, &'static str);
+/// Compare two string values as semantic versions, instead of lexicographically.
+///
+/// For example, `"1.10.0"` is greater than `"1.9.0"`, and a pre-release like `"1.0.0-rc.1"`
+/// is less than `"1.0.0"`. Returns `None` if either value isn't a string holding
+/// a valid semantic version.
+///
+/// Filters in queries compare strings lexicographically. Adapters whose properties
+/// hold versions can use this to evaluate comparison filters on those properties,
+/// e.g. when pushing them down to their backend.
+#[cfg(feature = "semver")]
+pub fn semver_compare(a: &FieldValue, b: &FieldValue) -> Option<Ordering> {
+    Some(a.as_semver()?.cmp(&b.as_semver()?))
+}
+
+/// Decode an IP address from the fixed-width representation used by [`FieldValue::from`].
+fn decode_ip(value: &str) -> Option<IpAddr> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 39 {
+        return None;
+    }
+
+    let mut segments = [0u16; 8];
+    for (segment, chunk) in segments.iter_mut().zip(bytes.chunks(5)) {
+        let (digits, separator) = chunk.split_at(4);
+        if !matches!(separator, [] | [b':']) {
+            return None;
+        }
+        for digit in digits {
+            let digit = match digit {
+                b'0'..=b'9' => digit - b'0',
+                b'a'..=b'f' => digit - b'a' + 10,
+                _ => return None,
+            };
+            *segment = (*segment << 4) | u16::from(digit);
+        }
+    }
+
+    let [a, b, c, d, e, f, g, h] = segments;
+    let ip = Ipv6Addr::new(a, b, c, d, e, f, g, h);
+    Some(match ip.to_ipv4_mapped() {
+        Some(ip) => IpAddr::V4(ip),
+        None => IpAddr::V6(ip),
+    })
+}
+
+/// Equality that ignores which numeric variant represents a number.
+///
+/// `Int64(5)`, `Uint64(5)`, and `Float64(5.0)` are all equal under this comparison,
+/// while `Float64(5.5)` is only equal to itself. Lists are compared element-wise
+/// using the same rule. All other values use ordinary equality.
+///
+/// Meant for comparing values that may have gone through a serialization round-trip,
+/// such as recorded traces, where the numeric variant isn't always preserved.
+///
+/// [`NormalizedValue`] wraps a value to use this equality with hash-based collections.
+pub fn value_eq_normalized(a: &FieldValue, b: &FieldValue) -> bool {
+    match (a, b) {
+        (FieldValue::List(l), FieldValue::List(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| value_eq_normalized(l, r))
+        }
+        _ => match (a.numeric_key(), b.numeric_key()) {
+            (Some(l), Some(r)) => l == r,
+            (None, None) => a == b,
+            _ => false,
+        },
+    }
+}
+
+/// The canonical form of a number, produced by [`FieldValue::numeric_key`].
+///
+/// Integral values are always `Integer`, whichever variant they came from.
+/// Only floats with a fractional part, or too large for `i128`, are `Float`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum NumericKey {
+    Integer(i128),
+    Float(u64),
+}
+
+/// A [`FieldValue`] whose equality and hashing ignore which numeric variant
+/// represents a number, as in [`value_eq_normalized`].
+///
+/// Useful as a key in hash-based collections that should treat e.g. `Int64(5)`
+/// and `Uint64(5)` as the same key.
+#[derive(Debug, Clone)]
+pub struct NormalizedValue(pub FieldValue);
+
+impl PartialEq for NormalizedValue {
+    fn eq(&self, other: &Self) -> bool {
+        value_eq_normalized(&self.0, &other.0)
+    }
+}
+
+impl Eq for NormalizedValue {}
+
+impl Hash for NormalizedValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_normalized(&self.0, state)
+    }
+}
+
+/// Hash a value the way [`NormalizedValue`] does, without needing to own the value.
+///
+/// Values equal under [`value_eq_normalized`] hash the same way. So do values that are
+/// equal as operands of filters, which treat fewer values as equal.
+pub(crate) fn hash_normalized<H: Hasher>(value: &FieldValue, state: &mut H) {
+    match (value, value.numeric_key()) {
+        (_, Some(key)) => key.hash(state),
+        (FieldValue::List(values), None) => {
+            core::mem::discriminant(value).hash(state);
+            values.len().hash(state);
+            for value in values {
+                hash_normalized(value, state);
+            }
+        }
+        (_, None) => value.hash(state),
+    }
+}
+
+/// Compact rendering of the value itself, without the variant name: `5`, `"abc"`, `[1, 2]`.
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Null => write!(f, "null"),
+            FieldValue::Int64(x) => write!(f, "{x}"),
+            FieldValue::Uint64(x) => write!(f, "{x}"),
+            FieldValue::Float64(x) => write!(f, "{x:?}"),
+            FieldValue::String(x) => write!(f, "{x:?}"),
+            FieldValue::Boolean(x) => write!(f, "{x}"),
+            FieldValue::DateTimeUtc(x) => write!(f, "{}", x.to_rfc3339()),
+            FieldValue::Enum(x) => write!(f, "{x}"),
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(x) => write!(f, "{x}"),
+            FieldValue::List(x) => {
+                write!(f, "[")?;
+                for (idx, value) in x.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// A value together with its declared type, for serialization to strongly-typed consumers.
+///
+/// Serializes the same way as [TransparentValue], except that nulls carry their declared type:
+/// they are serialized as a map with a single `null` key whose value is the type's name,
+/// such as `{"null": "Int"}` in JSON. Elements of list values are serialized the same way,
+/// using the list's element type.
+///
+/// Use [IndexedQuery::typed_row](super::indexed::IndexedQuery::typed_row) to pair
+/// each value in a query result row with the declared type of its output.
+#[derive(Debug, Clone, Copy)]
+pub struct TypedValue<'a> {
+    value: &'a FieldValue,
+    value_type: &'a Type,
+}
+
+impl<'a> TypedValue<'a> {
+    pub fn new(value: &'a FieldValue, value_type: &'a Type) -> Self {
+        Self { value, value_type }
+    }
+}
+
+impl Serialize for TypedValue<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self.value, &self.value_type.base) {
+            (FieldValue::Null, _) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("null", &self.value_type.base.to_string())?;
+                map.end()
+            }
+            (FieldValue::List(values), BaseType::List(element_type)) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(&TypedValue::new(value, element_type))?;
+                }
+                seq.end()
+            }
+            _ => TransparentValue::from(self.value.clone()).serialize(serializer),
+        }
+    }
+}
+
+impl AsRef<FieldValue> for FieldValue {
+    fn as_ref(&self) -> &FieldValue {
+        self
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(v: String) -> Self {
+        Self::String(v)
+    }
+}
+
+impl From<&String> for FieldValue {
+    fn from(v: &String) -> Self {
+        Self::String(v.clone())
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(v: &str) -> Self {
+        Self::from(v.to_owned())
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(v: bool) -> Self {
+        Self::Boolean(v)
+    }
+}
+
+/// Represents a finite (non-infinite, not-NaN) [f64] value
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct FiniteF64(f64);
+impl From<FiniteF64> for f64 {
+    fn from(f: FiniteF64) -> f64 {
+        f.0
+    }
+}
+impl From<FiniteF64> for FieldValue {
+    fn from(f: FiniteF64) -> FieldValue {
+        FieldValue::Float64(f.0)
+    }
+}
+
+macro_rules! impl_finite_f64_try_from_float {
+    ( $( $Float: ident )+ ) => {
+        $(
+            impl TryFrom<$Float> for FiniteF64 {
+                type Error = ($Float, &'static str);
+
+                fn try_from(v: $Float) -> Result<Self, Self::Error> {
+                    if v.is_finite() {
+                        Ok(Self(v.into()))
+                    } else {
+                        Err((v, "not a finite (non-infinite, not-NaN) value"))
+                    }
+                }
+            }
+        )+
+    }
+}
+
+impl_finite_f64_try_from_float!(f32 f64);
+
+macro_rules! impl_field_value_from_int {
+    ( $( $Int: ident )+ ) => {
+        $(
+            impl From<$Int> for FieldValue {
+                fn from(v: $Int) -> Self {
+                    Self::Int64(v.into())
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! impl_field_value_from_uint {
+    ( $( $Uint: ident )+ ) => {
+        $(
+            impl From<$Uint> for FieldValue {
+                fn from(v: $Uint) -> Self {
+                    Self::Uint64(v.into())
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! impl_field_value_from_nonzero {
+    ( $( $NonZero: ident )+ ) => {
+        $(
+            impl From<$NonZero> for FieldValue {
+                fn from(v: $NonZero) -> Self {
+                    v.get().into()
+                }
+            }
+        )+
+    }
+}
+
+impl_field_value_from_int!(i8 i16 i32 i64);
+impl_field_value_from_uint!(u8 u16 u32 u64);
+impl_field_value_from_nonzero!(NonZeroI8 NonZeroI16 NonZeroI32 NonZeroI64);
+impl_field_value_from_nonzero!(NonZeroU8 NonZeroU16 NonZeroU32 NonZeroU64);
+
+impl From<DateTime<Utc>> for FieldValue {
+    fn from(v: DateTime<Utc>) -> Self {
+        Self::DateTimeUtc(v)
+    }
+}
+
+/// Fails if the time is outside the range of representable datetimes,
+/// like [`FieldValue::from_unix_millis`] does for out-of-range timestamps.
+impl TryFrom<SystemTime> for FieldValue {
+    type Error = (SystemTime, &'static str);
+
+    fn try_from(v: SystemTime) -> Result<Self, Self::Error> {
+        let epoch = DateTime::<Utc>::from(UNIX_EPOCH);
+        let converted = match v.duration_since(UNIX_EPOCH) {
+            Ok(after) => chrono::Duration::from_std(after)
+                .ok()
+                .and_then(|after| epoch.checked_add_signed(after)),
+            Err(before) => chrono::Duration::from_std(before.duration())
+                .ok()
+                .and_then(|before| epoch.checked_sub_signed(before)),
+        };
+        converted
+            .map(Self::DateTimeUtc)
+            .ok_or((v, "outside the range of representable datetimes"))
+    }
+}
+
+/// IP addresses are represented as strings holding the fully-expanded form of their IPv6 address,
+/// such as `"0000:0000:0000:0000:0000:ffff:0a00:0002"` for `10.0.0.2`.
+///
+/// Every address has the same width and uses lowercase hex digits, so the filters
+/// and range hints that compare strings lexicographically order addresses numerically.
+/// IPv4 addresses are stored as their IPv4-mapped IPv6 addresses in `::ffff:0:0/96`.
+/// Use [`FieldValue::as_ip`] to get the address back, and
+/// [`FieldValue::ip_network_bounds`] to filter on the addresses in a network.
+impl From<IpAddr> for FieldValue {
+    fn from(v: IpAddr) -> Self {
+        let ip = match v {
+            IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+            IpAddr::V6(ip) => ip,
+        };
+        let segments: Vec<String> = ip.segments().iter().map(|s| format!("{s:04x}")).collect();
+        Self::String(segments.join(":"))
+    }
+}
+
+impl From<Ipv4Addr> for FieldValue {
+    fn from(v: Ipv4Addr) -> Self {
+        IpAddr::V4(v).into()
+    }
+}
+
+impl From<Ipv6Addr> for FieldValue {
+    fn from(v: Ipv6Addr) -> Self {
+        IpAddr::V6(v).into()
+    }
+}
+
+/// UUIDs are represented as strings in their lowercase hyphenated form.
+///
+/// In that form, string ordering matches the ordering of the UUIDs' bytes.
+#[cfg(feature = "uuid")]
+impl From<uuid::Uuid> for FieldValue {
+    fn from(v: uuid::Uuid) -> Self {
+        Self::String(v.hyphenated().to_string())
+    }
+}
+
+impl TryFrom<Option<f32>> for FieldValue {
+    type Error = (f32, &'static str);
+
+    fn try_from(value: Option<f32>) -> Result<Self, Self::Error> {
+        match value {
+            None => Ok(FieldValue::Null),
+            Some(v) => {
+                let finite_f64 = FiniteF64::try_from(v);
+                finite_f64.map(|x| x.into())
+            }
+        }
+    }
+}
+
+impl TryFrom<Option<f64>> for FieldValue {
+    type Error = (f64, &'static str);
 
     fn try_from(value: Option<f64>) -> Result<Self, Self::Error> {
         match value {
@@ -315,6 +1410,160 @@ impl<T: Into<FieldValue>> From<Vec<T>> for FieldValue {
     }
 }
 
+/// Error returned when a [FieldValue] can't be converted into the requested Rust type.
+///
+/// This happens when the value is of a different variant than the type requires,
+/// such as converting a `String` value into `u64`, or when an integer value
+/// is out of range for the requested integer type.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Cannot convert FieldValue::{actual_variant} value into {target_type}.")]
+pub struct FieldValueConversionError {
+    target_type: &'static str,
+    actual_variant: &'static str,
+}
+
+impl FieldValueConversionError {
+    fn new(target_type: &'static str, value: &FieldValue) -> Self {
+        Self {
+            target_type,
+            actual_variant: value.variant_name(),
+        }
+    }
+
+    /// The name of the Rust type the value was being converted into.
+    pub fn target_type(&self) -> &'static str {
+        self.target_type
+    }
+
+    /// The name of the [FieldValue] variant that couldn't be converted, such as `"Null"`.
+    pub fn actual_variant(&self) -> &'static str {
+        self.actual_variant
+    }
+}
+
+macro_rules! impl_try_from_field_value {
+    ( $( $Target: ty => $accessor: ident ),+ $(,)? ) => {
+        $(
+            impl TryFrom<&FieldValue> for $Target {
+                type Error = FieldValueConversionError;
+
+                fn try_from(value: &FieldValue) -> Result<Self, Self::Error> {
+                    value
+                        .$accessor()
+                        .ok_or_else(|| FieldValueConversionError::new(stringify!($Target), value))
+                }
+            }
+
+            impl TryFrom<FieldValue> for $Target {
+                type Error = FieldValueConversionError;
+
+                fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+                    Self::try_from(&value)
+                }
+            }
+        )+
+    }
+}
+
+impl_try_from_field_value!(i64 => as_i64, u64 => as_u64, usize => as_usize, bool => as_bool);
+
+impl TryFrom<&FieldValue> for String {
+    type Error = FieldValueConversionError;
+
+    fn try_from(value: &FieldValue) -> Result<Self, Self::Error> {
+        value
+            .as_str()
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| FieldValueConversionError::new("String", value))
+    }
+}
+
+impl TryFrom<FieldValue> for String {
+    type Error = FieldValueConversionError;
+
+    fn try_from(value: FieldValue) -> Result<Self, Self::Error> {
+        match value {
+            FieldValue::String(s) => Ok(s),
+            _ => Err(FieldValueConversionError::new("String", &value)),
+        }
+    }
+}
+
+/// Whether range bound `lower` is before `upper`, or also equal to it if `inclusive`.
+///
+/// Null bounds are unbounded, so they are before all other values.
+/// Values that can't be compared are never before each other.
+fn bound_before(lower: &FieldValue, upper: &FieldValue, inclusive: bool) -> bool {
+    if matches!(lower, FieldValue::Null) || matches!(upper, FieldValue::Null) {
+        return true;
+    }
+
+    let ordering = match (lower, upper) {
+        (FieldValue::String(l), FieldValue::String(r)) => Some(l.cmp(r)),
+        (FieldValue::DateTimeUtc(l), FieldValue::DateTimeUtc(r)) => Some(l.cmp(r)),
+        _ => match (lower.numeric_key(), upper.numeric_key()) {
+            (Some(NumericKey::Integer(l)), Some(NumericKey::Integer(r))) => Some(l.cmp(&r)),
+            (Some(_), Some(_)) => lower
+                .as_f64_lossy()
+                .zip(upper.as_f64_lossy())
+                .and_then(|(l, r)| l.partial_cmp(&r)),
+            _ => None,
+        },
+    };
+    match ordering {
+        Some(Ordering::Less) => true,
+        Some(Ordering::Equal) => inclusive,
+        Some(Ordering::Greater) | None => false,
+    }
+}
+
+/// Parses a string as a numeric [FieldValue], preferring the integer variants.
+///
+/// Returns `None` if the string isn't a number, or is a float that isn't finite.
+fn parse_number(s: &str) -> Option<FieldValue> {
+    if let Ok(i) = s.parse::<i64>() {
+        Some(FieldValue::Int64(i))
+    } else if let Ok(u) = s.parse::<u64>() {
+        Some(FieldValue::Uint64(u))
+    } else {
+        s.parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(FieldValue::Float64)
+    }
+}
+
+/// Converts a float to a [FieldValue], rejecting infinities and NaN.
+fn convert_float_to_field_value(f: f64) -> Result<FieldValue, String> {
+    FiniteF64::try_from(f)
+        .map(FieldValue::from)
+        .map_err(|(f, reason)| format!("Float {f} is {reason}"))
+}
+
+/// Deserializes a float that is allowed to be stored in a [FieldValue] or [TransparentValue].
+///
+/// Some serialization formats, such as RON, are able to represent infinities and NaN.
+/// Values containing them are rejected, since they'd break comparisons later.
+fn deserialize_finite_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let f = f64::deserialize(deserializer)?;
+    match convert_float_to_field_value(f) {
+        Ok(_) => Ok(f),
+        Err(message) => Err(serde::de::Error::custom(message)),
+    }
+}
+
+/// Deserialize a JSON object, and nothing else, into a JSON value.
+#[cfg(feature = "serde_json")]
+fn deserialize_json_object<'de, D>(deserializer: D) -> Result<serde_json::Value, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    serde_json::Map::deserialize(deserializer).map(serde_json::Value::Object)
+}
+
 /// Converts a JSON number to a [FieldValue]
 fn convert_number_to_field_value(n: &Number) -> Result<FieldValue, String> {
     // The order here matters!
@@ -325,7 +1574,9 @@ fn convert_number_to_field_value(n: &Number) -> Result<FieldValue, String> {
     } else if let Some(u) = n.as_u64() {
         Ok(FieldValue::Uint64(u))
     } else if let Some(f) = n.as_f64() {
-        Ok(FieldValue::Float64(f))
+        // Numbers can't be non-finite in JSON, but `as_f64()` may still produce
+        // an infinity e.g. if `serde_json`'s `arbitrary_precision` feature is enabled.
+        convert_float_to_field_value(f)
     } else {
         unreachable!()
     }
@@ -365,9 +1616,60 @@ impl TryFrom<ConstValue> for FieldValue {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{FieldValue, FiniteF64};
+/// Convert into a GraphQL value, e.g. for including query results in a GraphQL response.
+///
+/// Numbers keep their exact value, including `Uint64` values too large for `i64`,
+/// though such values are beyond the range of GraphQL's `Int` type.
+/// Datetimes become strings in RFC 3339 format. GraphQL has no datetime type,
+/// so converting the result back into a `FieldValue` produces a `String` value.
+impl From<FieldValue> for ConstValue {
+    fn from(value: FieldValue) -> Self {
+        match value {
+            FieldValue::Null => ConstValue::Null,
+            FieldValue::Int64(x) => ConstValue::Number(x.into()),
+            FieldValue::Uint64(x) => ConstValue::Number(x.into()),
+            FieldValue::Float64(x) => {
+                ConstValue::Number(Number::from_f64(x).expect("FieldValue::Float64 must be finite"))
+            }
+            FieldValue::String(x) => ConstValue::String(x),
+            FieldValue::Boolean(x) => ConstValue::Boolean(x),
+            FieldValue::DateTimeUtc(x) => ConstValue::String(x.to_rfc3339()),
+            FieldValue::Enum(x) => ConstValue::Enum(Name::new(x)),
+            FieldValue::List(x) => ConstValue::List(x.into_iter().map(ConstValue::from).collect()),
+            #[cfg(feature = "serde_json")]
+            FieldValue::Json(x) => {
+                ConstValue::from_json(x).expect("JSON values are valid GraphQL values")
+            }
+        }
+    }
+}
+
+/// Convert into a GraphQL value, in the same way as the conversion from [FieldValue].
+impl From<TransparentValue> for ConstValue {
+    fn from(value: TransparentValue) -> Self {
+        FieldValue::from(value).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        num::{NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64},
+        sync::Arc,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use async_graphql_parser::types::Type;
+    use async_graphql_value::{ConstValue, Name, Number, Value};
+
+    use crate::{ir::indexed::IndexedQuery, numbers_interpreter::parse_numbers_query};
+
+    use super::{
+        convert_float_to_field_value, value_eq_normalized, FieldValue, FieldValueConversionError,
+        FiniteF64, NormalizedValue, TransparentValue, TypedValue,
+    };
 
     #[test]
     fn test_field_value_into() {
@@ -376,6 +1678,17 @@ mod tests {
             (123u64.into(), FieldValue::Uint64(123)),
             (Option::<i64>::Some(123i64).into(), FieldValue::Int64(123)),
             (Option::<u64>::Some(123u64).into(), FieldValue::Uint64(123)),
+            (
+                NonZeroI64::new(-123).unwrap().into(),
+                FieldValue::Int64(-123),
+            ),
+            (
+                NonZeroU64::new(123).unwrap().into(),
+                FieldValue::Uint64(123),
+            ),
+            (NonZeroI8::new(12).unwrap().into(), FieldValue::Int64(12)),
+            (NonZeroU16::new(12).unwrap().into(), FieldValue::Uint64(12)),
+            (Option::<NonZeroU32>::None.into(), FieldValue::Null),
             (
                 FiniteF64::try_from(3.15).unwrap().into(),
                 FieldValue::Float64(3.15),
@@ -401,4 +1714,1251 @@ mod tests {
             assert_eq!(actual_value, expected_value);
         }
     }
+
+    #[test]
+    fn test_as_f64() {
+        assert_eq!(Some(1.5), FieldValue::Float64(1.5).as_f64());
+        assert_eq!(Some(-3.0), FieldValue::Int64(-3).as_f64());
+        assert_eq!(Some(3.0), FieldValue::Uint64(3).as_f64());
+        assert_eq!(None, FieldValue::String("1.5".into()).as_f64());
+        assert_eq!(None, FieldValue::Null.as_f64());
+
+        // Every integer up to 2^53 is exactly representable, but 2^53 + 1 is not.
+        let limit = 1i64 << 53;
+        assert_eq!(Some(limit as f64), FieldValue::Int64(limit).as_f64());
+        assert_eq!(Some(-limit as f64), FieldValue::Int64(-limit).as_f64());
+        assert_eq!(None, FieldValue::Int64(limit + 1).as_f64());
+        assert_eq!(None, FieldValue::Int64(-limit - 1).as_f64());
+        assert_eq!(None, FieldValue::Uint64(limit as u64 + 1).as_f64());
+        assert_eq!(
+            Some((limit + 2) as f64),
+            FieldValue::Int64(limit + 2).as_f64()
+        );
+
+        // The extreme values round to powers of two that don't fit back in the integer type.
+        assert_eq!(Some(i64::MIN as f64), FieldValue::Int64(i64::MIN).as_f64());
+        assert_eq!(None, FieldValue::Int64(i64::MAX).as_f64());
+        assert_eq!(None, FieldValue::Uint64(u64::MAX).as_f64());
+
+        assert_eq!(
+            Some(2.5),
+            FieldValue::Float64(2.5).as_finite_f64().map(f64::from)
+        );
+        assert_eq!(
+            Some(limit as f64),
+            FieldValue::Uint64(limit as u64)
+                .as_finite_f64()
+                .map(f64::from)
+        );
+        assert_eq!(None, FieldValue::Float64(f64::NAN).as_finite_f64());
+        assert_eq!(None, FieldValue::Float64(f64::INFINITY).as_finite_f64());
+        assert_eq!(None, FieldValue::Int64(limit + 1).as_finite_f64());
+    }
+
+    #[test]
+    fn test_as_bool_lenient() {
+        let test_data: Vec<(FieldValue, Option<bool>)> = vec![
+            (FieldValue::Boolean(true), Some(true)),
+            (FieldValue::Boolean(false), Some(false)),
+            (FieldValue::Int64(1), Some(true)),
+            (FieldValue::Int64(0), Some(false)),
+            (FieldValue::Uint64(1), Some(true)),
+            (FieldValue::Uint64(0), Some(false)),
+            (FieldValue::String("true".to_string()), Some(true)),
+            (FieldValue::String("false".to_string()), Some(false)),
+            (FieldValue::Int64(2), None),
+            (FieldValue::Int64(-1), None),
+            (FieldValue::String("TRUE".to_string()), None),
+            (FieldValue::String("1".to_string()), None),
+            (FieldValue::Float64(1.0), None),
+            (FieldValue::Null, None),
+        ];
+
+        for (value, expected) in test_data {
+            assert_eq!(expected, value.as_bool_lenient(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_enum_eq_str() {
+        let enum_value = FieldValue::Enum("RED".to_string());
+        let string_value = FieldValue::String("RED".to_string());
+        assert_ne!(enum_value, string_value);
+        assert!(enum_value.enum_eq_str(&string_value));
+        assert!(string_value.enum_eq_str(&enum_value));
+        assert!(!enum_value.enum_eq_str(&FieldValue::String("red".to_string())));
+
+        let enum_list = FieldValue::List(vec![enum_value.clone(), FieldValue::Int64(1)]);
+        let string_list = FieldValue::List(vec![string_value.clone(), FieldValue::Uint64(1)]);
+        assert!(!enum_list.enum_eq_str(&string_list));
+        let string_list = FieldValue::List(vec![string_value, FieldValue::Int64(1)]);
+        assert!(enum_list.enum_eq_str(&string_list));
+    }
+
+    #[test]
+    fn test_lenient_eq() {
+        let test_data: Vec<(FieldValue, FieldValue, bool)> = vec![
+            (FieldValue::String("5".into()), FieldValue::Int64(5), true),
+            (FieldValue::String("5".into()), FieldValue::Uint64(5), true),
+            (FieldValue::String("5.0".into()), FieldValue::Int64(5), true),
+            (
+                FieldValue::String("5.5".into()),
+                FieldValue::Float64(5.5),
+                true,
+            ),
+            (FieldValue::String("-3".into()), FieldValue::Int64(-3), true),
+            (
+                FieldValue::String("18446744073709551615".into()),
+                FieldValue::Uint64(u64::MAX),
+                true,
+            ),
+            (FieldValue::String("5".into()), FieldValue::Int64(6), false),
+            (FieldValue::String(" 5".into()), FieldValue::Int64(5), false),
+            (
+                FieldValue::String("five".into()),
+                FieldValue::Int64(5),
+                false,
+            ),
+            (
+                FieldValue::String("inf".into()),
+                FieldValue::Float64(1.0),
+                false,
+            ),
+            (
+                FieldValue::String("5".into()),
+                FieldValue::String("5.0".into()),
+                false,
+            ),
+            (
+                FieldValue::String("5".into()),
+                FieldValue::Enum("5".into()),
+                false,
+            ),
+            (
+                FieldValue::String("1".into()),
+                FieldValue::Boolean(true),
+                false,
+            ),
+            (FieldValue::String("5".into()), FieldValue::Null, false),
+            (FieldValue::Int64(5), FieldValue::Uint64(5), true),
+            (FieldValue::Int64(-1), FieldValue::Uint64(u64::MAX), false),
+            (
+                FieldValue::List(vec![FieldValue::Int64(1)]),
+                FieldValue::List(vec![FieldValue::Uint64(1)]),
+                true,
+            ),
+            (
+                FieldValue::List(vec![FieldValue::String("1".into()), FieldValue::Int64(2)]),
+                FieldValue::List(vec![FieldValue::Int64(1), FieldValue::String("2".into())]),
+                true,
+            ),
+            (FieldValue::Null, FieldValue::Null, true),
+        ];
+
+        for (left, right, expected) in test_data {
+            assert_eq!(expected, left.lenient_eq(&right), "{left:?} {right:?}");
+            assert_eq!(expected, right.lenient_eq(&left), "{right:?} {left:?}");
+        }
+    }
+
+    #[test]
+    fn test_set_eq() {
+        let list = |values: Vec<FieldValue>| FieldValue::List(values);
+        let test_data: Vec<(FieldValue, FieldValue, bool)> = vec![
+            (vec![1i64, 2, 2].into(), vec![2i64, 1, 2].into(), true),
+            (vec![1i64, 2, 2].into(), vec![1i64, 1, 2].into(), false),
+            (vec![1i64, 2].into(), vec![2i64, 1, 1].into(), false),
+            (list(vec![]), list(vec![]), true),
+            (
+                list(vec![vec![1i64, 2].into(), vec![3i64].into()]),
+                list(vec![vec![3i64].into(), vec![2i64, 1].into()]),
+                true,
+            ),
+            (
+                list(vec![vec![1i64, 2].into(), vec![3i64].into()]),
+                list(vec![vec![1i64].into(), vec![2i64, 3].into()]),
+                false,
+            ),
+            (
+                list(vec![FieldValue::Null, "a".into(), FieldValue::Int64(1)]),
+                list(vec![FieldValue::Int64(1), FieldValue::Null, "a".into()]),
+                true,
+            ),
+            (
+                list(vec![FieldValue::Int64(1)]),
+                list(vec![FieldValue::Uint64(1)]),
+                false,
+            ),
+            (FieldValue::Int64(1), FieldValue::Int64(1), true),
+            (
+                FieldValue::Int64(1),
+                list(vec![FieldValue::Int64(1)]),
+                false,
+            ),
+        ];
+
+        for (left, right, expected) in test_data {
+            assert_eq!(expected, left.set_eq(&right), "{left:?} {right:?}");
+            assert_eq!(expected, right.set_eq(&left), "{right:?} {left:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_truthy() {
+        let test_data: Vec<(FieldValue, bool)> = vec![
+            (FieldValue::Null, false),
+            (FieldValue::Boolean(true), true),
+            (FieldValue::Boolean(false), false),
+            (FieldValue::Int64(0), false),
+            (FieldValue::Int64(-1), true),
+            (FieldValue::Uint64(0), false),
+            (FieldValue::Uint64(u64::MAX), true),
+            (FieldValue::Float64(0.0), false),
+            (FieldValue::Float64(-0.0), false),
+            (FieldValue::Float64(0.5), true),
+            (FieldValue::String("".to_string()), false),
+            (FieldValue::String("false".to_string()), true),
+            (FieldValue::Enum("".to_string()), true),
+            (FieldValue::DateTimeUtc(Default::default()), true),
+            (FieldValue::List(vec![]), false),
+            (FieldValue::List(vec![FieldValue::Null]), true),
+        ];
+
+        for (value, expected) in test_data {
+            assert_eq!(expected, value.is_truthy(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let test_data: Vec<(FieldValue, FieldValue, bool)> = vec![
+            (FieldValue::Float64(1.0), FieldValue::Float64(1.0005), true),
+            (FieldValue::Float64(1.0), FieldValue::Float64(1.002), false),
+            (FieldValue::Float64(2.0), FieldValue::Int64(2), true),
+            (FieldValue::Uint64(2), FieldValue::Float64(2.0009), true),
+            (FieldValue::Int64(2), FieldValue::Int64(2), true),
+            (FieldValue::Int64(2), FieldValue::Int64(3), false),
+            (
+                FieldValue::Float64(2.0),
+                FieldValue::String("2".to_string()),
+                false,
+            ),
+            (FieldValue::Float64(2.0), FieldValue::Null, false),
+            (
+                FieldValue::List(vec![FieldValue::Float64(1.0), FieldValue::Int64(5)]),
+                FieldValue::List(vec![FieldValue::Float64(1.0001), FieldValue::Int64(5)]),
+                true,
+            ),
+            (
+                FieldValue::List(vec![FieldValue::Float64(1.0)]),
+                FieldValue::List(vec![FieldValue::Float64(1.0), FieldValue::Float64(1.0)]),
+                false,
+            ),
+            (FieldValue::Null, FieldValue::Null, true),
+        ];
+
+        for (left, right, expected) in test_data {
+            assert_eq!(expected, left.approx_eq(&right, 1e-3), "{left:?} {right:?}");
+            assert_eq!(expected, right.approx_eq(&left, 1e-3), "{right:?} {left:?}");
+        }
+    }
+
+    #[test]
+    fn test_typed_value_serialization() {
+        let nullable_int = Type::new("Int").unwrap();
+        let list_of_strings = Type::new("[String]!").unwrap();
+
+        let test_data = vec![
+            (FieldValue::Null, &nullable_int, r#"{"null":"Int"}"#),
+            (FieldValue::Int64(5), &nullable_int, "5"),
+            (FieldValue::Null, &list_of_strings, r#"{"null":"[String]"}"#),
+            (
+                vec![Some("a"), None].into(),
+                &list_of_strings,
+                r#"["a",{"null":"String"}]"#,
+            ),
+        ];
+
+        for (value, value_type, expected) in test_data {
+            let actual = serde_json::to_string(&TypedValue::new(&value, value_type)).unwrap();
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn test_typed_result_row() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Zero {
+        value @output
+        predecessor @optional {
+            predecessor: value @output
+        }
+    }
+}"#,
+        );
+        let row = BTreeMap::from([
+            (Arc::from("value"), FieldValue::Int64(0)),
+            (Arc::from("predecessor"), FieldValue::Null),
+        ]);
+
+        let actual = serde_json::to_string(&query.typed_row(&row).unwrap()).unwrap();
+        assert_eq!(r#"{"predecessor":{"null":"Int"},"value":0}"#, actual);
+
+        // Rows with values that aren't outputs of the query can't be typed.
+        let mut unknown_row = row.clone();
+        unknown_row.insert(Arc::from("name"), FieldValue::from("zero"));
+        assert!(query.typed_row(&unknown_row).is_none());
+    }
+
+    #[test]
+    fn test_ordered_result_row() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Zero {
+        value @output
+        name @output
+        successor @recurse(depth: 1, depth_output: "depth") {
+            successor: value @output
+        }
+        predecessor @fold @transform(op: "count") @output(name: "count")
+    }
+}"#,
+        );
+        assert_eq!(
+            vec!["value", "name", "successor", "count", "depth"],
+            query
+                .output_order
+                .iter()
+                .map(|name| name.as_ref())
+                .collect::<Vec<_>>()
+        );
+
+        let row = BTreeMap::from([
+            (Arc::from("value"), FieldValue::Int64(0)),
+            (Arc::from("name"), FieldValue::from("zero")),
+            (Arc::from("successor"), FieldValue::Int64(1)),
+            (Arc::from("count"), FieldValue::Uint64(0)),
+            (Arc::from("depth"), FieldValue::Uint64(1)),
+        ]);
+        let actual = serde_json::to_string(&query.ordered_row(&row)).unwrap();
+        assert_eq!(
+            r#"{"value":{"Int64":0},"name":{"String":"zero"},"successor":{"Int64":1},"#.to_owned()
+                + r#""count":{"Uint64":0},"depth":{"Uint64":1}}"#,
+            actual
+        );
+
+        // Without the query text, outputs are ordered by vertex and then by name.
+        let from_ir = IndexedQuery::try_from(query.ir_query.clone()).unwrap();
+        assert_eq!(
+            vec!["name", "value", "depth", "successor", "count"],
+            from_ir
+                .output_order
+                .iter()
+                .map(|name| name.as_ref())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_normalize_numeric() {
+        let test_data: Vec<(FieldValue, FieldValue)> = vec![
+            (FieldValue::Float64(1e10), FieldValue::Int64(10_000_000_000)),
+            (FieldValue::Float64(-3.0), FieldValue::Int64(-3)),
+            (FieldValue::Float64(-0.0), FieldValue::Int64(0)),
+            (FieldValue::Float64(1.5), FieldValue::Float64(1.5)),
+            (
+                FieldValue::Float64(-9223372036854775808.0),
+                FieldValue::Int64(i64::MIN),
+            ),
+            (
+                FieldValue::Float64(9223372036854775808.0),
+                FieldValue::Uint64(1 << 63),
+            ),
+            (FieldValue::Float64(1e20), FieldValue::Float64(1e20)),
+            (FieldValue::Float64(-1e19), FieldValue::Float64(-1e19)),
+            (FieldValue::Uint64(5), FieldValue::Uint64(5)),
+            (
+                FieldValue::String("1e10".to_string()),
+                FieldValue::String("1e10".to_string()),
+            ),
+            (
+                vec![FieldValue::Float64(2.0), FieldValue::Float64(2.5)].into(),
+                vec![FieldValue::Int64(2), FieldValue::Float64(2.5)].into(),
+            ),
+        ];
+
+        for (value, expected) in test_data {
+            assert_eq!(expected, value.clone().normalize_numeric(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_time_conversions() {
+        let expected = FieldValue::DateTimeUtc("2023-01-02T03:04:05.678Z".parse().unwrap());
+
+        assert_eq!(
+            Some(expected.clone()),
+            FieldValue::from_unix_millis(1672628645678)
+        );
+        assert_eq!(
+            Ok(expected.clone()),
+            FieldValue::try_from(UNIX_EPOCH + Duration::from_millis(1672628645678))
+        );
+        assert_eq!(
+            Ok(FieldValue::DateTimeUtc(
+                "1969-12-31T23:59:59.999999999Z".parse().unwrap()
+            )),
+            FieldValue::try_from(UNIX_EPOCH - Duration::from_nanos(1))
+        );
+        let far_future = UNIX_EPOCH + Duration::from_secs(1 << 50);
+        assert_eq!(
+            Err((far_future, "outside the range of representable datetimes")),
+            FieldValue::try_from(far_future)
+        );
+        assert_eq!(
+            Some(FieldValue::DateTimeUtc(
+                "1969-12-31T23:59:59.999Z".parse().unwrap()
+            )),
+            FieldValue::from_unix_millis(-1)
+        );
+        assert_eq!(None, FieldValue::from_unix_millis(i64::MAX));
+
+        let now = SystemTime::now();
+        let millis = now.duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+        let Ok(FieldValue::DateTimeUtc(converted)) = FieldValue::try_from(now) else {
+            unreachable!()
+        };
+        assert_eq!(millis, converted.timestamp_millis());
+
+        assert_eq!(
+            Some(FieldValue::DateTimeUtc(
+                "2023-01-02T03:04:05Z".parse().unwrap()
+            )),
+            FieldValue::from_unix_seconds(1672628645)
+        );
+        assert_eq!(None, FieldValue::from_unix_seconds(i64::MAX));
+
+        assert_eq!(Some(1672628645), expected.as_unix_seconds());
+        assert_eq!(Some(1672628645678), expected.as_unix_millis());
+        assert_eq!(
+            Some(1672628645),
+            FieldValue::Int64(1672628645).as_unix_seconds()
+        );
+        assert_eq!(
+            Some(1672628645),
+            FieldValue::Uint64(1672628645).as_unix_millis()
+        );
+        assert_eq!(None, FieldValue::Uint64(u64::MAX).as_unix_seconds());
+        assert_eq!(None, FieldValue::String("2023".into()).as_unix_seconds());
+        assert_eq!(None, FieldValue::Null.as_unix_millis());
+    }
+
+    #[test]
+    fn test_value_eq_normalized() {
+        let test_data: Vec<(FieldValue, FieldValue, bool)> = vec![
+            (FieldValue::Int64(5), FieldValue::Uint64(5), true),
+            (FieldValue::Int64(5), FieldValue::Float64(5.0), true),
+            (FieldValue::Uint64(5), FieldValue::Float64(5.0), true),
+            (FieldValue::Int64(5), FieldValue::Float64(5.5), false),
+            (FieldValue::Float64(5.5), FieldValue::Float64(5.5), true),
+            (FieldValue::Int64(-1), FieldValue::Uint64(u64::MAX), false),
+            (
+                FieldValue::Uint64(u64::MAX),
+                FieldValue::Uint64(u64::MAX),
+                true,
+            ),
+            (FieldValue::Int64(0), FieldValue::Null, false),
+            (FieldValue::Int64(1), FieldValue::Boolean(true), false),
+            (
+                FieldValue::String("5".to_string()),
+                FieldValue::String("5".to_string()),
+                true,
+            ),
+            (
+                vec![FieldValue::Int64(1), FieldValue::Uint64(2)].into(),
+                vec![FieldValue::Uint64(1), FieldValue::Float64(2.0)].into(),
+                true,
+            ),
+            (
+                vec![FieldValue::Int64(1)].into(),
+                vec![FieldValue::Int64(1), FieldValue::Int64(2)].into(),
+                false,
+            ),
+        ];
+
+        for (left, right, expected) in test_data {
+            assert_eq!(
+                expected,
+                value_eq_normalized(&left, &right),
+                "{left:?} {right:?}"
+            );
+            assert_eq!(
+                expected,
+                value_eq_normalized(&right, &left),
+                "{right:?} {left:?}"
+            );
+        }
+    }
+
+    /// Numbers of every variant, with many of them equal to each other across variants.
+    fn random_numbers(count: usize) -> Vec<FieldValue> {
+        // xorshift64: deterministic, and good enough for picking test cases.
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let interesting: [i128; 10] = [
+            0,
+            1,
+            -1,
+            1 << 53,
+            (1 << 53) + 1,
+            i64::MAX as i128,
+            i64::MIN as i128,
+            1 << 63,
+            u64::MAX as i128,
+            1 << 64,
+        ];
+        (0..count)
+            .map(|_| {
+                let base = match next() % 3 {
+                    0 => interesting[(next() % interesting.len() as u64) as usize],
+                    _ => (next() % 7) as i128 - 3,
+                };
+                match next() % 4 {
+                    0 => i64::try_from(base).map_or(FieldValue::Null, FieldValue::Int64),
+                    1 => u64::try_from(base).map_or(FieldValue::Null, FieldValue::Uint64),
+                    2 => FieldValue::Float64(base as f64),
+                    _ => FieldValue::Float64(base as f64 + 0.5),
+                }
+            })
+            .filter(|value| !matches!(value, FieldValue::Null))
+            .collect()
+    }
+
+    #[test]
+    fn value_eq_normalized_is_transitive_and_consistent_with_hash() {
+        use std::{
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+
+        let hash = |value: &FieldValue| {
+            let mut hasher = DefaultHasher::new();
+            NormalizedValue(value.clone()).hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let numbers = random_numbers(60);
+        let mut equal_pairs = 0;
+        for a in &numbers {
+            for b in &numbers {
+                let a_eq_b = value_eq_normalized(a, b);
+                assert_eq!(a_eq_b, value_eq_normalized(b, a), "{a:?} {b:?}");
+                if !a_eq_b {
+                    continue;
+                }
+
+                equal_pairs += 1;
+                assert_eq!(hash(a), hash(b), "{a:?} {b:?}");
+                for c in &numbers {
+                    if value_eq_normalized(b, c) {
+                        assert!(value_eq_normalized(a, c), "{a:?} {b:?} {c:?}");
+                    }
+                }
+            }
+        }
+
+        // Make sure the test data actually exercises cross-variant equality.
+        assert!(equal_pairs > numbers.len() * 2, "{equal_pairs}");
+    }
+
+    #[test]
+    fn test_ip_round_trip() {
+        let test_data: Vec<IpAddr> = vec![
+            Ipv4Addr::new(10, 0, 0, 1).into(),
+            Ipv6Addr::LOCALHOST.into(),
+            "2001:db8::ff00:42:8329".parse().unwrap(),
+        ];
+
+        for ip in test_data {
+            let value = FieldValue::from(ip);
+            assert_eq!(Some(ip), value.as_ip());
+        }
+        assert_eq!(
+            FieldValue::String("0000:0000:0000:0000:0000:ffff:0a00:0001".into()),
+            FieldValue::from(Ipv4Addr::new(10, 0, 0, 1)),
+        );
+
+        // Addresses in other formats are parsed too.
+        assert_eq!(
+            Some(IpAddr::from(Ipv4Addr::new(10, 0, 0, 1))),
+            FieldValue::String("10.0.0.1".into()).as_ip()
+        );
+        assert_eq!(
+            Some(IpAddr::from(Ipv6Addr::LOCALHOST)),
+            FieldValue::String("::1".into()).as_ip()
+        );
+
+        assert_eq!(None, FieldValue::String("10.0.0.256".into()).as_ip());
+        assert_eq!(None, FieldValue::Int64(167772161).as_ip());
+    }
+
+    #[test]
+    fn test_ip_ordering() {
+        let ips: Vec<IpAddr> = vec![
+            Ipv6Addr::UNSPECIFIED.into(),
+            Ipv6Addr::LOCALHOST.into(),
+            Ipv4Addr::new(9, 255, 255, 255).into(),
+            Ipv4Addr::new(10, 0, 0, 2).into(),
+            Ipv4Addr::new(10, 0, 0, 10).into(),
+            Ipv4Addr::new(192, 168, 0, 1).into(),
+            "2001:db8::ff00:42:8329".parse().unwrap(),
+            "fe80::1".parse().unwrap(),
+        ];
+        let mut values: Vec<FieldValue> = ips.iter().rev().map(|ip| (*ip).into()).collect();
+        values.sort_by(|a, b| a.as_str().unwrap().cmp(b.as_str().unwrap()));
+        let sorted: Vec<IpAddr> = values.iter().map(|v| v.as_ip().unwrap()).collect();
+        assert_eq!(ips, sorted);
+    }
+
+    #[test]
+    fn test_ip_network_bounds() {
+        let network = FieldValue::ip_network_bounds(Ipv4Addr::new(10, 1, 2, 3).into(), 16);
+        assert_eq!(
+            Some((
+                Ipv4Addr::new(10, 1, 0, 0).into(),
+                Ipv4Addr::new(10, 1, 255, 255).into()
+            )),
+            network
+        );
+
+        let network = FieldValue::ip_network_bounds("2001:db8::1".parse().unwrap(), 32);
+        assert_eq!(
+            Some((
+                "2001:db8::".parse::<Ipv6Addr>().unwrap().into(),
+                "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"
+                    .parse::<Ipv6Addr>()
+                    .unwrap()
+                    .into()
+            )),
+            network
+        );
+
+        let everything = FieldValue::ip_network_bounds(Ipv4Addr::new(10, 1, 2, 3).into(), 0);
+        assert_eq!(
+            Some((Ipv4Addr::UNSPECIFIED.into(), Ipv4Addr::BROADCAST.into())),
+            everything
+        );
+        let single = FieldValue::ip_network_bounds(Ipv4Addr::new(10, 1, 2, 3).into(), 32);
+        assert_eq!(
+            Some((
+                Ipv4Addr::new(10, 1, 2, 3).into(),
+                Ipv4Addr::new(10, 1, 2, 3).into()
+            )),
+            single
+        );
+        assert_eq!(
+            None,
+            FieldValue::ip_network_bounds(Ipv4Addr::new(10, 1, 2, 3).into(), 33)
+        );
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_semver_compare() {
+        use std::cmp::Ordering;
+
+        use super::semver_compare;
+
+        let test_data: Vec<(&str, &str, Option<Ordering>)> = vec![
+            ("1.10.0", "1.9.0", Some(Ordering::Greater)),
+            ("1.0.0-rc.1", "1.0.0", Some(Ordering::Less)),
+            ("1.0.0-alpha.2", "1.0.0-alpha.10", Some(Ordering::Less)),
+            ("0.3.1", "0.3.1", Some(Ordering::Equal)),
+            ("1.0", "1.0.0", None),
+            ("not a version", "1.0.0", None),
+        ];
+        for (left, right, expected) in test_data {
+            assert_eq!(
+                expected,
+                semver_compare(&FieldValue::from(left), &FieldValue::from(right)),
+                "{left} {right}"
+            );
+        }
+
+        assert_eq!(
+            None,
+            semver_compare(&FieldValue::Int64(1), &FieldValue::from("1.0.0"))
+        );
+        assert_eq!(
+            Some(semver::Version::new(1, 2, 3)),
+            FieldValue::from("1.2.3").as_semver()
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_round_trip() {
+        let lower = uuid::Uuid::parse_str("0a1b2c3d-0000-4000-8000-000000000001").unwrap();
+        let higher = uuid::Uuid::parse_str("F0000000-0000-4000-8000-000000000000").unwrap();
+
+        let lower_value = FieldValue::from(lower);
+        let higher_value = FieldValue::from(higher);
+        assert_eq!(Some(lower), lower_value.as_uuid());
+        assert_eq!(Some(higher), higher_value.as_uuid());
+        assert_eq!(
+            lower.cmp(&higher),
+            lower_value
+                .as_str()
+                .unwrap()
+                .cmp(higher_value.as_str().unwrap())
+        );
+        assert_eq!(None, FieldValue::String("not-a-uuid".into()).as_uuid());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_json_values() {
+        let object = FieldValue::Json(serde_json::json!({"a": [1, 2.5, null], "b": {"c": true}}));
+        let reordered =
+            FieldValue::Json(serde_json::json!({"b": {"c": true}, "a": [1, 2.5, null]}));
+        assert_eq!(object, reordered);
+        assert_ne!(
+            FieldValue::Json(serde_json::json!(1)),
+            FieldValue::Json(serde_json::json!(1.0))
+        );
+        assert_ne!(
+            FieldValue::Json(serde_json::json!("x")),
+            FieldValue::String("x".into())
+        );
+
+        // Carried through serialization untouched, and as native JSON when transparent.
+        let transparent = serde_json::to_value(TransparentValue::from(object.clone())).unwrap();
+        assert_eq!(
+            serde_json::json!({"a": [1, 2.5, null], "b": {"c": true}}),
+            transparent
+        );
+        let round_tripped: TransparentValue = serde_json::from_value(transparent).unwrap();
+        assert_eq!(object, FieldValue::from(round_tripped));
+        let round_tripped: FieldValue = ron::from_str(&ron::to_string(&object).unwrap()).unwrap();
+        assert_eq!(object, round_tripped);
+
+        crate::ir::check_field_value_contract(&[
+            object,
+            reordered,
+            FieldValue::Json(serde_json::json!(null)),
+            FieldValue::Json(serde_json::json!(-1)),
+            FieldValue::Json(serde_json::json!(0.0)),
+            FieldValue::Json(serde_json::json!(-0.0)),
+            FieldValue::Json(serde_json::json!(1)),
+            FieldValue::Json(serde_json::json!("1")),
+            FieldValue::Json(serde_json::json!([])),
+            FieldValue::Json(serde_json::json!({"a": 1})),
+            FieldValue::Json(serde_json::json!({"a": 1, "b": 1})),
+        ]);
+    }
+
+    #[cfg(feature = "rmp-serde")]
+    #[test]
+    fn test_msgpack_round_trip() {
+        let test_data = vec![
+            FieldValue::Null,
+            FieldValue::Int64(-3),
+            FieldValue::Uint64(u64::MAX),
+            FieldValue::Float64(2.5),
+            FieldValue::String("text".to_string()),
+            FieldValue::Boolean(true),
+            FieldValue::DateTimeUtc("2023-01-02T03:04:05Z".parse().unwrap()),
+            FieldValue::Enum("VARIANT".to_string()),
+            FieldValue::List(vec![FieldValue::Int64(1), FieldValue::Null]),
+        ];
+
+        for value in test_data {
+            let encoded = value.to_msgpack().unwrap();
+            assert_eq!(value, FieldValue::from_msgpack(&encoded).unwrap());
+        }
+
+        // Integer variants must not be conflated, even though MessagePack
+        // would encode a small Uint64 and Int64 the same way if left untagged.
+        let encoded = FieldValue::Uint64(5).to_msgpack().unwrap();
+        assert!(matches!(
+            FieldValue::from_msgpack(&encoded).unwrap(),
+            FieldValue::Uint64(5)
+        ));
+    }
+
+    #[test]
+    fn const_value_conversion() {
+        let datetime = "2023-01-02T03:04:05Z".parse().unwrap();
+        let test_data = vec![
+            (FieldValue::Null, ConstValue::Null),
+            (FieldValue::Int64(-3), ConstValue::Number((-3i64).into())),
+            (
+                FieldValue::Uint64(u64::MAX),
+                ConstValue::Number(u64::MAX.into()),
+            ),
+            (
+                FieldValue::Float64(1.5),
+                ConstValue::Number(Number::from_f64(1.5).unwrap()),
+            ),
+            ("text".into(), ConstValue::String("text".into())),
+            (true.into(), ConstValue::Boolean(true)),
+            (
+                FieldValue::DateTimeUtc(datetime),
+                ConstValue::String("2023-01-02T03:04:05+00:00".into()),
+            ),
+            (
+                FieldValue::Enum("RED".into()),
+                ConstValue::Enum(Name::new("RED")),
+            ),
+            (
+                FieldValue::List(vec![1i64.into(), FieldValue::Null]),
+                ConstValue::List(vec![ConstValue::Number(1i64.into()), ConstValue::Null]),
+            ),
+        ];
+
+        for (value, expected) in test_data {
+            assert_eq!(expected, ConstValue::from(value.clone()), "{value:?}");
+            assert_eq!(
+                expected,
+                ConstValue::from(TransparentValue::from(value.clone())),
+                "{value:?}"
+            );
+
+            // All values except datetimes convert back into the original value.
+            if !matches!(value, FieldValue::DateTimeUtc(_)) {
+                assert_eq!(
+                    Ok(value.clone()),
+                    FieldValue::try_from(expected),
+                    "{value:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn non_finite_floats_are_rejected() {
+        for f in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+            let err = convert_float_to_field_value(f).unwrap_err();
+            assert!(err.contains("not a finite"), "{err}");
+        }
+        assert_eq!(
+            Ok(FieldValue::Float64(f64::MAX)),
+            convert_float_to_field_value(f64::MAX)
+        );
+
+        // JSON numbers are always finite, and convert successfully.
+        let value = Value::Number(Number::from_f64(-2.5).unwrap());
+        assert_eq!(Ok(FieldValue::Float64(-2.5)), FieldValue::try_from(value));
+        let value = Value::List(vec![Value::Number(Number::from_f64(f64::MIN).unwrap())]);
+        assert_eq!(
+            Ok(FieldValue::List(vec![FieldValue::Float64(f64::MIN)])),
+            FieldValue::try_from(value)
+        );
+
+        // RON is able to represent non-finite floats, but they must not deserialize.
+        for ron_float in ["inf", "-inf", "NaN"] {
+            let field_value = format!("Float64({ron_float})");
+            let err = ron::from_str::<FieldValue>(&field_value).unwrap_err();
+            assert!(err.to_string().contains("not a finite"), "{err}");
+
+            let nested = format!("List([Int64(1), Float64({ron_float})])");
+            assert!(ron::from_str::<FieldValue>(&nested).is_err());
+
+            assert!(ron::from_str::<TransparentValue>(ron_float).is_err());
+        }
+        assert_eq!(
+            FieldValue::Float64(1.5),
+            ron::from_str::<FieldValue>("Float64(1.5)").unwrap()
+        );
+        assert_eq!(
+            FieldValue::Float64(1.5),
+            ron::from_str::<TransparentValue>("1.5").unwrap().into()
+        );
+    }
+
+    #[test]
+    fn integer_boundaries_round_trip_through_ron() {
+        let values = [
+            FieldValue::Int64(i64::MIN),
+            FieldValue::Int64(-1),
+            FieldValue::Int64(0),
+            FieldValue::Int64(i64::MAX),
+            FieldValue::Uint64(0),
+            FieldValue::Uint64(i64::MAX as u64),
+            FieldValue::Uint64(i64::MAX as u64 + 1),
+            FieldValue::Uint64(u64::MAX),
+        ];
+        for value in values
+            .iter()
+            .cloned()
+            .chain([FieldValue::List(values.to_vec())])
+        {
+            let serialized = ron::to_string(&value).unwrap();
+            let round_tripped: FieldValue = ron::from_str(&serialized).unwrap();
+            assert_eq!(value, round_tripped, "{serialized}");
+        }
+
+        // Untagged values become the first integer variant able to represent them exactly.
+        for (ron_value, expected) in [
+            ("-9223372036854775808", FieldValue::Int64(i64::MIN)),
+            ("9223372036854775807", FieldValue::Int64(i64::MAX)),
+            (
+                "9223372036854775808",
+                FieldValue::Uint64(i64::MAX as u64 + 1),
+            ),
+            ("18446744073709551615", FieldValue::Uint64(u64::MAX)),
+        ] {
+            let value: FieldValue = ron::from_str::<TransparentValue>(ron_value).unwrap().into();
+            assert_eq!(format!("{expected:?}"), format!("{value:?}"), "{ron_value}");
+        }
+    }
+
+    #[test]
+    fn map_deep_is_bottom_up() {
+        let value = FieldValue::List(vec![
+            "secret".into(),
+            FieldValue::Null,
+            FieldValue::List(vec!["hidden".into(), 3.into()]),
+        ]);
+
+        let mut visited = vec![];
+        let redacted = value.map_deep(&mut |value| {
+            visited.push(value.clone());
+            match value {
+                FieldValue::String(_) => "<redacted>".into(),
+                FieldValue::List(values) => FieldValue::List(
+                    values
+                        .into_iter()
+                        .filter(|value| value != &FieldValue::Null)
+                        .collect(),
+                ),
+                _ => value,
+            }
+        });
+
+        assert_eq!(
+            FieldValue::List(vec![
+                "<redacted>".into(),
+                FieldValue::List(vec!["<redacted>".into(), 3.into()]),
+            ]),
+            redacted,
+        );
+
+        // Lists are passed to the transform after their elements have been transformed.
+        assert_eq!(
+            vec![
+                "secret".into(),
+                FieldValue::Null,
+                "hidden".into(),
+                3.into(),
+                FieldValue::List(vec!["<redacted>".into(), 3.into()]),
+                FieldValue::List(vec![
+                    "<redacted>".into(),
+                    FieldValue::Null,
+                    FieldValue::List(vec!["<redacted>".into(), 3.into()]),
+                ]),
+            ],
+            visited,
+        );
+    }
+
+    #[test]
+    fn list_ordering() {
+        let list = |values: &[i64]| FieldValue::from(values.to_vec());
+
+        // Shorter lists are less than lists they are a prefix of.
+        assert!(list(&[]) < list(&[1]));
+        assert!(list(&[1]) < list(&[1, 2]));
+        assert!(list(&[1, 2]) < list(&[1, 2, 0]));
+
+        // Otherwise, the first differing element decides.
+        assert!(list(&[1, 2]) < list(&[2]));
+        assert!(list(&[1, 3]) > list(&[1, 2, 5]));
+        assert_eq!(std::cmp::Ordering::Equal, list(&[1, 2]).cmp(&list(&[1, 2])));
+
+        // Nested lists and mixed-type elements.
+        assert!(
+            FieldValue::List(vec![vec![1i64].into()])
+                < FieldValue::List(vec![vec![1i64, 0].into()])
+        );
+        assert!(
+            FieldValue::List(vec![FieldValue::Null, 5.into()])
+                < FieldValue::List(vec![0.into(), 0.into()])
+        );
+        assert!(FieldValue::List(vec![1.into()]) < FieldValue::List(vec!["a".into()]));
+
+        let mut values = vec![
+            list(&[2]),
+            list(&[1, 2]),
+            list(&[]),
+            list(&[1]),
+            FieldValue::Null,
+            "text".into(),
+        ];
+        values.sort();
+        assert_eq!(
+            vec![
+                FieldValue::Null,
+                "text".into(),
+                list(&[]),
+                list(&[1]),
+                list(&[1, 2]),
+                list(&[2]),
+            ],
+            values
+        );
+    }
+
+    #[test]
+    fn mixed_variant_ordering_is_by_kind() {
+        let values = [
+            FieldValue::Null,
+            FieldValue::Float64(0.5),
+            FieldValue::Uint64(1),
+            FieldValue::Int64(10),
+            FieldValue::String("a".into()),
+            FieldValue::Boolean(false),
+            FieldValue::DateTimeUtc("2023-01-02T03:04:05Z".parse().unwrap()),
+            FieldValue::Enum("A".into()),
+            FieldValue::List(vec![]),
+        ];
+        for (i, left) in values.iter().enumerate() {
+            for (j, right) in values.iter().enumerate() {
+                assert_eq!(i.cmp(&j), left.cmp(right), "{left:?} {right:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn sorting_values_of_every_variant() {
+        let two_pow_53 = 1i64 << 53;
+        let mut values = vec![
+            FieldValue::List(vec![FieldValue::Int64(2)]),
+            FieldValue::Enum("B".into()),
+            FieldValue::DateTimeUtc("2023-01-02T03:04:05Z".parse().unwrap()),
+            FieldValue::Boolean(true),
+            FieldValue::String("b".into()),
+            FieldValue::Uint64(u64::MAX),
+            FieldValue::Float64(1.5),
+            FieldValue::Int64(two_pow_53 + 1),
+            FieldValue::Float64(two_pow_53 as f64),
+            FieldValue::Uint64(1),
+            FieldValue::Int64(1),
+            FieldValue::Float64(-0.5),
+            FieldValue::Int64(i64::MIN),
+            FieldValue::Null,
+            FieldValue::List(vec![FieldValue::Uint64(1), FieldValue::Int64(5)]),
+            FieldValue::Enum("A".into()),
+            FieldValue::DateTimeUtc("2022-01-02T03:04:05Z".parse().unwrap()),
+            FieldValue::Boolean(false),
+            FieldValue::String("a".into()),
+            FieldValue::Float64(1.0),
+            FieldValue::Float64(-1e300),
+            FieldValue::Float64(1e300),
+        ];
+        values.sort();
+
+        let expected = vec![
+            FieldValue::Null,
+            FieldValue::Float64(-1e300),
+            FieldValue::Int64(i64::MIN),
+            FieldValue::Float64(-0.5),
+            FieldValue::Int64(1),
+            FieldValue::Uint64(1),
+            FieldValue::Float64(1.0),
+            FieldValue::Float64(1.5),
+            // Exact comparison, even though 2^53 + 1 rounds to 2^53 as an f64.
+            FieldValue::Float64(two_pow_53 as f64),
+            FieldValue::Int64(two_pow_53 + 1),
+            FieldValue::Uint64(u64::MAX),
+            FieldValue::Float64(1e300),
+            FieldValue::String("a".into()),
+            FieldValue::String("b".into()),
+            FieldValue::Boolean(false),
+            FieldValue::Boolean(true),
+            FieldValue::DateTimeUtc("2022-01-02T03:04:05Z".parse().unwrap()),
+            FieldValue::DateTimeUtc("2023-01-02T03:04:05Z".parse().unwrap()),
+            FieldValue::Enum("A".into()),
+            FieldValue::Enum("B".into()),
+            FieldValue::List(vec![FieldValue::Uint64(1), FieldValue::Int64(5)]),
+            FieldValue::List(vec![FieldValue::Int64(2)]),
+        ];
+        assert_eq!(format!("{expected:?}"), format!("{values:?}"));
+
+        // The order is consistent with equality.
+        for left in &values {
+            for right in &values {
+                assert_eq!(left == right, left.cmp(right).is_eq(), "{left:?} {right:?}");
+            }
+        }
+        assert!(FieldValue::Float64(-0.0)
+            .cmp(&FieldValue::Float64(0.0))
+            .is_eq());
+    }
+
+    #[test]
+    fn hash_is_consistent_with_eq() {
+        use std::collections::HashSet;
+
+        let values: HashSet<FieldValue> = [
+            FieldValue::Float64(0.0),
+            FieldValue::Float64(-0.0),
+            FieldValue::Int64(0),
+            FieldValue::Uint64(0),
+            FieldValue::String("a".into()),
+            FieldValue::Enum("a".into()),
+            vec![1i64, 2].into(),
+            vec![1i64, 2].into(),
+            FieldValue::Null,
+        ]
+        .into_iter()
+        .collect();
+
+        // Equal values hash the same way, while values of different variants are distinct.
+        assert_eq!(7, values.len());
+        assert!(values.contains(&FieldValue::Float64(-0.0)));
+        assert!(values.contains(&vec![1i64, 2].into()));
+        assert!(!values.contains(&vec![2i64, 1].into()));
+    }
+
+    #[test]
+    fn try_from_field_value() {
+        let n: u64 = FieldValue::Int64(42).try_into().unwrap();
+        assert_eq!(42, n);
+        let n: i64 = (&FieldValue::Uint64(7)).try_into().unwrap();
+        assert_eq!(7, n);
+        let s: String = FieldValue::String("abc".into()).try_into().unwrap();
+        assert_eq!("abc", s);
+        let b: bool = (&FieldValue::Boolean(true)).try_into().unwrap();
+        assert!(b);
+
+        let err = u64::try_from(FieldValue::String("42".into())).unwrap_err();
+        assert_eq!("u64", err.target_type());
+        assert_eq!("String", err.actual_variant());
+        assert_eq!(
+            "Cannot convert FieldValue::String value into u64.",
+            err.to_string()
+        );
+
+        // Out-of-range integers are rejected, naming the variant that held them.
+        let err: FieldValueConversionError = u64::try_from(&FieldValue::Int64(-1)).unwrap_err();
+        assert_eq!("Int64", err.actual_variant());
+        assert_eq!(
+            "Null",
+            String::try_from(FieldValue::Null)
+                .unwrap_err()
+                .actual_variant()
+        );
+        assert_eq!(
+            "Enum",
+            bool::try_from(&FieldValue::Enum("true".into()))
+                .unwrap_err()
+                .actual_variant()
+        );
+    }
+
+    #[test]
+    fn as_slice() {
+        let list: FieldValue = vec![1i64, 2].into();
+        assert_eq!(
+            Some([FieldValue::Int64(1), FieldValue::Int64(2)].as_slice()),
+            list.as_slice()
+        );
+        assert_eq!(Some([].as_slice()), FieldValue::List(vec![]).as_slice());
+        assert_eq!(None, FieldValue::Null.as_slice());
+        assert_eq!(None, FieldValue::String("[1, 2]".into()).as_slice());
+    }
+
+    #[test]
+    fn contains_value() {
+        let list: FieldValue = vec![1i64, 2, 3].into();
+        assert!(list.contains_value(&FieldValue::Int64(2)));
+        assert!(!list.contains_value(&FieldValue::Int64(4)));
+        assert!(list.contains_value(&FieldValue::Uint64(2)));
+        assert!(!list.contains_value(&FieldValue::Float64(2.0)));
+        assert!(!FieldValue::Null.contains_value(&FieldValue::Null));
+        assert!(!FieldValue::Int64(2).contains_value(&FieldValue::Int64(2)));
+    }
+
+    #[test]
+    fn ranges() {
+        let range = FieldValue::range(1i64, 5u64);
+        assert_eq!(
+            Some((&FieldValue::Int64(1), &FieldValue::Uint64(5))),
+            range.as_range()
+        );
+        assert_eq!(None, FieldValue::List(vec![1i64.into()]).as_range());
+        assert_eq!(None, FieldValue::Int64(1).as_range());
+
+        assert!(range.range_contains(&FieldValue::Int64(1)));
+        assert!(range.range_contains(&FieldValue::Float64(4.5)));
+        assert!(!range.range_contains(&FieldValue::Uint64(5)));
+        assert!(!range.range_contains(&FieldValue::Int64(0)));
+        assert!(!range.range_contains(&FieldValue::Null));
+        assert!(!range.range_contains(&FieldValue::String("2".into())));
+
+        let unbounded_start = FieldValue::range(FieldValue::Null, 5i64);
+        assert!(unbounded_start.range_contains(&FieldValue::Int64(i64::MIN)));
+        assert!(!unbounded_start.range_contains(&FieldValue::Int64(5)));
+
+        let test_data = [
+            (FieldValue::range(4i64, 9i64), true),
+            (FieldValue::range(5i64, 9i64), false),
+            (FieldValue::range(-3i64, 2u64), true),
+            (FieldValue::range(2i64, 2i64), false),
+            (
+                FieldValue::range(FieldValue::Float64(3.5), FieldValue::Null),
+                true,
+            ),
+            (FieldValue::range(FieldValue::Null, FieldValue::Null), true),
+            (FieldValue::range("a", "z"), false),
+            (FieldValue::Int64(3), false),
+        ];
+        for (other, expected) in test_data {
+            assert_eq!(expected, range.range_overlaps(&other), "{other:?}");
+            assert_eq!(expected, other.range_overlaps(&range), "{other:?}");
+        }
+
+        let first_day = FieldValue::range(
+            FieldValue::from_unix_seconds(0).unwrap(),
+            FieldValue::from_unix_seconds(86400).unwrap(),
+        );
+        assert!(first_day.range_contains(&FieldValue::from_unix_seconds(3600).unwrap()));
+        assert!(!first_day.range_contains(&FieldValue::Int64(3600)));
+    }
+
+    #[test]
+    fn deserialize_list_contains() {
+        let check = |json: &str, needle: FieldValue| {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            let outcome = FieldValue::deserialize_list_contains(&mut deserializer, &needle);
+            deserializer.end().expect("input not fully consumed");
+            outcome.unwrap()
+        };
+
+        assert!(check(r#"[1, "two", [3], 4]"#, FieldValue::Int64(1)));
+        assert!(check(r#"[1, "two", [3], 4]"#, "two".into()));
+        assert!(check(r#"[1, "two", [3], 4]"#, vec![3i64].into()));
+        assert!(!check(r#"[1, "two", [3], 4]"#, FieldValue::Int64(3)));
+        assert!(check(r#"[1, "two", [3], 4]"#, FieldValue::Uint64(4)));
+        assert!(check(r#"[1, "two", [3], 4]"#, vec![3u64].into()));
+        assert!(check(r#"[-1]"#, FieldValue::Int64(-1)));
+        assert!(check(r#"[1, null]"#, FieldValue::Null));
+        assert!(!check("[]", FieldValue::Null));
+        assert!(!check("null", FieldValue::Null));
+
+        let mut deserializer = serde_json::Deserializer::from_str(r#"{"not": "a list"}"#);
+        assert!(
+            FieldValue::deserialize_list_contains(&mut deserializer, &FieldValue::Null).is_err()
+        );
+    }
 }
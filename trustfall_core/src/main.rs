@@ -5,17 +5,11 @@
 #[macro_use]
 extern crate maplit;
 
-#[macro_use]
-extern crate lazy_static;
-
+// The test data generator uses the library for everything but its test adapters.
 mod filesystem_interpreter;
-mod frontend;
-mod graphql_query;
-mod interpreter;
-mod ir;
 mod nullables_interpreter;
 mod numbers_interpreter;
-mod schema;
+#[allow(dead_code)] // the rest of it is used by the library
 mod util;
 
 use std::{
@@ -25,7 +19,10 @@ use std::{
 use async_graphql_parser::{parse_query, parse_schema};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use trustfall_core::{interpreter::error::QueryArgumentsError, schema::error::InvalidSchemaError};
+use trustfall_core::{
+    frontend, graphql_query, interpreter, interpreter::error::QueryArgumentsError, ir, schema,
+    schema::error::InvalidSchemaError,
+};
 
 use crate::{
     filesystem_interpreter::{FilesystemInterpreter, FilesystemVertex},
@@ -1,18 +1,56 @@
-use std::{collections::BTreeSet, sync::Arc};
+#[cfg(test)]
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+use std::{
+    collections::BTreeSet,
+    ops::{Bound, RangeInclusive},
+    sync::Arc,
+};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+#[cfg(test)]
+use crate::{frontend::parse, interpreter::execution::interpret_ir, ir::indexed::IndexedQuery};
 use crate::{
     interpreter::{
         self,
         helpers::{resolve_coercion_with, resolve_neighbors_with, resolve_property_with},
-        Adapter, ContextIterator, ContextOutcomeIterator, QueryInfo, Typename, VertexIterator,
+        Adapter, CandidateValue, ContextIterator, ContextOutcomeIterator, QueryInfo, Typename,
+        VertexIterator,
     },
     ir::{EdgeParameters, FieldValue},
     schema::Schema,
 };
 
+/// The schema that [`NumbersAdapter`] implements.
+pub(crate) fn numbers_schema() -> Schema {
+    Schema::parse(include_str!("../test_data/schemas/numbers.graphql"))
+        .expect("schema is not valid")
+}
+
+/// Parse a query against [`numbers_schema`], panicking if the query isn't valid.
+#[cfg(test)]
+pub(crate) fn parse_numbers_query(query: &str) -> Arc<IndexedQuery> {
+    parse(&numbers_schema(), query).expect("query is not valid")
+}
+
+/// A fresh [`NumbersAdapter`], ready to be passed to the interpreter.
+#[cfg(test)]
+pub(crate) fn numbers_adapter() -> Rc<RefCell<NumbersAdapter>> {
+    Rc::new(RefCell::new(NumbersAdapter::new()))
+}
+
+/// Run a query against a fresh [`NumbersAdapter`] and collect its results.
+#[cfg(test)]
+pub(crate) fn run_numbers_query(
+    query: Arc<IndexedQuery>,
+    arguments: Arc<BTreeMap<Arc<str>, FieldValue>>,
+) -> Vec<BTreeMap<Arc<str>, FieldValue>> {
+    interpret_ir(numbers_adapter(), query, arguments)
+        .expect("arguments are not valid")
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) enum NumbersVertex {
     Neither(NeitherNumber), // zero and one
@@ -180,6 +218,26 @@ fn make_number_vertex(primes: &mut BTreeSet<i64>, num: i64) -> NumbersVertex {
     }
 }
 
+/// The vertices for the given values that fall within the given range, in ascending order.
+fn lookup_numbers<'a>(
+    range: RangeInclusive<i64>,
+    values: impl IntoIterator<Item = &'a FieldValue>,
+) -> VertexIterator<'static, NumbersVertex> {
+    let mut primes = btreeset![2, 3];
+    let numbers: BTreeSet<i64> = values
+        .into_iter()
+        .filter_map(|value| value.as_i64())
+        .filter(|value| range.contains(value))
+        .collect();
+    Box::new(
+        numbers
+            .into_iter()
+            .map(move |n| make_number_vertex(&mut primes, n))
+            .collect_vec()
+            .into_iter(),
+    )
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct NumbersAdapter {
     schema: Schema,
@@ -189,8 +247,7 @@ impl NumbersAdapter {
     #[allow(dead_code)]
     pub(crate) fn new() -> Self {
         Self {
-            schema: Schema::parse(include_str!("../test_data/schemas/numbers.graphql"))
-                .expect("schema is not valid"),
+            schema: numbers_schema(),
         }
     }
 }
@@ -212,8 +269,43 @@ impl Adapter<'static> for NumbersAdapter {
             "Two" => Box::new(std::iter::once(make_number_vertex(&mut primes, 2))),
             "Four" => Box::new(std::iter::once(make_number_vertex(&mut primes, 4))),
             "Number" | "NumberImplicitNullDefault" => {
-                let min_value = parameters["min"].as_i64().unwrap_or(0);
-                let max_value = parameters["max"].as_i64().unwrap();
+                let mut min_value = parameters["min"].as_i64().unwrap_or(0);
+                let mut max_value = parameters["max"].as_i64().unwrap();
+
+                // If the query filters the starting vertices' values, look up just the vertices
+                // with matching values instead of producing the full range only to filter it.
+                match query_info.starting_vertex_candidate("value") {
+                    Some(CandidateValue::Impossible) => return Box::new(std::iter::empty()),
+                    Some(CandidateValue::Single(value)) => {
+                        return lookup_numbers(min_value..=max_value, [value]);
+                    }
+                    Some(CandidateValue::Multiple(values)) => {
+                        return lookup_numbers(min_value..=max_value, values);
+                    }
+                    Some(CandidateValue::Range(range)) => {
+                        match range.start() {
+                            Bound::Included(start) => {
+                                min_value = min_value.max(start.as_i64().unwrap_or(i64::MAX));
+                            }
+                            Bound::Excluded(start) => {
+                                min_value = min_value
+                                    .max(start.as_i64().unwrap_or(i64::MAX).saturating_add(1));
+                            }
+                            Bound::Unbounded => {}
+                        }
+                        match range.end() {
+                            Bound::Included(end) => {
+                                max_value = max_value.min(end.as_i64().unwrap_or(i64::MAX));
+                            }
+                            Bound::Excluded(end) => {
+                                max_value = max_value
+                                    .min(end.as_i64().unwrap_or(i64::MAX).saturating_sub(1));
+                            }
+                            Bound::Unbounded => {}
+                        }
+                    }
+                    _ => {}
+                }
 
                 if min_value > max_value {
                     Box::new(std::iter::empty())
@@ -394,3 +486,36 @@ impl Adapter<'static> for NumbersAdapter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use crate::ir::FieldValue;
+
+    use super::{parse_numbers_query, run_numbers_query};
+
+    #[test]
+    fn starting_vertices_filtered_by_value_are_looked_up_directly() {
+        let query = parse_numbers_query(
+            r#"
+{
+    Number(max: 1000000000) {
+        value @filter(op: "one_of", value: ["$values"]) @output
+    }
+}"#,
+        );
+        let arguments = Arc::new(BTreeMap::from([(
+            Arc::from("values"),
+            vec![12i64, 7, -1, 12].into(),
+        )]));
+
+        // Scanning all billion numbers would not finish, so getting results at all
+        // means the starting vertices came straight from the filter's values.
+        let values: Vec<_> = run_numbers_query(query, arguments)
+            .into_iter()
+            .map(|row| row["value"].clone())
+            .collect();
+        assert_eq!(vec![FieldValue::Int64(7), FieldValue::Int64(12)], values);
+    }
+}
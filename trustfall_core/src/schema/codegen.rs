@@ -0,0 +1,381 @@
+//! Generation of skeleton adapter code from a schema.
+use std::fmt::Write;
+
+use async_graphql_parser::types::{FieldDefinition, TypeKind};
+
+use crate::ir::types::get_base_named_type;
+
+use super::{get_vertex_type_fields, Schema};
+
+/// Generate the Rust source code of a skeleton [`BasicAdapter`] implementation for the schema.
+///
+/// The generated code contains:
+/// - a `Vertex` enum with a variant for every non-interface vertex type in the schema,
+///   together with an `as_*` accessor method for each variant and a [`Typename`] implementation;
+/// - an `Adapter` struct implementing [`BasicAdapter`], whose resolver methods match on
+///   every starting edge, property, and edge defined in the schema.
+///
+/// Each `Vertex` variant holds `()` as a placeholder for the vertex's data, and the match arms
+/// for starting edges, properties, and edges are `todo!()` stubs. Type coercions are fully
+/// implemented, since they only depend on which variant each vertex is.
+///
+/// The output is meant as a starting point for writing an adapter by hand. Regenerating it
+/// after the schema changes and diffing against the hand-written adapter is an easy way
+/// to find the resolvers that need to be added or removed.
+///
+/// [`BasicAdapter`]: crate::interpreter::basic_adapter::BasicAdapter
+/// [`Typename`]: crate::interpreter::Typename
+pub fn generate_adapter_skeleton(schema: &Schema) -> String {
+    let query_type_name = schema.query_type_name();
+    let mut type_names: Vec<&str> = schema
+        .vertex_types
+        .keys()
+        .map(|name| name.as_ref())
+        .filter(|name| *name != query_type_name)
+        .collect();
+    type_names.sort_unstable();
+
+    let concrete_types: Vec<&str> = type_names
+        .iter()
+        .copied()
+        .filter(|name| matches!(schema.vertex_types[*name].kind, TypeKind::Object(_)))
+        .collect();
+
+    let mut output = String::new();
+    output.push_str(
+        "use trustfall_core::{
+    interpreter::{
+        basic_adapter::BasicAdapter,
+        helpers::{resolve_coercion_with, resolve_neighbors_with, resolve_property_with},
+        ContextIterator, ContextOutcomeIterator, Typename, VertexIterator,
+    },
+    ir::{EdgeParameters, FieldValue},
+};
+",
+    );
+
+    // The vertex enum, its accessors, and its typename implementation.
+    output.push_str("\n#[derive(Debug, Clone)]\npub enum Vertex {\n");
+    for name in &concrete_types {
+        writeln!(output, "    {name}(()),").unwrap();
+    }
+    output.push_str("}\n\nimpl Vertex {\n");
+    for (index, name) in concrete_types.iter().enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        let method_name = to_snake_case(name);
+        writeln!(
+            output,
+            "    pub fn as_{method_name}(&self) -> Option<&()> {{
+        match self {{
+            Self::{name}(data) => Some(data),
+            _ => None,
+        }}
+    }}"
+        )
+        .unwrap();
+    }
+    output.push_str(
+        "}
+
+impl Typename for Vertex {
+    fn typename(&self) -> &'static str {
+        match self {
+",
+    );
+    for name in &concrete_types {
+        writeln!(output, "            Self::{name}(..) => \"{name}\",").unwrap();
+    }
+    output.push_str("        }\n    }\n}\n");
+
+    // The adapter itself.
+    output.push_str(
+        "
+#[derive(Debug, Clone, Default)]
+pub struct Adapter;
+
+impl<'vertex> BasicAdapter<'vertex> for Adapter {
+    type Vertex = Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &str,
+        parameters: &EdgeParameters,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        match edge_name {
+",
+    );
+    for field in &schema.query_type.fields {
+        let edge_name = field.node.name.node.as_ref();
+        let message = format!(
+            "resolve starting edge {edge_name}{}",
+            describe_parameters(&field.node)
+        );
+        writeln!(
+            output,
+            "            \"{edge_name}\" => {},",
+            todo_stub(&message),
+        )
+        .unwrap();
+    }
+    output.push_str(
+        "            _ => unreachable!(\"unexpected starting edge: {edge_name} {parameters:?}\"),
+        }
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &str,
+        property_name: &str,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        match (type_name, property_name) {
+",
+    );
+    for (type_name, field) in vertex_type_fields(schema, &type_names) {
+        if schema
+            .vertex_types
+            .contains_key(get_base_named_type(&field.ty.node))
+        {
+            continue;
+        }
+        let property_name = field.name.node.as_ref();
+        let message = format!(
+            "resolve property {type_name}.{property_name}: {}",
+            field.ty.node
+        );
+        writeln!(
+            output,
+            "            (\"{type_name}\", \"{property_name}\") => resolve_property_with(contexts, |vertex| {{
+                {}
+            }}),",
+            todo_stub(&message),
+        )
+        .unwrap();
+    }
+    output.push_str(
+        "            _ => unreachable!(\"unexpected property: {type_name}.{property_name}\"),
+        }
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &str,
+        edge_name: &str,
+        parameters: &EdgeParameters,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        match (type_name, edge_name) {
+",
+    );
+    for (type_name, field) in vertex_type_fields(schema, &type_names) {
+        if !schema
+            .vertex_types
+            .contains_key(get_base_named_type(&field.ty.node))
+        {
+            continue;
+        }
+        let edge_name = field.name.node.as_ref();
+        let message = format!(
+            "resolve edge {type_name}.{edge_name}{}: {}",
+            describe_parameters(field),
+            field.ty.node,
+        );
+        writeln!(
+            output,
+            "            (\"{type_name}\", \"{edge_name}\") => resolve_neighbors_with(contexts, |vertex| {{
+                {}
+            }}),",
+            todo_stub(&message),
+        )
+        .unwrap();
+    }
+    output.push_str(
+        "            _ => unreachable!(\"unexpected edge: {type_name}.{edge_name} {parameters:?}\"),
+        }
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &str,
+        coerce_to_type: &str,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        match (type_name, coerce_to_type) {
+",
+    );
+    for type_name in &type_names {
+        let mut subtypes: Vec<&str> = schema
+            .subtypes(type_name)
+            .expect("type is defined")
+            .filter(|subtype| subtype != type_name)
+            .collect();
+        subtypes.sort_unstable();
+
+        for subtype in subtypes {
+            let mut variants = schema
+                .subtypes(subtype)
+                .expect("type is defined")
+                .filter(|name| concrete_types.contains(name))
+                .map(|name| format!("Vertex::{name}(..)"))
+                .collect::<Vec<_>>();
+            variants.sort_unstable();
+            let pattern = if variants.is_empty() {
+                // No vertex can be of this type, so the coercion always fails.
+                "_ if false".to_string()
+            } else {
+                variants.join(" | ")
+            };
+            writeln!(
+                output,
+                "            (\"{type_name}\", \"{subtype}\") => resolve_coercion_with(contexts, |vertex| {{
+                matches!(vertex, {pattern})
+            }}),"
+            )
+            .unwrap();
+        }
+    }
+    output.push_str(
+        "            _ => unreachable!(\"unexpected coercion: {type_name} to {coerce_to_type}\"),
+        }
+    }
+}
+",
+    );
+
+    output
+}
+
+/// The fields of each of the named types, in the order they are defined in the schema,
+/// skipping any built-in meta fields like `__typename` that are resolved separately.
+fn vertex_type_fields<'a>(
+    schema: &'a Schema,
+    type_names: &'a [&'a str],
+) -> impl Iterator<Item = (&'a str, &'a FieldDefinition)> + 'a {
+    type_names.iter().flat_map(move |type_name| {
+        get_vertex_type_fields(&schema.vertex_types[*type_name])
+            .iter()
+            .map(move |field| (*type_name, &field.node))
+            .filter(|(_, field)| !field.name.node.starts_with("__"))
+    })
+}
+
+/// Describe the edge's parameters, if any, in the style of the schema definition.
+fn describe_parameters(field: &FieldDefinition) -> String {
+    if field.arguments.is_empty() {
+        return String::new();
+    }
+
+    let parameters: Vec<String> = field
+        .arguments
+        .iter()
+        .map(|arg| {
+            let arg = &arg.node;
+            match &arg.default_value {
+                Some(default) => format!("{}: {} = {}", arg.name.node, arg.ty.node, default.node),
+                None => format!("{}: {}", arg.name.node, arg.ty.node),
+            }
+        })
+        .collect();
+    format!("({})", parameters.join(", "))
+}
+
+/// A `todo!()` call that panics with the given message.
+///
+/// The message may contain arbitrary text from the schema, such as string default values,
+/// so it's escaped both as a string literal and as a format string.
+fn todo_stub(message: &str) -> String {
+    format!("todo!({:?})", message.replace('{', "{{").replace('}', "}}"))
+}
+
+/// Convert a type name like `HackerNewsItem` into `hacker_news_item`.
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    let mut previous: Option<char> = None;
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_uppercase() {
+            // Start a new word at a lowercase-to-uppercase boundary like "sN" in "HackerNews",
+            // and at the end of an acronym like "LI" in "HTMLItem".
+            let starts_word = match previous {
+                None => false,
+                Some(p) if p.is_lowercase() || p.is_ascii_digit() => true,
+                Some(p) => p.is_uppercase() && chars.peek().is_some_and(|n| n.is_lowercase()),
+            };
+            if starts_word {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+        previous = Some(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{numbers_interpreter::numbers_schema, schema::Schema};
+
+    use super::{generate_adapter_skeleton, to_snake_case};
+
+    #[test]
+    fn skeleton_snapshot() {
+        // The snapshot is compiled by the `codegen_skeleton` integration test.
+        let schema = Schema::parse(include_str!("../../test_data/codegen/skeleton.graphql"))
+            .expect("valid schema");
+        let skeleton = generate_adapter_skeleton(&schema);
+        let snapshot_path = "test_data/codegen/skeleton.rs";
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(snapshot_path, &skeleton).unwrap();
+        }
+        let snapshot = std::fs::read_to_string(snapshot_path).unwrap();
+        assert_eq!(
+            snapshot, skeleton,
+            "skeleton differs from {snapshot_path}, rerun with UPDATE_SNAPSHOTS=1 to update it"
+        );
+    }
+
+    #[test]
+    fn snake_case_type_names() {
+        assert_eq!("number", to_snake_case("Number"));
+        assert_eq!("hacker_news_item", to_snake_case("HackerNewsItem"));
+        assert_eq!("html_item", to_snake_case("HTMLItem"));
+        assert_eq!("item2_story", to_snake_case("Item2Story"));
+    }
+
+    #[test]
+    fn numbers_schema_skeleton() {
+        let schema = numbers_schema();
+        let skeleton = generate_adapter_skeleton(&schema);
+
+        // Interfaces don't get their own variants, and neither does the root query type.
+        assert!(skeleton.contains("    Composite(()),\n"));
+        assert!(skeleton.contains("    Neither(()),\n"));
+        assert!(!skeleton.contains("    Number(()),\n"));
+        assert!(!skeleton.contains("RootSchemaQuery(()),"));
+        assert!(skeleton.contains("pub fn as_prime(&self) -> Option<&()> {"));
+        assert!(skeleton.contains("Self::Letter(..) => \"Letter\","));
+
+        assert!(skeleton.contains(
+            "\"Number\" => todo!(\"resolve starting edge Number(min: Int! = 0, max: Int!)\"),"
+        ));
+        assert!(skeleton.contains("(\"Number\", \"value\") => resolve_property_with("));
+        assert!(skeleton.contains("(\"Composite\", \"primeFactor\") => resolve_neighbors_with("));
+        assert!(!skeleton.contains("(\"Composite\", \"primeFactor\") => resolve_property_with("));
+        assert!(
+            skeleton.contains("todo!(\"resolve edge Number.multiple(max: Int!): [Composite!]\")")
+        );
+
+        // Coercions to interfaces match all of the interface's implementations.
+        assert!(skeleton.contains(
+            "(\"Named\", \"Number\") => resolve_coercion_with(contexts, |vertex| {
+                matches!(vertex, Vertex::Composite(..) | Vertex::Neither(..) | Vertex::Prime(..))
+            }),"
+        ));
+        assert!(skeleton.contains("(\"Number\", \"Prime\") => resolve_coercion_with("));
+        assert!(!skeleton.contains("(\"Prime\", \"Number\")"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Schema introspection packaged for editor tooling, such as query autocompletion.
+use std::sync::Arc;
+
+use async_graphql_parser::types::{FieldDefinition, Type};
+
+use crate::ir::{types::get_base_named_type, FieldValue};
+
+use super::{get_vertex_type_fields, Schema, BUILTIN_SCALARS};
+
+/// The names of all operators supported by the `@filter` directive.
+pub const FILTER_OPERATORS: &[&str] = &[
+    "is_null",
+    "is_not_null",
+    "=",
+    "!=",
+    "<",
+    "<=",
+    ">",
+    ">=",
+    "contains",
+    "not_contains",
+    "one_of",
+    "not_one_of",
+    "has_prefix",
+    "not_has_prefix",
+    "has_suffix",
+    "not_has_suffix",
+    "has_substring",
+    "not_has_substring",
+    "regex",
+    "not_regex",
+];
+
+/// Everything that may be queried at a vertex of a given type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeCompletions {
+    pub type_name: Arc<str>,
+
+    /// The type's properties, in the order the schema defines them.
+    pub properties: Vec<PropertyCompletion>,
+
+    /// The type's edges, in the order the schema defines them.
+    pub edges: Vec<EdgeCompletion>,
+
+    /// The names of the types to which a vertex of this type may be coerced
+    /// using a type coercion like `... on Subtype`, sorted by name.
+    /// Does not include the type itself.
+    pub coercions: Vec<Arc<str>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyCompletion {
+    pub name: Arc<str>,
+    pub property_type: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeCompletion {
+    pub name: Arc<str>,
+
+    /// The type of the edge, e.g. `[Number!]`.
+    /// Its base named type is the type of the vertices at the other end of the edge.
+    pub edge_type: Type,
+
+    /// The edge's parameters, in the order the schema defines them.
+    pub parameters: Vec<ParameterCompletion>,
+}
+
+impl EdgeCompletion {
+    /// The type of the vertices at the other end of the edge.
+    pub fn target_type_name(&self) -> &str {
+        get_base_named_type(&self.edge_type)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterCompletion {
+    pub name: Arc<str>,
+    pub parameter_type: Type,
+
+    /// The value used when the query doesn't specify this parameter, if the schema sets one.
+    pub default_value: Option<FieldValue>,
+}
+
+impl ParameterCompletion {
+    /// Whether a query must specify a value for this parameter.
+    ///
+    /// Nullable parameters without a default are implicitly `null` when not specified.
+    pub fn is_required(&self) -> bool {
+        self.default_value.is_none() && !self.parameter_type.nullable
+    }
+}
+
+impl Schema {
+    /// Everything that may be queried at a vertex of the named type, or `None` if
+    /// no such vertex type is defined.
+    ///
+    /// To complete a query's starting edges, use [`Schema::starting_edge_completions`].
+    pub fn completions(&self, type_name: &str) -> Option<TypeCompletions> {
+        let (type_name, defn) = self.vertex_types.get_key_value(type_name)?;
+
+        let mut properties = vec![];
+        let mut edges = vec![];
+        for field in get_vertex_type_fields(defn) {
+            let field = &field.node;
+            let name: Arc<str> = Arc::from(field.name.node.as_ref());
+            if BUILTIN_SCALARS.contains(get_base_named_type(&field.ty.node)) {
+                properties.push(PropertyCompletion {
+                    name,
+                    property_type: field.ty.node.clone(),
+                });
+            } else {
+                edges.push(EdgeCompletion {
+                    name,
+                    edge_type: field.ty.node.clone(),
+                    parameters: edge_parameters(field),
+                });
+            }
+        }
+
+        let mut coercions: Vec<Arc<str>> = self
+            .subtypes(type_name)
+            .expect("type was just looked up")
+            .filter(|subtype| *subtype != type_name.as_ref())
+            .map(Arc::from)
+            .collect();
+        coercions.sort_unstable();
+
+        Some(TypeCompletions {
+            type_name: type_name.clone(),
+            properties,
+            edges,
+            coercions,
+        })
+    }
+
+    /// The edges at which a query may start, as the completions of the schema's
+    /// root query type.
+    pub fn starting_edge_completions(&self) -> TypeCompletions {
+        self.completions(self.query_type_name())
+            .expect("the root query type is a vertex type")
+    }
+}
+
+fn edge_parameters(field: &FieldDefinition) -> Vec<ParameterCompletion> {
+    field
+        .arguments
+        .iter()
+        .map(|param| {
+            let param = &param.node;
+            ParameterCompletion {
+                name: Arc::from(param.name.node.as_ref()),
+                parameter_type: param.ty.node.clone(),
+                // Default values were validated when the schema was constructed.
+                default_value: param
+                    .default_value
+                    .as_ref()
+                    .map(|value| value.node.clone().try_into().unwrap()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc};
+
+    use async_graphql_parser::{
+        parse_query,
+        types::{DocumentOperations, Selection},
+    };
+
+    use crate::{
+        graphql_query::directives::FilterDirective, ir::FieldValue,
+        numbers_interpreter::numbers_schema,
+    };
+
+    use super::FILTER_OPERATORS;
+
+    #[test]
+    fn filter_operators_are_supported_by_the_parser() {
+        let unique: BTreeSet<_> = FILTER_OPERATORS.iter().collect();
+        assert_eq!(FILTER_OPERATORS.len(), unique.len());
+
+        for op in FILTER_OPERATORS {
+            let value = match *op {
+                "is_null" | "is_not_null" => "",
+                _ => r#", value: ["$arg"]"#,
+            };
+            let document =
+                parse_query(format!(r#"{{ field @filter(op: "{op}"{value}) }}"#)).unwrap();
+            let DocumentOperations::Single(operation) = document.operations else {
+                unreachable!("the query has one operation")
+            };
+            let Selection::Field(field) = &operation.node.selection_set.node.items[0].node else {
+                unreachable!("the query selects a field")
+            };
+
+            let directive = FilterDirective::try_from(&field.node.directives[0])
+                .unwrap_or_else(|e| panic!("operator {op} is not supported: {e:?}"));
+            assert_eq!(*op, directive.operation.operation_name());
+        }
+    }
+
+    #[test]
+    fn completions_for_interface_type() {
+        let schema = numbers_schema();
+
+        let completions = schema.completions("Number").expect("no such type");
+        let properties: Vec<_> = completions
+            .properties
+            .iter()
+            .map(|p| (p.name.as_ref(), p.property_type.to_string()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("name", "String".to_string()),
+                ("value", "Int".to_string()),
+                ("vowelsInName", "[String]".to_string()),
+            ],
+            properties,
+        );
+
+        let edges: Vec<_> = completions
+            .edges
+            .iter()
+            .map(|e| (e.name.as_ref(), e.target_type_name(), e.parameters.len()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("predecessor", "Number", 0),
+                ("successor", "Number", 0),
+                ("multiple", "Composite", 1),
+            ],
+            edges,
+        );
+        let max = &completions.edges[2].parameters[0];
+        assert_eq!("max", max.name.as_ref());
+        assert!(max.is_required());
+
+        let coercions: Vec<Arc<str>> = vec!["Composite".into(), "Neither".into(), "Prime".into()];
+        assert_eq!(coercions, completions.coercions);
+
+        assert!(schema.completions("NoSuchType").is_none());
+    }
+
+    #[test]
+    fn completions_for_starting_edges() {
+        let schema = numbers_schema();
+
+        let completions = schema.starting_edge_completions();
+        assert_eq!("RootSchemaQuery", completions.type_name.as_ref());
+        assert!(completions.properties.is_empty());
+        assert!(completions.coercions.is_empty());
+
+        let number = &completions.edges[0];
+        assert_eq!("Number", number.name.as_ref());
+        let min = &number.parameters[0];
+        assert_eq!(Some(FieldValue::Int64(0)), min.default_value);
+        assert!(!min.is_required());
+
+        // Nullable parameters without a default are implicitly null.
+        let implicit_null = &completions.edges[1].parameters[0];
+        assert_eq!(None, implicit_null.default_value);
+        assert!(!implicit_null.is_required());
+    }
+}
@@ -92,6 +92,12 @@ pub enum InvalidSchemaError {
     )]
     InvalidEdgeType(String, String, String),
 
+    #[error(
+        "Type \"{0}\" defines edge \"{1}\" of type {2} and marks it with the @key directive, \
+        but only property fields may be keys."
+    )]
+    KeyDirectiveOnEdge(String, String, String),
+
     #[error(
         "The schema's root query type \"{0}\" defines a field \"{1}\" which is determined to \
         be a property field because of its type {2}. The root query type may only contain \
@@ -24,6 +24,8 @@ use crate::util::{BTreeMapTryInsertExt, HashMapTryInsertExt};
 
 use self::error::InvalidSchemaError;
 
+pub mod codegen;
+pub mod completions;
 pub mod error;
 
 #[derive(Debug, Clone)]
@@ -88,8 +90,8 @@ directive @filter(op: String!, value: [String!]) on FIELD | INLINE_FRAGMENT
 directive @tag(name: String) on FIELD
 directive @output(name: String) on FIELD
 directive @optional on FIELD
-directive @recurse(depth: Int!) on FIELD
-directive @fold on FIELD
+directive @recurse(depth: Int!, depth_output: String) on FIELD
+directive @fold(sort_by: String, descending: Boolean, limit: Int) on FIELD
 directive @transform(op: String!) on FIELD
 ";
 
@@ -250,6 +252,20 @@ directive @transform(op: String!) on FIELD
         }))
     }
 
+    /// The names of the properties of the named vertex type that are marked as unique keys
+    /// using the `@key` directive, in the order they are defined.
+    ///
+    /// Each key property on its own uniquely identifies a vertex of that type.
+    /// Keys are not inherited: a type implementing an interface with key properties
+    /// must mark them as `@key` on its own as well.
+    pub(crate) fn key_fields(&self, type_name: &str) -> Vec<Arc<str>> {
+        get_vertex_type_fields(&self.vertex_types[type_name])
+            .iter()
+            .filter(|defn| is_key_field(&defn.node))
+            .map(|defn| Arc::from(defn.node.name.node.as_ref()))
+            .collect()
+    }
+
     pub(crate) fn query_type_name(&self) -> &str {
         self.schema.query.as_ref().unwrap().node.as_ref()
     }
@@ -338,6 +354,14 @@ fn check_type_and_property_and_edge_invariants(
                 }
             } else if vertex_types.contains_key(base_named_type) {
                 // We're looking at an edge field.
+                if is_key_field(field_defn) {
+                    errors.push(InvalidSchemaError::KeyDirectiveOnEdge(
+                        type_name.to_string(),
+                        field_defn.name.node.to_string(),
+                        field_type.to_string(),
+                    ));
+                }
+
                 if base_named_type == query_type_definition.name.node.as_ref() {
                     // This edge points to the root query type. That's not supported.
                     errors.push(InvalidSchemaError::EdgePointsToRootQueryType(
@@ -683,6 +707,14 @@ fn check_field_type_narrowing(
     }
 }
 
+/// Whether the field is marked as a unique key of its vertex type.
+fn is_key_field(field: &FieldDefinition) -> bool {
+    field
+        .directives
+        .iter()
+        .any(|directive| directive.node.name.node.as_ref() == "key")
+}
+
 fn get_vertex_type_fields(vertex: &TypeDefinition) -> &[Positioned<FieldDefinition>] {
     match &vertex.kind {
         TypeKind::Object(obj) => &obj.fields,
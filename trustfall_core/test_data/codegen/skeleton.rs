@@ -0,0 +1,121 @@
+use trustfall_core::{
+    interpreter::{
+        basic_adapter::BasicAdapter,
+        helpers::{resolve_coercion_with, resolve_neighbors_with, resolve_property_with},
+        ContextIterator, ContextOutcomeIterator, Typename, VertexIterator,
+    },
+    ir::{EdgeParameters, FieldValue},
+};
+
+#[derive(Debug, Clone)]
+pub enum Vertex {
+    Comment(()),
+    Story(()),
+}
+
+impl Vertex {
+    pub fn as_comment(&self) -> Option<&()> {
+        match self {
+            Self::Comment(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_story(&self) -> Option<&()> {
+        match self {
+            Self::Story(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+impl Typename for Vertex {
+    fn typename(&self) -> &'static str {
+        match self {
+            Self::Comment(..) => "Comment",
+            Self::Story(..) => "Story",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Adapter;
+
+impl<'vertex> BasicAdapter<'vertex> for Adapter {
+    type Vertex = Vertex;
+
+    fn resolve_starting_vertices(
+        &mut self,
+        edge_name: &str,
+        parameters: &EdgeParameters,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        match edge_name {
+            "Item" => todo!("resolve starting edge Item(pattern: String! = \"say \\\"{{hi}}\\\"\\\\n\", limit: Int = 10)"),
+            _ => unreachable!("unexpected starting edge: {edge_name} {parameters:?}"),
+        }
+    }
+
+    fn resolve_property(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &str,
+        property_name: &str,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, FieldValue> {
+        match (type_name, property_name) {
+            ("Comment", "name") => resolve_property_with(contexts, |vertex| {
+                todo!("resolve property Comment.name: String")
+            }),
+            ("Comment", "text") => resolve_property_with(contexts, |vertex| {
+                todo!("resolve property Comment.text: String")
+            }),
+            ("Item", "name") => resolve_property_with(contexts, |vertex| {
+                todo!("resolve property Item.name: String")
+            }),
+            ("Story", "name") => resolve_property_with(contexts, |vertex| {
+                todo!("resolve property Story.name: String")
+            }),
+            ("Story", "score") => resolve_property_with(contexts, |vertex| {
+                todo!("resolve property Story.score: Int!")
+            }),
+            _ => unreachable!("unexpected property: {type_name}.{property_name}"),
+        }
+    }
+
+    fn resolve_neighbors(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &str,
+        edge_name: &str,
+        parameters: &EdgeParameters,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, VertexIterator<'vertex, Self::Vertex>> {
+        match (type_name, edge_name) {
+            ("Comment", "related") => resolve_neighbors_with(contexts, |vertex| {
+                todo!("resolve edge Comment.related(kind: String = \"}}{{\"): [Item!]!")
+            }),
+            ("Item", "related") => resolve_neighbors_with(contexts, |vertex| {
+                todo!("resolve edge Item.related(kind: String = \"}}{{\"): [Item!]!")
+            }),
+            ("Story", "related") => resolve_neighbors_with(contexts, |vertex| {
+                todo!("resolve edge Story.related(kind: String = \"}}{{\"): [Item!]!")
+            }),
+            _ => unreachable!("unexpected edge: {type_name}.{edge_name} {parameters:?}"),
+        }
+    }
+
+    fn resolve_coercion(
+        &mut self,
+        contexts: ContextIterator<'vertex, Self::Vertex>,
+        type_name: &str,
+        coerce_to_type: &str,
+    ) -> ContextOutcomeIterator<'vertex, Self::Vertex, bool> {
+        match (type_name, coerce_to_type) {
+            ("Item", "Comment") => resolve_coercion_with(contexts, |vertex| {
+                matches!(vertex, Vertex::Comment(..))
+            }),
+            ("Item", "Story") => resolve_coercion_with(contexts, |vertex| {
+                matches!(vertex, Vertex::Story(..))
+            }),
+            _ => unreachable!("unexpected coercion: {type_name} to {coerce_to_type}"),
+        }
+    }
+}
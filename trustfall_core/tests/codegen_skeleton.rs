@@ -0,0 +1,27 @@
+//! Checks that the adapter skeleton generated from `test_data/codegen/skeleton.graphql`
+//! compiles, and that its stubs panic with the intended messages.
+use trustfall_core::{interpreter::basic_adapter::BasicAdapter, ir::EdgeParameters};
+
+#[allow(unused_variables, dead_code)]
+mod skeleton {
+    include!("../test_data/codegen/skeleton.rs");
+}
+
+#[test]
+fn starting_edge_stub_message() {
+    let payload = std::panic::catch_unwind(|| {
+        let mut adapter = skeleton::Adapter;
+        let _ = adapter.resolve_starting_vertices("Item", &EdgeParameters::default());
+    })
+    .unwrap_err();
+
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .expect("message is a string");
+    assert_eq!(
+        r#"not yet implemented: resolve starting edge Item(pattern: String! = "say \"{hi}\"\\n", limit: Int = 10)"#,
+        message,
+    );
+}
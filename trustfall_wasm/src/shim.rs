@@ -9,7 +9,7 @@ use trustfall_core::{
     ir::FieldValue,
 };
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum JsFieldValue {
     Null,
@@ -18,6 +18,8 @@ pub enum JsFieldValue {
     Float(f64),
     Boolean(bool),
     List(Vec<JsFieldValue>),
+    // Only JS objects deserialize into this variant, since the ones above match first.
+    Json(serde_json::Value),
 }
 
 impl From<JsFieldValue> for FieldValue {
@@ -29,6 +31,7 @@ impl From<JsFieldValue> for FieldValue {
             JsFieldValue::Float(n) => FieldValue::Float64(n),
             JsFieldValue::Boolean(b) => FieldValue::Boolean(b),
             JsFieldValue::List(v) => FieldValue::List(v.into_iter().map(|x| x.into()).collect()),
+            JsFieldValue::Json(v) => FieldValue::Json(v),
         }
     }
 }
@@ -46,8 +49,12 @@ impl From<FieldValue> for JsFieldValue {
             FieldValue::Float64(n) => JsFieldValue::Float(n),
             FieldValue::Boolean(b) => JsFieldValue::Boolean(b),
             FieldValue::List(v) => JsFieldValue::List(v.into_iter().map(|x| x.into()).collect()),
+            FieldValue::Json(v) => JsFieldValue::Json(v),
             FieldValue::DateTimeUtc(_) => unimplemented!(),
             FieldValue::Enum(_) => unimplemented!(),
+            other => {
+                panic!("value cannot be converted to a JS value by this version: {other:?}")
+            }
         }
     }
 }
@@ -1,4 +1,4 @@
-export type JsFieldValue = string | boolean | number | null | JsFieldValue[];
+export type JsFieldValue = string | boolean | number | null | JsFieldValue[] | { [key: string]: unknown };
 export type JsEdgeParameters = Record<string, JsFieldValue>;
 
 export interface JsContext<T> {